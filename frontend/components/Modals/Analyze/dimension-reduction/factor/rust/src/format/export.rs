@@ -0,0 +1,255 @@
+use serde::{ Deserialize, Serialize };
+
+use super::table::Table;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    #[serde(rename = "csv")]
+    Csv,
+    #[serde(rename = "markdown")]
+    Markdown,
+    #[serde(rename = "html")]
+    Html,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "html" => Ok(ExportFormat::Html),
+            _ => Err(format!("Unknown export format '{}'", name)),
+        }
+    }
+}
+
+pub fn render_tables(tables: &[Table], format: ExportFormat) -> String {
+    tables
+        .iter()
+        .map(|table| render_table(table, format))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn render_table(table: &Table, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => render_csv(table),
+        ExportFormat::Markdown => render_markdown(table),
+        ExportFormat::Html => render_html(table),
+    }
+}
+
+// Group and label columns come first; a group column is only added when rows are grouped
+fn header_cells(table: &Table) -> Vec<String> {
+    let mut headers = Vec::new();
+    if has_groups(table) {
+        headers.push(String::new());
+    }
+    headers.push(String::new());
+    headers.extend(table.columns.iter().cloned());
+    headers
+}
+
+fn row_cells(table: &Table) -> Vec<Vec<String>> {
+    let grouped = has_groups(table);
+
+    table.rows
+        .iter()
+        .map(|row| {
+            let mut cells = Vec::new();
+            if grouped {
+                cells.push(row.group.clone().unwrap_or_default());
+            }
+            cells.push(row.label.clone());
+            cells.extend(row.cells.iter().map(|cell| cell.text.clone()));
+            cells
+        })
+        .collect()
+}
+
+fn has_groups(table: &Table) -> bool {
+    table.rows.iter().any(|row| row.group.is_some())
+}
+
+fn render_csv(table: &Table) -> String {
+    let mut lines = vec![csv_line(std::slice::from_ref(&table.title))];
+
+    if !table.column_groups.is_empty() {
+        let mut group_line = vec![String::new(); header_cells(table).len() - table.columns.len()];
+        for group in &table.column_groups {
+            group_line.push(group.label.clone());
+            group_line.extend(std::iter::repeat_n(String::new(), group.span.saturating_sub(1)));
+        }
+        lines.push(csv_line(&group_line));
+    }
+
+    lines.push(csv_line(&header_cells(table)));
+    for cells in row_cells(table) {
+        lines.push(csv_line(&cells));
+    }
+    for footnote in &table.footnotes {
+        lines.push(csv_line(std::slice::from_ref(footnote)));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn csv_line(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| csv_escape(cell))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn csv_escape(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn render_markdown(table: &Table) -> String {
+    let mut lines = vec![format!("### {}", table.title), String::new()];
+
+    // Markdown has no column spans, so grouped headers are folded into the column names
+    let mut headers = header_cells(table);
+    if !table.column_groups.is_empty() {
+        let offset = headers.len() - table.columns.len();
+        let mut column = offset;
+        for group in &table.column_groups {
+            for header in headers.iter_mut().skip(column).take(group.span) {
+                *header = format!("{} {}", group.label, header);
+            }
+            column += group.span;
+        }
+    }
+
+    lines.push(markdown_line(&headers));
+    lines.push(
+        markdown_line(
+            &headers
+                .iter()
+                .enumerate()
+                .map(|(i, _)| {
+                    if i < headers.len() - table.columns.len() {
+                        "---".to_string()
+                    } else {
+                        "---:".to_string()
+                    }
+                })
+                .collect::<Vec<String>>()
+        )
+    );
+    for cells in row_cells(table) {
+        lines.push(markdown_line(&cells));
+    }
+
+    if !table.footnotes.is_empty() {
+        lines.push(String::new());
+        for footnote in &table.footnotes {
+            lines.push(format!("_{}_", markdown_escape(footnote)));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn markdown_line(cells: &[String]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .map(|cell| markdown_escape(cell))
+        .collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+// Backslashes go first so the escapes added after them stay intact; a cell can't span lines
+fn markdown_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('*', "\\*")
+        .replace('_', "\\_")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+fn render_html(table: &Table) -> String {
+    let mut html = String::from("<table>\n");
+    html.push_str(&format!("  <caption>{}</caption>\n", html_escape(&table.title)));
+
+    let label_columns = header_cells(table).len() - table.columns.len();
+    html.push_str("  <thead>\n");
+    if !table.column_groups.is_empty() {
+        html.push_str("    <tr>");
+        html.push_str(&format!("<th colspan=\"{}\" rowspan=\"2\"></th>", label_columns));
+        for group in &table.column_groups {
+            html.push_str(
+                &format!("<th colspan=\"{}\">{}</th>", group.span, html_escape(&group.label))
+            );
+        }
+        html.push_str("</tr>\n    <tr>");
+    } else {
+        html.push_str("    <tr>");
+        html.push_str(&format!("<th colspan=\"{}\"></th>", label_columns));
+    }
+    for column in &table.columns {
+        html.push_str(&format!("<th>{}</th>", html_escape(column)));
+    }
+    html.push_str("</tr>\n  </thead>\n  <tbody>\n");
+
+    let grouped = has_groups(table);
+    for (i, row) in table.rows.iter().enumerate() {
+        html.push_str("    <tr>");
+
+        // Consecutive rows of the same group share one spanning cell
+        if grouped {
+            let starts_group = i == 0 || table.rows[i - 1].group != row.group;
+            if starts_group {
+                let span = table.rows[i..]
+                    .iter()
+                    .take_while(|other| other.group == row.group)
+                    .count();
+                html.push_str(
+                    &format!(
+                        "<th rowspan=\"{}\">{}</th>",
+                        span,
+                        html_escape(row.group.as_deref().unwrap_or(""))
+                    )
+                );
+            }
+        }
+
+        html.push_str(&format!("<th>{}</th>", html_escape(&row.label)));
+        for cell in &row.cells {
+            html.push_str(&format!("<td>{}</td>", html_escape(&cell.text)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("  </tbody>\n");
+
+    if !table.footnotes.is_empty() {
+        let colspan = label_columns + table.columns.len();
+        html.push_str("  <tfoot>\n");
+        for footnote in &table.footnotes {
+            html.push_str(
+                &format!(
+                    "    <tr><td colspan=\"{}\">{}</td></tr>\n",
+                    colspan,
+                    html_escape(footnote)
+                )
+            );
+        }
+        html.push_str("  </tfoot>\n");
+    }
+
+    html.push_str("</table>\n");
+    html
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
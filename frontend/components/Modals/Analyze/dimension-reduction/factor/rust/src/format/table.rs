@@ -0,0 +1,220 @@
+use serde::{ Deserialize, Serialize };
+
+use crate::models::config::{ FactorAnalysisConfig, OutputPrecision };
+
+// Default number of decimal places, as in the SPSS output viewer
+pub const DEFAULT_DECIMALS: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FormatOptions {
+    #[serde(rename = "Decimals", default = "default_decimals")]
+    pub decimals: usize,
+    #[serde(rename = "SignificanceStars", default)]
+    pub significance_stars: bool,
+    #[serde(rename = "SuppressBelow", default)]
+    pub suppress_below: Option<f64>,
+    #[serde(rename = "SortBySize", default)]
+    pub sort_by_size: bool,
+    #[serde(rename = "Precision", default)]
+    pub precision: OutputPrecision,
+}
+
+fn default_decimals() -> usize {
+    DEFAULT_DECIMALS
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            decimals: DEFAULT_DECIMALS,
+            significance_stars: false,
+            suppress_below: None,
+            sort_by_size: false,
+            precision: OutputPrecision::default(),
+        }
+    }
+}
+
+impl FormatOptions {
+    // Display options implied by the Options tab of the dialog
+    pub fn from_config(config: &FactorAnalysisConfig) -> Self {
+        FormatOptions {
+            decimals: DEFAULT_DECIMALS,
+            significance_stars: config.descriptives.significance_lvl,
+            suppress_below: if config.options.suppress_values {
+                Some(config.options.suppress_values_num)
+            } else {
+                None
+            },
+            sort_by_size: config.options.sort_size,
+            precision: config.options.output_precision.clone(),
+        }
+    }
+
+    pub fn correlation_decimals(&self) -> usize {
+        self.precision.correlations.unwrap_or(self.decimals)
+    }
+
+    pub fn loading_decimals(&self) -> usize {
+        self.precision.loadings.unwrap_or(self.decimals)
+    }
+
+    pub fn p_value_decimals(&self) -> usize {
+        self.precision.p_values.unwrap_or(self.decimals)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Table {
+    pub title: String,
+    #[serde(rename = "column_groups")]
+    pub column_groups: Vec<ColumnGroup>,
+    pub columns: Vec<String>,
+    pub rows: Vec<TableRow>,
+    pub footnotes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ColumnGroup {
+    pub label: String,
+    pub span: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TableRow {
+    pub group: Option<String>,
+    pub label: String,
+    pub cells: Vec<Cell>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Cell {
+    pub value: Option<f64>,
+    pub text: String,
+    pub suppressed: bool,
+}
+
+impl Table {
+    pub fn new(title: &str, columns: Vec<String>) -> Self {
+        Table {
+            title: title.to_string(),
+            column_groups: Vec::new(),
+            columns,
+            rows: Vec::new(),
+            footnotes: Vec::new(),
+        }
+    }
+
+    pub fn add_row(&mut self, group: Option<&str>, label: &str, cells: Vec<Cell>) {
+        self.rows.push(TableRow {
+            group: group.map(|g| g.to_string()),
+            label: label.to_string(),
+            cells,
+        });
+    }
+}
+
+impl Cell {
+    pub fn number(value: f64, options: &FormatOptions) -> Self {
+        Cell::with_decimals(value, options.decimals, options)
+    }
+
+    pub fn correlation(value: f64, options: &FormatOptions) -> Self {
+        Cell::with_decimals(value, options.correlation_decimals(), options)
+    }
+
+    pub fn p_value(value: f64, options: &FormatOptions) -> Self {
+        let decimals = options.p_value_decimals();
+        let bound = (10f64).powi(-(decimals as i32));
+        if options.precision.p_value_bound && value < bound && decimals > 0 {
+            let bound_text = format_value(bound, decimals);
+            return Cell {
+                value: Some(value),
+                text: format!("<{}", bound_text.trim_start_matches('0')),
+                suppressed: false,
+            };
+        }
+        Cell::with_decimals(value, decimals, options)
+    }
+
+    fn with_decimals(value: f64, decimals: usize, options: &FormatOptions) -> Self {
+        Cell {
+            value: Some(value),
+            text: format_number(value, decimals, &options.precision),
+            suppressed: false,
+        }
+    }
+
+    pub fn integer(value: usize) -> Self {
+        Cell {
+            value: Some(value as f64),
+            text: value.to_string(),
+            suppressed: false,
+        }
+    }
+
+    pub fn text(text: &str) -> Self {
+        Cell {
+            value: None,
+            text: text.to_string(),
+            suppressed: false,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Cell::text("")
+    }
+
+    // Loading cell that is blanked when its absolute value is below the suppression threshold
+    pub fn loading(value: f64, options: &FormatOptions) -> Self {
+        match options.suppress_below {
+            Some(threshold) if value.abs() < threshold => Cell {
+                value: Some(value),
+                text: String::new(),
+                suppressed: true,
+            },
+            _ => Cell::with_decimals(value, options.loading_decimals(), options),
+        }
+    }
+
+    // Correlation cell flagged with * (p < .05) or ** (p < .01)
+    pub fn with_significance(value: f64, p_value: Option<f64>, options: &FormatOptions) -> Self {
+        let mut cell = Cell::correlation(value, options);
+        if options.significance_stars {
+            cell.text.push_str(significance_stars(p_value));
+        }
+        cell
+    }
+}
+
+pub fn significance_stars(p_value: Option<f64>) -> &'static str {
+    match p_value {
+        Some(p) if p < 0.01 => "**",
+        Some(p) if p < 0.05 => "*",
+        _ => "",
+    }
+}
+
+// Fixed decimals, or scientific notation for small values when the precision asks for it
+pub fn format_number(value: f64, decimals: usize, precision: &OutputPrecision) -> String {
+    match precision.scientific_below {
+        Some(threshold) if value != 0.0 && value.is_finite() && value.abs() < threshold => {
+            format!("{:.*e}", decimals, value)
+        }
+        _ => format_value(value, decimals),
+    }
+}
+
+pub fn format_value(value: f64, decimals: usize) -> String {
+    if value.is_nan() {
+        return String::new();
+    }
+    let text = format!("{:.*}", decimals, value);
+
+    // Avoid "-0.000" for values that round to zero
+    if text.starts_with('-') && text[1..].chars().all(|c| c == '0' || c == '.') {
+        text[1..].to_string()
+    } else {
+        text
+    }
+}
@@ -0,0 +1,960 @@
+use std::collections::HashMap;
+
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    result::{
+        Communalities,
+        ComponentScoreCovarianceMatrix,
+        ComponentTransformationMatrix,
+        CorrelationMatrix,
+        DescriptiveStatistic,
+        DiscriminantValidity,
+        EffectSizeSummary,
+        FactorCountComparison,
+        FactorReliability,
+        FactorAnalysisResult,
+        IntermediateMatrices,
+        KMOBartlettsTest,
+        ParallelAnalysis,
+        RobustFit,
+        TotalVarianceComponent,
+        TotalVarianceExplained,
+    },
+};
+
+use super::table::{ Cell, ColumnGroup, FormatOptions, Table };
+
+type SquareMatrix = HashMap<String, HashMap<String, f64>>;
+
+// Every table for the parts of the result that were computed, in SPSS output order
+pub fn build_result_tables(
+    result: &FactorAnalysisResult,
+    config: &FactorAnalysisConfig,
+    var_names: &[String],
+    options: &FormatOptions
+) -> Vec<Table> {
+    let label = factor_label(config);
+    let extraction_note = extraction_footnote(config);
+    let mut tables = Vec::new();
+
+    if let Some(stats) = &result.descriptive_statistics {
+        tables.push(build_descriptive_statistics_table(stats, options));
+    }
+    if let Some(matrix) = &result.correlation_matrix {
+        let title = match (config.extraction.correlation, &config.main.within_groups_var) {
+            (true, None) if config.extraction.polychoric => "Polychoric Correlation Matrix",
+            (true, None) => "Correlation Matrix",
+            (false, None) => "Covariance Matrix",
+            (true, Some(_)) => "Pooled Within-Groups Correlation Matrix",
+            (false, Some(_)) => "Pooled Within-Groups Covariance Matrix",
+        };
+        let include_sig = config.descriptives.significance_lvl && config.extraction.correlation;
+        tables.push(build_correlation_table(title, matrix, var_names, include_sig, options));
+        if let Some(summary) = &matrix.effect_size_summary {
+            tables.push(build_effect_size_table(summary, options));
+        }
+    }
+    if let Some(matrix) = &result.sscp_matrix {
+        tables.push(
+            build_square_table(
+                "Sums of Squares and Cross-products",
+                &[
+                    (Some("Raw SSCP"), &matrix.raw),
+                    (Some("Adjusted SSCP"), &matrix.adjusted),
+                ],
+                var_names,
+                options
+            )
+        );
+    }
+    if let Some(matrix) = &result.inverse_correlation_matrix {
+        tables.push(
+            build_square_table(
+                "Inverse of Correlation Matrix",
+                &[(None, &matrix.inverse_correlations)],
+                var_names,
+                options
+            )
+        );
+    }
+    if let Some(test) = &result.kmo_bartletts_test {
+        tables.push(build_kmo_bartletts_table(test, options));
+    }
+    if let Some(matrices) = &result.anti_image_matrices {
+        let mut table = build_square_table(
+            "Anti-image Matrices",
+            &[
+                (Some("Anti-image Covariance"), &matrices.anti_image_covariance),
+                (Some("Anti-image Correlation"), &matrices.anti_image_correlation),
+            ],
+            var_names,
+            options
+        );
+        table.footnotes.push("a. Measures of Sampling Adequacy(MSA)".to_string());
+        tables.push(table);
+    }
+    if let Some(communalities) = &result.communalities {
+        let mut table = build_communalities_table(communalities, var_names, options);
+        table.footnotes.push(extraction_note.clone());
+        tables.push(table);
+    }
+    if let Some(variance) = &result.total_variance_explained {
+        let mut table = build_total_variance_table(variance, label, options);
+        table.footnotes.push(extraction_note.clone());
+        tables.push(table);
+    }
+    if let Some(matrix) = &result.component_matrix {
+        let mut table = build_loading_table(
+            &format!("{} Matrix", label),
+            &matrix.components,
+            var_names,
+            label,
+            options,
+            true
+        );
+        table.footnotes.push(extraction_note.clone());
+        tables.push(table);
+    }
+    if let Some(reproduced) = &result.reproduced_correlations {
+        let mut table = build_square_table(
+            "Reproduced Correlations",
+            &[
+                (Some("Reproduced Correlation"), &reproduced.reproduced_correlation),
+                (Some("Residual"), &reproduced.residual),
+            ],
+            var_names,
+            options
+        );
+        table.footnotes.push(extraction_note.clone());
+        tables.push(table);
+    }
+    if let Some(matrix) = &result.rotated_component_matrix {
+        let is_oblique = !config.rotation.none && !is_orthogonal(config);
+        let title = if is_oblique {
+            "Pattern Matrix".to_string()
+        } else {
+            format!("Rotated {} Matrix", label)
+        };
+        let mut table = build_loading_table(
+            &title,
+            &matrix.components,
+            var_names,
+            label,
+            options,
+            true
+        );
+        table.footnotes.push(extraction_note.clone());
+        table.footnotes.push(rotation_footnote(config));
+        tables.push(table);
+    }
+    if let Some(matrix) = &result.component_transformation_matrix {
+        let mut table = build_transformation_table(matrix, label, options);
+        table.footnotes.push(extraction_note.clone());
+        table.footnotes.push(rotation_footnote(config));
+        tables.push(table);
+    }
+    if let Some(matrix) = &result.component_score_coefficient_matrix {
+        let mut table = build_loading_table(
+            &format!("{} Score Coefficient Matrix", label),
+            &matrix.components,
+            var_names,
+            label,
+            options,
+            false
+        );
+        table.footnotes.push(extraction_note.clone());
+        tables.push(table);
+    }
+    if let Some(matrix) = &result.component_score_covariance_matrix {
+        let mut table = build_score_covariance_table(matrix, label, options);
+        table.footnotes.push(extraction_note.clone());
+        tables.push(table);
+    }
+    if let Some(reliability) = &result.factor_reliability {
+        tables.push(build_reliability_table(reliability, label, options));
+    }
+    if let Some(validity) = &result.discriminant_validity {
+        tables.push(build_discriminant_validity_table(validity, label, options));
+    }
+    if let Some(analysis) = &result.parallel_analysis {
+        tables.push(build_parallel_analysis_table(analysis, label, options));
+    }
+    if let Some(comparison) = &result.factor_count_comparison {
+        tables.push(build_factor_count_table(comparison, label, options));
+    }
+    if let Some(solution) = &result.weighted_least_squares {
+        let mut table = build_loading_table(
+            "Factor Matrix (WLSMV)",
+            &solution.loadings,
+            var_names,
+            "Factor",
+            options,
+            true
+        );
+        table.footnotes.push(
+            format!(
+                "Extraction Method: Diagonally Weighted Least Squares on the polychoric \
+                 correlations. N = {}.",
+                solution.n
+            )
+        );
+        if !solution.converged {
+            table.footnotes.push(
+                format!("The solution did not converge in {} iterations.", solution.iterations)
+            );
+        }
+        tables.push(table);
+        tables.push(build_robust_fit_table(&solution.fit, options));
+    }
+    if let Some(intermediate) = &result.intermediate_matrices {
+        tables.extend(build_intermediate_tables(intermediate, var_names, label, options));
+    }
+
+    tables
+}
+
+pub fn build_descriptive_statistics_table(
+    stats: &[DescriptiveStatistic],
+    options: &FormatOptions
+) -> Table {
+    let mut table = Table::new(
+        "Descriptive Statistics",
+        vec!["Mean".to_string(), "Std. Deviation".to_string(), "Analysis N".to_string()]
+    );
+
+    for stat in stats {
+        table.add_row(
+            None,
+            &stat.variable,
+            vec![
+                Cell::number(stat.mean, options),
+                Cell::number(stat.std_deviation, options),
+                Cell::integer(stat.analysis_n)
+            ]
+        );
+    }
+
+    table
+}
+
+pub fn build_correlation_table(
+    title: &str,
+    matrix: &CorrelationMatrix,
+    var_names: &[String],
+    include_sig: bool,
+    options: &FormatOptions
+) -> Table {
+    let names = ordered_names(var_names, matrix.correlations.keys());
+    let mut table = Table::new(title, names.clone());
+
+    for row_var in &names {
+        let cells = names
+            .iter()
+            .map(|col_var| {
+                let value = lookup(&matrix.correlations, row_var, col_var);
+                let p_value = if row_var == col_var {
+                    None
+                } else {
+                    matrix.sig_values.get(row_var).and_then(|row| row.get(col_var)).copied()
+                };
+                Cell::with_significance(value, p_value, options)
+            })
+            .collect();
+        table.add_row(Some("Correlation"), row_var, cells);
+    }
+
+    if include_sig {
+        for row_var in &names {
+            let cells = names
+                .iter()
+                .map(|col_var| {
+                    if row_var == col_var {
+                        Cell::empty()
+                    } else {
+                        Cell::p_value(lookup(&matrix.sig_values, row_var, col_var), options)
+                    }
+                })
+                .collect();
+            table.add_row(Some("Sig. (2-tailed)"), row_var, cells);
+        }
+    }
+
+    if options.significance_stars {
+        table.footnotes.push("**. Significant at the 0.01 level (2-tailed).".to_string());
+        table.footnotes.push("*. Significant at the 0.05 level (2-tailed).".to_string());
+    }
+
+    table
+}
+
+// Variable-by-variable table with one block of rows per matrix
+pub fn build_square_table(
+    title: &str,
+    blocks: &[(Option<&str>, &SquareMatrix)],
+    var_names: &[String],
+    options: &FormatOptions
+) -> Table {
+    let names = match blocks.first() {
+        Some((_, matrix)) => ordered_names(var_names, matrix.keys()),
+        None => Vec::new(),
+    };
+    let mut table = Table::new(title, names.clone());
+
+    for (group, matrix) in blocks {
+        for row_var in &names {
+            let cells = names
+                .iter()
+                .map(|col_var| Cell::number(lookup(matrix, row_var, col_var), options))
+                .collect();
+            table.add_row(*group, row_var, cells);
+        }
+    }
+
+    table
+}
+
+pub fn build_kmo_bartletts_table(test: &KMOBartlettsTest, options: &FormatOptions) -> Table {
+    let mut table = Table::new("KMO and Bartlett's Test", vec!["Value".to_string()]);
+
+    table.add_row(
+        None,
+        "Kaiser-Meyer-Olkin Measure of Sampling Adequacy.",
+        vec![Cell::number(test.kaiser_meyer_olkin, options)]
+    );
+    table.add_row(
+        Some("Bartlett's Test of Sphericity"),
+        "Approx. Chi-Square",
+        vec![Cell::number(test.bartletts_test_chi_square, options)]
+    );
+    table.add_row(Some("Bartlett's Test of Sphericity"), "df", vec![Cell::integer(test.df)]);
+    table.add_row(
+        Some("Bartlett's Test of Sphericity"),
+        "Sig.",
+        vec![Cell::p_value(test.significance, options)]
+    );
+
+    table
+}
+
+// Pairs of variables per magnitude band, with the bounds of each band
+pub fn build_effect_size_table(summary: &EffectSizeSummary, options: &FormatOptions) -> Table {
+    let thresholds = &summary.thresholds;
+    let total = summary.negligible + summary.small + summary.medium + summary.large;
+    let mut table = Table::new(
+        "Correlation Effect Sizes",
+        vec!["|r| from".to_string(), "Pairs".to_string(), "Percent".to_string()]
+    );
+
+    let bands = [
+        ("Negligible", 0.0, summary.negligible),
+        ("Small", thresholds.small, summary.small),
+        ("Medium", thresholds.medium, summary.medium),
+        ("Large", thresholds.large, summary.large),
+    ];
+    for (label, lower, count) in bands {
+        let percent = if total > 0 { (100.0 * (count as f64)) / (total as f64) } else { 0.0 };
+        table.add_row(
+            None,
+            label,
+            vec![Cell::number(lower, options), Cell::integer(count), Cell::number(percent, options)]
+        );
+    }
+    table.add_row(None, "Total", vec![Cell::empty(), Cell::integer(total), Cell::empty()]);
+
+    table
+}
+
+pub fn build_communalities_table(
+    communalities: &Communalities,
+    var_names: &[String],
+    options: &FormatOptions
+) -> Table {
+    let names = ordered_names(var_names, communalities.initial.keys());
+    let mut table = Table::new(
+        "Communalities",
+        vec!["Initial".to_string(), "Extraction".to_string()]
+    );
+
+    for var_name in &names {
+        let extraction = match communalities.extraction.get(var_name) {
+            Some(value) => Cell::number(*value, options),
+            None => Cell::empty(),
+        };
+        table.add_row(
+            None,
+            var_name,
+            vec![Cell::number(communalities.initial[var_name], options), extraction]
+        );
+    }
+
+    table
+}
+
+pub fn build_total_variance_table(
+    variance: &TotalVarianceExplained,
+    label: &str,
+    options: &FormatOptions
+) -> Table {
+    let mut sections: Vec<(&str, &Vec<TotalVarianceComponent>)> = vec![
+        ("Initial Eigenvalues", &variance.initial_eigenvalues),
+        ("Extraction Sums of Squared Loadings", &variance.extraction_sums)
+    ];
+    if !variance.rotation_sums.is_empty() {
+        sections.push(("Rotation Sums of Squared Loadings", &variance.rotation_sums));
+    }
+
+    let mut columns = Vec::new();
+    let mut column_groups = Vec::new();
+    for (section, _) in &sections {
+        columns.extend(
+            ["Total", "% of Variance", "Cumulative %"].iter().map(|header| header.to_string())
+        );
+        column_groups.push(ColumnGroup {
+            label: section.to_string(),
+            span: 3,
+        });
+    }
+
+    let mut table = Table::new("Total Variance Explained", columns);
+    table.column_groups = column_groups;
+
+    for i in 0..variance.initial_eigenvalues.len() {
+        let mut cells = Vec::new();
+        for (_, components) in &sections {
+            match components.get(i) {
+                Some(component) => {
+                    cells.push(Cell::number(component.total, options));
+                    cells.push(Cell::number(component.percent_of_variance, options));
+                    cells.push(Cell::number(component.cumulative_percent, options));
+                }
+                None => {
+                    cells.extend([Cell::empty(), Cell::empty(), Cell::empty()]);
+                }
+            }
+        }
+        table.add_row(Some(label), &(i + 1).to_string(), cells);
+    }
+
+    table
+}
+
+// Variables by factors; sorting and blanking small values only apply to loading matrices
+pub fn build_loading_table(
+    title: &str,
+    loadings: &HashMap<String, Vec<f64>>,
+    var_names: &[String],
+    label: &str,
+    options: &FormatOptions,
+    is_loading_matrix: bool
+) -> Table {
+    let mut names = ordered_names(var_names, loadings.keys());
+    let n_factors = names
+        .iter()
+        .map(|name| loadings[name].len())
+        .max()
+        .unwrap_or(0);
+
+    if is_loading_matrix && options.sort_by_size {
+        sort_by_size(&mut names, loadings);
+    }
+
+    let mut table = Table::new(
+        title,
+        (1..=n_factors).map(|j| j.to_string()).collect()
+    );
+    table.column_groups.push(ColumnGroup {
+        label: label.to_string(),
+        span: n_factors,
+    });
+
+    for var_name in &names {
+        let cells = (0..n_factors)
+            .map(|j| {
+                match loadings[var_name].get(j) {
+                    Some(&value) if is_loading_matrix => Cell::loading(value, options),
+                    Some(&value) => Cell::number(value, options),
+                    None => Cell::empty(),
+                }
+            })
+            .collect();
+        table.add_row(None, var_name, cells);
+    }
+
+    table
+}
+
+pub fn build_transformation_table(
+    matrix: &ComponentTransformationMatrix,
+    label: &str,
+    options: &FormatOptions
+) -> Table {
+    let title = format!("{} Transformation Matrix", label);
+    build_factor_square_table(&title, &matrix.components, label, options)
+}
+
+pub fn build_score_covariance_table(
+    matrix: &ComponentScoreCovarianceMatrix,
+    label: &str,
+    options: &FormatOptions
+) -> Table {
+    let title = format!("{} Score Covariance Matrix", label);
+    build_factor_square_table(&title, &matrix.components, label, options)
+}
+
+pub fn build_reliability_table(
+    reliability: &FactorReliability,
+    label: &str,
+    options: &FormatOptions
+) -> Table {
+    let (title, alpha, omega) = if reliability.ordinal {
+        ("Ordinal Reliability", "Ordinal Alpha", "Ordinal Omega")
+    } else {
+        ("Reliability", "Cronbach's Alpha Based on Standardized Items", "Omega")
+    };
+    let mut table = Table::new(
+        title,
+        vec![
+            "N of Items".to_string(),
+            alpha.to_string(),
+            omega.to_string(),
+            "AVE".to_string(),
+            "Composite Reliability".to_string()
+        ]
+    );
+
+    let optional = |value: Option<f64>| {
+        value.map_or_else(Cell::empty, |value| Cell::number(value, options))
+    };
+    for scale in &reliability.factors {
+        table.add_row(
+            Some(label),
+            &scale.factor.to_string(),
+            vec![
+                Cell::integer(scale.items.len()),
+                optional(scale.alpha),
+                optional(scale.omega),
+                optional(scale.ave),
+                optional(scale.composite_reliability)
+            ]
+        );
+        if !scale.reversed_items.is_empty() {
+            table.footnotes.push(
+                format!(
+                    "{} {}: reversed items {}.",
+                    label,
+                    scale.factor,
+                    scale.reversed_items.join(", ")
+                )
+            );
+        }
+    }
+
+    table.footnotes.push(
+        "Items belong to the factor of their largest absolute loading.".to_string()
+    );
+    table
+}
+
+// One row per pair of factors, with the AVE of both next to their squared correlation
+pub fn build_discriminant_validity_table(
+    validity: &DiscriminantValidity,
+    label: &str,
+    options: &FormatOptions
+) -> Table {
+    let mut table = Table::new(
+        "Discriminant Validity",
+        vec![
+            "Correlation".to_string(),
+            "Squared Correlation".to_string(),
+            "AVE (First)".to_string(),
+            "AVE (Second)".to_string(),
+            "Fornell-Larcker".to_string(),
+            "HTMT".to_string()
+        ]
+    );
+
+    let optional = |value: Option<f64>| {
+        value.map_or_else(Cell::empty, |value| Cell::number(value, options))
+    };
+    let ave = |factor: usize| {
+        validity.factors
+            .iter()
+            .find(|validity| validity.factor == factor)
+            .and_then(|validity| validity.ave)
+    };
+    for pair in &validity.pairs {
+        let (first, second) = (ave(pair.factor_one), ave(pair.factor_two));
+        let fornell_larcker = match (first, second) {
+            (Some(first), Some(second)) => {
+                let met = first.min(second) > pair.squared_correlation;
+                Cell::text(if met { "Met" } else { "Not met" })
+            }
+            _ => Cell::empty(),
+        };
+        table.add_row(
+            Some(label),
+            &format!("{} - {}", pair.factor_one, pair.factor_two),
+            vec![
+                Cell::correlation(pair.correlation, options),
+                Cell::number(pair.squared_correlation, options),
+                optional(first),
+                optional(second),
+                fornell_larcker,
+                optional(pair.htmt)
+            ]
+        );
+        if pair.htmt_discriminant == Some(false) {
+            table.footnotes.push(
+                format!(
+                    "{}s {} and {}: HTMT not below {}.",
+                    label,
+                    pair.factor_one,
+                    pair.factor_two,
+                    validity.htmt_threshold
+                )
+            );
+        }
+    }
+
+    table.footnotes.push(
+        "Fornell-Larcker is met when both AVEs exceed the squared correlation.".to_string()
+    );
+    table
+}
+
+pub fn build_parallel_analysis_table(
+    analysis: &ParallelAnalysis,
+    label: &str,
+    options: &FormatOptions
+) -> Table {
+    let mut table = Table::new(
+        "Parallel Analysis",
+        vec![
+            "Observed Eigenvalue".to_string(),
+            "Random Mean".to_string(),
+            format!("Random {} Percentile", analysis.percentile)
+        ]
+    );
+
+    for (i, observed) in analysis.observed_eigenvalues.iter().enumerate() {
+        table.add_row(
+            Some(label),
+            &(i + 1).to_string(),
+            vec![
+                Cell::number(*observed, options),
+                Cell::number(analysis.random_mean_eigenvalues[i], options),
+                Cell::number(analysis.random_percentile_eigenvalues[i], options)
+            ]
+        );
+    }
+
+    table.footnotes.push(
+        format!(
+            "{} random data sets (seed {}); suggested number of factors: {}.",
+            analysis.iterations,
+            analysis.seed,
+            analysis.suggested_factors
+        )
+    );
+
+    table
+}
+
+// One row per number of factors, each tested against the row above it
+pub fn build_factor_count_table(
+    comparison: &FactorCountComparison,
+    label: &str,
+    options: &FormatOptions
+) -> Table {
+    let mut table = Table::new(
+        "Factor Count Comparison",
+        vec![
+            "Chi-Square".to_string(),
+            "df".to_string(),
+            "Sig.".to_string(),
+            "AIC".to_string(),
+            "BIC".to_string(),
+            "RMSEA".to_string(),
+            "Chi-Square Difference".to_string(),
+            "df Difference".to_string(),
+            "Sig. of Difference".to_string()
+        ]
+    );
+
+    let optional = |value: Option<f64>| {
+        value.map_or_else(Cell::empty, |value| Cell::number(value, options))
+    };
+    let degrees = |df: i64| {
+        if df >= 0 { Cell::integer(df as usize) } else { Cell::text(&df.to_string()) }
+    };
+    for model in &comparison.models {
+        let difference = match &model.difference {
+            Some(difference) => {
+                vec![
+                    Cell::number(difference.chi_square, options),
+                    degrees(difference.df),
+                    Cell::p_value(difference.significance, options)
+                ]
+            }
+            None => vec![Cell::empty(), Cell::empty(), Cell::empty()],
+        };
+        let mut cells = vec![
+            optional(model.chi_square),
+            degrees(model.df),
+            model.significance.map_or_else(Cell::empty, |p| Cell::p_value(p, options)),
+            optional(model.aic),
+            optional(model.bic),
+            optional(model.rmsea)
+        ];
+        cells.extend(difference);
+        table.add_row(Some(label), &model.factors.to_string(), cells);
+
+        if let Some(error) = &model.error {
+            table.footnotes.push(
+                format!("The {}-factor solution could not be fitted: {}", model.factors, error)
+            );
+        } else if !model.converged {
+            table.footnotes.push(
+                format!("The {}-factor solution did not converge.", model.factors)
+            );
+        }
+    }
+
+    table.footnotes.push(
+        format!("Extraction Method: Maximum Likelihood. N = {}.", comparison.n)
+    );
+    table
+}
+
+// The scaled and shifted chi-square is the one the fit indices are computed from
+pub fn build_robust_fit_table(fit: &RobustFit, options: &FormatOptions) -> Table {
+    let mut table = Table::new("Model Fit (WLSMV)", vec!["Value".to_string()]);
+
+    let optional = |value: Option<f64>| {
+        value.map_or_else(Cell::empty, |value| Cell::number(value, options))
+    };
+    let p_value = |value: Option<f64>| {
+        value.map_or_else(Cell::empty, |value| Cell::p_value(value, options))
+    };
+    let degrees = if fit.df >= 0 {
+        Cell::integer(fit.df as usize)
+    } else {
+        Cell::text(&fit.df.to_string())
+    };
+
+    let unscaled = Some("Chi-Square Test");
+    table.add_row(unscaled, "Chi-Square", vec![optional(fit.chi_square)]);
+    table.add_row(unscaled, "df", vec![degrees.clone()]);
+    table.add_row(unscaled, "Sig.", vec![p_value(fit.significance)]);
+
+    let scaled = Some("Scaled and Shifted Chi-Square Test");
+    table.add_row(scaled, "Chi-Square", vec![optional(fit.scaled_chi_square)]);
+    table.add_row(scaled, "df", vec![degrees]);
+    table.add_row(scaled, "Sig.", vec![p_value(fit.scaled_significance)]);
+    table.add_row(scaled, "Scaling Factor", vec![optional(fit.scaling_factor)]);
+    table.add_row(scaled, "Shift Parameter", vec![optional(fit.shift_parameter)]);
+
+    let baseline = Some("Baseline Model");
+    table.add_row(baseline, "Chi-Square", vec![optional(fit.baseline_chi_square)]);
+    table.add_row(baseline, "df", vec![Cell::integer(fit.baseline_df)]);
+
+    table.add_row(None, "CFI", vec![optional(fit.cfi)]);
+    table.add_row(None, "TLI", vec![optional(fit.tli)]);
+    table.add_row(None, "RMSEA", vec![optional(fit.rmsea)]);
+    table.add_row(None, "SRMR", vec![Cell::number(fit.srmr, options)]);
+
+    table
+}
+
+// One table per step included
+pub fn build_intermediate_tables(
+    intermediate: &IntermediateMatrices,
+    var_names: &[String],
+    label: &str,
+    options: &FormatOptions
+) -> Vec<Table> {
+    let mut tables = Vec::new();
+
+    if let Some(summary) = &intermediate.centered_data {
+        let mut table = Table::new(
+            "Centered Data",
+            ["Mean", "Centered Mean", "Std. Deviation", "Minimum", "Maximum"]
+                .iter()
+                .map(|header| header.to_string())
+                .collect()
+        );
+        for (j, var_name) in summary.variables.iter().enumerate() {
+            let cells = [
+                summary.means[j],
+                summary.centered_means[j],
+                summary.std_deviations[j],
+                summary.minimums[j],
+                summary.maximums[j],
+            ]
+                .iter()
+                .map(|value| Cell::number(*value, options))
+                .collect();
+            table.add_row(None, var_name, cells);
+        }
+        table.footnotes.push(format!("N = {}.", summary.n));
+        tables.push(table);
+    }
+    if let Some(smc) = &intermediate.smc {
+        let mut table = Table::new("Squared Multiple Correlations", vec!["SMC".to_string()]);
+        for var_name in ordered_names(var_names, smc.keys()) {
+            table.add_row(None, &var_name, vec![Cell::number(smc[&var_name], options)]);
+        }
+        tables.push(table);
+    }
+    if let Some(loadings) = &intermediate.unrotated_loadings {
+        tables.push(
+            build_loading_table(
+                &format!("Unrotated {} Matrix", label),
+                loadings,
+                var_names,
+                label,
+                options,
+                true
+            )
+        );
+    }
+    if let Some(rows) = &intermediate.rotation_matrix {
+        tables.push(build_factor_square_table("Rotation Matrix", rows, label, options));
+    }
+    if let Some(eigenvalues) = &intermediate.residual_eigenvalues {
+        let mut table = Table::new("Residual Eigenvalues", vec!["Eigenvalue".to_string()]);
+        for (i, eigenvalue) in eigenvalues.iter().enumerate() {
+            table.add_row(None, &(i + 1).to_string(), vec![Cell::number(*eigenvalue, options)]);
+        }
+        tables.push(table);
+    }
+
+    tables
+}
+
+fn build_factor_square_table(
+    title: &str,
+    rows: &[Vec<f64>],
+    label: &str,
+    options: &FormatOptions
+) -> Table {
+    let mut table = Table::new(title, (1..=rows.len()).map(|j| j.to_string()).collect());
+
+    for (i, row) in rows.iter().enumerate() {
+        let cells = row
+            .iter()
+            .map(|value| Cell::number(*value, options))
+            .collect();
+        table.add_row(Some(label), &(i + 1).to_string(), cells);
+    }
+
+    table
+}
+
+// SPSS labels the solution "Component" for principal components and "Factor" otherwise
+pub fn factor_label(config: &FactorAnalysisConfig) -> &'static str {
+    match config.extraction.method {
+        ExtractionMethod::PrincipalComponents => "Component",
+        _ => "Factor",
+    }
+}
+
+pub fn extraction_footnote(config: &FactorAnalysisConfig) -> String {
+    let method = match config.extraction.method {
+        ExtractionMethod::PrincipalComponents => "Principal Component Analysis",
+        ExtractionMethod::UnweightedLeastSquares => "Unweighted Least Squares",
+        ExtractionMethod::GeneralizedLeastSquares => "Generalized Least Squares",
+        ExtractionMethod::MaximumLikelihood => "Maximum Likelihood",
+        ExtractionMethod::PrincipalAxisFactoring => "Principal Axis Factoring",
+        ExtractionMethod::AlphaFactoring => "Alpha Factoring",
+        ExtractionMethod::ImageFactoring => "Image Factoring",
+    };
+    match &config.main.within_groups_var {
+        Some(group_var) => {
+            format!(
+                "Extraction Method: {}. Analysis of the pooled within-groups matrix of {}.",
+                method,
+                group_var
+            )
+        }
+        None => format!("Extraction Method: {}.", method),
+    }
+}
+
+pub fn rotation_footnote(config: &FactorAnalysisConfig) -> String {
+    let method = if config.rotation.none {
+        return "Rotation Method: None.".to_string();
+    } else if config.rotation.varimax {
+        "Varimax"
+    } else if config.rotation.quartimax {
+        "Quartimax"
+    } else if config.rotation.equimax {
+        "Equamax"
+    } else if config.rotation.oblimin {
+        "Oblimin"
+    } else if config.rotation.promax {
+        "Promax"
+    } else {
+        "Varimax"
+    };
+    format!("Rotation Method: {} with Kaiser Normalization.", method)
+}
+
+// Same precedence as rotate_factors: oblimin and promax only apply when no orthogonal method is set
+fn is_orthogonal(config: &FactorAnalysisConfig) -> bool {
+    config.rotation.varimax ||
+        config.rotation.quartimax ||
+        config.rotation.equimax ||
+        !(config.rotation.oblimin || config.rotation.promax)
+}
+
+// Analysis order first, then any remaining keys alphabetically
+fn ordered_names<'a>(
+    var_names: &[String],
+    keys: impl Iterator<Item = &'a String>
+) -> Vec<String> {
+    let keys: Vec<&String> = keys.collect();
+    let mut names: Vec<String> = var_names
+        .iter()
+        .filter(|name| keys.contains(name))
+        .cloned()
+        .collect();
+
+    let mut remaining: Vec<String> = keys
+        .into_iter()
+        .filter(|key| !var_names.contains(key))
+        .cloned()
+        .collect();
+    remaining.sort();
+    names.extend(remaining);
+
+    names
+}
+
+// Group variables by their highest loading factor, largest loadings first within a group
+fn sort_by_size(names: &mut [String], loadings: &HashMap<String, Vec<f64>>) {
+    let primary = |name: &String| -> (usize, f64) {
+        loadings[name]
+            .iter()
+            .enumerate()
+            .fold((0, 0.0), |best, (j, value)| {
+                if value.abs() > best.1 { (j, value.abs()) } else { best }
+            })
+    };
+
+    names.sort_by(|a, b| {
+        let (factor_a, size_a) = primary(a);
+        let (factor_b, size_b) = primary(b);
+        factor_a
+            .cmp(&factor_b)
+            .then(size_b.partial_cmp(&size_a).unwrap_or(std::cmp::Ordering::Equal))
+    });
+}
+
+fn lookup(matrix: &SquareMatrix, row: &str, col: &str) -> f64 {
+    matrix
+        .get(row)
+        .and_then(|values| values.get(col))
+        .copied()
+        .unwrap_or(f64::NAN)
+}
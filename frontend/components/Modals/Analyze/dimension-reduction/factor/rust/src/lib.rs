@@ -1,7 +1,25 @@
+pub mod format;
 pub mod models;
 pub mod stats;
 pub mod utils;
 pub mod wasm;
 
+// Analyses run on worker threads and behind server-side handlers, so the configuration, data,
+// results and error collector must stay Send + Sync; this stops compiling if one of them gains
+// an Rc, RefCell or other thread-bound field
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<models::config::FactorAnalysisConfig>();
+    assert_send_sync::<models::data::AnalysisData>();
+    assert_send_sync::<models::result::FactorAnalysisResult>();
+    assert_send_sync::<models::result::StageResult>();
+    assert_send_sync::<models::result::BlockAnalysisResult>();
+    assert_send_sync::<models::result::AnalysisSession>();
+    assert_send_sync::<models::result::ExtractionResult>();
+    assert_send_sync::<models::result::RotationResult>();
+    assert_send_sync::<format::table::Table>();
+    assert_send_sync::<utils::error::ErrorCollector>();
+};
+
 #[cfg(test)]
 pub mod test;
\ No newline at end of file
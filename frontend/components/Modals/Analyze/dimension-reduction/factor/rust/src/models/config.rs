@@ -17,6 +17,51 @@ pub struct MainConfig {
     pub target_var: Option<Vec<String>>,
     #[serde(rename = "ValueTarget")]
     pub value_target: Option<String>,
+    #[serde(rename = "CategoricalVars", default)]
+    pub categorical_vars: Vec<CategoricalVariable>,
+    // Grouping variable whose mean differences are removed: the analysis runs on the pooled
+    // within-groups matrix
+    #[serde(rename = "WithinGroupsVar", default)]
+    pub within_groups_var: Option<String>,
+}
+
+// Non-numeric variable entering the analysis through an encoding of its levels
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CategoricalVariable {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Coding")]
+    pub coding: CategoricalCoding,
+    // Level order; observed levels in natural order when omitted
+    #[serde(rename = "Levels", default)]
+    pub levels: Option<Vec<String>>,
+    // Reference level of dummy and effect coding; the last level when omitted
+    #[serde(rename = "Reference", default)]
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum CategoricalCoding {
+    // Nominal: one 0/1 column per non-reference level
+    #[serde(rename = "Dummy")]
+    Dummy,
+    // Nominal: as Dummy, with the reference level coded -1 in every column
+    #[serde(rename = "Effect")]
+    Effect,
+    // Ordinal: mid-ranks of the cases by level order
+    #[serde(rename = "Rank")]
+    Rank,
+    // Ordinal: position of the level in the level order, starting at 1
+    #[serde(rename = "Ordered")]
+    Ordered,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VariableBlock {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Variables")]
+    pub variables: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -35,6 +80,9 @@ pub struct DescriptivesConfig {
     pub coefficient: bool,
     #[serde(rename = "Inverse")]
     pub inverse: bool,
+    // Raw and mean-adjusted sums of squares and cross-products
+    #[serde(rename = "SSCP", default)]
+    pub sscp: bool,
     #[serde(rename = "SignificanceLvl")]
     pub significance_lvl: bool,
     #[serde(rename = "Reproduced")]
@@ -45,6 +93,51 @@ pub struct DescriptivesConfig {
     pub anti_image: bool,
     #[serde(rename = "KMO")]
     pub kmo: bool,
+    #[serde(rename = "MSA", default)]
+    pub msa: bool,
+    #[serde(rename = "DistanceCorr", default)]
+    pub distance_corr: bool,
+    #[serde(rename = "DistancePermutations", default)]
+    pub distance_permutations: Option<i32>,
+    #[serde(rename = "ICC", default)]
+    pub icc: bool,
+    #[serde(rename = "ICCConfidence", default)]
+    pub icc_confidence: Option<f64>,
+    // Categorical variable splitting the univariate descriptives into groups
+    #[serde(rename = "GroupVar", default)]
+    pub group_var: Option<String>,
+    // Leave-one-out influence of each case on the correlation matrix
+    #[serde(rename = "CaseInfluence", default)]
+    pub case_influence: bool,
+    // Number of most influential cases listed; 10 when not set
+    #[serde(rename = "InfluenceTop", default)]
+    pub influence_top: Option<i32>,
+    // Magnitude band of each correlation, with a count of the pairs per band
+    #[serde(rename = "EffectSizes", default)]
+    pub effect_sizes: bool,
+    // Cohen's (1988) .10, .30 and .50 when not set
+    #[serde(rename = "EffectSizeThresholds", default)]
+    pub effect_size_thresholds: Option<EffectSizeThresholds>,
+    // Alpha, omega, AVE and composite reliability of the items of each factor
+    #[serde(rename = "Reliability", default)]
+    pub reliability: bool,
+    // HTMT ratios and the Fornell-Larcker criterion between the factors
+    #[serde(rename = "DiscriminantValidity", default)]
+    pub discriminant_validity: bool,
+    // Largest HTMT ratio taken as discriminant; .85 when not set
+    #[serde(rename = "HtmtThreshold", default)]
+    pub htmt_threshold: Option<f64>,
+}
+
+// Smallest absolute correlation of each band; anything below Small is negligible
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EffectSizeThresholds {
+    #[serde(rename = "Small")]
+    pub small: f64,
+    #[serde(rename = "Medium")]
+    pub medium: f64,
+    #[serde(rename = "Large")]
+    pub large: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -69,9 +162,62 @@ pub struct ExtractionConfig {
     pub max_factors: Option<i32>,
     #[serde(rename = "MaxIter")]
     pub max_iter: i32,
+    // Largest communality change accepted as converged; 0.001 when not set
+    #[serde(rename = "Converge", default)]
+    pub converge: Option<f64>,
+    // Width of the final bracket of each polychoric correlation search; 1e-8 when not set
+    #[serde(rename = "PolychoricConverge", default)]
+    pub polychoric_converge: Option<f64>,
+    // Bracket reductions allowed in each polychoric correlation search; 100 when not set
+    #[serde(rename = "PolychoricMaxIter", default)]
+    pub polychoric_max_iter: Option<i32>,
+    #[serde(rename = "ParallelAnalysis", default)]
+    pub parallel_analysis: bool,
+    #[serde(rename = "ParallelIter", default)]
+    pub parallel_iter: Option<i32>,
+    #[serde(rename = "ParallelPercentile", default)]
+    pub parallel_percentile: Option<f64>,
+    // Principal coordinates of the cases from Gower distances, for mixed-type variables
+    #[serde(rename = "PCoA", default)]
+    pub pcoa: bool,
+    // Axes kept; 2 when not set
+    #[serde(rename = "PCoAAxes", default)]
+    pub pcoa_axes: Option<i32>,
+    // Classical scaling of the variables on 1 - |r|
+    #[serde(rename = "MDS", default)]
+    pub mds: bool,
+    // 2 or 3; 2 when not set
+    #[serde(rename = "MDSDimensions", default)]
+    pub mds_dimensions: Option<i32>,
+    // Principal components next to a common-factor extraction with the same settings
+    #[serde(rename = "CompareModels", default)]
+    pub compare_models: bool,
+    // Polychoric correlations of the variables as ordered categories in place of the
+    // product-moment correlations; covariances stay product-moment
+    #[serde(rename = "Polychoric", default)]
+    pub polychoric: bool,
+    // Diagonally weighted least squares fit to the polychoric matrix with the scaled and
+    // shifted chi-square of WLSMV; needs Polychoric
+    #[serde(rename = "WLSMV", default)]
+    pub wlsmv: bool,
+    // Maximum likelihood fit of every factor count in FactorCountRange, with chi-square
+    // difference tests between neighbouring counts
+    #[serde(rename = "FactorCountComparison", default)]
+    pub factor_count_comparison: bool,
+    // From one factor to the most the degrees of freedom allow when not set
+    #[serde(rename = "FactorCountRange", default)]
+    pub factor_count_range: Option<FactorCountRange>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FactorCountRange {
+    #[serde(rename = "Min")]
+    pub min: i32,
+    #[serde(rename = "Max")]
+    pub max: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum ExtractionMethod {
     #[serde(rename = "PrincipalComp")]
     PrincipalComponents,
@@ -113,6 +259,9 @@ pub struct RotationConfig {
     pub loading_plot: bool,
     #[serde(rename = "MaxIter")]
     pub max_iter: i32,
+    // Largest change of the rotation criterion accepted as converged; 0.00001 when not set
+    #[serde(rename = "Converge", default)]
+    pub converge: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -127,6 +276,15 @@ pub struct ScoresConfig {
     pub anderson: bool,
     #[serde(rename = "DisplayFactor")]
     pub display_factor: bool,
+    // Expected a posteriori scores from the ordinal response patterns
+    #[serde(rename = "EAP", default)]
+    pub eap: bool,
+    // Quadrature points per factor; 15 when not set
+    #[serde(rename = "EAPPoints", default)]
+    pub eap_points: Option<i32>,
+    // Plausible values drawn for every case from its posterior; none when not set
+    #[serde(rename = "PlausibleValues", default)]
+    pub plausible_values: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -143,4 +301,200 @@ pub struct OptionsConfig {
     pub suppress_values: bool,
     #[serde(rename = "SuppressValuesNum")]
     pub suppress_values_num: f64,
+    #[serde(rename = "Seed", default)]
+    pub seed: Option<u64>,
+    #[serde(rename = "MinPairwiseN", default)]
+    pub min_pairwise_n: Option<usize>,
+    #[serde(rename = "MemoryLimitMb", default)]
+    pub memory_limit_mb: Option<f64>,
+    #[serde(rename = "LargeDataMode", default)]
+    pub large_data_mode: DataMode,
+    #[serde(rename = "LongDuplicates", default)]
+    pub long_duplicates: DuplicateCellPolicy,
+    #[serde(rename = "LongMissing", default)]
+    pub long_missing: MissingCellPolicy,
+    // Single N for tests on a pairwise matrix, such as Bartlett's test
+    #[serde(rename = "PairwiseN", default)]
+    pub pairwise_n: PairwiseNConvention,
+    // Reuse correlation matrices and extractions computed earlier for the same data and settings
+    #[serde(rename = "Cache", default)]
+    pub cache: bool,
+    #[serde(rename = "OutputPrecision", default)]
+    pub output_precision: OutputPrecision,
+    // What to do with pairs of variables correlating +1 or -1
+    #[serde(rename = "PerfectCorrelation", default)]
+    pub perfect_correlation: PerfectCorrelationPolicy,
+    // Steps of the computation to include in the result
+    #[serde(rename = "Intermediate", default)]
+    pub intermediate: IntermediateOutput,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct IntermediateOutput {
+    // Column statistics of the data matrix after centering
+    #[serde(rename = "CenteredData", default)]
+    pub centered_data: bool,
+    // Squared multiple correlations, the initial communalities of the common factor methods
+    #[serde(rename = "SMC", default)]
+    pub smc: bool,
+    #[serde(rename = "UnrotatedLoadings", default)]
+    pub unrotated_loadings: bool,
+    #[serde(rename = "RotationMatrix", default)]
+    pub rotation_matrix: bool,
+    // Eigenvalues of the residual matrix R - LL'
+    #[serde(rename = "ResidualEigenvalues", default)]
+    pub residual_eigenvalues: bool,
+}
+
+// Variables correlating perfectly make the correlation matrix singular
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum PerfectCorrelationPolicy {
+    // Stop the analysis
+    #[serde(rename = "Error")]
+    Error,
+    // Report the pairs and analyze all variables anyway
+    #[default]
+    #[serde(rename = "Warn")]
+    Warn,
+    // Report the pairs and leave out the second variable of each
+    #[serde(rename = "Drop")]
+    Drop,
+}
+
+// Decimal places and notation of the numbers in display tables; unset places follow Decimals
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct OutputPrecision {
+    #[serde(rename = "Correlations", default)]
+    pub correlations: Option<usize>,
+    #[serde(rename = "Loadings", default)]
+    pub loadings: Option<usize>,
+    #[serde(rename = "PValues", default)]
+    pub p_values: Option<usize>,
+    // Non-zero values smaller in magnitude than this are written in scientific notation
+    #[serde(rename = "ScientificBelow", default)]
+    pub scientific_below: Option<f64>,
+    // p-values below the smallest shown digit are written as "<.001" rather than ".000"
+    #[serde(rename = "PValueBound", default)]
+    pub p_value_bound: bool,
+}
+
+// Long-format input with more than one value for the same case and variable
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum DuplicateCellPolicy {
+    #[default]
+    #[serde(rename = "Error")]
+    Error,
+    #[serde(rename = "First")]
+    First,
+    #[serde(rename = "Last")]
+    Last,
+    // Mean of the numeric values; an error if any of them is not a number
+    #[serde(rename = "Mean")]
+    Mean,
+}
+
+// Long-format input without a value for some case and variable
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum MissingCellPolicy {
+    // Leave the cell missing for the missing value options to handle
+    #[default]
+    #[serde(rename = "Missing")]
+    Missing,
+    #[serde(rename = "Error")]
+    Error,
+    #[serde(rename = "DropCase")]
+    DropCase,
+}
+
+// Summary of the pair counts of a pairwise matrix used where a statistic needs one N
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum PairwiseNConvention {
+    // Smallest pair count; conservative, as SPSS does
+    #[default]
+    #[serde(rename = "Minimum")]
+    Minimum,
+    #[serde(rename = "Average")]
+    Average,
+    // Harmonic mean, which weights the sparsest pairs more than the average does
+    #[serde(rename = "Harmonic")]
+    Harmonic,
+}
+
+// Population factor model and sample settings for generated data
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SimulationConfig {
+    // Variables x factors
+    #[serde(rename = "Loadings")]
+    pub loadings: Vec<Vec<f64>>,
+    // Uncorrelated factors when omitted
+    #[serde(rename = "FactorCorrelations", default)]
+    pub factor_correlations: Option<Vec<Vec<f64>>>,
+    // One minus the communalities when omitted
+    #[serde(rename = "Uniquenesses", default)]
+    pub uniquenesses: Option<Vec<f64>>,
+    #[serde(rename = "Cases")]
+    pub cases: usize,
+    #[serde(rename = "Seed", default)]
+    pub seed: Option<u64>,
+    // Ordered categories cut at equal-probability points of the standardized variables
+    #[serde(rename = "Categories", default)]
+    pub categories: Option<usize>,
+    // Cut points on the standardized scale; take precedence over Categories
+    #[serde(rename = "Thresholds", default)]
+    pub thresholds: Option<Vec<f64>>,
+    // x1, x2, ... when omitted
+    #[serde(rename = "VariableNames", default)]
+    pub variable_names: Option<Vec<String>>,
+}
+
+// Hypothesized factor model and recovery targets for sample size planning
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PowerConfig {
+    // Variables x factors
+    #[serde(rename = "Loadings")]
+    pub loadings: Vec<Vec<f64>>,
+    #[serde(rename = "FactorCorrelations", default)]
+    pub factor_correlations: Option<Vec<Vec<f64>>>,
+    #[serde(rename = "Uniquenesses", default)]
+    pub uniquenesses: Option<Vec<f64>>,
+    // Candidate numbers of cases; a default ladder from 50 to 1000 when omitted
+    #[serde(rename = "SampleSizes", default)]
+    pub sample_sizes: Option<Vec<usize>>,
+    #[serde(rename = "Replicates", default)]
+    pub replicates: Option<usize>,
+    // Lowest factor congruence with the population that counts as recovering the structure
+    #[serde(rename = "TargetCongruence", default)]
+    pub target_congruence: Option<f64>,
+    // Share of replicates that has to recover the structure
+    #[serde(rename = "TargetProportion", default)]
+    pub target_proportion: Option<f64>,
+    // Widest acceptable 90% range of the replicate RMSEA values; not a target when omitted
+    #[serde(rename = "RmseaWidth", default)]
+    pub rmsea_width: Option<f64>,
+    #[serde(rename = "Seed", default)]
+    pub seed: Option<u64>,
+}
+
+// How one loading matrix is brought into line with a reference solution
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum AlignmentMethod {
+    // Orthogonal Procrustes: the rotation, possibly improper, closest in least squares
+    #[default]
+    #[serde(rename = "Orthogonal")]
+    Orthogonal,
+    // Only reorder factors and reverse their signs, keeping each factor intact
+    #[serde(rename = "Permutation")]
+    Permutation,
+}
+
+// How the correlation stage reads the cases; InMemory as LargeDataMode disables switching
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum DataMode {
+    #[serde(rename = "InMemory")]
+    InMemory,
+    #[default]
+    #[serde(rename = "Streaming")]
+    Streaming,
+    #[serde(rename = "Subsample")]
+    Subsample,
 }
@@ -7,6 +7,15 @@ pub struct DataRecord {
     pub values: HashMap<String, DataValue>,
 }
 
+// One cell of long-format data: the value of a variable for a case
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LongRecord {
+    #[serde(rename = "caseId")]
+    pub case_id: DataValue,
+    pub variable: String,
+    pub value: DataValue,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum DataValue {
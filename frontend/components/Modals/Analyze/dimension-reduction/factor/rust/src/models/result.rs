@@ -2,18 +2,37 @@ use serde::{ Deserialize, Serialize };
 use std::collections::HashMap;
 use nalgebra::DMatrix;
 
+use crate::models::config::{
+    AlignmentMethod,
+    DataMode,
+    EffectSizeThresholds,
+    ExtractionMethod,
+    FactorAnalysisConfig,
+    PairwiseNConvention,
+};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FactorAnalysisResult {
     #[serde(rename = "descriptive_statistics")]
     pub descriptive_statistics: Option<Vec<DescriptiveStatistic>>,
+    #[serde(rename = "grouped_descriptive_statistics")]
+    pub grouped_descriptive_statistics: Option<GroupedDescriptiveStatistics>,
     #[serde(rename = "scree_plot")]
     pub scree_plot: Option<ScreePlot>,
     #[serde(rename = "correlation_matrix")]
     pub correlation_matrix: Option<CorrelationMatrix>,
+    #[serde(rename = "pairwise_deletion")]
+    pub pairwise_deletion: Option<PairwiseDeletion>,
+    #[serde(rename = "distance_correlation_matrix")]
+    pub distance_correlation_matrix: Option<DistanceCorrelationMatrix>,
+    #[serde(rename = "sscp_matrix")]
+    pub sscp_matrix: Option<SscpMatrix>,
     #[serde(rename = "inverse_correlation_matrix")]
     pub inverse_correlation_matrix: Option<InverseCorrelationMatrix>,
     #[serde(rename = "kmo_bartletts_test")]
     pub kmo_bartletts_test: Option<KMOBartlettsTest>,
+    #[serde(rename = "intraclass_correlation")]
+    pub intraclass_correlation: Option<IntraclassCorrelation>,
     #[serde(rename = "anti_image_matrices")]
     pub anti_image_matrices: Option<AntiImageMatrices>,
     #[serde(rename = "communalities")]
@@ -22,6 +41,20 @@ pub struct FactorAnalysisResult {
     pub total_variance_explained: Option<TotalVarianceExplained>,
     #[serde(rename = "component_matrix")]
     pub component_matrix: Option<ComponentMatrix>,
+    #[serde(rename = "covariance_extraction")]
+    pub covariance_extraction: Option<CovarianceExtraction>,
+    #[serde(rename = "eigen_diagnostics")]
+    pub eigen_diagnostics: Option<EigenDiagnostics>,
+    #[serde(rename = "case_influence")]
+    pub case_influence: Option<CaseInfluence>,
+    #[serde(rename = "principal_coordinates")]
+    pub principal_coordinates: Option<PrincipalCoordinates>,
+    #[serde(rename = "multidimensional_scaling")]
+    pub multidimensional_scaling: Option<MultidimensionalScaling>,
+    #[serde(rename = "component_factor_comparison")]
+    pub component_factor_comparison: Option<ComponentFactorComparison>,
+    #[serde(rename = "eap_scores")]
+    pub eap_scores: Option<EapScores>,
     #[serde(rename = "reproduced_correlations")]
     pub reproduced_correlations: Option<ReproducedCorrelations>,
     #[serde(rename = "rotated_component_matrix")]
@@ -32,6 +65,56 @@ pub struct FactorAnalysisResult {
     pub component_score_coefficient_matrix: Option<ComponentScoreCoefficientMatrix>,
     #[serde(rename = "component_score_covariance_matrix")]
     pub component_score_covariance_matrix: Option<ComponentScoreCovarianceMatrix>,
+    #[serde(rename = "factor_score_determinacy")]
+    pub factor_score_determinacy: Option<FactorScoreDeterminacy>,
+    #[serde(rename = "factor_reliability")]
+    pub factor_reliability: Option<FactorReliability>,
+    #[serde(rename = "discriminant_validity")]
+    pub discriminant_validity: Option<DiscriminantValidity>,
+    #[serde(rename = "parallel_analysis")]
+    pub parallel_analysis: Option<ParallelAnalysis>,
+    #[serde(rename = "factor_count_comparison")]
+    pub factor_count_comparison: Option<FactorCountComparison>,
+    #[serde(rename = "weighted_least_squares")]
+    pub weighted_least_squares: Option<WeightedLeastSquares>,
+    #[serde(rename = "intermediate_matrices")]
+    pub intermediate_matrices: Option<IntermediateMatrices>,
+    pub reproducibility: Option<ReproducibilityLog>,
+    #[serde(rename = "metadata")]
+    pub metadata: Option<AnalysisMetadata>,
+}
+
+// Part of the result emitted as soon as its pipeline stage is done, tagged with the stage
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "stage")]
+pub enum StageResult {
+    #[serde(rename = "descriptives")]
+    Descriptives {
+        #[serde(rename = "descriptive_statistics")]
+        descriptive_statistics: Option<Vec<DescriptiveStatistic>>,
+        #[serde(rename = "grouped_descriptive_statistics")]
+        grouped_descriptive_statistics: Option<GroupedDescriptiveStatistics>,
+    },
+    #[serde(rename = "correlation_matrix")]
+    CorrelationMatrix {
+        #[serde(rename = "correlation_matrix")]
+        correlation_matrix: CorrelationMatrix,
+    },
+    #[serde(rename = "extraction")]
+    Extraction {
+        communalities: Option<Communalities>,
+        #[serde(rename = "total_variance_explained")]
+        total_variance_explained: Option<TotalVarianceExplained>,
+        #[serde(rename = "component_matrix")]
+        component_matrix: Option<ComponentMatrix>,
+    },
+    #[serde(rename = "rotation")]
+    Rotation {
+        #[serde(rename = "rotated_component_matrix")]
+        rotated_component_matrix: Option<RotatedComponentMatrix>,
+        #[serde(rename = "component_transformation_matrix")]
+        component_transformation_matrix: Option<ComponentTransformationMatrix>,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -44,6 +127,19 @@ pub struct DescriptiveStatistic {
     pub analysis_n: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupedDescriptiveStatistics {
+    #[serde(rename = "group_variable")]
+    pub group_variable: String,
+    pub groups: Vec<GroupDescriptiveStatistics>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupDescriptiveStatistics {
+    pub group: String,
+    pub statistics: Vec<DescriptiveStatistic>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScreePlot {
     pub eigenvalues: Vec<f64>,
@@ -56,6 +152,82 @@ pub struct CorrelationMatrix {
     pub correlations: HashMap<String, HashMap<String, f64>>,
     #[serde(rename = "sig_values")]
     pub sig_values: HashMap<String, HashMap<String, f64>>,
+    // Off-diagonal cells only; cells without a correlation are left out
+    #[serde(rename = "effect_sizes", default)]
+    pub effect_sizes: Option<HashMap<String, HashMap<String, EffectSize>>>,
+    #[serde(rename = "effect_size_summary", default)]
+    pub effect_size_summary: Option<EffectSizeSummary>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum EffectSize {
+    #[serde(rename = "negligible")]
+    Negligible,
+    #[serde(rename = "small")]
+    Small,
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "large")]
+    Large,
+}
+
+// Number of variable pairs in each band, each pair counted once
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EffectSizeSummary {
+    pub thresholds: EffectSizeThresholds,
+    pub negligible: usize,
+    pub small: usize,
+    pub medium: usize,
+    pub large: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DistanceCorrelationMatrix {
+    pub correlations: HashMap<String, HashMap<String, f64>>,
+    #[serde(rename = "sig_values")]
+    pub sig_values: Option<HashMap<String, HashMap<String, f64>>>,
+    pub permutations: usize,
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PairwiseDeletion {
+    #[serde(rename = "min_pairwise_n")]
+    pub min_pairwise_n: usize,
+    #[serde(rename = "analysis_n")]
+    pub analysis_n: usize,
+    #[serde(rename = "n_convention", default)]
+    pub n_convention: PairwiseNConvention,
+    #[serde(rename = "pair_counts")]
+    pub pair_counts: HashMap<String, HashMap<String, usize>>,
+    #[serde(rename = "flagged_pairs")]
+    pub flagged_pairs: Vec<FlaggedPair>,
+}
+
+// Variable pair with too few complete cases; its correlation is treated as missing
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlaggedPair {
+    #[serde(rename = "variable_a")]
+    pub variable_a: String,
+    #[serde(rename = "variable_b")]
+    pub variable_b: String,
+    pub n: usize,
+}
+
+// Sums of squares and cross-products about zero (raw) and about the means (adjusted)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SscpMatrix {
+    pub raw: HashMap<String, HashMap<String, f64>>,
+    pub adjusted: HashMap<String, HashMap<String, f64>>,
+    pub n: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StandardizedCovariance {
+    #[serde(rename = "correlation_matrix")]
+    pub correlation_matrix: Vec<Vec<f64>>,
+    #[serde(rename = "std_deviations")]
+    pub std_deviations: Vec<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -71,6 +243,47 @@ pub struct KMOBartlettsTest {
     pub bartletts_test_chi_square: f64,
     pub df: usize,
     pub significance: f64,
+    // Cases Bartlett's test is based on
+    #[serde(default)]
+    pub n: Option<usize>,
+    // How n was derived from the pair counts when the matrix is pairwise
+    #[serde(rename = "n_convention", default)]
+    pub n_convention: Option<PairwiseNConvention>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntraclassCorrelation {
+    #[serde(rename = "n_subjects")]
+    pub n_subjects: usize,
+    #[serde(rename = "n_raters")]
+    pub n_raters: usize,
+    #[serde(rename = "confidence_level")]
+    pub confidence_level: f64,
+    #[serde(rename = "ms_rows")]
+    pub ms_rows: f64,
+    #[serde(rename = "ms_columns")]
+    pub ms_columns: f64,
+    #[serde(rename = "ms_within")]
+    pub ms_within: f64,
+    #[serde(rename = "ms_error")]
+    pub ms_error: f64,
+    pub estimates: Vec<IccEstimate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IccEstimate {
+    // ICC1, ICC2, ICC3 for single measures; ICC1k, ICC2k, ICC3k for average measures
+    pub model: String,
+    pub value: f64,
+    #[serde(rename = "f_value")]
+    pub f_value: f64,
+    pub df1: f64,
+    pub df2: f64,
+    pub significance: f64,
+    #[serde(rename = "lower_bound")]
+    pub lower_bound: f64,
+    #[serde(rename = "upper_bound")]
+    pub upper_bound: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -79,6 +292,9 @@ pub struct AntiImageMatrices {
     pub anti_image_covariance: HashMap<String, HashMap<String, f64>>,
     #[serde(rename = "anti_image_correlation")]
     pub anti_image_correlation: HashMap<String, HashMap<String, f64>>,
+    // Measures of sampling adequacy, also on the anti-image correlation diagonal
+    #[serde(default)]
+    pub msa: Option<HashMap<String, f64>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -111,6 +327,27 @@ pub struct ComponentMatrix {
     pub components: HashMap<String, Vec<f64>>,
 }
 
+// Solution of the covariance matrix, as SPSS reports it under "Analyze: Covariance matrix"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CovarianceExtraction {
+    #[serde(rename = "raw_loadings")]
+    pub raw_loadings: HashMap<String, Vec<f64>>,
+    #[serde(rename = "rescaled_loadings")]
+    pub rescaled_loadings: HashMap<String, Vec<f64>>,
+    #[serde(rename = "raw_communalities")]
+    pub raw_communalities: Communalities,
+    #[serde(rename = "rescaled_communalities")]
+    pub rescaled_communalities: Communalities,
+    #[serde(rename = "raw_variance")]
+    pub raw_variance: TotalVarianceExplained,
+    #[serde(rename = "rescaled_extraction_sums")]
+    pub rescaled_extraction_sums: Vec<TotalVarianceComponent>,
+    #[serde(rename = "n_factors")]
+    pub n_factors: usize,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ReproducedCorrelations {
     pub reproduced_correlation: HashMap<String, HashMap<String, f64>>,
@@ -127,6 +364,204 @@ pub struct ComponentTransformationMatrix {
     pub components: Vec<Vec<f64>>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EapScores {
+    // Quadrature points per factor
+    #[serde(rename = "n_points")]
+    pub n_points: usize,
+    // Share of the score variance not due to posterior uncertainty, per factor
+    #[serde(rename = "marginal_reliability")]
+    pub marginal_reliability: Vec<f64>,
+    pub cases: Vec<EapCaseScore>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EapCaseScore {
+    #[serde(rename = "case_number")]
+    pub case_number: usize,
+    pub scores: Vec<f64>,
+    #[serde(rename = "posterior_sd")]
+    pub posterior_sd: Vec<f64>,
+    // One vector of factor values per draw; empty unless PlausibleValues is set
+    #[serde(rename = "plausible_values", default)]
+    pub plausible_values: Vec<Vec<f64>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PrincipalCoordinates {
+    #[serde(rename = "n_cases")]
+    pub n_cases: usize,
+    // How each variable entered the Gower distance
+    pub variables: Vec<GowerVariable>,
+    // Eigenvalue and share of the positive eigenvalues for each retained axis
+    pub axes: Vec<TotalVarianceComponent>,
+    pub coordinates: Vec<CaseCoordinates>,
+    #[serde(rename = "negative_eigenvalues")]
+    pub negative_eigenvalues: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GowerVariable {
+    pub name: String,
+    pub kind: GowerVariableKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum GowerVariableKind {
+    // Absolute difference divided by the range
+    #[serde(rename = "Numeric")]
+    Numeric,
+    // As Numeric, on the positions of the levels in their order
+    #[serde(rename = "Ordinal")]
+    Ordinal,
+    // 0 when the levels match, 1 otherwise
+    #[serde(rename = "Nominal")]
+    Nominal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaseCoordinates {
+    #[serde(rename = "case_number")]
+    pub case_number: usize,
+    pub coordinates: Vec<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MultidimensionalScaling {
+    pub distance: MdsDistance,
+    pub dimensions: usize,
+    // Eigenvalue of the double-centered squared distances for each dimension
+    pub eigenvalues: Vec<f64>,
+    pub coordinates: Vec<VariableCoordinates>,
+    // Kruskal's stress-1 of the distances between the fitted points
+    pub stress: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum MdsDistance {
+    #[serde(rename = "OneMinusAbsoluteCorrelation")]
+    OneMinusAbsoluteCorrelation,
+    // Distances passed in directly rather than derived from the data
+    #[serde(rename = "Supplied")]
+    Supplied,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VariableCoordinates {
+    pub variable: String,
+    pub coordinates: Vec<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaseInfluence {
+    #[serde(rename = "n_cases")]
+    pub n_cases: usize,
+    #[serde(rename = "largest_eigenvalue")]
+    pub largest_eigenvalue: f64,
+    // Most influential cases first, by the absolute change in the largest eigenvalue
+    pub cases: Vec<CaseInfluenceEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaseInfluenceEntry {
+    // 1-based position of the case in the data set
+    #[serde(rename = "case_number")]
+    pub case_number: usize,
+    // Largest eigenvalue with all cases minus the largest eigenvalue without this case
+    #[serde(rename = "eigenvalue_change")]
+    pub eigenvalue_change: f64,
+    // Square root of the summed squared changes of the off-diagonal correlations
+    #[serde(rename = "matrix_change")]
+    pub matrix_change: f64,
+    #[serde(rename = "max_correlation_change")]
+    pub max_correlation_change: f64,
+    #[serde(rename = "max_change_pair")]
+    pub max_change_pair: (String, String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum EigenSolver {
+    #[serde(rename = "ImplicitQR")]
+    ImplicitQR,
+    // Fallback when QR does not converge
+    #[serde(rename = "Jacobi")]
+    Jacobi,
+    // Jacobi stopped at its sweep limit; the decomposition is the best one reached
+    #[serde(rename = "JacobiUnconverged")]
+    JacobiUnconverged,
+}
+
+// Canonical correlation analysis of two variable sets; matrices have a row per variable and a
+// column per canonical function
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CanonicalCorrelation {
+    pub n: usize,
+    #[serde(rename = "set_one")]
+    pub set_one: CanonicalSet,
+    #[serde(rename = "set_two")]
+    pub set_two: CanonicalSet,
+    pub functions: Vec<CanonicalFunction>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CanonicalSet {
+    pub variables: Vec<String>,
+    #[serde(rename = "standardized_coefficients")]
+    pub standardized_coefficients: Vec<Vec<f64>>,
+    #[serde(rename = "raw_coefficients")]
+    pub raw_coefficients: Vec<Vec<f64>>,
+    // Correlations of the variables with the variates of their own set
+    pub loadings: Vec<Vec<f64>>,
+    // Correlations of the variables with the variates of the other set
+    #[serde(rename = "cross_loadings")]
+    pub cross_loadings: Vec<Vec<f64>>,
+}
+
+// Wilks' lambda and its test cover this function and all later ones
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CanonicalFunction {
+    pub function: usize,
+    pub correlation: f64,
+    pub eigenvalue: f64,
+    #[serde(rename = "wilks_lambda")]
+    pub wilks_lambda: f64,
+    #[serde(rename = "chi_square")]
+    pub chi_square: f64,
+    pub df: usize,
+    pub significance: f64,
+}
+
+// Full spectrum of a symmetric matrix, largest eigenvalue first. eigenvectors[i][k] is the
+// weight of variables[i] in the k-th unit eigenvector.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EigenDecomposition {
+    pub variables: Vec<String>,
+    pub eigenvalues: Vec<f64>,
+    pub eigenvectors: Vec<Vec<f64>>,
+    pub solver: EigenSolver,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EigenDiagnostics {
+    pub solver: EigenSolver,
+    // Largest |a_ij - a_ji| before the matrix was averaged with its transpose
+    pub asymmetry: f64,
+    pub symmetrized: bool,
+    #[serde(rename = "smallest_eigenvalue")]
+    pub smallest_eigenvalue: f64,
+    #[serde(rename = "positive_definite")]
+    pub positive_definite: bool,
+    #[serde(rename = "negative_eigenvalues")]
+    pub negative_eigenvalues: Vec<NegativeEigenvalue>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NegativeEigenvalue {
+    // 1-based position in descending order
+    pub position: usize,
+    pub value: f64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ComponentScoreCoefficientMatrix {
     pub components: HashMap<String, Vec<f64>>,
@@ -137,6 +572,586 @@ pub struct ComponentScoreCovarianceMatrix {
     pub components: Vec<Vec<f64>>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FactorScoreDeterminacy {
+    pub method: String,
+    pub factors: Vec<ScoreDeterminacy>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScoreDeterminacy {
+    pub factor: usize,
+    pub determinacy: f64,
+    pub reliability: f64,
+    #[serde(rename = "minimum_correlation")]
+    pub minimum_correlation: f64,
+    #[serde(rename = "validity_coefficients")]
+    pub validity_coefficients: Vec<f64>,
+}
+
+// Alpha, omega, AVE and composite reliability of the items of each factor; ordinal when the
+// matrix is polychoric
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FactorReliability {
+    pub ordinal: bool,
+    pub factors: Vec<ScaleReliability>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScaleReliability {
+    pub factor: usize,
+    pub items: Vec<String>,
+    // Items loading negatively, reversed in the scale
+    #[serde(rename = "reversed_items")]
+    pub reversed_items: Vec<String>,
+    // None below two items
+    pub alpha: Option<f64>,
+    pub omega: Option<f64>,
+    // Average variance extracted and composite reliability of the loadings; None without items
+    pub ave: Option<f64>,
+    #[serde(rename = "composite_reliability")]
+    pub composite_reliability: Option<f64>,
+}
+
+// Discriminant validity of the factors, with items assigned as for the reliability
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiscriminantValidity {
+    #[serde(rename = "htmt_threshold")]
+    pub htmt_threshold: f64,
+    pub factors: Vec<FactorValidity>,
+    pub pairs: Vec<FactorPairValidity>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FactorValidity {
+    pub factor: usize,
+    pub items: Vec<String>,
+    // Average variance extracted: the mean squared loading of the items; None without items
+    pub ave: Option<f64>,
+    #[serde(rename = "max_squared_correlation")]
+    pub max_squared_correlation: f64,
+    // AVE above the squared correlation with every other factor
+    #[serde(rename = "fornell_larcker")]
+    pub fornell_larcker: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FactorPairValidity {
+    #[serde(rename = "factor_one")]
+    pub factor_one: usize,
+    #[serde(rename = "factor_two")]
+    pub factor_two: usize,
+    // Zero for orthogonal solutions
+    pub correlation: f64,
+    #[serde(rename = "squared_correlation")]
+    pub squared_correlation: f64,
+    // Heterotrait-monotrait ratio; None when a factor has fewer than two items
+    pub htmt: Option<f64>,
+    #[serde(rename = "htmt_discriminant")]
+    pub htmt_discriminant: Option<bool>,
+}
+
+// Maximum likelihood solutions for a range of factor counts, fewest factors first
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FactorCountComparison {
+    pub n: usize,
+    pub models: Vec<FactorCountModel>,
+}
+
+// Fit statistics are None when the model has no degrees of freedom or R isn't positive
+// definite
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FactorCountModel {
+    pub factors: usize,
+    #[serde(rename = "chi_square")]
+    pub chi_square: Option<f64>,
+    pub df: i64,
+    pub significance: Option<f64>,
+    pub aic: Option<f64>,
+    pub bic: Option<f64>,
+    pub rmsea: Option<f64>,
+    pub converged: bool,
+    // Against the model with one factor fewer; None for the first model
+    pub difference: Option<ChiSquareDifference>,
+    // Why the extraction failed; the fit statistics are then None
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChiSquareDifference {
+    #[serde(rename = "chi_square")]
+    pub chi_square: f64,
+    pub df: i64,
+    pub significance: f64,
+}
+
+// Steps of the computation selected under Intermediate; unselected ones are None
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntermediateMatrices {
+    #[serde(rename = "centered_data")]
+    pub centered_data: Option<CenteredDataSummary>,
+    pub smc: Option<HashMap<String, f64>>,
+    #[serde(rename = "unrotated_loadings")]
+    pub unrotated_loadings: Option<HashMap<String, Vec<f64>>>,
+    // None without rotation
+    #[serde(rename = "rotation_matrix")]
+    pub rotation_matrix: Option<Vec<Vec<f64>>>,
+    #[serde(rename = "residual_eigenvalues")]
+    pub residual_eigenvalues: Option<Vec<f64>>,
+}
+
+// Column statistics of the data before and after centering; the centered means are zero up to
+// rounding
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CenteredDataSummary {
+    pub n: usize,
+    pub variables: Vec<String>,
+    pub means: Vec<f64>,
+    #[serde(rename = "centered_means")]
+    pub centered_means: Vec<f64>,
+    #[serde(rename = "std_deviations")]
+    pub std_deviations: Vec<f64>,
+    pub minimums: Vec<f64>,
+    pub maximums: Vec<f64>,
+}
+
+// Diagonally weighted least squares solution of the polychoric matrix, unrotated
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WeightedLeastSquares {
+    pub n: usize,
+    #[serde(rename = "n_factors")]
+    pub n_factors: usize,
+    pub thresholds: HashMap<String, Vec<f64>>,
+    pub loadings: HashMap<String, Vec<f64>>,
+    pub communalities: HashMap<String, f64>,
+    pub iterations: usize,
+    pub converged: bool,
+    pub fit: RobustFit,
+}
+
+// Chi-square of the weighted discrepancy and its scaled and shifted version, with the fit
+// indices from the scaled one. Tests are None without degrees of freedom.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RobustFit {
+    pub df: i64,
+    #[serde(rename = "chi_square")]
+    pub chi_square: Option<f64>,
+    pub significance: Option<f64>,
+    #[serde(rename = "scaled_chi_square")]
+    pub scaled_chi_square: Option<f64>,
+    #[serde(rename = "scaled_significance")]
+    pub scaled_significance: Option<f64>,
+    #[serde(rename = "scaling_factor")]
+    pub scaling_factor: Option<f64>,
+    #[serde(rename = "shift_parameter")]
+    pub shift_parameter: Option<f64>,
+    #[serde(rename = "baseline_chi_square")]
+    pub baseline_chi_square: Option<f64>,
+    #[serde(rename = "baseline_df")]
+    pub baseline_df: usize,
+    pub cfi: Option<f64>,
+    pub tli: Option<f64>,
+    pub rmsea: Option<f64>,
+    pub srmr: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParallelAnalysis {
+    #[serde(rename = "observed_eigenvalues")]
+    pub observed_eigenvalues: Vec<f64>,
+    #[serde(rename = "random_mean_eigenvalues")]
+    pub random_mean_eigenvalues: Vec<f64>,
+    #[serde(rename = "random_percentile_eigenvalues")]
+    pub random_percentile_eigenvalues: Vec<f64>,
+    pub percentile: f64,
+    pub iterations: usize,
+    #[serde(rename = "suggested_factors")]
+    pub suggested_factors: usize,
+    pub seed: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PairDetail {
+    #[serde(rename = "variable_x")]
+    pub variable_x: String,
+    #[serde(rename = "variable_y")]
+    pub variable_y: String,
+    pub n: usize,
+    pub correlation: f64,
+    pub significance: f64,
+    #[serde(rename = "confidence_level")]
+    pub confidence_level: f64,
+    #[serde(rename = "lower_bound")]
+    pub lower_bound: Option<f64>,
+    #[serde(rename = "upper_bound")]
+    pub upper_bound: Option<f64>,
+    // Regression line of the second variable on the first
+    pub intercept: f64,
+    pub slope: f64,
+    pub points: Vec<PairPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PairPoint {
+    #[serde(rename = "case_number")]
+    pub case_number: usize,
+    pub x: f64,
+    pub y: f64,
+    // A value of the pair was replaced by its mean
+    pub imputed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SampleSizePlan {
+    #[serde(rename = "n_variables")]
+    pub n_variables: usize,
+    #[serde(rename = "n_factors")]
+    pub n_factors: usize,
+    pub method: ExtractionMethod,
+    pub replicates: usize,
+    #[serde(rename = "target_congruence")]
+    pub target_congruence: f64,
+    #[serde(rename = "target_proportion")]
+    pub target_proportion: f64,
+    #[serde(rename = "rmsea_width")]
+    pub rmsea_width: Option<f64>,
+    #[serde(rename = "sample_sizes")]
+    pub sample_sizes: Vec<SampleSizeRecovery>,
+    // Smallest candidate meeting every target; None when none of them does
+    #[serde(rename = "recommended_cases")]
+    pub recommended_cases: Option<usize>,
+    pub seed: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SampleSizeRecovery {
+    pub cases: usize,
+    // Replicates whose extraction failed; they count as not recovered
+    pub failed: usize,
+    // Mean over replicates of the lowest factor congruence
+    #[serde(rename = "mean_congruence")]
+    pub mean_congruence: f64,
+    #[serde(rename = "proportion_recovered")]
+    pub proportion_recovered: f64,
+    #[serde(rename = "rmsea_mean")]
+    pub rmsea_mean: Option<f64>,
+    // 5th and 95th percentiles of the replicate RMSEA values
+    #[serde(rename = "rmsea_lower")]
+    pub rmsea_lower: Option<f64>,
+    #[serde(rename = "rmsea_upper")]
+    pub rmsea_upper: Option<f64>,
+    #[serde(rename = "meets_target")]
+    pub meets_target: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReproducibilityLog {
+    pub syntax: String,
+    #[serde(rename = "effective_config")]
+    pub effective_config: String,
+}
+
+// Field names follow the object returned by R's psych::fa
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PsychSolution {
+    pub factors: usize,
+    #[serde(rename = "n.obs")]
+    pub n_obs: usize,
+    pub fm: String,
+    pub rotation: String,
+    pub rownames: Vec<String>,
+    pub colnames: Vec<String>,
+    pub loadings: Vec<Vec<f64>>,
+    #[serde(rename = "Phi")]
+    pub phi: Option<Vec<Vec<f64>>>,
+    #[serde(rename = "Structure")]
+    pub structure: Vec<Vec<f64>>,
+    pub communality: Vec<f64>,
+    pub uniquenesses: Vec<f64>,
+    pub complexity: Vec<f64>,
+    #[serde(rename = "e.values")]
+    pub e_values: Vec<f64>,
+    pub values: Vec<f64>,
+    #[serde(rename = "Vaccounted")]
+    pub vaccounted: PsychVarianceAccounted,
+    pub residual: Vec<Vec<f64>>,
+    pub dof: i64,
+    pub objective: Option<f64>,
+    #[serde(rename = "STATISTIC")]
+    pub statistic: Option<f64>,
+    #[serde(rename = "PVAL")]
+    pub pval: Option<f64>,
+    #[serde(rename = "null.model")]
+    pub null_model: Option<f64>,
+    #[serde(rename = "null.dof")]
+    pub null_dof: usize,
+    #[serde(rename = "null.chisq")]
+    pub null_chisq: Option<f64>,
+    #[serde(rename = "TLI")]
+    pub tli: Option<f64>,
+    #[serde(rename = "RMSEA")]
+    pub rmsea: Option<f64>,
+    #[serde(rename = "BIC")]
+    pub bic: Option<f64>,
+    pub rms: f64,
+    pub crms: Option<f64>,
+    pub fit: f64,
+    #[serde(rename = "fit.off")]
+    pub fit_off: f64,
+    pub weights: Option<Vec<Vec<f64>>>,
+    #[serde(rename = "R2")]
+    pub r2: Option<Vec<f64>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PsychVarianceAccounted {
+    #[serde(rename = "SS loadings")]
+    pub ss_loadings: Vec<f64>,
+    #[serde(rename = "Proportion Var")]
+    pub proportion_var: Vec<f64>,
+    #[serde(rename = "Cumulative Var")]
+    pub cumulative_var: Vec<f64>,
+    #[serde(rename = "Proportion Explained")]
+    pub proportion_explained: Vec<f64>,
+    #[serde(rename = "Cumulative Proportion")]
+    pub cumulative_proportion: Vec<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalysisMetadata {
+    #[serde(rename = "library_version")]
+    pub library_version: String,
+    pub seed: Option<u64>,
+    #[serde(rename = "stage_timings")]
+    pub stage_timings: Vec<StageTiming>,
+    #[serde(rename = "total_time_ms")]
+    pub total_time_ms: f64,
+    pub iterations: Vec<IterationSummary>,
+    #[serde(rename = "data_mode")]
+    pub data_mode: Option<DataModeReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DataModeReport {
+    pub mode: DataMode,
+    #[serde(rename = "estimated_bytes")]
+    pub estimated_bytes: usize,
+    #[serde(rename = "memory_limit_bytes")]
+    pub memory_limit_bytes: Option<usize>,
+    #[serde(rename = "cases_available")]
+    pub cases_available: usize,
+    #[serde(rename = "cases_used")]
+    pub cases_used: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StageTiming {
+    pub stage: String,
+    #[serde(rename = "duration_ms")]
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IterationSummary {
+    pub routine: String,
+    pub iterations: usize,
+    #[serde(rename = "max_iterations")]
+    pub max_iterations: usize,
+    pub converged: bool,
+    #[serde(default)]
+    pub tolerance: Option<f64>,
+    // Change at the last iteration, on the same scale as the tolerance
+    #[serde(rename = "achieved_tolerance", default)]
+    pub achieved_tolerance: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SolutionComparison {
+    #[serde(rename = "congruence_matrix")]
+    pub congruence_matrix: Vec<Vec<f64>>,
+    #[serde(rename = "factor_matches")]
+    pub factor_matches: Vec<FactorMatch>,
+    #[serde(rename = "unmatched_factors_a")]
+    pub unmatched_factors_a: Vec<usize>,
+    #[serde(rename = "unmatched_factors_b")]
+    pub unmatched_factors_b: Vec<usize>,
+    #[serde(rename = "primary_factor_changes")]
+    pub primary_factor_changes: Vec<PrimaryFactorChange>,
+    #[serde(rename = "variance_changes")]
+    pub variance_changes: Vec<VarianceChange>,
+    #[serde(rename = "cumulative_percent_a")]
+    pub cumulative_percent_a: f64,
+    #[serde(rename = "cumulative_percent_b")]
+    pub cumulative_percent_b: f64,
+    #[serde(rename = "fit_a")]
+    pub fit_a: Option<ResidualFit>,
+    #[serde(rename = "fit_b")]
+    pub fit_b: Option<ResidualFit>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FactorAlignment {
+    pub method: AlignmentMethod,
+    // loadings * transformation = aligned loadings
+    pub transformation: Vec<Vec<f64>>,
+    #[serde(rename = "aligned_loadings")]
+    pub aligned_loadings: HashMap<String, Vec<f64>>,
+    // 1-based factor of the aligned solution that supplies each reference factor
+    #[serde(rename = "factor_order")]
+    pub factor_order: Vec<usize>,
+    pub reflected: Vec<bool>,
+    // Tucker congruence of each aligned factor with its reference factor
+    pub congruences: Vec<f64>,
+    #[serde(rename = "residual_sum_of_squares")]
+    pub residual_sum_of_squares: f64,
+    pub rmsd: f64,
+}
+
+// Where principal components and a common-factor extraction of the same matrix diverge
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ComponentFactorComparison {
+    #[serde(rename = "factor_method")]
+    pub factor_method: ExtractionMethod,
+    #[serde(rename = "n_factors")]
+    pub n_factors: usize,
+    pub factors: Vec<ModelFactorComparison>,
+    pub variables: Vec<ModelVariableComparison>,
+    #[serde(rename = "total_component_percent")]
+    pub total_component_percent: f64,
+    #[serde(rename = "total_factor_percent")]
+    pub total_factor_percent: f64,
+    #[serde(rename = "mean_absolute_loading_difference")]
+    pub mean_absolute_loading_difference: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelFactorComparison {
+    pub component: usize,
+    pub factor: usize,
+    pub congruence: f64,
+    #[serde(rename = "component_percent")]
+    pub component_percent: f64,
+    #[serde(rename = "factor_percent")]
+    pub factor_percent: f64,
+    // Congruence of at least 0.95
+    pub equal: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelVariableComparison {
+    pub variable: String,
+    #[serde(rename = "component_communality")]
+    pub component_communality: f64,
+    #[serde(rename = "factor_communality")]
+    pub factor_communality: f64,
+    #[serde(rename = "communality_difference")]
+    pub communality_difference: f64,
+    #[serde(rename = "component_loadings")]
+    pub component_loadings: Vec<f64>,
+    // Reordered and reflected to follow the components
+    #[serde(rename = "factor_loadings")]
+    pub factor_loadings: Vec<f64>,
+    #[serde(rename = "max_loading_difference")]
+    pub max_loading_difference: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FactorMatch {
+    #[serde(rename = "factor_a")]
+    pub factor_a: usize,
+    #[serde(rename = "factor_b")]
+    pub factor_b: usize,
+    pub congruence: f64,
+    #[serde(rename = "sign_reversed")]
+    pub sign_reversed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PrimaryFactorChange {
+    pub variable: String,
+    #[serde(rename = "factor_a")]
+    pub factor_a: usize,
+    #[serde(rename = "factor_b")]
+    pub factor_b: usize,
+    #[serde(rename = "loading_a")]
+    pub loading_a: f64,
+    #[serde(rename = "loading_b")]
+    pub loading_b: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VarianceChange {
+    #[serde(rename = "factor_a")]
+    pub factor_a: usize,
+    #[serde(rename = "factor_b")]
+    pub factor_b: usize,
+    #[serde(rename = "percent_a")]
+    pub percent_a: f64,
+    #[serde(rename = "percent_b")]
+    pub percent_b: f64,
+    pub difference: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResidualFit {
+    pub rmsr: f64,
+    #[serde(rename = "large_residuals")]
+    pub large_residuals: usize,
+    #[serde(rename = "large_residual_percent")]
+    pub large_residual_percent: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlockAnalysisResult {
+    pub block: String,
+    pub variables: Vec<String>,
+    pub result: Option<FactorAnalysisResult>,
+    pub error: Option<String>,
+    // Summaries of the messages collected while analyzing the block
+    #[serde(default)]
+    pub errors: Option<String>,
+    #[serde(default)]
+    pub warnings: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalysisSession {
+    #[serde(rename = "format_version")]
+    pub format_version: u32,
+    #[serde(rename = "library_version")]
+    pub library_version: String,
+    pub config: FactorAnalysisConfig,
+    #[serde(rename = "var_names")]
+    pub var_names: Vec<String>,
+    #[serde(rename = "n_cases")]
+    pub n_cases: usize,
+    pub means: Vec<f64>,
+    #[serde(rename = "std_deviations")]
+    pub std_deviations: Vec<f64>,
+    #[serde(rename = "correlation_matrix")]
+    pub correlation_matrix: Vec<Vec<f64>>,
+    pub extraction: SessionExtraction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionExtraction {
+    pub loadings: Vec<Vec<f64>>,
+    pub eigenvalues: Vec<f64>,
+    pub communalities: Vec<f64>,
+    #[serde(rename = "explained_variance")]
+    pub explained_variance: Vec<f64>,
+    #[serde(rename = "cumulative_variance")]
+    pub cumulative_variance: Vec<f64>,
+    #[serde(rename = "n_factors")]
+    pub n_factors: usize,
+    pub iterations: usize,
+    pub converged: bool,
+    #[serde(rename = "achieved_tolerance", default)]
+    pub achieved_tolerance: Option<f64>,
+}
+
+#[derive(Clone)]
 pub struct ExtractionResult {
     pub loadings: DMatrix<f64>,
     pub eigenvalues: Vec<f64>,
@@ -145,10 +1160,72 @@ pub struct ExtractionResult {
     pub cumulative_variance: Vec<f64>,
     pub n_factors: usize,
     pub var_names: Vec<String>,
+    pub iterations: usize,
+    pub converged: bool,
+    // Largest communality change at the last iteration; NaN for non-iterative methods
+    pub achieved_tolerance: f64,
+}
+
+// Largest deviation of the output from the reference values of every embedded case
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationReport {
+    pub passed: bool,
+    pub cases: Vec<ValidationCase>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationCase {
+    pub name: String,
+    pub source: String,
+    pub passed: bool,
+    pub checks: Vec<ValidationCheck>,
+    // Set when the analysis of the case failed, leaving no checks
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationCheck {
+    pub statistic: String,
+    #[serde(rename = "max_deviation")]
+    pub max_deviation: f64,
+    pub tolerance: f64,
+    pub passed: bool,
+}
+
+// Population model for simulated data: loadings (variables x factors), factor correlations and
+// unique variances
+pub struct FactorModel {
+    pub loadings: DMatrix<f64>,
+    pub factor_correlations: DMatrix<f64>,
+    pub uniquenesses: Vec<f64>,
+}
+
+// Values of one variable for the Gower distance: numbers, level positions or level codes
+pub struct GowerColumn {
+    pub kind: GowerVariableKind,
+    pub values: Vec<Option<f64>>,
+}
+
+// Gower distances between cases, with the case number of each row
+pub struct GowerDistances {
+    pub distances: DMatrix<f64>,
+    pub variables: Vec<GowerVariable>,
+    pub case_numbers: Vec<usize>,
+}
+
+pub struct PairwiseMatrix {
+    pub matrix: DMatrix<f64>,
+    pub pair_counts: Vec<Vec<usize>>,
+    pub n_obs: usize,
+    pub flagged: Vec<(usize, usize)>,
 }
 
 pub struct RotationResult {
     pub rotated_loadings: DMatrix<f64>,
     pub transformation_matrix: DMatrix<f64>,
     pub factor_correlations: Option<DMatrix<f64>>,
+    pub iterations: usize,
+    pub converged: bool,
+    // Change of the rotation criterion at the last iteration; NaN when none was measured
+    pub achieved_tolerance: f64,
 }
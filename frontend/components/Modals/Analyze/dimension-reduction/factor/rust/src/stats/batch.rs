@@ -0,0 +1,100 @@
+use nalgebra::DMatrix;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::models::{
+    config::{ FactorAnalysisConfig, VariableBlock },
+    data::AnalysisData,
+    result::AnalysisSession,
+};
+
+use super::core::{
+    build_analysis_session,
+    build_pairwise_session,
+    create_analysis_session,
+    extract_raw_data_matrix,
+    handle_missing_values,
+    resolve_variable_names,
+    uses_pairwise_deletion,
+};
+
+// Configuration for a single block: the same settings restricted to the block's variables
+pub fn block_config(config: &FactorAnalysisConfig, block: &VariableBlock) -> FactorAnalysisConfig {
+    let mut block_config = config.clone();
+    block_config.main.target_var = Some(block.variables.clone());
+    block_config
+}
+
+// Parse the data once for all blocks, then correlate and extract each block independently
+pub fn create_block_sessions(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    blocks: &[VariableBlock]
+) -> Result<Vec<Result<AnalysisSession, String>>, String> {
+    if blocks.is_empty() {
+        return Err("No variable blocks provided".to_string());
+    }
+
+    // Block variables with patterns and ranges expanded
+    let block_variables: Vec<Vec<String>> = blocks
+        .iter()
+        .map(|block| resolve_variable_names(data, &block_config(config, block)))
+        .collect::<Result<_, _>>()?;
+
+    // Union of the block variables, in order of first appearance
+    let mut all_vars: Vec<String> = Vec::new();
+    for var in block_variables.iter().flatten() {
+        if !all_vars.contains(var) {
+            all_vars.push(var.clone());
+        }
+    }
+
+    let mut union_config = config.clone();
+    union_config.main.target_var = Some(all_vars.clone());
+    let (raw_matrix, var_names) = extract_raw_data_matrix(data, &union_config)?;
+
+    let run_block = |(block, variables): (&VariableBlock, &Vec<String>)| {
+        if variables.len() < 2 {
+            return Err(format!("Block '{}' needs at least two variables", block.name));
+        }
+
+        // A categorical variable contributes all of its encoded columns
+        let columns: Vec<usize> = variables
+            .iter()
+            .flat_map(|var| {
+                let encoded_prefix = format!("{}=", var);
+                var_names
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, name)| *name == var || name.starts_with(&encoded_prefix))
+                    .map(|(j, _)| j)
+            })
+            .collect();
+        let block_vars: Vec<String> = columns
+            .iter()
+            .map(|&j| var_names[j].clone())
+            .collect();
+
+        // Missing values are handled per block so one scale's gaps don't drop cases from another
+        let block_config = block_config(config, block);
+        // Pooling within groups needs the grouping variable, and polychoric correlations the
+        // ordinal levels, rather than the numeric matrix
+        if block_config.main.within_groups_var.is_some() || block_config.extraction.polychoric {
+            return create_analysis_session(data, &block_config);
+        }
+        let block_raw: DMatrix<f64> = raw_matrix.select_columns(&columns);
+        if uses_pairwise_deletion(&block_config) {
+            return build_pairwise_session(&block_raw, block_vars, &block_config);
+        }
+        let block_matrix = handle_missing_values(&block_raw, &block_config)?;
+
+        build_analysis_session(&block_matrix, block_vars, &block_config)
+    };
+
+    #[cfg(feature = "parallel")]
+    let sessions = blocks.par_iter().zip(&block_variables).map(run_block).collect();
+    #[cfg(not(feature = "parallel"))]
+    let sessions = blocks.iter().zip(&block_variables).map(run_block).collect();
+
+    Ok(sessions)
+}
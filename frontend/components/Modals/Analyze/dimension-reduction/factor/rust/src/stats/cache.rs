@@ -0,0 +1,170 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{ Hash, Hasher };
+use std::sync::{ Mutex, MutexGuard };
+
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::{ AnalysisData, DataRecord, DataValue },
+    result::ExtractionResult,
+};
+
+// Entries kept per cache; the least recently used one is dropped first
+pub const CACHE_CAPACITY: usize = 8;
+
+// Matrix, variable names and case count as returned by extract_correlation_matrix
+type CorrelationEntry = (DMatrix<f64>, Vec<String>, usize);
+
+struct FingerprintCache<T> {
+    entries: Vec<(u64, T)>,
+}
+
+impl<T: Clone> FingerprintCache<T> {
+    const fn new() -> Self {
+        FingerprintCache { entries: Vec::new() }
+    }
+
+    fn get(&mut self, key: u64) -> Option<T> {
+        let position = self.entries.iter().position(|(entry_key, _)| *entry_key == key)?;
+        let entry = self.entries.remove(position);
+        let value = entry.1.clone();
+        self.entries.push(entry);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: u64, value: T) {
+        self.entries.retain(|(entry_key, _)| *entry_key != key);
+        if self.entries.len() >= CACHE_CAPACITY {
+            self.entries.remove(0);
+        }
+        self.entries.push((key, value));
+    }
+}
+
+// Shared by every analysis in the process, whichever thread it runs on
+static CORRELATION_CACHE: Mutex<FingerprintCache<CorrelationEntry>> = Mutex::new(
+    FingerprintCache::new()
+);
+static EXTRACTION_CACHE: Mutex<FingerprintCache<ExtractionResult>> = Mutex::new(
+    FingerprintCache::new()
+);
+
+// A panic while the lock was held leaves at worst a stale entry, so a poisoned cache is reused
+fn lock<T>(cache: &Mutex<FingerprintCache<T>>) -> MutexGuard<'_, FingerprintCache<T>> {
+    cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn cache_enabled(config: &FactorAnalysisConfig) -> bool {
+    config.options.cache
+}
+
+// Correlation or covariance matrix from the cache, computing and storing it on a miss
+pub fn cached_correlation_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    matrix_type: &str,
+    compute: impl FnOnce() -> Result<CorrelationEntry, String>
+) -> Result<CorrelationEntry, String> {
+    let key = correlation_fingerprint(data, config, matrix_type);
+    if let Some(entry) = lock(&CORRELATION_CACHE).get(key) {
+        return Ok(entry);
+    }
+
+    // The lock isn't held while computing, so other analyses aren't blocked behind this one
+    let entry = compute()?;
+    lock(&CORRELATION_CACHE).insert(key, entry.clone());
+    Ok(entry)
+}
+
+// Extraction of a matrix from the cache, computing and storing it on a miss
+pub fn cached_extraction(
+    matrix: &DMatrix<f64>,
+    config: &FactorAnalysisConfig,
+    var_names: &[String],
+    compute: impl FnOnce() -> Result<ExtractionResult, String>
+) -> Result<ExtractionResult, String> {
+    let key = extraction_fingerprint(matrix, config, var_names);
+    if let Some(result) = lock(&EXTRACTION_CACHE).get(key) {
+        return Ok(result);
+    }
+
+    let result = compute()?;
+    lock(&EXTRACTION_CACHE).insert(key, result.clone());
+    Ok(result)
+}
+
+pub fn clear_analysis_cache() {
+    lock(&CORRELATION_CACHE).entries.clear();
+    lock(&EXTRACTION_CACHE).entries.clear();
+}
+
+// Data and the settings that decide which cases and variables enter the matrix. Display
+// options such as sorting or suppressing loadings are left out so toggling them still hits.
+pub fn correlation_fingerprint(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    matrix_type: &str
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    matrix_type.hash(&mut hasher);
+    hash_records(&data.target_data, &mut hasher);
+    hash_records(&data.value_target_data, &mut hasher);
+    hash_json(&data.target_data_defs, &mut hasher);
+    hash_json(&config.main, &mut hasher);
+    hash_json(&config.value, &mut hasher);
+    config.extraction.polychoric.hash(&mut hasher);
+    config.extraction.polychoric_converge.map(f64::to_bits).hash(&mut hasher);
+    config.extraction.polychoric_max_iter.hash(&mut hasher);
+
+    let options = &config.options;
+    (options.exclude_list_wise, options.exclude_pair_wise, options.replace_mean).hash(&mut hasher);
+    options.min_pairwise_n.hash(&mut hasher);
+    hash_json(&options.pairwise_n, &mut hasher);
+    hasher.finish()
+}
+
+// Matrix, variable names and the extraction settings
+pub fn extraction_fingerprint(
+    matrix: &DMatrix<f64>,
+    config: &FactorAnalysisConfig,
+    var_names: &[String]
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (matrix.nrows(), matrix.ncols()).hash(&mut hasher);
+    for value in matrix.iter() {
+        value.to_bits().hash(&mut hasher);
+    }
+    var_names.hash(&mut hasher);
+
+    let extraction = &config.extraction;
+    hash_json(&extraction.method, &mut hasher);
+    extraction.eigen_val.to_bits().hash(&mut hasher);
+    (extraction.max_factors, extraction.max_iter).hash(&mut hasher);
+    extraction.converge.map(f64::to_bits).hash(&mut hasher);
+    hasher.finish()
+}
+
+// Records hash by variable name order, since the order of their maps is arbitrary
+fn hash_records(datasets: &[Vec<DataRecord>], hasher: &mut DefaultHasher) {
+    for dataset in datasets {
+        dataset.len().hash(hasher);
+        for record in dataset {
+            let mut values: Vec<(&String, &DataValue)> = record.values.iter().collect();
+            values.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, value) in values {
+                name.hash(hasher);
+                match value {
+                    DataValue::Number(number) => (0u8, number.to_bits()).hash(hasher),
+                    DataValue::Text(text) => (1u8, text).hash(hasher),
+                    DataValue::Boolean(flag) => (2u8, flag).hash(hasher),
+                    DataValue::Null => (3u8).hash(hasher),
+                }
+            }
+        }
+    }
+}
+
+fn hash_json<T: serde::Serialize>(value: &T, hasher: &mut DefaultHasher) {
+    serde_json::to_string(value).unwrap_or_default().hash(hasher);
+}
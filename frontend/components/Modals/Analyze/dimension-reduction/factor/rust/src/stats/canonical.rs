@@ -0,0 +1,196 @@
+use nalgebra::DMatrix;
+use statrs::distribution::{ ChiSquared, ContinuousCDF };
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ CanonicalCorrelation, CanonicalFunction, CanonicalSet },
+};
+
+use super::core::{
+    extract_correlation_matrix,
+    matrix_to_rows,
+    require_more_cases_than_variables,
+    resolve_variable_names,
+    sorted_symmetric_eigen,
+};
+
+// Eigenvalues of a set's correlation matrix below this make the set linearly dependent
+const SINGULAR_TOLERANCE: f64 = 1e-10;
+
+// Canonical correlations between two variable sets, on the cases and missing value handling
+// the configuration gives the union of the sets
+pub fn calculate_canonical_correlation(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    set_one: &[String],
+    set_two: &[String]
+) -> Result<CanonicalCorrelation, String> {
+    let set_config = |variables: &[String]| {
+        let mut set_config = config.clone();
+        set_config.main.target_var = Some(variables.to_vec());
+        set_config
+    };
+    let vars_one = resolve_variable_names(data, &set_config(set_one))?;
+    let vars_two = resolve_variable_names(data, &set_config(set_two))?;
+    if let Some(shared) = vars_one.iter().find(|var| vars_two.contains(var)) {
+        return Err(format!("{} is in both variable sets", shared));
+    }
+
+    let union: Vec<String> = vars_one.iter().chain(&vars_two).cloned().collect();
+    let union_config = set_config(&union);
+    let (corr_matrix, var_names, n_obs) = extract_correlation_matrix(
+        data,
+        &union_config,
+        "correlation"
+    )?;
+    let (cov_matrix, _, _) = extract_correlation_matrix(data, &union_config, "covariance")?;
+    let std_deviations: Vec<f64> = cov_matrix
+        .diagonal()
+        .iter()
+        .map(|variance| variance.sqrt())
+        .collect();
+
+    // A categorical variable contributes all of its encoded columns
+    let columns = |variables: &[String]| -> Vec<usize> {
+        variables
+            .iter()
+            .flat_map(|var| {
+                let encoded_prefix = format!("{}=", var);
+                var_names
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, name)| *name == var || name.starts_with(&encoded_prefix))
+                    .map(|(j, _)| j)
+            })
+            .collect()
+    };
+
+    build_canonical_correlation(
+        &corr_matrix,
+        &std_deviations,
+        &var_names,
+        (&columns(&vars_one), &columns(&vars_two)),
+        n_obs
+    )
+}
+
+// With R11^-1/2 R12 R22^-1/2 = U D V', the canonical correlations are D, the standardized
+// coefficients R11^-1/2 U and R22^-1/2 V, and the loadings the correlations of each set with
+// its own variates. Wilks' lambda for functions k and up is prod (1 - r_i^2) over i >= k, tested
+// with Bartlett's chi-square on (p - k + 1)(q - k + 1) degrees of freedom.
+pub fn build_canonical_correlation(
+    corr_matrix: &DMatrix<f64>,
+    std_deviations: &[f64],
+    var_names: &[String],
+    (columns_one, columns_two): (&[usize], &[usize]),
+    n_obs: usize
+) -> Result<CanonicalCorrelation, String> {
+    let (p, q) = (columns_one.len(), columns_two.len());
+    if p == 0 || q == 0 {
+        return Err("Each variable set needs at least one variable".to_string());
+    }
+    require_more_cases_than_variables("Canonical correlation", n_obs, p + q)?;
+
+    let r11 = corr_matrix.select_rows(columns_one).select_columns(columns_one);
+    let r22 = corr_matrix.select_rows(columns_two).select_columns(columns_two);
+    let r12 = corr_matrix.select_rows(columns_one).select_columns(columns_two);
+    let root_one = inverse_square_root(&r11, "first")?;
+    let root_two = inverse_square_root(&r22, "second")?;
+
+    let cross = &root_one * &r12 * &root_two;
+    let (squared, left_vectors, _) = sorted_symmetric_eigen(&(&cross * cross.transpose()));
+    let n_functions = p.min(q);
+    let correlations: Vec<f64> = squared[..n_functions]
+        .iter()
+        .map(|value| value.clamp(0.0, 1.0).sqrt())
+        .collect();
+
+    let u = left_vectors.columns(0, n_functions).into_owned();
+    let mut v = cross.transpose() * &u;
+    for (k, mut column) in v.column_iter_mut().enumerate() {
+        if correlations[k] > 0.0 {
+            column /= correlations[k];
+        }
+    }
+    let coefficients_one = &root_one * &u;
+    let coefficients_two = &root_two * &v;
+
+    let count = n_obs as f64;
+    let bartlett_factor = count - 1.0 - ((p + q + 1) as f64) / 2.0;
+    let functions = (0..n_functions)
+        .map(|k| {
+            let wilks_lambda: f64 = correlations[k..]
+                .iter()
+                .map(|r| 1.0 - r * r)
+                .product();
+            let df = (p - k) * (q - k);
+            let chi_square = -bartlett_factor * wilks_lambda.max(f64::MIN_POSITIVE).ln();
+            let significance = ChiSquared::new(df as f64)
+                .map(|distribution| 1.0 - distribution.cdf(chi_square))
+                .unwrap_or(f64::NAN);
+
+            CanonicalFunction {
+                function: k + 1,
+                correlation: correlations[k],
+                eigenvalue: squared[k] / (1.0 - squared[k]),
+                wilks_lambda,
+                chi_square,
+                df,
+                significance,
+            }
+        })
+        .collect();
+
+    let canonical_set = |
+        columns: &[usize],
+        coefficients: &DMatrix<f64>,
+        own: DMatrix<f64>,
+        other: DMatrix<f64>
+    | {
+        let raw_coefficients = DMatrix::from_fn(coefficients.nrows(), n_functions, |i, k| {
+            coefficients[(i, k)] / std_deviations[columns[i]]
+        });
+        CanonicalSet {
+            variables: columns
+                .iter()
+                .map(|&j| var_names[j].clone())
+                .collect(),
+            standardized_coefficients: matrix_to_rows(coefficients),
+            raw_coefficients: matrix_to_rows(&raw_coefficients),
+            loadings: matrix_to_rows(&own),
+            cross_loadings: matrix_to_rows(&other),
+        }
+    };
+    let r21 = r12.transpose();
+
+    Ok(CanonicalCorrelation {
+        n: n_obs,
+        set_one: canonical_set(
+            columns_one,
+            &coefficients_one,
+            &r11 * &coefficients_one,
+            &r12 * &coefficients_two
+        ),
+        set_two: canonical_set(
+            columns_two,
+            &coefficients_two,
+            &r22 * &coefficients_two,
+            &r21 * &coefficients_one
+        ),
+        functions,
+    })
+}
+
+// R^-1/2 of a set's correlation matrix from its eigen decomposition
+fn inverse_square_root(matrix: &DMatrix<f64>, set: &str) -> Result<DMatrix<f64>, String> {
+    let (eigenvalues, vectors, _) = sorted_symmetric_eigen(matrix);
+    if !eigenvalues.last().is_some_and(|smallest| *smallest >= SINGULAR_TOLERANCE) {
+        return Err(format!("The variables of the {} set are linearly dependent", set));
+    }
+
+    let scaled = DMatrix::from_fn(vectors.nrows(), vectors.ncols(), |i, k| {
+        vectors[(i, k)] / eigenvalues[k].sqrt()
+    });
+    Ok(&scaled * vectors.transpose())
+}
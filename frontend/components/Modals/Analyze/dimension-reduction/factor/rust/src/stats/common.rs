@@ -78,14 +78,15 @@ pub fn gamma_function(x: f64) -> f64 {
         12.507343278686905, -0.13857109526572012, 9.9843695780195716e-6, 1.5056327351493116e-7,
     ];
 
-    let mut y = x;
+    let y = x;
     let mut result = 0.99999999999980993;
 
     for i in 0..8 {
         result += p[i] / (y + (i as f64));
     }
 
-    let t = y + 7.5;
+    // The series above is A(x - 1), so t = (x - 1) + g + 0.5 with g = 7
+    let t = y + 6.5;
     let sqrt_2pi = ((2.0 * PI) as f64).sqrt();
 
     sqrt_2pi * t.powf(y - 0.5) * (-t).exp() * result
@@ -137,15 +138,16 @@ pub fn ln_gamma(x: f64) -> f64 {
         12.507343278686905, -0.13857109526572012, 9.9843695780195716e-6, 1.5056327351493116e-7,
     ];
 
-    let mut y = x;
+    let y = x;
     let mut result = 0.99999999999980993;
 
     for i in 0..8 {
         result += p[i] / (y + (i as f64));
     }
 
-    let t = y + 7.5;
+    // Same shift as gamma_function
+    let t = y + 6.5;
     let sqrt_2pi = ((2.0 * PI) as f64).sqrt();
 
-    sqrt_2pi.ln() + (y - 0.5) * t.ln() - t + (result / y).ln()
+    sqrt_2pi.ln() + (y - 0.5) * t.ln() - t + result.ln()
 }
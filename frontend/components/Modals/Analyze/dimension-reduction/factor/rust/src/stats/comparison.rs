@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+
+use crate::models::result::{
+    FactorAnalysisResult,
+    FactorMatch,
+    PrimaryFactorChange,
+    ReproducedCorrelations,
+    ResidualFit,
+    SolutionComparison,
+    VarianceChange,
+};
+
+// Residuals above this size are counted as in SPSS's reproduced correlations footnote
+const LARGE_RESIDUAL: f64 = 0.05;
+
+// Compare two completed solutions, matching factors by absolute Tucker congruence
+pub fn compare_solutions(
+    result_a: &FactorAnalysisResult,
+    result_b: &FactorAnalysisResult
+) -> Result<SolutionComparison, String> {
+    let loadings_a = solution_loadings(result_a).ok_or(
+        "First result has no component or rotated matrix".to_string()
+    )?;
+    let loadings_b = solution_loadings(result_b).ok_or(
+        "Second result has no component or rotated matrix".to_string()
+    )?;
+
+    // Only variables present in both solutions can be compared
+    let mut var_names: Vec<String> = loadings_a
+        .keys()
+        .filter(|var| loadings_b.contains_key(*var))
+        .cloned()
+        .collect();
+    var_names.sort();
+
+    if var_names.is_empty() {
+        return Err("The two results have no variables in common".to_string());
+    }
+
+    let matrix_a = loadings_to_matrix(loadings_a, &var_names);
+    let matrix_b = loadings_to_matrix(loadings_b, &var_names);
+    let n_factors_a = matrix_a.ncols();
+    let n_factors_b = matrix_b.ncols();
+
+    if n_factors_a == 0 || n_factors_b == 0 {
+        return Err("Both results need at least one factor".to_string());
+    }
+
+    let congruence = tucker_congruence(&matrix_a, &matrix_b);
+    let matching = optimal_factor_matching(&congruence);
+
+    let factor_matches: Vec<FactorMatch> = matching
+        .iter()
+        .map(|&(i, j)| FactorMatch {
+            factor_a: i + 1,
+            factor_b: j + 1,
+            congruence: congruence[(i, j)].abs(),
+            sign_reversed: congruence[(i, j)] < 0.0,
+        })
+        .collect();
+
+    let unmatched_factors_a = (0..n_factors_a)
+        .filter(|i| !matching.iter().any(|&(a, _)| a == *i))
+        .map(|i| i + 1)
+        .collect();
+    let unmatched_factors_b = (0..n_factors_b)
+        .filter(|j| !matching.iter().any(|&(_, b)| b == *j))
+        .map(|j| j + 1)
+        .collect();
+
+    // A variable changes primary factor when its strongest factor in A isn't matched to
+    // its strongest factor in B
+    let mut primary_factor_changes = Vec::new();
+    for (k, var_name) in var_names.iter().enumerate() {
+        let primary_a = primary_factor(&matrix_a, k);
+        let primary_b = primary_factor(&matrix_b, k);
+        let matched_b = matching
+            .iter()
+            .find(|&&(a, _)| a == primary_a)
+            .map(|&(_, b)| b);
+
+        if matched_b != Some(primary_b) {
+            primary_factor_changes.push(PrimaryFactorChange {
+                variable: var_name.clone(),
+                factor_a: primary_a + 1,
+                factor_b: primary_b + 1,
+                loading_a: matrix_a[(k, primary_a)],
+                loading_b: matrix_b[(k, primary_b)],
+            });
+        }
+    }
+
+    let percents_a = factor_variance_percents(result_a, &matrix_a);
+    let percents_b = factor_variance_percents(result_b, &matrix_b);
+    let variance_changes = matching
+        .iter()
+        .map(|&(i, j)| {
+            let percent_a = percents_a.get(i).copied().unwrap_or(0.0);
+            let percent_b = percents_b.get(j).copied().unwrap_or(0.0);
+
+            VarianceChange {
+                factor_a: i + 1,
+                factor_b: j + 1,
+                percent_a,
+                percent_b,
+                difference: percent_b - percent_a,
+            }
+        })
+        .collect();
+
+    Ok(SolutionComparison {
+        congruence_matrix: (0..n_factors_a)
+            .map(|i| (0..n_factors_b).map(|j| congruence[(i, j)]).collect())
+            .collect(),
+        factor_matches,
+        unmatched_factors_a,
+        unmatched_factors_b,
+        primary_factor_changes,
+        variance_changes,
+        cumulative_percent_a: percents_a.iter().sum(),
+        cumulative_percent_b: percents_b.iter().sum(),
+        fit_a: result_a.reproduced_correlations.as_ref().map(calculate_residual_fit),
+        fit_b: result_b.reproduced_correlations.as_ref().map(calculate_residual_fit),
+    })
+}
+
+// Tucker's coefficient of congruence between every column of a and every column of b
+pub fn tucker_congruence(a: &DMatrix<f64>, b: &DMatrix<f64>) -> DMatrix<f64> {
+    let mut congruence = DMatrix::zeros(a.ncols(), b.ncols());
+
+    for i in 0..a.ncols() {
+        for j in 0..b.ncols() {
+            let col_a = a.column(i);
+            let col_b = b.column(j);
+            let denominator = (col_a.norm_squared() * col_b.norm_squared()).sqrt();
+
+            if denominator > 0.0 {
+                congruence[(i, j)] = col_a.dot(&col_b) / denominator;
+            }
+        }
+    }
+
+    congruence
+}
+
+// One-to-one matching of factors maximizing the total absolute congruence
+pub fn optimal_factor_matching(congruence: &DMatrix<f64>) -> Vec<(usize, usize)> {
+    let cost = congruence.map(|value| -value.abs());
+
+    let mut pairs: Vec<(usize, usize)> = if cost.nrows() <= cost.ncols() {
+        hungarian_assignment(&cost).into_iter().enumerate().collect()
+    } else {
+        hungarian_assignment(&cost.transpose())
+            .into_iter()
+            .enumerate()
+            .map(|(j, i)| (i, j))
+            .collect()
+    };
+
+    pairs.sort();
+    pairs
+}
+
+// Hungarian algorithm for an n x m cost matrix with n <= m; returns the column for each row
+fn hungarian_assignment(cost: &DMatrix<f64>) -> Vec<usize> {
+    let n = cost.nrows();
+    let m = cost.ncols();
+
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; m + 1];
+    let mut assigned_row = vec![0usize; m + 1];
+    let mut way = vec![0usize; m + 1];
+
+    for i in 1..=n {
+        assigned_row[0] = i;
+        let mut j0 = 0;
+        let mut min_value = vec![f64::INFINITY; m + 1];
+        let mut used = vec![false; m + 1];
+
+        loop {
+            used[j0] = true;
+            let i0 = assigned_row[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+
+            for j in 1..=m {
+                if !used[j] {
+                    let current = cost[(i0 - 1, j - 1)] - u[i0] - v[j];
+                    if current < min_value[j] {
+                        min_value[j] = current;
+                        way[j] = j0;
+                    }
+                    if min_value[j] < delta {
+                        delta = min_value[j];
+                        j1 = j;
+                    }
+                }
+            }
+
+            for j in 0..=m {
+                if used[j] {
+                    u[assigned_row[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_value[j] -= delta;
+                }
+            }
+
+            j0 = j1;
+            if assigned_row[j0] == 0 {
+                break;
+            }
+        }
+
+        // Augment along the alternating path
+        loop {
+            let j1 = way[j0];
+            assigned_row[j0] = assigned_row[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut assignment = vec![0; n];
+    for j in 1..=m {
+        if assigned_row[j] != 0 {
+            assignment[assigned_row[j] - 1] = j - 1;
+        }
+    }
+
+    assignment
+}
+
+// Root mean square of the off-diagonal residuals and the count above 0.05
+pub fn calculate_residual_fit(reproduced: &ReproducedCorrelations) -> ResidualFit {
+    let mut var_names: Vec<&String> = reproduced.residual.keys().collect();
+    var_names.sort();
+
+    let mut sum_squared = 0.0;
+    let mut count = 0;
+    let mut large_residuals = 0;
+
+    for (i, var_i) in var_names.iter().enumerate() {
+        for var_j in var_names.iter().skip(i + 1) {
+            let residual = reproduced.residual
+                .get(*var_i)
+                .and_then(|row| row.get(*var_j))
+                .copied()
+                .unwrap_or(0.0);
+
+            sum_squared += residual.powi(2);
+            count += 1;
+            if residual.abs() > LARGE_RESIDUAL {
+                large_residuals += 1;
+            }
+        }
+    }
+
+    ResidualFit {
+        rmsr: if count > 0 { (sum_squared / (count as f64)).sqrt() } else { 0.0 },
+        large_residuals,
+        large_residual_percent: if count > 0 {
+            ((large_residuals as f64) / (count as f64)) * 100.0
+        } else {
+            0.0
+        },
+    }
+}
+
+// Rotated loadings when available, otherwise the unrotated component matrix
+pub fn solution_loadings(result: &FactorAnalysisResult) -> Option<&HashMap<String, Vec<f64>>> {
+    result.rotated_component_matrix
+        .as_ref()
+        .map(|matrix| &matrix.components)
+        .or_else(|| result.component_matrix.as_ref().map(|matrix| &matrix.components))
+}
+
+fn loadings_to_matrix(loadings: &HashMap<String, Vec<f64>>, var_names: &[String]) -> DMatrix<f64> {
+    let n_factors = var_names
+        .iter()
+        .map(|var| loadings[var].len())
+        .min()
+        .unwrap_or(0);
+
+    DMatrix::from_fn(var_names.len(), n_factors, |i, j| loadings[&var_names[i]][j])
+}
+
+fn primary_factor(loadings: &DMatrix<f64>, row: usize) -> usize {
+    let mut best = 0;
+    for j in 1..loadings.ncols() {
+        if loadings[(row, j)].abs() > loadings[(row, best)].abs() {
+            best = j;
+        }
+    }
+    best
+}
+
+// Percent of variance per factor, preferring the rotation sums of squared loadings
+fn factor_variance_percents(result: &FactorAnalysisResult, loadings: &DMatrix<f64>) -> Vec<f64> {
+    if let Some(variance) = &result.total_variance_explained {
+        let sums = if !variance.rotation_sums.is_empty() {
+            &variance.rotation_sums
+        } else {
+            &variance.extraction_sums
+        };
+
+        if sums.len() == loadings.ncols() {
+            return sums
+                .iter()
+                .map(|component| component.percent_of_variance)
+                .collect();
+        }
+    }
+
+    let n_vars = loadings.nrows() as f64;
+    (0..loadings.ncols())
+        .map(|j| (loadings.column(j).norm_squared() / n_vars) * 100.0)
+        .collect()
+}
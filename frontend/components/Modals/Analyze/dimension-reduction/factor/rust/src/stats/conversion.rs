@@ -0,0 +1,102 @@
+use nalgebra::DMatrix;
+
+// Relative asymmetry tolerated in supplied matrices, e.g. from rounding in published tables
+const SYMMETRY_TOLERANCE: f64 = 1e-6;
+
+// Correlation matrix and standard deviations of a covariance matrix
+pub fn covariance_to_correlation(
+    cov_matrix: &DMatrix<f64>
+) -> Result<(DMatrix<f64>, Vec<f64>), String> {
+    check_symmetric(cov_matrix, "Covariance matrix")?;
+    let std_deviations: Vec<f64> = cov_matrix
+        .diagonal()
+        .iter()
+        .map(|variance| variance.sqrt())
+        .collect();
+    if let Some(j) = std_deviations.iter().position(|sd| !(sd.is_finite() && *sd > 0.0)) {
+        return Err(format!("Variable {} has no positive variance", j + 1));
+    }
+
+    let inverse_sd: Vec<f64> = std_deviations
+        .iter()
+        .map(|sd| 1.0 / sd)
+        .collect();
+    let mut corr_matrix = scale_symmetric(cov_matrix, &inverse_sd);
+    // Exact ones on the diagonal, whatever the rounding of the scaling
+    corr_matrix.fill_diagonal(1.0);
+
+    Ok((corr_matrix, std_deviations))
+}
+
+// Covariance matrix of a correlation matrix and the standard deviations of its variables
+pub fn correlation_to_covariance(
+    corr_matrix: &DMatrix<f64>,
+    std_deviations: &[f64]
+) -> Result<DMatrix<f64>, String> {
+    check_symmetric(corr_matrix, "Correlation matrix")?;
+    check_std_deviations(std_deviations, corr_matrix.nrows())?;
+    if corr_matrix.diagonal().iter().any(|value| (value - 1.0).abs() > SYMMETRY_TOLERANCE) {
+        return Err("Correlation matrix needs ones on the diagonal".to_string());
+    }
+
+    Ok(scale_symmetric(corr_matrix, std_deviations))
+}
+
+// Loadings on the scale of the variables from loadings on standardized variables
+pub fn unstandardize_loadings(
+    loadings: &DMatrix<f64>,
+    std_deviations: &[f64]
+) -> Result<DMatrix<f64>, String> {
+    check_std_deviations(std_deviations, loadings.nrows())?;
+
+    Ok(DMatrix::from_fn(loadings.nrows(), loadings.ncols(), |i, j| {
+        loadings[(i, j)] * std_deviations[i]
+    }))
+}
+
+// Loadings on standardized variables from loadings on the scale of the variables, as the
+// rescaled loadings of a covariance extraction
+pub fn standardize_loadings(
+    loadings: &DMatrix<f64>,
+    std_deviations: &[f64]
+) -> Result<DMatrix<f64>, String> {
+    check_std_deviations(std_deviations, loadings.nrows())?;
+
+    Ok(DMatrix::from_fn(loadings.nrows(), loadings.ncols(), |i, j| {
+        loadings[(i, j)] / std_deviations[i]
+    }))
+}
+
+// D M D for D = diag(scales)
+fn scale_symmetric(matrix: &DMatrix<f64>, scales: &[f64]) -> DMatrix<f64> {
+    DMatrix::from_fn(matrix.nrows(), matrix.ncols(), |i, j| matrix[(i, j)] * scales[i] * scales[j])
+}
+
+fn check_symmetric(matrix: &DMatrix<f64>, label: &str) -> Result<(), String> {
+    if !matrix.is_square() || matrix.nrows() == 0 {
+        return Err(format!("{} must be square and non-empty", label));
+    }
+    if matrix.iter().any(|value| !value.is_finite()) {
+        return Err(format!("{} has missing or infinite values", label));
+    }
+
+    let tolerance = SYMMETRY_TOLERANCE * matrix.amax().max(1.0);
+    if (matrix - matrix.transpose()).amax() > tolerance {
+        return Err(format!("{} is not symmetric", label));
+    }
+
+    Ok(())
+}
+
+fn check_std_deviations(std_deviations: &[f64], n_vars: usize) -> Result<(), String> {
+    if std_deviations.len() != n_vars {
+        return Err(
+            format!("{} standard deviations given for {} variables", std_deviations.len(), n_vars)
+        );
+    }
+    if let Some(j) = std_deviations.iter().position(|sd| !(sd.is_finite() && *sd > 0.0)) {
+        return Err(format!("Variable {} needs a positive standard deviation", j + 1));
+    }
+
+    Ok(())
+}
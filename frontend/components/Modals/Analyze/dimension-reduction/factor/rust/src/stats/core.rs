@@ -1,8 +1,48 @@
+pub use crate::stats::batch::*;
+pub use crate::stats::cache::*;
+pub use crate::stats::canonical::*;
 pub use crate::stats::common::*;
+pub use crate::stats::comparison::*;
+pub use crate::stats::conversion::*;
+pub use crate::stats::covariance_extraction::*;
+pub use crate::stats::distance_correlation::*;
+pub use crate::stats::eap_scores::*;
+pub use crate::stats::eigen::*;
+pub use crate::stats::encoding::*;
+pub use crate::stats::factor_count::*;
 pub use crate::stats::factor_extraction::*;
 pub use crate::stats::generate_plots::*;
+pub use crate::stats::gower::*;
+pub use crate::stats::influence::*;
+pub use crate::stats::intermediate::*;
+pub use crate::stats::intraclass::*;
 pub use crate::stats::kmo_test::*;
+pub use crate::stats::large_data::*;
 pub use crate::stats::matrix::*;
+pub use crate::stats::mds::*;
+pub use crate::stats::metadata::*;
+pub use crate::stats::model_comparison::*;
+pub use crate::stats::pair_detail::*;
+pub use crate::stats::pairwise::*;
+pub use crate::stats::polychoric::*;
+pub use crate::stats::parallel_analysis::*;
+pub use crate::stats::perfect_correlation::*;
+pub use crate::stats::pipeline::*;
+pub use crate::stats::power::*;
 pub use crate::stats::prepare_data::*;
+pub use crate::stats::principal_coordinates::*;
+pub use crate::stats::procrustes::*;
+pub use crate::stats::psych::*;
+pub use crate::stats::reliability::*;
 pub use crate::stats::report::*;
+pub use crate::stats::reproducibility::*;
+pub use crate::stats::requirements::*;
+pub use crate::stats::reshape::*;
 pub use crate::stats::rotation::*;
+pub use crate::stats::selection::*;
+pub use crate::stats::session::*;
+pub use crate::stats::simulate::*;
+pub use crate::stats::validation::*;
+pub use crate::stats::validity::*;
+pub use crate::stats::weighted_least_squares::*;
+pub use crate::stats::within_groups::*;
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    data::AnalysisData,
+    result::{ Communalities, CovarianceExtraction, TotalVarianceComponent, TotalVarianceExplained },
+};
+
+use super::core::{
+    extract_correlation_matrix,
+    extraction_convergence,
+    robust_symmetric_eigen,
+    standardize_loadings,
+};
+
+// Whether "Analyze: Covariance matrix" is selected
+pub fn analyzes_covariance(config: &FactorAnalysisConfig) -> bool {
+    config.extraction.covariance && !config.extraction.correlation
+}
+
+// Extraction on the covariance matrix, reported as raw loadings and as loadings rescaled by the
+// standard deviations of the variables
+pub fn calculate_covariance_extraction(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<CovarianceExtraction, String> {
+    let (cov_matrix, var_names, _) = extract_correlation_matrix(data, config, "covariance")?;
+
+    build_covariance_extraction(&cov_matrix, &var_names, config)
+}
+
+pub fn build_covariance_extraction(
+    cov_matrix: &DMatrix<f64>,
+    var_names: &[String],
+    config: &FactorAnalysisConfig
+) -> Result<CovarianceExtraction, String> {
+    let n_vars = cov_matrix.nrows();
+    let variances: Vec<f64> = (0..n_vars).map(|i| cov_matrix[(i, i)]).collect();
+    if variances.iter().any(|variance| *variance <= 0.0 || variance.is_nan()) {
+        return Err("Covariance extraction needs variables with positive variance".to_string());
+    }
+    let total_variance: f64 = variances.iter().sum();
+
+    let (eigenvalues, eigenvectors) = sorted_eigen(cov_matrix);
+    let n_factors = covariance_factors_to_retain(&eigenvalues, config);
+
+    let (loadings, initial, iterations, converged) = match config.extraction.method {
+        ExtractionMethod::PrincipalComponents => {
+            let loadings = loadings_from_eigen(&eigenvalues, &eigenvectors, n_factors);
+            (loadings, variances.clone(), 0, true)
+        }
+        ExtractionMethod::PrincipalAxisFactoring => {
+            let initial = initial_covariance_communalities(cov_matrix);
+            let (loadings, iterations, converged) = iterate_principal_axes(
+                cov_matrix,
+                &initial,
+                n_factors,
+                config.extraction.max_iter.max(0) as usize,
+                extraction_convergence(config)
+            );
+            (loadings, initial, iterations, converged)
+        }
+        _ => {
+            return Err(
+                "Covariance matrix extraction is available for principal components and \
+                 principal axis factoring"
+                    .to_string()
+            );
+        }
+    };
+
+    let std_deviations: Vec<f64> = variances
+        .iter()
+        .map(|variance| variance.sqrt())
+        .collect();
+    let rescaled = standardize_loadings(&loadings, &std_deviations)?;
+
+    let raw_extraction: Vec<f64> = loadings
+        .row_iter()
+        .map(|row| row.iter().map(|value| value.powi(2)).sum())
+        .collect();
+    let rescaled_initial: Vec<f64> = initial
+        .iter()
+        .zip(&variances)
+        .map(|(communality, variance)| communality / variance)
+        .collect();
+    let rescaled_extraction: Vec<f64> = raw_extraction
+        .iter()
+        .zip(&variances)
+        .map(|(communality, variance)| communality / variance)
+        .collect();
+
+    let column_sums = |matrix: &DMatrix<f64>| -> Vec<f64> {
+        matrix
+            .column_iter()
+            .map(|column| column.iter().map(|value| value.powi(2)).sum())
+            .collect()
+    };
+
+    Ok(CovarianceExtraction {
+        raw_loadings: by_variable(var_names, &loadings),
+        rescaled_loadings: by_variable(var_names, &rescaled),
+        raw_communalities: Communalities {
+            initial: by_name(var_names, &initial),
+            extraction: by_name(var_names, &raw_extraction),
+        },
+        rescaled_communalities: Communalities {
+            initial: by_name(var_names, &rescaled_initial),
+            extraction: by_name(var_names, &rescaled_extraction),
+        },
+        raw_variance: TotalVarianceExplained {
+            initial_eigenvalues: variance_components(&eigenvalues, total_variance),
+            extraction_sums: variance_components(&column_sums(&loadings), total_variance),
+            rotation_sums: Vec::new(),
+        },
+        rescaled_extraction_sums: variance_components(&column_sums(&rescaled), n_vars as f64),
+        n_factors,
+        iterations,
+        converged,
+    })
+}
+
+// Maximum factors when set, otherwise eigenvalues above EigenVal times the mean eigenvalue
+fn covariance_factors_to_retain(eigenvalues: &[f64], config: &FactorAnalysisConfig) -> usize {
+    if let Some(max_factors) = config.extraction.max_factors {
+        let max = max_factors as usize;
+        if max > 0 && max <= eigenvalues.len() {
+            return max;
+        }
+    }
+
+    let mean = eigenvalues.iter().sum::<f64>() / (eigenvalues.len().max(1) as f64);
+    let cutoff = config.extraction.eigen_val * mean;
+    eigenvalues
+        .iter()
+        .take_while(|value| **value >= cutoff)
+        .count()
+        .max(1)
+}
+
+// Variance explained by the other variables: s_ii - 1 / (S^-1)_ii
+fn initial_covariance_communalities(cov_matrix: &DMatrix<f64>) -> Vec<f64> {
+    let n_vars = cov_matrix.nrows();
+    let inverse = cov_matrix.clone().try_inverse();
+
+    (0..n_vars)
+        .map(|i| {
+            match &inverse {
+                Some(inverse) if inverse[(i, i)] > 0.0 => {
+                    cov_matrix[(i, i)] - 1.0 / inverse[(i, i)]
+                }
+                // Singular matrix: largest squared correlation scaled to the variable's variance
+                _ => {
+                    let max_r2 = (0..n_vars)
+                        .filter(|&j| j != i)
+                        .map(|j| {
+                            cov_matrix[(i, j)].powi(2) / (cov_matrix[(i, i)] * cov_matrix[(j, j)])
+                        })
+                        .fold(0.0, f64::max);
+                    cov_matrix[(i, i)] * max_r2
+                }
+            }
+        })
+        .collect()
+}
+
+// Principal axis iterations on the reduced covariance matrix; convergence is judged on the
+// communalities relative to the variances
+fn iterate_principal_axes(
+    cov_matrix: &DMatrix<f64>,
+    initial: &[f64],
+    n_factors: usize,
+    max_iterations: usize,
+    tolerance: f64
+) -> (DMatrix<f64>, usize, bool) {
+    let n_vars = cov_matrix.nrows();
+    let mut communalities = initial.to_vec();
+    let mut reduced = cov_matrix.clone();
+    let mut loadings = DMatrix::zeros(n_vars, n_factors);
+
+    for iteration in 1..=max_iterations {
+        for (i, communality) in communalities.iter().enumerate() {
+            reduced[(i, i)] = *communality;
+        }
+
+        let (eigenvalues, eigenvectors) = sorted_eigen(&reduced);
+        loadings = loadings_from_eigen(&eigenvalues, &eigenvectors, n_factors);
+
+        let mut max_change: f64 = 0.0;
+        for (i, communality) in communalities.iter_mut().enumerate() {
+            let updated: f64 = loadings.row(i).iter().map(|value| value.powi(2)).sum();
+            max_change = max_change.max((updated - *communality).abs() / cov_matrix[(i, i)]);
+            *communality = updated;
+        }
+
+        if max_change < tolerance {
+            return (loadings, iteration, true);
+        }
+    }
+
+    (loadings, max_iterations, false)
+}
+
+// Eigenvalues in descending order with their eigenvectors as columns
+fn sorted_eigen(matrix: &DMatrix<f64>) -> (Vec<f64>, DMatrix<f64>) {
+    let eigen = robust_symmetric_eigen(matrix);
+    let mut order: Vec<usize> = (0..matrix.nrows()).collect();
+    order.sort_by(|&a, &b| {
+        eigen.eigenvalues[b].partial_cmp(&eigen.eigenvalues[a]).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let eigenvalues = order
+        .iter()
+        .map(|&k| eigen.eigenvalues[k])
+        .collect();
+    let eigenvectors = eigen.eigenvectors.select_columns(&order);
+    (eigenvalues, eigenvectors)
+}
+
+fn loadings_from_eigen(
+    eigenvalues: &[f64],
+    eigenvectors: &DMatrix<f64>,
+    n_factors: usize
+) -> DMatrix<f64> {
+    DMatrix::from_fn(eigenvectors.nrows(), n_factors, |i, j| {
+        eigenvectors[(i, j)] * eigenvalues[j].max(0.0).sqrt()
+    })
+}
+
+fn variance_components(totals: &[f64], total_variance: f64) -> Vec<TotalVarianceComponent> {
+    let mut cumulative = 0.0;
+    totals
+        .iter()
+        .map(|total| {
+            let percent = (total / total_variance) * 100.0;
+            cumulative += percent;
+            TotalVarianceComponent {
+                total: *total,
+                percent_of_variance: percent,
+                cumulative_percent: cumulative,
+            }
+        })
+        .collect()
+}
+
+fn by_variable(var_names: &[String], matrix: &DMatrix<f64>) -> HashMap<String, Vec<f64>> {
+    var_names
+        .iter()
+        .enumerate()
+        .map(|(i, var_name)| (var_name.clone(), matrix.row(i).iter().copied().collect()))
+        .collect()
+}
+
+fn by_name(var_names: &[String], values: &[f64]) -> HashMap<String, f64> {
+    var_names.iter().cloned().zip(values.iter().copied()).collect()
+}
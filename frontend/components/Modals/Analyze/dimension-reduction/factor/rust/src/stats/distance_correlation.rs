@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+use rand::seq::SliceRandom;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::DistanceCorrelationMatrix,
+};
+use crate::utils::rng::{ resolve_seed, run_replicates };
+
+use super::core::{ extract_data_matrix, extract_raw_data_matrix, uses_pairwise_deletion };
+
+// Permutation count when a test is requested without an explicit count
+pub const DEFAULT_DISTANCE_PERMUTATIONS: usize = 499;
+
+// Distance correlation (Szekely, Rizzo & Bakirov, 2007) between every pair of variables; zero
+// only under independence, so it also picks up non-linear dependence Pearson's r misses
+pub fn calculate_distance_correlation_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<DistanceCorrelationMatrix, String> {
+    // Under pairwise deletion every pair uses its own complete cases
+    let (data_matrix, var_names) = if uses_pairwise_deletion(config) {
+        extract_raw_data_matrix(data, config)?
+    } else {
+        extract_data_matrix(data, config)?
+    };
+
+    let permutations = distance_permutations(config);
+    let seed = (permutations > 0).then(|| resolve_seed(config));
+
+    build_distance_correlation_matrix(&data_matrix, &var_names, permutations, seed)
+}
+
+// Permutations for the significance test; zero disables it. Without an explicit count the test
+// follows the significance levels option of the Pearson matrix.
+pub fn distance_permutations(config: &FactorAnalysisConfig) -> usize {
+    match config.descriptives.distance_permutations {
+        Some(count) if count > 0 => count as usize,
+        Some(_) => 0,
+        None if config.descriptives.significance_lvl => DEFAULT_DISTANCE_PERMUTATIONS,
+        None => 0,
+    }
+}
+
+pub fn build_distance_correlation_matrix(
+    data_matrix: &DMatrix<f64>,
+    var_names: &[String],
+    permutations: usize,
+    seed: Option<u64>
+) -> Result<DistanceCorrelationMatrix, String> {
+    let n_vars = data_matrix.ncols();
+    if n_vars != var_names.len() {
+        return Err("Variable names do not match the data matrix".to_string());
+    }
+
+    let mut correlations: HashMap<String, HashMap<String, f64>> = HashMap::new();
+    let mut sig_values: HashMap<String, HashMap<String, f64>> = HashMap::new();
+
+    for i in 0..n_vars {
+        for j in i..n_vars {
+            let (x, y): (Vec<f64>, Vec<f64>) = data_matrix
+                .column(i)
+                .iter()
+                .zip(data_matrix.column(j).iter())
+                .filter(|(x, y)| !x.is_nan() && !y.is_nan())
+                .map(|(x, y)| (*x, *y))
+                .unzip();
+            if x.len() < 2 {
+                return Err(
+                    format!(
+                        "Not enough data to calculate distance correlation of {} and {}",
+                        var_names[i],
+                        var_names[j]
+                    )
+                );
+            }
+
+            let (value, p_value) = if i == j {
+                (1.0, 0.0)
+            } else {
+                let a = double_centered_distances(&x);
+                let b = double_centered_distances(&y);
+                let value = distance_correlation(&a, &b);
+                let p_value = match seed {
+                    Some(seed) if permutations > 0 => {
+                        permutation_p_value(&a, &b, permutations, seed)
+                    }
+                    _ => f64::NAN,
+                };
+                (value, p_value)
+            };
+
+            for (row, col) in [(i, j), (j, i)] {
+                correlations
+                    .entry(var_names[row].clone())
+                    .or_default()
+                    .insert(var_names[col].clone(), value);
+                sig_values
+                    .entry(var_names[row].clone())
+                    .or_default()
+                    .insert(var_names[col].clone(), p_value);
+            }
+        }
+    }
+
+    Ok(DistanceCorrelationMatrix {
+        correlations,
+        sig_values: (permutations > 0).then_some(sig_values),
+        permutations,
+        seed,
+    })
+}
+
+// Pairwise distance matrix with row, column and grand means removed
+fn double_centered_distances(values: &[f64]) -> DMatrix<f64> {
+    let n = values.len();
+    let distances = DMatrix::from_fn(n, n, |i, j| (values[i] - values[j]).abs());
+    let row_means: Vec<f64> = distances
+        .row_iter()
+        .map(|row| row.sum() / (n as f64))
+        .collect();
+    let grand_mean = row_means.iter().sum::<f64>() / (n as f64);
+
+    // Distances are symmetric, so row means double as column means
+    DMatrix::from_fn(n, n, |i, j| distances[(i, j)] - row_means[i] - row_means[j] + grand_mean)
+}
+
+fn distance_correlation(a: &DMatrix<f64>, b: &DMatrix<f64>) -> f64 {
+    let dcov_xy = a.dot(b);
+    let dvar_x = a.dot(a);
+    let dvar_y = b.dot(b);
+
+    if dvar_x <= 0.0 || dvar_y <= 0.0 {
+        return 0.0;
+    }
+
+    (dcov_xy / (dvar_x * dvar_y).sqrt()).max(0.0).sqrt()
+}
+
+// Permute the cases of y and count permuted distance covariances at least as large as observed;
+// the observed ordering is counted as one of the permutations
+fn permutation_p_value(a: &DMatrix<f64>, b: &DMatrix<f64>, permutations: usize, seed: u64) -> f64 {
+    let n = a.nrows();
+    let observed = a.dot(b);
+    let tolerance = observed.abs() * 1e-12;
+
+    let exceeded = run_replicates(seed, permutations, |_, rng| {
+        let mut order: Vec<usize> = (0..n).collect();
+        order.shuffle(rng);
+
+        let mut permuted = 0.0;
+        for (j, &pj) in order.iter().enumerate() {
+            for (i, &pi) in order.iter().enumerate() {
+                permuted += a[(i, j)] * b[(pi, pj)];
+            }
+        }
+        permuted >= observed - tolerance
+    });
+
+    let count = exceeded.iter().filter(|exceeded| **exceeded).count();
+    ((count + 1) as f64) / ((permutations + 1) as f64)
+}
@@ -0,0 +1,280 @@
+use nalgebra::DMatrix;
+use rand::Rng;
+use statrs::distribution::{ ContinuousCDF, Normal };
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ EapCaseScore, EapScores },
+};
+use crate::utils::rng::{ resolve_seed, run_replicates };
+
+use super::core::{
+    extract_case_levels,
+    extract_correlation_matrix,
+    extract_factors,
+    level_positions,
+    robust_symmetric_eigen,
+    selected_case_numbers,
+};
+
+// Quadrature points per factor when EAPPoints is not set
+pub const DEFAULT_EAP_POINTS: usize = 15;
+
+// Quadrature grows as points^factors; larger grids are refused rather than run for minutes
+pub const MAX_EAP_GRID: usize = 20000;
+
+// Variables with more levels than this are not treated as ordered categories
+pub const MAX_ORDINAL_LEVELS: usize = 20;
+
+// Expected a posteriori factor scores from the ordinal response patterns. Each variable follows
+// a normal-ogive model with the unrotated loadings, thresholds from its marginal proportions
+// and a standard normal prior on the factors, as the other score methods do. The loadings are
+// only those of the latent responses when they come from the polychoric matrix.
+pub fn calculate_eap_scores(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<EapScores, String> {
+    if !config.extraction.polychoric {
+        return Err(
+            "EAP scores need loadings from the polychoric matrix; select Polychoric".to_string()
+        );
+    }
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
+    let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
+
+    let mut responses = Vec::with_capacity(var_names.len());
+    let mut n_levels = Vec::with_capacity(var_names.len());
+    for var_name in &var_names {
+        let levels = extract_case_levels(data, config, var_name);
+        let (order, positions) = level_positions(config, var_name, &levels);
+        if order.len() < 2 {
+            return Err(format!("'{}' needs at least 2 observed levels for EAP scores", var_name));
+        }
+        if order.len() > MAX_ORDINAL_LEVELS {
+            return Err(
+                format!(
+                    "'{}' has {} levels, too many to score as an ordinal variable",
+                    var_name,
+                    order.len()
+                )
+            );
+        }
+        responses.push(positions);
+        n_levels.push(order.len());
+    }
+
+    build_eap_scores(
+        &extraction_result.loadings,
+        &responses,
+        &n_levels,
+        &selected_case_numbers(data, config),
+        eap_points(config),
+        (plausible_values(config), resolve_seed(config))
+    )
+}
+
+pub fn eap_points(config: &FactorAnalysisConfig) -> usize {
+    match config.scores.eap_points {
+        Some(points) if points > 1 => points as usize,
+        _ => DEFAULT_EAP_POINTS,
+    }
+}
+
+pub fn plausible_values(config: &FactorAnalysisConfig) -> usize {
+    config.scores.plausible_values.filter(|draws| *draws > 0).unwrap_or(0) as usize
+}
+
+// responses[j][i] is the level position of case i on variable j; cases without any observed
+// level get no score. Plausible values are grid points drawn with their posterior probability,
+// each case on its own ChaCha stream of the seed so the draws don't depend on the threads.
+pub fn build_eap_scores(
+    loadings: &DMatrix<f64>,
+    responses: &[Vec<Option<usize>>],
+    n_levels: &[usize],
+    case_numbers: &[usize],
+    n_points: usize,
+    (draws, seed): (usize, u64)
+) -> Result<EapScores, String> {
+    let n_vars = loadings.nrows();
+    let n_factors = loadings.ncols();
+    if responses.len() != n_vars || n_levels.len() != n_vars {
+        return Err("Need one response column and level count per variable".to_string());
+    }
+    if n_factors == 0 {
+        return Err("EAP scores need at least one factor".to_string());
+    }
+    let grid_size = (n_points as f64).powi(n_factors as i32);
+    if grid_size > (MAX_EAP_GRID as f64) {
+        return Err(
+            format!(
+                "{} quadrature points for {} factors exceed the limit of {} grid points",
+                n_points,
+                n_factors,
+                MAX_EAP_GRID
+            )
+        );
+    }
+    let grid_size = grid_size as usize;
+
+    // Product grid of the factor values with the log of their prior weights
+    let (nodes, weights) = gauss_hermite(n_points);
+    let mut grid = DMatrix::zeros(grid_size, n_factors);
+    let mut log_prior = vec![0.0; grid_size];
+    for g in 0..grid_size {
+        let mut index = g;
+        for k in 0..n_factors {
+            grid[(g, k)] = nodes[index % n_points];
+            log_prior[g] += weights[index % n_points].ln();
+            index /= n_points;
+        }
+    }
+
+    // Log probability of every level of every variable at every grid point
+    let standard_normal = Normal::new(0.0, 1.0).map_err(|e| e.to_string())?;
+    let linear = &grid * loadings.transpose();
+    let mut log_probabilities = Vec::with_capacity(n_vars);
+    for j in 0..n_vars {
+        let thresholds = level_thresholds(&responses[j], n_levels[j], &standard_normal);
+        let residual_sd = (1.0 - loadings.row(j).norm_squared()).max(0.01).sqrt();
+        let table = DMatrix::from_fn(grid_size, n_levels[j], |g, level| {
+            let upper = thresholds.get(level).copied().unwrap_or(f64::INFINITY);
+            let lower = if level == 0 { f64::NEG_INFINITY } else { thresholds[level - 1] };
+            let probability =
+                standard_normal.cdf((upper - linear[(g, j)]) / residual_sd) -
+                standard_normal.cdf((lower - linear[(g, j)]) / residual_sd);
+            probability.max(f64::MIN_POSITIVE).ln()
+        });
+        log_probabilities.push(table);
+    }
+
+    let scored = run_replicates(seed, case_numbers.len(), |i, rng| {
+        let observed: Vec<(usize, usize)> = (0..n_vars)
+            .filter_map(|j| responses[j].get(i).copied().flatten().map(|level| (j, level)))
+            .collect();
+        if observed.is_empty() {
+            return None;
+        }
+
+        let log_posterior: Vec<f64> = (0..grid_size)
+            .map(|g| {
+                log_prior[g] +
+                    observed
+                        .iter()
+                        .map(|&(j, level)| log_probabilities[j][(g, level)])
+                        .sum::<f64>()
+            })
+            .collect();
+        let max_log = log_posterior.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let posterior: Vec<f64> = log_posterior
+            .iter()
+            .map(|value| (value - max_log).exp())
+            .collect();
+        let total: f64 = posterior.iter().sum();
+
+        let mut scores = vec![0.0; n_factors];
+        let mut second_moments = vec![0.0; n_factors];
+        for (g, weight) in posterior.iter().enumerate() {
+            for k in 0..n_factors {
+                scores[k] += (weight / total) * grid[(g, k)];
+                second_moments[k] += (weight / total) * grid[(g, k)].powi(2);
+            }
+        }
+        let posterior_sd = scores
+            .iter()
+            .zip(&second_moments)
+            .map(|(mean, second)| (second - mean * mean).max(0.0).sqrt())
+            .collect();
+
+        let plausible_values = (0..draws)
+            .map(|_| {
+                let mut remaining = rng.gen::<f64>() * total;
+                let g = posterior
+                    .iter()
+                    .position(|weight| {
+                        remaining -= weight;
+                        remaining < 0.0
+                    })
+                    .unwrap_or(grid_size - 1);
+                grid.row(g).iter().copied().collect()
+            })
+            .collect();
+
+        Some(EapCaseScore { case_number: case_numbers[i], scores, posterior_sd, plausible_values })
+    });
+    let cases: Vec<EapCaseScore> = scored.into_iter().flatten().collect();
+
+    if cases.is_empty() {
+        return Err("No cases with observed levels to score".to_string());
+    }
+
+    Ok(EapScores {
+        n_points,
+        marginal_reliability: (0..n_factors)
+            .map(|k| marginal_reliability(&cases, k))
+            .collect(),
+        cases,
+    })
+}
+
+// Thresholds between consecutive levels from the cumulative proportions of the observed levels
+pub fn level_thresholds(responses: &[Option<usize>], n_levels: usize, normal: &Normal) -> Vec<f64> {
+    let mut counts = vec![0usize; n_levels];
+    for level in responses.iter().flatten() {
+        counts[*level] += 1;
+    }
+    let n = counts.iter().sum::<usize>().max(1) as f64;
+
+    let mut cumulative = 0usize;
+    counts[..n_levels - 1]
+        .iter()
+        .map(|count| {
+            cumulative += count;
+            normal.inverse_cdf(((cumulative as f64) / n).clamp(1e-6, 1.0 - 1e-6))
+        })
+        .collect()
+}
+
+// Variance of the scores over the variance of the scores plus the mean posterior variance
+fn marginal_reliability(cases: &[EapCaseScore], k: usize) -> f64 {
+    let n = cases.len() as f64;
+    let mean = cases.iter().map(|case| case.scores[k]).sum::<f64>() / n;
+    let score_variance = cases
+        .iter()
+        .map(|case| (case.scores[k] - mean).powi(2))
+        .sum::<f64>() / n;
+    let error_variance = cases
+        .iter()
+        .map(|case| case.posterior_sd[k].powi(2))
+        .sum::<f64>() / n;
+
+    if score_variance + error_variance > 0.0 {
+        score_variance / (score_variance + error_variance)
+    } else {
+        0.0
+    }
+}
+
+// Gauss-Hermite nodes and weights for the standard normal density (Golub-Welsch)
+pub fn gauss_hermite(n_points: usize) -> (Vec<f64>, Vec<f64>) {
+    let jacobi = DMatrix::from_fn(n_points, n_points, |i, j| {
+        if i.abs_diff(j) == 1 { (i.max(j) as f64).sqrt() } else { 0.0 }
+    });
+    let eigen = robust_symmetric_eigen(&jacobi);
+
+    let mut order: Vec<usize> = (0..n_points).collect();
+    order.sort_by(|&a, &b| {
+        eigen.eigenvalues[a].partial_cmp(&eigen.eigenvalues[b]).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    (
+        order
+            .iter()
+            .map(|&k| eigen.eigenvalues[k])
+            .collect(),
+        order
+            .iter()
+            .map(|&k| eigen.eigenvectors[(0, k)].powi(2))
+            .collect(),
+    )
+}
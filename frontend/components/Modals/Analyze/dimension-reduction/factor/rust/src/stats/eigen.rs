@@ -0,0 +1,238 @@
+use nalgebra::{ DMatrix, DVector, Dyn, SymmetricEigen };
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{
+        CorrelationMatrix,
+        EigenDecomposition,
+        EigenDiagnostics,
+        EigenSolver,
+        NegativeEigenvalue,
+    },
+};
+
+use super::core::{ analyzes_covariance, extract_correlation_matrix, matrix_to_rows };
+
+// Asymmetry above this is reported; smaller differences are rounding in the matrix products
+const ASYMMETRY_TOLERANCE: f64 = 1e-10;
+// Eigenvalues below -NEGATIVE_TOLERANCE count as negative rather than as a rounded zero
+const NEGATIVE_TOLERANCE: f64 = 1e-10;
+const JACOBI_MAX_SWEEPS: usize = 100;
+// Eigenvector weights summing to less than this in magnitude are taken to sum to zero
+const SIGN_TOLERANCE: f64 = 1e-10;
+
+// Decompose the average of A and A' with the implicit QR solver, switching to cyclic Jacobi
+// when QR does not converge within its iteration limit
+pub fn robust_symmetric_eigen(matrix: &DMatrix<f64>) -> SymmetricEigen<f64, Dyn> {
+    decompose_symmetric(matrix).0
+}
+
+pub fn decompose_symmetric(matrix: &DMatrix<f64>) -> (SymmetricEigen<f64, Dyn>, EigenSolver) {
+    let _span = crate::trace_span!("symmetric_eigen", size = matrix.nrows());
+    let (symmetric, _) = symmetrize(matrix);
+    let max_iterations = 1000.max(50 * symmetric.nrows());
+
+    match SymmetricEigen::try_new(symmetric.clone(), f64::EPSILON, max_iterations) {
+        Some(eigen) if eigen.eigenvalues.iter().all(|value| value.is_finite()) => {
+            (eigen, EigenSolver::ImplicitQR)
+        }
+        _ => {
+            let (eigen, converged) = jacobi_eigen(&symmetric, JACOBI_MAX_SWEEPS);
+            crate::trace_event!("implicit QR failed, used Jacobi", converged = converged);
+            (eigen, if converged { EigenSolver::Jacobi } else { EigenSolver::JacobiUnconverged })
+        }
+    }
+}
+
+// (A + A') / 2 and the largest absolute difference between A and A'
+pub fn symmetrize(matrix: &DMatrix<f64>) -> (DMatrix<f64>, f64) {
+    let transpose = matrix.transpose();
+    let asymmetry = (matrix - &transpose).amax();
+    ((matrix + transpose) * 0.5, asymmetry)
+}
+
+// Cyclic Jacobi rotations until the off-diagonal mass vanishes relative to the whole matrix;
+// slower than QR but it converges on every symmetric matrix
+pub fn jacobi_eigen(matrix: &DMatrix<f64>, max_sweeps: usize) -> (SymmetricEigen<f64, Dyn>, bool) {
+    let n = matrix.nrows();
+    let mut a = matrix.clone();
+    let mut vectors = DMatrix::identity(n, n);
+    let tolerance = f64::EPSILON * a.norm().max(f64::MIN_POSITIVE);
+    let mut converged = false;
+
+    for _ in 0..max_sweeps {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+            .map(|(i, j)| a[(i, j)].powi(2))
+            .sum();
+        if off_diagonal.sqrt() <= tolerance {
+            converged = true;
+            break;
+        }
+
+        for p in 0..n {
+            for q in (p + 1)..n {
+                if a[(p, q)].abs() <= f64::MIN_POSITIVE {
+                    continue;
+                }
+
+                // Rotation angle that zeroes a_pq, taking the smaller root for stability
+                let theta = (a[(q, q)] - a[(p, p)]) / (2.0 * a[(p, q)]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for k in 0..n {
+                    let (akp, akq) = (a[(k, p)], a[(k, q)]);
+                    a[(k, p)] = c * akp - s * akq;
+                    a[(k, q)] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[(p, k)], a[(q, k)]);
+                    a[(p, k)] = c * apk - s * aqk;
+                    a[(q, k)] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (vectors[(k, p)], vectors[(k, q)]);
+                    vectors[(k, p)] = c * vkp - s * vkq;
+                    vectors[(k, q)] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    let eigen = SymmetricEigen {
+        eigenvalues: DVector::from_fn(n, |i, _| a[(i, i)]),
+        eigenvectors: vectors,
+    };
+    (eigen, converged)
+}
+
+// Eigenvalues in decreasing order with their eigenvectors as the columns of the matrix. Each
+// eigenvector is reflected so its weights sum to a positive value, or, when they sum to zero,
+// so its largest weight is positive; the same matrix always gives the same vectors.
+pub fn sorted_symmetric_eigen(matrix: &DMatrix<f64>) -> (Vec<f64>, DMatrix<f64>, EigenSolver) {
+    let (eigen, solver) = decompose_symmetric(matrix);
+    let mut order: Vec<usize> = (0..eigen.eigenvalues.len()).collect();
+    order.sort_by(|&a, &b| {
+        eigen.eigenvalues[b].partial_cmp(&eigen.eigenvalues[a]).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let eigenvalues = order
+        .iter()
+        .map(|&k| eigen.eigenvalues[k])
+        .collect();
+    let mut eigenvectors = eigen.eigenvectors.select_columns(&order);
+    for mut vector in eigenvectors.column_iter_mut() {
+        let sum = vector.sum();
+        let reflect = if sum.abs() > SIGN_TOLERANCE {
+            sum < 0.0
+        } else {
+            vector[vector.iamax()] < 0.0
+        };
+        if reflect {
+            vector.neg_mut();
+        }
+    }
+
+    (eigenvalues, eigenvectors, solver)
+}
+
+// Eigen decomposition of the matrix the configuration analyzes, the covariance matrix for a
+// covariance extraction and the correlation matrix otherwise
+pub fn calculate_eigen_decomposition(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<EigenDecomposition, String> {
+    let matrix_type = if analyzes_covariance(config) { "covariance" } else { "correlation" };
+    let (matrix, var_names, _) = extract_correlation_matrix(data, config, matrix_type)?;
+
+    build_eigen_decomposition(&matrix, &var_names)
+}
+
+// Decomposition of a correlation table, with the rows of the eigenvectors in the order of
+// var_names rather than the table's map order
+pub fn eigen_decomposition(
+    table: &CorrelationMatrix,
+    var_names: &[String]
+) -> Result<EigenDecomposition, String> {
+    let value = |row_var: &String, col_var: &String| {
+        table.correlations
+            .get(row_var)
+            .and_then(|row| row.get(col_var))
+            .copied()
+            .ok_or_else(|| format!("No correlation of {} with {} in the table", row_var, col_var))
+    };
+    let mut matrix = DMatrix::zeros(var_names.len(), var_names.len());
+    for (i, row_var) in var_names.iter().enumerate() {
+        for (j, col_var) in var_names.iter().enumerate() {
+            matrix[(i, j)] = value(row_var, col_var)?;
+        }
+    }
+
+    build_eigen_decomposition(&matrix, var_names)
+}
+
+pub fn build_eigen_decomposition(
+    matrix: &DMatrix<f64>,
+    var_names: &[String]
+) -> Result<EigenDecomposition, String> {
+    if !matrix.is_square() || matrix.nrows() != var_names.len() {
+        return Err(
+            format!(
+                "A {} x {} matrix can't be decomposed for {} variables",
+                matrix.nrows(),
+                matrix.ncols(),
+                var_names.len()
+            )
+        );
+    }
+    if matrix.iter().any(|value| !value.is_finite()) {
+        return Err("Matrix has missing or infinite values".to_string());
+    }
+
+    let (eigenvalues, eigenvectors, solver) = sorted_symmetric_eigen(matrix);
+    Ok(EigenDecomposition {
+        variables: var_names.to_vec(),
+        eigenvalues,
+        eigenvectors: matrix_to_rows(&eigenvectors),
+        solver,
+    })
+}
+
+// How the analyzed matrix decomposed: the solver that succeeded, how far the matrix was from
+// symmetric, and any negative eigenvalues, whose square roots are taken as zero in the loadings
+pub fn calculate_eigen_diagnostics(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<EigenDiagnostics, String> {
+    let matrix_type = if analyzes_covariance(config) { "covariance" } else { "correlation" };
+    let (matrix, _, _) = extract_correlation_matrix(data, config, matrix_type)?;
+
+    Ok(build_eigen_diagnostics(&matrix))
+}
+
+pub fn build_eigen_diagnostics(matrix: &DMatrix<f64>) -> EigenDiagnostics {
+    let (_, asymmetry) = symmetrize(matrix);
+    let (eigen, solver) = decompose_symmetric(matrix);
+
+    let mut eigenvalues: Vec<f64> = eigen.eigenvalues.iter().copied().collect();
+    eigenvalues.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+
+    let negative_eigenvalues = eigenvalues
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value < -NEGATIVE_TOLERANCE)
+        .map(|(index, value)| NegativeEigenvalue { position: index + 1, value: *value })
+        .collect::<Vec<_>>();
+
+    EigenDiagnostics {
+        solver,
+        asymmetry,
+        symmetrized: asymmetry > ASYMMETRY_TOLERANCE,
+        smallest_eigenvalue: eigenvalues.last().copied().unwrap_or(f64::NAN),
+        positive_definite: eigenvalues.last().is_some_and(|value| *value > 0.0),
+        negative_eigenvalues,
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp::Ordering;
+
+use crate::models::{
+    config::{ CategoricalCoding, CategoricalVariable, FactorAnalysisConfig },
+    data::DataValue,
+};
+
+// Encoding declared for a variable, if any
+pub fn categorical_variable<'a>(
+    config: &'a FactorAnalysisConfig,
+    var_name: &str
+) -> Option<&'a CategoricalVariable> {
+    config.main.categorical_vars.iter().find(|variable| variable.name == var_name)
+}
+
+// Level of a value as text; None for missing values and blank text
+pub fn level_of(value: &DataValue) -> Option<String> {
+    match value {
+        DataValue::Number(number) if number.is_nan() => None,
+        DataValue::Number(number) => Some(number.to_string()),
+        DataValue::Text(text) if text.trim().is_empty() => None,
+        DataValue::Text(text) => Some(text.trim().to_string()),
+        DataValue::Boolean(flag) => Some(flag.to_string()),
+        DataValue::Null => None,
+    }
+}
+
+// Encoded columns of a categorical variable as (column name, values), with NaN for missing cases
+pub fn encode_categorical(
+    variable: &CategoricalVariable,
+    levels_by_case: &[Option<String>]
+) -> Result<Vec<(String, Vec<f64>)>, String> {
+    let levels = resolve_levels(variable, levels_by_case)?;
+    let positions: Vec<Option<usize>> = levels_by_case
+        .iter()
+        .map(|level| level.as_ref().and_then(|level| levels.iter().position(|l| l == level)))
+        .collect();
+
+    match variable.coding {
+        CategoricalCoding::Dummy | CategoricalCoding::Effect => {
+            if levels.len() < 2 {
+                return Err(
+                    format!("Categorical variable {} needs at least 2 levels", variable.name)
+                );
+            }
+
+            let reference = match &variable.reference {
+                Some(reference) =>
+                    levels
+                        .iter()
+                        .position(|level| level == reference)
+                        .ok_or_else(|| {
+                            format!(
+                                "Reference level {} of {} is not among its levels",
+                                reference,
+                                variable.name
+                            )
+                        })?,
+                None => levels.len() - 1,
+            };
+            let reference_value = if variable.coding == CategoricalCoding::Effect {
+                -1.0
+            } else {
+                0.0
+            };
+
+            Ok(
+                levels
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != reference)
+                    .map(|(index, level)| {
+                        let column = positions
+                            .iter()
+                            .map(|position| {
+                                match position {
+                                    Some(position) if *position == index => 1.0,
+                                    Some(position) if *position == reference => reference_value,
+                                    Some(_) => 0.0,
+                                    None => f64::NAN,
+                                }
+                            })
+                            .collect();
+                        (format!("{}={}", variable.name, level), column)
+                    })
+                    .collect()
+            )
+        }
+        CategoricalCoding::Ordered => {
+            let column = positions
+                .iter()
+                .map(|position| position.map_or(f64::NAN, |position| (position + 1) as f64))
+                .collect();
+            Ok(vec![(variable.name.clone(), column)])
+        }
+        CategoricalCoding::Rank => {
+            // Cases sharing a level share the mean of the ranks they occupy
+            let mut counts = vec![0usize; levels.len()];
+            for position in positions.iter().flatten() {
+                counts[*position] += 1;
+            }
+            let mut mid_ranks = Vec::with_capacity(levels.len());
+            let mut preceding = 0usize;
+            for count in &counts {
+                mid_ranks.push((preceding as f64) + ((*count as f64) + 1.0) / 2.0);
+                preceding += count;
+            }
+
+            let column = positions
+                .iter()
+                .map(|position| position.map_or(f64::NAN, |position| mid_ranks[position]))
+                .collect();
+            Ok(vec![(variable.name.clone(), column)])
+        }
+    }
+}
+
+// Observed levels in natural order: numerically when they are all numbers, otherwise as text
+pub fn distinct_levels(levels_by_case: &[Option<String>]) -> Vec<String> {
+    let mut levels: Vec<String> = levels_by_case.iter().flatten().cloned().collect();
+    let all_numeric = levels.iter().all(|level| level.parse::<f64>().is_ok());
+    levels.sort_by(|a, b| {
+        if all_numeric {
+            let a = a.parse::<f64>().unwrap_or(f64::NAN);
+            let b = b.parse::<f64>().unwrap_or(f64::NAN);
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        } else {
+            a.cmp(b)
+        }
+    });
+    levels.dedup();
+    levels
+}
+
+// Declared or natural level order of a variable, with the position of each case's level in it
+pub fn level_positions(
+    config: &FactorAnalysisConfig,
+    var_name: &str,
+    levels_by_case: &[Option<String>]
+) -> (Vec<String>, Vec<Option<usize>>) {
+    let order = categorical_variable(config, var_name)
+        .and_then(|variable| variable.levels.clone())
+        .unwrap_or_else(|| distinct_levels(levels_by_case));
+    let positions = levels_by_case
+        .iter()
+        .map(|level| {
+            level.as_ref().and_then(|level| order.iter().position(|known| known == level))
+        })
+        .collect();
+
+    (order, positions)
+}
+
+// Declared level order, or the observed levels in natural order
+fn resolve_levels(
+    variable: &CategoricalVariable,
+    levels_by_case: &[Option<String>]
+) -> Result<Vec<String>, String> {
+    if let Some(levels) = &variable.levels {
+        if let Some(unknown) = levels_by_case
+            .iter()
+            .flatten()
+            .find(|level| !levels.contains(level))
+        {
+            return Err(
+                format!(
+                    "Value {} of {} is not among its declared levels",
+                    unknown,
+                    variable.name
+                )
+            );
+        }
+        return Ok(levels.clone());
+    }
+
+    let levels = distinct_levels(levels_by_case);
+    if levels.is_empty() {
+        return Err(format!("Categorical variable {} has no valid values", variable.name));
+    }
+
+    Ok(levels)
+}
@@ -0,0 +1,147 @@
+use nalgebra::DMatrix;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use statrs::distribution::{ ChiSquared, ContinuousCDF };
+
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    data::AnalysisData,
+    result::{ ChiSquareDifference, FactorCountComparison, FactorCountModel },
+};
+
+use super::core::{
+    extract_correlation_matrix,
+    extract_factors,
+    model_fit_statistics,
+    require_model_degrees_of_freedom,
+};
+
+// Maximum likelihood models for every factor count in the configured range
+pub fn calculate_factor_count_comparison(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<FactorCountComparison, String> {
+    let (corr_matrix, var_names, n_obs) = extract_correlation_matrix(data, config, "correlation")?;
+
+    build_factor_count_comparison(&corr_matrix, &var_names, n_obs, config)
+}
+
+// Chi-square, RMSEA and BIC follow psych's fa.stats; AIC is the matching chi-square - 2 df.
+// Each model is tested against the one with a factor fewer by the drop in chi-square on the
+// drop in degrees of freedom.
+pub fn build_factor_count_comparison(
+    corr_matrix: &DMatrix<f64>,
+    var_names: &[String],
+    n_obs: usize,
+    config: &FactorAnalysisConfig
+) -> Result<FactorCountComparison, String> {
+    if config.extraction.method != ExtractionMethod::MaximumLikelihood {
+        return Err("Factor count comparison needs maximum likelihood extraction".to_string());
+    }
+    let (min_factors, max_factors) = factor_count_range(corr_matrix.nrows(), config)?;
+
+    // A count whose extraction fails is kept as a row without fit statistics, so the other
+    // counts are still compared
+    let fit_model = |&n_factors: &usize| -> FactorCountModel {
+        let mut model_config = config.clone();
+        model_config.extraction.eigen = false;
+        model_config.extraction.factor = true;
+        model_config.extraction.max_factors = Some(n_factors as i32);
+        let extraction_result = match extract_factors(corr_matrix, &model_config, var_names) {
+            Ok(extraction_result) => extraction_result,
+            Err(e) => {
+                return failed_model(n_factors, corr_matrix.nrows(), e);
+            }
+        };
+        let fit = model_fit_statistics(corr_matrix, &extraction_result.loadings, n_obs);
+
+        FactorCountModel {
+            factors: n_factors,
+            chi_square: fit.statistic,
+            df: fit.dof,
+            significance: fit.pval,
+            aic: fit.statistic
+                .filter(|_| fit.dof > 0)
+                .map(|chi_square| chi_square - 2.0 * (fit.dof as f64)),
+            bic: fit.bic,
+            rmsea: fit.rmsea,
+            converged: extraction_result.converged,
+            difference: None,
+            error: None,
+        }
+    };
+
+    // The models share nothing but the matrix, so they are fitted in parallel
+    let factor_counts: Vec<usize> = (min_factors..=max_factors).collect();
+    #[cfg(feature = "parallel")]
+    let mut models: Vec<FactorCountModel> = factor_counts
+        .par_iter()
+        .map(fit_model)
+        .collect();
+    #[cfg(not(feature = "parallel"))]
+    let mut models: Vec<FactorCountModel> = factor_counts
+        .iter()
+        .map(fit_model)
+        .collect();
+
+    for k in 1..models.len() {
+        let (fewer, more) = (&models[k - 1], &models[k]);
+        let df = fewer.df - more.df;
+        models[k].difference = match (fewer.chi_square, more.chi_square) {
+            (Some(fewer_chi_square), Some(more_chi_square)) if df > 0 => {
+                let chi_square = fewer_chi_square - more_chi_square;
+                let significance = ChiSquared::new(df as f64)
+                    .map(|distribution| 1.0 - distribution.cdf(chi_square.max(0.0)))
+                    .unwrap_or(f64::NAN);
+                Some(ChiSquareDifference { chi_square, df, significance })
+            }
+            _ => None,
+        };
+    }
+
+    Ok(FactorCountComparison { n: n_obs, models })
+}
+
+// Row of a factor count whose extraction failed, with the degrees of freedom it would have had
+fn failed_model(n_factors: usize, n_vars: usize, error: String) -> FactorCountModel {
+    let (p, k) = (n_vars as i64, n_factors as i64);
+
+    FactorCountModel {
+        factors: n_factors,
+        chi_square: None,
+        df: ((p - k).pow(2) - (p + k)) / 2,
+        significance: None,
+        aic: None,
+        bic: None,
+        rmsea: None,
+        converged: false,
+        difference: None,
+        error: Some(error),
+    }
+}
+
+// Factor counts to fit, capped at the largest count with non-negative degrees of freedom
+pub fn factor_count_range(
+    n_vars: usize,
+    config: &FactorAnalysisConfig
+) -> Result<(usize, usize), String> {
+    let largest = (1..n_vars)
+        .take_while(|&k| require_model_degrees_of_freedom(k, n_vars).is_ok())
+        .last()
+        .ok_or_else(|| format!("{} variables leave no degrees of freedom for a factor", n_vars))?;
+
+    let (min_factors, max_factors) = match &config.extraction.factor_count_range {
+        Some(range) if range.min >= 1 && range.max >= range.min => {
+            (range.min as usize, (range.max as usize).min(largest))
+        }
+        Some(_) => {
+            return Err("Factor count range needs 1 <= Min <= Max".to_string());
+        }
+        None => (1, largest),
+    };
+    if min_factors > max_factors {
+        return Err(format!("{} variables allow at most {} factors", n_vars, largest));
+    }
+
+    Ok((min_factors, max_factors))
+}
@@ -5,11 +5,53 @@ use crate::models::{
     result::ExtractionResult,
 };
 
+use super::core::{
+    cache_enabled,
+    cached_extraction,
+    require_model_degrees_of_freedom,
+    robust_symmetric_eigen,
+    sorted_symmetric_eigen,
+};
+
+// Largest communality change accepted as converged when Converge is not set
+pub const DEFAULT_EXTRACTION_CONVERGENCE: f64 = 0.001;
+
+// Convergence tolerance of the iterative extraction methods
+pub fn extraction_convergence(config: &FactorAnalysisConfig) -> f64 {
+    config.extraction.converge.unwrap_or(DEFAULT_EXTRACTION_CONVERGENCE)
+}
+
 // Extract factors using specified method
 pub fn extract_factors(
     matrix: &DMatrix<f64>,
     config: &FactorAnalysisConfig,
     var_names: &[String]
+) -> Result<ExtractionResult, String> {
+    let _span = crate::trace_span!("extract_factors", variables = matrix.nrows());
+    let result = if cache_enabled(config) {
+        cached_extraction(matrix, config, var_names, || {
+            extract_with_method(matrix, config, var_names)
+        })
+    } else {
+        extract_with_method(matrix, config, var_names)
+    };
+
+    if let Ok(extraction) = &result {
+        crate::trace_event!(
+            "extraction finished",
+            factors = extraction.n_factors,
+            iterations = extraction.iterations,
+            converged = extraction.converged,
+            achieved_tolerance = extraction.achieved_tolerance
+        );
+    }
+    result
+}
+
+fn extract_with_method(
+    matrix: &DMatrix<f64>,
+    config: &FactorAnalysisConfig,
+    var_names: &[String]
 ) -> Result<ExtractionResult, String> {
     match config.extraction.method {
         ExtractionMethod::PrincipalComponents =>
@@ -36,7 +78,7 @@ pub fn extract_principal_components(
     let n_vars = matrix.nrows();
 
     // Perform eigenvalue decomposition
-    let eigen = matrix.clone().symmetric_eigen();
+    let eigen = robust_symmetric_eigen(matrix);
 
     // Sort eigenvalues and eigenvectors in descending order
     let mut indices: Vec<usize> = (0..n_vars).collect();
@@ -66,7 +108,7 @@ pub fn extract_principal_components(
     let mut loadings = DMatrix::zeros(n_vars, n_factors);
     for i in 0..n_vars {
         for j in 0..n_factors {
-            loadings[(i, j)] = eigenvectors[(i, j)] * eigenvalues[j].sqrt();
+            loadings[(i, j)] = eigenvectors[(i, j)] * eigenvalues[j].max(0.0).sqrt();
         }
     }
 
@@ -102,6 +144,9 @@ pub fn extract_principal_components(
         cumulative_variance,
         n_factors,
         var_names: var_names.to_vec(),
+        iterations: 0,
+        converged: true,
+        achieved_tolerance: f64::NAN,
     })
 }
 
@@ -145,7 +190,10 @@ pub fn extract_principal_axis_factoring(
                 &r_matrix,
                 config,
                 var_names,
-                communalities
+                communalities,
+                0,
+                false,
+                f64::NAN
             );
         }
     };
@@ -175,11 +223,13 @@ pub fn extract_principal_axis_factoring(
 
     // Iterative solution for communalities
     let max_iterations = config.extraction.max_iter as usize;
-    let convergence_criterion = 0.001;
+    let convergence_criterion = extraction_convergence(config);
+
+    let mut achieved_tolerance = f64::NAN;
 
     for iteration in 0..max_iterations {
         // Perform eigenvalue decomposition on adjusted correlation matrix
-        let eigen = r_matrix.clone().symmetric_eigen();
+        let eigen = robust_symmetric_eigen(&r_matrix);
 
         // Store all eigenvalues for reporting
         let mut all_eigenvalues = Vec::with_capacity(n_vars);
@@ -234,7 +284,8 @@ pub fn extract_principal_axis_factoring(
             let mut loadings = DMatrix::zeros(n_vars, n_factors);
             for i in 0..n_vars {
                 for j in 0..n_factors {
-                    loadings[(i, j)] = sorted_eigenvectors[(i, j)] * sorted_eigenvalues[j].sqrt();
+                    loadings[(i, j)] =
+                        sorted_eigenvectors[(i, j)] * sorted_eigenvalues[j].max(0.0).sqrt();
                 }
             }
 
@@ -262,10 +313,14 @@ pub fn extract_principal_axis_factoring(
                 cumulative_variance,
                 n_factors,
                 var_names: var_names.to_vec(),
+                iterations: iteration + 1,
+                converged: true,
+                achieved_tolerance: max_change,
             });
         }
 
         // Update communalities and correlation matrix for next iteration
+        achieved_tolerance = max_change;
         communalities = new_communalities;
         for i in 0..n_vars {
             r_matrix[(i, i)] = communalities[i];
@@ -274,7 +329,15 @@ pub fn extract_principal_axis_factoring(
 
     // If we reach here, we've hit the maximum iterations without converging
     // Return the best result we have with current communalities
-    extract_factors_from_adjusted_matrix(&r_matrix, config, var_names, communalities)
+    extract_factors_from_adjusted_matrix(
+        &r_matrix,
+        config,
+        var_names,
+        communalities,
+        max_iterations,
+        false,
+        achieved_tolerance
+    )
 }
 
 // Helper function to extract factors from adjusted matrix - Modified to return all eigenvalues
@@ -282,12 +345,15 @@ fn extract_factors_from_adjusted_matrix(
     r_matrix: &DMatrix<f64>,
     config: &FactorAnalysisConfig,
     var_names: &[String],
-    communalities: Vec<f64>
+    communalities: Vec<f64>,
+    iterations: usize,
+    converged: bool,
+    achieved_tolerance: f64
 ) -> Result<ExtractionResult, String> {
     let n_vars = r_matrix.nrows();
 
     // Perform eigenvalue decomposition
-    let eigen = r_matrix.clone().symmetric_eigen();
+    let eigen = robust_symmetric_eigen(r_matrix);
 
     // Store all eigenvalues for reporting
     let mut all_eigenvalues = Vec::with_capacity(n_vars);
@@ -324,7 +390,8 @@ fn extract_factors_from_adjusted_matrix(
     let mut loadings = DMatrix::zeros(n_vars, n_factors);
     for i in 0..n_vars {
         for j in 0..n_factors {
-            loadings[(i, j)] = sorted_eigenvectors[(i, j)] * sorted_eigenvalues[j].sqrt();
+            loadings[(i, j)] =
+                sorted_eigenvectors[(i, j)] * sorted_eigenvalues[j].max(0.0).sqrt();
         }
     }
 
@@ -352,6 +419,9 @@ fn extract_factors_from_adjusted_matrix(
         cumulative_variance,
         n_factors,
         var_names: var_names.to_vec(),
+        iterations,
+        converged,
+        achieved_tolerance,
     })
 }
 
@@ -397,7 +467,7 @@ pub fn extract_unweighted_least_squares(
 
     // Iterative solution for communalities
     let max_iterations = config.extraction.max_iter as usize;
-    let convergence_criterion = 0.001;
+    let convergence_criterion = extraction_convergence(config);
 
     for iteration in 0..max_iterations {
         // Calculate reduced correlation matrix R - diagonal(uniqueness)
@@ -407,7 +477,7 @@ pub fn extract_unweighted_least_squares(
         }
 
         // Perform eigenvalue decomposition on reduced matrix
-        let eigen = reduced_matrix.symmetric_eigen();
+        let eigen = robust_symmetric_eigen(&reduced_matrix);
 
         // Sort eigenvalues and eigenvectors
         let mut indices: Vec<usize> = (0..n_vars).collect();
@@ -455,14 +525,16 @@ pub fn extract_unweighted_least_squares(
             }
         }
 
-        if max_change < convergence_criterion {
-            // Converged, calculate final loadings
+        // At the iteration limit the last iterate is kept, flagged as not converged
+        let converged = max_change < convergence_criterion;
+        if converged || iteration + 1 == max_iterations {
+            // Final loadings
             let mut loadings = DMatrix::zeros(n_vars, n_factors);
             for i in 0..n_vars {
                 for j in 0..n_factors {
                     if sorted_eigenvalues[j] > 0.0 {
                         loadings[(i, j)] =
-                            sorted_eigenvectors[(i, j)] * sorted_eigenvalues[j].sqrt();
+                            sorted_eigenvectors[(i, j)] * sorted_eigenvalues[j].max(0.0).sqrt();
                     }
                 }
             }
@@ -491,6 +563,9 @@ pub fn extract_unweighted_least_squares(
                 cumulative_variance,
                 n_factors,
                 var_names: var_names.to_vec(),
+                iterations: iteration + 1,
+                converged,
+                achieved_tolerance: max_change,
             });
         }
 
@@ -503,9 +578,8 @@ pub fn extract_unweighted_least_squares(
         }
     }
 
-    // If we reach here, we've hit the maximum iterations without converging
-    // Return a result with the current estimates
-    Err("ULS extraction failed to converge within the maximum iterations".to_string())
+    // Only reached when MaxIter allows no iteration at all
+    Err("ULS extraction needs at least one iteration".to_string())
 }
 
 // Generalized Least Squares extraction
@@ -522,7 +596,7 @@ pub fn extract_generalized_least_squares(
 
     // Iterative solution for communalities
     let max_iterations = config.extraction.max_iter as usize;
-    let convergence_criterion = 0.001;
+    let convergence_criterion = extraction_convergence(config);
 
     for iteration in 0..max_iterations {
         // Calculate weight matrix W = R^(-2)
@@ -537,7 +611,7 @@ pub fn extract_generalized_least_squares(
         let weighted_matrix = &r_inverse * matrix * &r_inverse;
 
         // Perform eigenvalue decomposition
-        let eigen = weighted_matrix.symmetric_eigen();
+        let eigen = robust_symmetric_eigen(&weighted_matrix);
 
         // Sort eigenvalues and eigenvectors
         let mut indices: Vec<usize> = (0..n_vars).collect();
@@ -570,7 +644,7 @@ pub fn extract_generalized_least_squares(
         for i in 0..n_vars {
             for j in 0..n_factors {
                 loadings[(i, j)] =
-                    sorted_eigenvectors[(i, j)] * (sorted_eigenvalues[j] - 1.0).sqrt();
+                    sorted_eigenvectors[(i, j)] * (sorted_eigenvalues[j] - 1.0).max(0.0).sqrt();
             }
         }
 
@@ -590,7 +664,9 @@ pub fn extract_generalized_least_squares(
             }
         }
 
-        if max_change < convergence_criterion {
+        // At the iteration limit the last iterate is kept, flagged as not converged
+        let converged = max_change < convergence_criterion;
+        if converged || iteration + 1 == max_iterations {
             // Calculate explained variance
             let total_variance: f64 = sorted_eigenvalues.iter().take(n_vars).sum();
             let explained_variance: Vec<f64> = sorted_eigenvalues
@@ -623,6 +699,9 @@ pub fn extract_generalized_least_squares(
                 cumulative_variance,
                 n_factors,
                 var_names: var_names.to_vec(),
+                iterations: iteration + 1,
+                converged,
+                achieved_tolerance: max_change,
             });
         }
 
@@ -643,8 +722,8 @@ pub fn extract_generalized_least_squares(
         }
     }
 
-    // If we reach here, we've hit the maximum iterations without converging
-    Err("GLS extraction failed to converge within the maximum iterations".to_string())
+    // Only reached when MaxIter allows no iteration at all
+    Err("GLS extraction needs at least one iteration".to_string())
 }
 
 // Maximum Likelihood extraction
@@ -690,98 +769,58 @@ pub fn extract_maximum_likelihood(
         }
     }
 
+    // The initial eigenvalues are those of R, as for the other methods; the retained factors
+    // follow from them, not from the weighted matrix, whose eigenvalues change every iteration
+    let (initial_eigenvalues, _, _) = sorted_symmetric_eigen(matrix);
+    let n_factors = determine_factors_to_retain(&initial_eigenvalues, config);
+    if n_factors == 0 {
+        return Err("No factors meet the retention criteria".to_string());
+    }
+    require_model_degrees_of_freedom(n_factors, n_vars)?;
+
     // Iterative solution for Maximum Likelihood
     let max_iterations = config.extraction.max_iter as usize;
-    let convergence_criterion = 0.001;
+    let convergence_criterion = extraction_convergence(config);
 
+    let mut rate: f64 = 0.0;
     for iteration in 0..max_iterations {
-        // Construct psi matrix (diagonal matrix of uniquenesses)
-        let mut psi_matrix = DMatrix::zeros(n_vars, n_vars);
-        for i in 0..n_vars {
-            psi_matrix[(i, i)] = psi_squared[i];
-        }
-
-        // Calculate psi^(-1) * R * psi^(-1)
-        let mut psi_inv = DMatrix::zeros(n_vars, n_vars);
-        for i in 0..n_vars {
-            psi_inv[(i, i)] = 1.0 / (psi_squared[i] as f64).sqrt();
-        }
-
-        let weighted_r = &psi_inv * matrix * &psi_inv;
-
-        // Perform eigenvalue decomposition
-        let eigen = weighted_r.symmetric_eigen();
-
-        // Sort eigenvalues and eigenvectors
-        let mut indices: Vec<usize> = (0..n_vars).collect();
-        indices.sort_by(|&i, &j|
-            eigen.eigenvalues[j]
-                .partial_cmp(&eigen.eigenvalues[i])
-                .unwrap_or(std::cmp::Ordering::Equal)
-        );
-
-        let sorted_eigenvalues: Vec<f64> = indices
-            .iter()
-            .map(|&i| eigen.eigenvalues[i].max(0.0)) // Ensure non-negative
+        // SQUAREM (Varadhan and Roland, 2008): the fixed point creeps towards the optimum, so
+        // two plain steps set the direction and length of an extrapolated one
+        let (_, _, psi_one) = maximum_likelihood_step(matrix, &psi_squared, n_factors);
+        let (_, _, psi_two) = maximum_likelihood_step(matrix, &psi_one, n_factors);
+        let r: Vec<f64> = (0..n_vars).map(|i| psi_one[i] - psi_squared[i]).collect();
+        let v: Vec<f64> = (0..n_vars).map(|i| psi_two[i] - psi_one[i] - r[i]).collect();
+        let r_norm = r.iter().map(|x| x * x).sum::<f64>().sqrt();
+        let v_norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+        // alpha = -1 is the second plain step
+        let alpha = if v_norm > 0.0 { (-r_norm / v_norm).min(-1.0) } else { -1.0 };
+        let extrapolated: Vec<f64> = (0..n_vars)
+            .map(|i| {
+                let psi = psi_squared[i] - 2.0 * alpha * r[i] + alpha.powi(2) * v[i];
+                psi.clamp(0.005, 1.0)
+            })
             .collect();
+        let (loadings, new_communalities, new_psi_squared) = maximum_likelihood_step(
+            matrix,
+            &extrapolated,
+            n_factors
+        );
 
-        let mut sorted_eigenvectors = DMatrix::zeros(n_vars, n_vars);
-        for i in 0..n_vars {
-            for j in 0..n_vars {
-                sorted_eigenvectors[(i, j)] = eigen.eigenvectors[(i, indices[j])];
-            }
-        }
-
-        // Determine number of factors
-        let n_factors = determine_factors_to_retain(&sorted_eigenvalues, config);
-        if n_factors == 0 {
-            return Err("No factors meet the retention criteria".to_string());
-        }
-
-        // Calculate loadings
-        let mut loadings = DMatrix::zeros(n_vars, n_factors);
-        for i in 0..n_vars {
-            for j in 0..n_factors {
-                loadings[(i, j)] =
-                    (psi_squared[i] as f64).sqrt() *
-                    sorted_eigenvectors[(i, j)] *
-                    (sorted_eigenvalues[j] - 1.0).sqrt();
-            }
-        }
-
-        // Calculate new communality estimates
-        let mut new_communalities = vec![0.0; n_vars];
-        for i in 0..n_vars {
-            for j in 0..n_factors {
-                new_communalities[i] += loadings[(i, j)].powi(2);
-            }
-
-            // Ensure communalities don't exceed 1.0
-            if new_communalities[i] > 0.995 {
-                new_communalities[i] = 0.995;
-            }
-        }
-
-        // Calculate new uniquenesses
-        let mut new_psi_squared = vec![0.0; n_vars];
-        for i in 0..n_vars {
-            new_psi_squared[i] = 1.0 - new_communalities[i];
-            if new_psi_squared[i] < 0.005 {
-                // Avoid very small values
-                new_psi_squared[i] = 0.005;
-            }
+        // A plain step shrinks the distance to the optimum by about `rate`, so one of size d
+        // leaves at most d / (1 - rate) to go; a small step alone would stop short of it. The
+        // estimate varies between iterations, so the slowest one seen is used.
+        if r_norm > 0.0 {
+            let shrink: f64 = (0..n_vars).map(|i| (psi_two[i] - psi_one[i]).powi(2)).sum();
+            rate = rate.max((shrink.sqrt() / r_norm).min(0.99));
         }
-
-        // Check for convergence
-        let mut max_change = 0.0;
-        for i in 0..n_vars {
-            let change = ((new_psi_squared[i] - psi_squared[i]) as f64).abs();
-            if change > max_change {
-                max_change = change;
-            }
-        }
-
-        if max_change < convergence_criterion {
+        let max_change =
+            (0..n_vars)
+                .map(|i| (new_psi_squared[i] - extrapolated[i]).abs())
+                .fold(0.0, f64::max) / (1.0 - rate);
+
+        // At the iteration limit the last iterate is kept, flagged as not converged
+        let converged = max_change < convergence_criterion;
+        if converged || iteration + 1 == max_iterations {
             // Calculate explained variance
             let total_variance: f64 = n_vars as f64; // Total variance is p for correlation matrix
             let explained_variance: Vec<f64> = (0..n_factors)
@@ -805,27 +844,17 @@ pub fn extract_maximum_likelihood(
                 cumulative_variance[i] = cum_sum;
             }
 
-            // Calculate chi-square for ML
-            let n = matrix.nrows() as f64;
-            let ml_function = sorted_eigenvalues
-                .iter()
-                .skip(n_factors)
-                .map(|&e| e.ln() + 1.0 / e - 1.0)
-                .sum::<f64>();
-
-            let chi_square =
-                (n - 1.0 - (2.0 * (n_vars as f64) + 5.0) / 6.0 - (2.0 * (n_factors as f64)) / 3.0) *
-                ml_function;
-            let df = ((n_vars - n_factors).pow(2) - n_vars - n_factors) / 2;
-
             return Ok(ExtractionResult {
                 loadings,
-                eigenvalues: sorted_eigenvalues.into_iter().take(n_factors).collect(),
+                eigenvalues: initial_eigenvalues,
                 communalities: new_communalities,
                 explained_variance,
                 cumulative_variance,
                 n_factors,
                 var_names: var_names.to_vec(),
+                iterations: iteration + 1,
+                converged,
+                achieved_tolerance: max_change,
             });
         }
 
@@ -833,8 +862,53 @@ pub fn extract_maximum_likelihood(
         psi_squared = new_psi_squared;
     }
 
-    // If we reach here, we've hit the maximum iterations without converging
-    Err("ML extraction failed to converge within the maximum iterations".to_string())
+    // Only reached when MaxIter allows no iteration at all
+    Err("ML extraction needs at least one iteration".to_string())
+}
+
+// One update of the uniquenesses: the loadings maximizing the likelihood for the current ones,
+// then the uniquenesses 1 - h2 they imply
+fn maximum_likelihood_step(
+    matrix: &DMatrix<f64>,
+    psi_squared: &[f64],
+    n_factors: usize
+) -> (DMatrix<f64>, Vec<f64>, Vec<f64>) {
+    let n_vars = matrix.nrows();
+
+    // Calculate psi^(-1/2) * R * psi^(-1/2)
+    let mut psi_inv = DMatrix::zeros(n_vars, n_vars);
+    for i in 0..n_vars {
+        psi_inv[(i, i)] = 1.0 / psi_squared[i].sqrt();
+    }
+    let weighted_r = &psi_inv * matrix * &psi_inv;
+    let (sorted_eigenvalues, sorted_eigenvectors, _) = sorted_symmetric_eigen(&weighted_r);
+
+    // Calculate loadings
+    let mut loadings = DMatrix::zeros(n_vars, n_factors);
+    for i in 0..n_vars {
+        for j in 0..n_factors {
+            loadings[(i, j)] =
+                psi_squared[i].sqrt() *
+                sorted_eigenvectors[(i, j)] *
+                (sorted_eigenvalues[j] - 1.0).max(0.0).sqrt();
+        }
+    }
+
+    // Communalities capped below 1 and uniquenesses kept away from 0
+    let communalities: Vec<f64> = (0..n_vars)
+        .map(|i| {
+            (0..n_factors)
+                .map(|j| loadings[(i, j)].powi(2))
+                .sum::<f64>()
+                .min(0.995)
+        })
+        .collect();
+    let new_psi_squared = communalities
+        .iter()
+        .map(|h| (1.0 - h).max(0.005))
+        .collect();
+
+    (loadings, communalities, new_psi_squared)
 }
 
 // Alpha Factoring extraction
@@ -888,7 +962,7 @@ pub fn extract_alpha_factoring(
 
     // Setup for iterations
     let max_iterations = config.extraction.max_iter as usize;
-    let convergence_criterion = 0.001;
+    let convergence_criterion = extraction_convergence(config);
 
     let mut h_current = h_initial.clone();
 
@@ -906,7 +980,7 @@ pub fn extract_alpha_factoring(
         let transformed = &h_sqrt * &r_minus_i * &h_sqrt + identity;
 
         // Perform eigenvalue decomposition
-        let eigen = transformed.symmetric_eigen();
+        let eigen = robust_symmetric_eigen(&transformed);
 
         // Sort eigenvalues and eigenvectors
         let mut indices: Vec<usize> = (0..n_vars).collect();
@@ -958,15 +1032,17 @@ pub fn extract_alpha_factoring(
             }
         }
 
-        if max_change < convergence_criterion {
-            // Converged, calculate final loadings
+        // At the iteration limit the last iterate is kept, flagged as not converged
+        let converged = max_change < convergence_criterion;
+        if converged || iteration + 1 == max_iterations {
+            // Final loadings
             let mut loadings = DMatrix::zeros(n_vars, n_factors);
             for i in 0..n_vars {
                 for j in 0..n_factors {
                     loadings[(i, j)] =
                         h_current[i].sqrt() *
                         sorted_eigenvectors[(i, j)] *
-                        sorted_eigenvalues[j].sqrt();
+                        sorted_eigenvalues[j].max(0.0).sqrt();
                 }
             }
 
@@ -994,6 +1070,9 @@ pub fn extract_alpha_factoring(
                 cumulative_variance,
                 n_factors,
                 var_names: var_names.to_vec(),
+                iterations: iteration + 1,
+                converged,
+                achieved_tolerance: max_change,
             });
         }
 
@@ -1001,8 +1080,8 @@ pub fn extract_alpha_factoring(
         h_current = h_new;
     }
 
-    // If we reach here, we've hit the maximum iterations without converging
-    Err("Alpha factoring failed to converge within the maximum iterations".to_string())
+    // Only reached when MaxIter allows no iteration at all
+    Err("Alpha factoring needs at least one iteration".to_string())
 }
 
 // Image Factoring extraction
@@ -1032,7 +1111,7 @@ pub fn extract_image_factoring(
     let transformed = &s_inv * matrix * &s_inv;
 
     // Perform eigenvalue decomposition
-    let eigen = transformed.symmetric_eigen();
+    let eigen = robust_symmetric_eigen(&transformed);
 
     // Sort eigenvalues and eigenvectors
     let mut indices: Vec<usize> = (0..n_vars).collect();
@@ -1116,5 +1195,8 @@ pub fn extract_image_factoring(
         cumulative_variance,
         n_factors,
         var_names: var_names.to_vec(),
+        iterations: 0,
+        converged: true,
+        achieved_tolerance: f64::NAN,
     })
 }
@@ -0,0 +1,171 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::{ CategoricalCoding, FactorAnalysisConfig },
+    data::{ AnalysisData, VariableMeasure },
+    result::{ GowerColumn, GowerDistances, GowerVariable, GowerVariableKind },
+};
+
+use super::core::{
+    categorical_variable,
+    extract_case_levels,
+    level_positions,
+    resolve_variable_names,
+    selected_case_numbers,
+};
+
+// The distance matrix grows with the square of the case count
+pub const MAX_DISTANCE_CASES: usize = 5000;
+
+// Gower (1971) distances between the selected cases, with each variable scored by its kind.
+// Cases without any observed value are left out; the returned case numbers give the rows.
+pub fn calculate_gower_distances(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<GowerDistances, String> {
+    let var_names = resolve_variable_names(data, config)?;
+    if var_names.is_empty() {
+        return Err("No valid variables found".to_string());
+    }
+
+    let mut columns = Vec::with_capacity(var_names.len());
+    let mut variables = Vec::with_capacity(var_names.len());
+    for var_name in &var_names {
+        let levels = extract_case_levels(data, config, var_name);
+        let kind = variable_kind(data, config, var_name, &levels);
+        columns.push(GowerColumn {
+            values: column_values(config, var_name, &kind, &levels),
+            kind: kind.clone(),
+        });
+        variables.push(GowerVariable { name: var_name.clone(), kind });
+    }
+
+    // Drop cases where every variable is missing
+    let case_numbers = selected_case_numbers(data, config);
+    let observed: Vec<usize> = (0..case_numbers.len())
+        .filter(|&i| columns.iter().any(|column| column.values[i].is_some()))
+        .collect();
+    if observed.is_empty() {
+        return Err("No cases with observed values".to_string());
+    }
+    if observed.len() > MAX_DISTANCE_CASES {
+        return Err(
+            format!(
+                "Gower distances are limited to {} cases, the data has {}",
+                MAX_DISTANCE_CASES,
+                observed.len()
+            )
+        );
+    }
+    for column in &mut columns {
+        column.values = observed.iter().map(|&i| column.values[i]).collect();
+    }
+
+    Ok(GowerDistances {
+        distances: build_gower_distances(&columns)?,
+        variables,
+        case_numbers: observed
+            .iter()
+            .map(|&i| case_numbers[i])
+            .collect(),
+    })
+}
+
+// Mean of the per-variable distances over the variables observed in both cases
+pub fn build_gower_distances(columns: &[GowerColumn]) -> Result<DMatrix<f64>, String> {
+    let n_cases = columns.first().map_or(0, |column| column.values.len());
+    let ranges: Vec<f64> = columns
+        .iter()
+        .map(|column| {
+            let (min, max) = column.values
+                .iter()
+                .flatten()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                    (min.min(*value), max.max(*value))
+                });
+            max - min
+        })
+        .collect();
+
+    let mut distances = DMatrix::zeros(n_cases, n_cases);
+    for i in 0..n_cases {
+        for j in (i + 1)..n_cases {
+            let mut sum = 0.0;
+            let mut weight = 0usize;
+            for (column, range) in columns.iter().zip(&ranges) {
+                let (Some(a), Some(b)) = (column.values[i], column.values[j]) else {
+                    continue;
+                };
+                weight += 1;
+                sum += match column.kind {
+                    GowerVariableKind::Nominal => if a == b { 0.0 } else { 1.0 },
+                    // A variable without spread does not separate any cases
+                    _ if *range <= 0.0 => 0.0,
+                    _ => (a - b).abs() / range,
+                };
+            }
+
+            if weight == 0 {
+                return Err(
+                    format!("Cases in rows {} and {} share no observed variable", i + 1, j + 1)
+                );
+            }
+            let distance = sum / (weight as f64);
+            distances[(i, j)] = distance;
+            distances[(j, i)] = distance;
+        }
+    }
+
+    Ok(distances)
+}
+
+// Declared categorical coding first, then the measurement level in the variable definitions,
+// then whether every observed value is a number
+fn variable_kind(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    var_name: &str,
+    levels: &[Option<String>]
+) -> GowerVariableKind {
+    if let Some(variable) = categorical_variable(config, var_name) {
+        return match variable.coding {
+            CategoricalCoding::Dummy | CategoricalCoding::Effect => GowerVariableKind::Nominal,
+            CategoricalCoding::Rank | CategoricalCoding::Ordered => GowerVariableKind::Ordinal,
+        };
+    }
+
+    let measure = data.target_data_defs
+        .iter()
+        .flatten()
+        .find(|var_def| var_def.name == var_name)
+        .map(|var_def| &var_def.measure);
+    match measure {
+        Some(VariableMeasure::Nominal) => GowerVariableKind::Nominal,
+        Some(VariableMeasure::Ordinal) => GowerVariableKind::Ordinal,
+        _ if levels.iter().flatten().all(|level| level.parse::<f64>().is_ok()) => {
+            GowerVariableKind::Numeric
+        }
+        _ => GowerVariableKind::Nominal,
+    }
+}
+
+fn column_values(
+    config: &FactorAnalysisConfig,
+    var_name: &str,
+    kind: &GowerVariableKind,
+    levels: &[Option<String>]
+) -> Vec<Option<f64>> {
+    if *kind == GowerVariableKind::Numeric {
+        return levels
+            .iter()
+            .map(|level| level.as_ref().and_then(|level| level.parse::<f64>().ok()))
+            .collect();
+    }
+
+    // Ordinal and nominal values become positions in the declared or natural level order
+    let (_, positions) = level_positions(config, var_name, levels);
+    positions
+        .iter()
+        .map(|position| position.map(|position| position as f64))
+        .collect()
+}
@@ -0,0 +1,143 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ CaseInfluence, CaseInfluenceEntry },
+};
+
+use super::core::{
+    extract_raw_data_matrix,
+    handle_missing_values,
+    robust_symmetric_eigen,
+    selected_case_numbers,
+};
+
+// Cases listed when InfluenceTop is not set
+pub const DEFAULT_INFLUENCE_TOP: usize = 10;
+
+// Leave-one-out influence of every analyzed case on the correlation matrix, listing the cases
+// that move the largest eigenvalue the most
+pub fn calculate_case_influence(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<CaseInfluence, String> {
+    let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+    let data_matrix = handle_missing_values(&raw_matrix, config)?;
+
+    // Case numbers of the rows handle_missing_values keeps, for reporting
+    let keeps_incomplete = config.options.replace_mean && !config.options.exclude_list_wise;
+    let case_numbers: Vec<usize> = selected_case_numbers(data, config)
+        .into_iter()
+        .zip(raw_matrix.row_iter())
+        .filter(|(_, row)| keeps_incomplete || !row.iter().any(|value| value.is_nan()))
+        .map(|(case_number, _)| case_number)
+        .collect();
+
+    build_case_influence(&data_matrix, &var_names, &case_numbers, influence_top(config))
+}
+
+pub fn influence_top(config: &FactorAnalysisConfig) -> usize {
+    match config.descriptives.influence_top {
+        Some(top) if top > 0 => top as usize,
+        _ => DEFAULT_INFLUENCE_TOP,
+    }
+}
+
+// Removing case i changes the centered cross products by n / (n - 1) d d', with d the case's
+// deviation from the full-sample means, so each leave-one-out matrix costs O(p^2)
+pub fn build_case_influence(
+    data_matrix: &DMatrix<f64>,
+    var_names: &[String],
+    case_numbers: &[usize],
+    top: usize
+) -> Result<CaseInfluence, String> {
+    let n_cases = data_matrix.nrows();
+    let n_vars = data_matrix.ncols();
+    if n_cases < 4 {
+        return Err("Case influence needs at least 4 cases".to_string());
+    }
+
+    let means: Vec<f64> = data_matrix
+        .column_iter()
+        .map(|column| column.sum() / (n_cases as f64))
+        .collect();
+    let centered = DMatrix::from_fn(n_cases, n_vars, |i, j| data_matrix[(i, j)] - means[j]);
+    let cross_products = centered.transpose() * &centered;
+
+    let full_correlations = scale_to_correlation(&cross_products)?;
+    let full_eigenvalue = largest_eigenvalue(&full_correlations);
+    let scale = (n_cases as f64) / ((n_cases - 1) as f64);
+
+    let mut cases = Vec::with_capacity(n_cases);
+    for i in 0..n_cases {
+        let deviation = centered.row(i).transpose();
+        let reduced = &cross_products - (&deviation * deviation.transpose()) * scale;
+        let Ok(correlations) = scale_to_correlation(&reduced) else {
+            // Without this case a variable is constant; treat that as maximal influence
+            cases.push(CaseInfluenceEntry {
+                case_number: case_numbers.get(i).copied().unwrap_or(i + 1),
+                eigenvalue_change: f64::INFINITY,
+                matrix_change: f64::INFINITY,
+                max_correlation_change: f64::INFINITY,
+                max_change_pair: (String::new(), String::new()),
+            });
+            continue;
+        };
+
+        let mut sum_squared = 0.0;
+        let mut max_change = 0.0;
+        let mut max_pair = (0, 0);
+        for j in 0..n_vars {
+            for k in (j + 1)..n_vars {
+                let change = full_correlations[(j, k)] - correlations[(j, k)];
+                sum_squared += change.powi(2);
+                if change.abs() > max_change {
+                    max_change = change.abs();
+                    max_pair = (j, k);
+                }
+            }
+        }
+
+        cases.push(CaseInfluenceEntry {
+            case_number: case_numbers.get(i).copied().unwrap_or(i + 1),
+            eigenvalue_change: full_eigenvalue - largest_eigenvalue(&correlations),
+            matrix_change: sum_squared.sqrt(),
+            max_correlation_change: max_change,
+            max_change_pair: (var_names[max_pair.0].clone(), var_names[max_pair.1].clone()),
+        });
+    }
+
+    cases.sort_by(|a, b| {
+        b.eigenvalue_change
+            .abs()
+            .partial_cmp(&a.eigenvalue_change.abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    cases.truncate(top);
+
+    Ok(CaseInfluence {
+        n_cases,
+        largest_eigenvalue: full_eigenvalue,
+        cases,
+    })
+}
+
+fn scale_to_correlation(cross_products: &DMatrix<f64>) -> Result<DMatrix<f64>, String> {
+    let sd: Vec<f64> = (0..cross_products.nrows())
+        .map(|j| cross_products[(j, j)].sqrt())
+        .collect();
+    if sd.iter().any(|value| value.is_nan() || *value <= 0.0) {
+        return Err("A variable has zero variance".to_string());
+    }
+
+    Ok(
+        DMatrix::from_fn(cross_products.nrows(), cross_products.ncols(), |j, k| {
+            cross_products[(j, k)] / (sd[j] * sd[k])
+        })
+    )
+}
+
+fn largest_eigenvalue(matrix: &DMatrix<f64>) -> f64 {
+    robust_symmetric_eigen(matrix).eigenvalues.max()
+}
@@ -0,0 +1,189 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ CenteredDataSummary, ExtractionResult, IntermediateMatrices, RotationResult },
+};
+
+use super::core::{
+    center_within_groups,
+    extract_case_levels,
+    extract_correlation_matrix,
+    extract_factors,
+    extract_raw_data_matrix,
+    handle_missing_values,
+    matrix_to_rows,
+    rotate_factors,
+    sorted_symmetric_eigen,
+    uses_pairwise_deletion,
+};
+
+// Whether any step of the computation is to be included in the result
+pub fn includes_intermediate_matrices(config: &FactorAnalysisConfig) -> bool {
+    let intermediate = &config.options.intermediate;
+    intermediate.centered_data ||
+        intermediate.smc ||
+        intermediate.unrotated_loadings ||
+        intermediate.rotation_matrix ||
+        intermediate.residual_eigenvalues
+}
+
+pub fn calculate_intermediate_matrices(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<IntermediateMatrices, String> {
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
+    let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
+    let rotation_result = if config.rotation.none || !config.options.intermediate.rotation_matrix {
+        None
+    } else {
+        Some(rotate_factors(&extraction_result, config)?)
+    };
+
+    let mut intermediate = build_intermediate_matrices(
+        &corr_matrix,
+        &extraction_result,
+        rotation_result.as_ref(),
+        &var_names,
+        config
+    )?;
+    if config.options.intermediate.centered_data {
+        intermediate.centered_data = Some(calculate_centered_data_summary(data, config)?);
+    }
+
+    Ok(intermediate)
+}
+
+// The steps that follow from the matrix and the solution; the centered data needs the cases
+// and is left to calculate_intermediate_matrices
+pub fn build_intermediate_matrices(
+    corr_matrix: &DMatrix<f64>,
+    extraction_result: &ExtractionResult,
+    rotation_result: Option<&RotationResult>,
+    var_names: &[String],
+    config: &FactorAnalysisConfig
+) -> Result<IntermediateMatrices, String> {
+    let intermediate = &config.options.intermediate;
+    let loadings = &extraction_result.loadings;
+
+    let smc = if intermediate.smc {
+        let inverse = corr_matrix
+            .clone()
+            .try_inverse()
+            .ok_or("A singular correlation matrix has no squared multiple correlations")?;
+        Some(
+            var_names
+                .iter()
+                .enumerate()
+                .map(|(i, var_name)| (var_name.clone(), 1.0 - 1.0 / inverse[(i, i)]))
+                .collect()
+        )
+    } else {
+        None
+    };
+
+    let unrotated_loadings = intermediate.unrotated_loadings.then(|| {
+        var_names
+            .iter()
+            .enumerate()
+            .map(|(i, var_name)| (var_name.clone(), loadings.row(i).iter().copied().collect()))
+            .collect()
+    });
+
+    // The uniquenesses stay on the diagonal, so a good fit leaves the eigenvalues near them
+    let residual_eigenvalues = intermediate.residual_eigenvalues.then(|| {
+        let residual = corr_matrix - loadings * loadings.transpose();
+        sorted_symmetric_eigen(&residual).0
+    });
+
+    Ok(IntermediateMatrices {
+        centered_data: None,
+        smc,
+        unrotated_loadings,
+        rotation_matrix: rotation_result
+            .filter(|_| intermediate.rotation_matrix)
+            .map(|rotation| matrix_to_rows(&rotation.transformation_matrix)),
+        residual_eigenvalues,
+    })
+}
+
+// The data matrix as it enters the correlations: after the missing value handling, centered on
+// the column means, or on the group means for a pooled within-groups analysis. Pairwise
+// deletion leaves the missing values in and centers on the available values.
+pub fn calculate_centered_data_summary(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<CenteredDataSummary, String> {
+    let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+
+    let (uncentered, centered) = match &config.main.within_groups_var {
+        Some(group_var) => {
+            let case_groups = extract_case_levels(data, config, group_var);
+            let (centered, _) = center_within_groups(&raw_matrix, &case_groups, config)?;
+            let grouped_rows: Vec<usize> = case_groups
+                .iter()
+                .enumerate()
+                .filter(|(_, group)| group.is_some())
+                .map(|(i, _)| i)
+                .collect();
+            (raw_matrix.select_rows(&grouped_rows), centered)
+        }
+        None => {
+            let uncentered = if uses_pairwise_deletion(config) {
+                raw_matrix
+            } else {
+                handle_missing_values(&raw_matrix, config)?
+            };
+            let mut centered = uncentered.clone();
+            for mut column in centered.column_iter_mut() {
+                let mean = available_mean(column.iter());
+                column.iter_mut().for_each(|value| {
+                    *value -= mean;
+                });
+            }
+            (uncentered, centered)
+        }
+    };
+
+    let column_statistic = |matrix: &DMatrix<f64>, statistic: &dyn Fn(Vec<f64>) -> f64| {
+        matrix
+            .column_iter()
+            .map(|column| {
+                statistic(
+                    column
+                        .iter()
+                        .copied()
+                        .filter(|value| !value.is_nan())
+                        .collect()
+                )
+            })
+            .collect::<Vec<f64>>()
+    };
+    let std_deviation = |values: Vec<f64>| {
+        let mean = available_mean(values.iter());
+        let squares: f64 = values.iter().map(|value| (value - mean).powi(2)).sum();
+        if values.len() > 1 { (squares / ((values.len() - 1) as f64)).sqrt() } else { f64::NAN }
+    };
+    let extreme = |pick: fn(f64, f64) -> f64| {
+        move |values: Vec<f64>| values.into_iter().reduce(pick).unwrap_or(f64::NAN)
+    };
+
+    Ok(CenteredDataSummary {
+        n: centered.nrows(),
+        variables: var_names,
+        means: column_statistic(&uncentered, &|values| available_mean(values.iter())),
+        centered_means: column_statistic(&centered, &|values| available_mean(values.iter())),
+        std_deviations: column_statistic(&centered, &std_deviation),
+        minimums: column_statistic(&centered, &extreme(f64::min)),
+        maximums: column_statistic(&centered, &extreme(f64::max)),
+    })
+}
+
+// Mean of the values that aren't missing; NaN when all are
+fn available_mean<'a>(values: impl Iterator<Item = &'a f64>) -> f64 {
+    let (sum, count) = values
+        .filter(|value| !value.is_nan())
+        .fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+    if count > 0 { sum / (count as f64) } else { f64::NAN }
+}
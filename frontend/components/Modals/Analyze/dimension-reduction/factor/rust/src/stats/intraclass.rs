@@ -0,0 +1,205 @@
+use nalgebra::DMatrix;
+use statrs::distribution::{ ContinuousCDF, FisherSnedecor };
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ IccEstimate, IntraclassCorrelation },
+};
+
+use super::core::extract_data_matrix;
+
+pub const DEFAULT_ICC_CONFIDENCE: f64 = 0.95;
+
+// Intraclass correlations (Shrout & Fleiss, 1979) treating the cases as subjects and the analysis
+// variables as raters or repeated measures
+pub fn calculate_intraclass_correlation(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<IntraclassCorrelation, String> {
+    let (data_matrix, _) = extract_data_matrix(data, config)?;
+
+    // Accept both 0.95 and 95
+    let confidence = match config.descriptives.icc_confidence {
+        Some(level) if level > 1.0 && level < 100.0 => level / 100.0,
+        Some(level) if level > 0.0 && level < 1.0 => level,
+        _ => DEFAULT_ICC_CONFIDENCE,
+    };
+
+    build_intraclass_correlation(&data_matrix, confidence)
+}
+
+pub fn build_intraclass_correlation(
+    data_matrix: &DMatrix<f64>,
+    confidence: f64
+) -> Result<IntraclassCorrelation, String> {
+    let n = data_matrix.nrows();
+    let k = data_matrix.ncols();
+    if n < 2 || k < 2 {
+        return Err("ICC needs at least 2 cases and 2 variables".to_string());
+    }
+    let n_f = n as f64;
+    let k_f = k as f64;
+
+    // Two-way ANOVA sums of squares
+    let grand_mean = data_matrix.mean();
+    let ss_total: f64 = data_matrix
+        .iter()
+        .map(|value| (value - grand_mean).powi(2))
+        .sum();
+    let ss_rows: f64 = data_matrix
+        .row_iter()
+        .map(|row| k_f * (row.mean() - grand_mean).powi(2))
+        .sum();
+    let ss_columns: f64 = data_matrix
+        .column_iter()
+        .map(|column| n_f * (column.mean() - grand_mean).powi(2))
+        .sum();
+    let ss_error = ss_total - ss_rows - ss_columns;
+
+    let df_rows = n_f - 1.0;
+    let df_columns = k_f - 1.0;
+    let df_within = n_f * (k_f - 1.0);
+    let df_error = df_rows * df_columns;
+
+    let ms_rows = ss_rows / df_rows;
+    let ms_columns = ss_columns / df_columns;
+    let ms_within = (ss_columns + ss_error) / df_within;
+    let ms_error = ss_error / df_error;
+
+    let alpha = 1.0 - confidence;
+    let mut estimates = Vec::with_capacity(6);
+
+    // ICC(1): one-way random effects
+    let f_one_way = ms_rows / ms_within;
+    let icc1 = (ms_rows - ms_within) / (ms_rows + (k_f - 1.0) * ms_within);
+    let (f_lower, f_upper) = f_bounds(f_one_way, df_rows, df_within, alpha);
+    let significance = f_significance(f_one_way, df_rows, df_within);
+    estimates.push(IccEstimate {
+        model: "ICC1".to_string(),
+        value: icc1,
+        f_value: f_one_way,
+        df1: df_rows,
+        df2: df_within,
+        significance,
+        lower_bound: (f_lower - 1.0) / (f_lower + k_f - 1.0),
+        upper_bound: (f_upper - 1.0) / (f_upper + k_f - 1.0),
+    });
+
+    // ICC(2): two-way random effects, absolute agreement
+    let f_two_way = ms_rows / ms_error;
+    let significance_two_way = f_significance(f_two_way, df_rows, df_error);
+    let icc2 =
+        (ms_rows - ms_error) /
+        (ms_rows + (k_f - 1.0) * ms_error + (k_f * (ms_columns - ms_error)) / n_f);
+    let (icc2_lower, icc2_upper) = icc2_bounds(
+        icc2,
+        ms_rows,
+        ms_columns,
+        ms_error,
+        n_f,
+        k_f,
+        alpha
+    );
+    estimates.push(IccEstimate {
+        model: "ICC2".to_string(),
+        value: icc2,
+        f_value: f_two_way,
+        df1: df_rows,
+        df2: df_error,
+        significance: significance_two_way,
+        lower_bound: icc2_lower,
+        upper_bound: icc2_upper,
+    });
+
+    // ICC(3): two-way mixed effects, consistency
+    let icc3 = (ms_rows - ms_error) / (ms_rows + (k_f - 1.0) * ms_error);
+    let (f_lower, f_upper) = f_bounds(f_two_way, df_rows, df_error, alpha);
+    estimates.push(IccEstimate {
+        model: "ICC3".to_string(),
+        value: icc3,
+        f_value: f_two_way,
+        df1: df_rows,
+        df2: df_error,
+        significance: significance_two_way,
+        lower_bound: (f_lower - 1.0) / (f_lower + k_f - 1.0),
+        upper_bound: (f_upper - 1.0) / (f_upper + k_f - 1.0),
+    });
+
+    // Average measures follow from the single measures by the Spearman-Brown formula
+    let single_measures = estimates.clone();
+    for single in single_measures {
+        estimates.push(IccEstimate {
+            model: format!("{}k", single.model),
+            value: spearman_brown(single.value, k_f),
+            lower_bound: spearman_brown(single.lower_bound, k_f),
+            upper_bound: spearman_brown(single.upper_bound, k_f),
+            ..single
+        });
+    }
+
+    Ok(IntraclassCorrelation {
+        n_subjects: n,
+        n_raters: k,
+        confidence_level: confidence,
+        ms_rows,
+        ms_columns,
+        ms_within,
+        ms_error,
+        estimates,
+    })
+}
+
+// Reliability of the mean of k measures from the reliability of a single measure
+fn spearman_brown(value: f64, k: f64) -> f64 {
+    (k * value) / (1.0 + (k - 1.0) * value)
+}
+
+fn f_significance(f_value: f64, df1: f64, df2: f64) -> f64 {
+    match FisherSnedecor::new(df1, df2) {
+        Ok(distribution) if f_value.is_finite() => distribution.sf(f_value),
+        _ => f64::NAN,
+    }
+}
+
+fn f_quantile(p: f64, df1: f64, df2: f64) -> f64 {
+    match FisherSnedecor::new(df1, df2) {
+        Ok(distribution) => distribution.inverse_cdf(p),
+        Err(_) => f64::NAN,
+    }
+}
+
+// Lower and upper limits of the F ratio for the ICC(1) and ICC(3) intervals
+fn f_bounds(f_value: f64, df1: f64, df2: f64, alpha: f64) -> (f64, f64) {
+    let lower = f_value / f_quantile(1.0 - alpha / 2.0, df1, df2);
+    let upper = f_value * f_quantile(1.0 - alpha / 2.0, df2, df1);
+    (lower, upper)
+}
+
+// ICC(2) interval with Satterthwaite's approximate degrees of freedom (McGraw & Wong, 1996)
+fn icc2_bounds(
+    icc: f64,
+    ms_rows: f64,
+    ms_columns: f64,
+    ms_error: f64,
+    n: f64,
+    k: f64,
+    alpha: f64
+) -> (f64, f64) {
+    let a = (k * icc) / (n * (1.0 - icc));
+    let b = 1.0 + (k * icc * (n - 1.0)) / (n * (1.0 - icc));
+    let v =
+        (a * ms_columns + b * ms_error).powi(2) /
+        ((a * ms_columns).powi(2) / (k - 1.0) +
+            (b * ms_error).powi(2) / ((n - 1.0) * (k - 1.0)));
+
+    let f_star = f_quantile(1.0 - alpha / 2.0, n - 1.0, v);
+    let f_star_inverse = f_quantile(1.0 - alpha / 2.0, v, n - 1.0);
+    let denominator_term = k * ms_columns + (k * n - k - n) * ms_error;
+
+    let lower = (n * (ms_rows - f_star * ms_error)) / (f_star * denominator_term + n * ms_rows);
+    let upper =
+        (n * (f_star_inverse * ms_rows - ms_error)) /
+        (denominator_term + n * f_star_inverse * ms_rows);
+    (lower, upper)
+}
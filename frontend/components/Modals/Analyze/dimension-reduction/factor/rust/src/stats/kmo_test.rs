@@ -6,63 +6,87 @@ use crate::models::{
     result::KMOBartlettsTest,
 };
 
-use super::core::{ calculate_matrix, chi_square_cdf, extract_data_matrix };
+use super::core::{
+    chi_square_cdf,
+    extract_correlation_matrix,
+    require_factorability_variables,
+    require_more_cases_than_variables,
+    uses_pairwise_deletion,
+};
 
 pub fn calculate_kmo_bartletts_test(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<KMOBartlettsTest, String> {
-    let (data_matrix, _) = extract_data_matrix(data, config)?;
-    let correlation_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (correlation_matrix, _, n_obs) = extract_correlation_matrix(data, config, "correlation")?;
+    let n_vars = correlation_matrix.nrows();
+    require_factorability_variables("The KMO measure", n_vars)?;
+    require_more_cases_than_variables("Bartlett's test of sphericity", n_obs, n_vars)?;
+
+    let mut test = build_kmo_bartletts_test(&correlation_matrix, n_obs);
+    if uses_pairwise_deletion(config) {
+        test.n_convention = Some(config.options.pairwise_n.clone());
+    }
+    Ok(test)
+}
 
+// Measure of sampling adequacy of each variable; None when the matrix is singular
+pub fn calculate_msa(correlation_matrix: &DMatrix<f64>) -> Option<Vec<f64>> {
+    let inverse = correlation_matrix.clone().try_inverse()?;
+    let partial = partial_correlations(&inverse);
     let n_vars = correlation_matrix.nrows();
-    let n_obs = data_matrix.nrows();
+
+    let msa = (0..n_vars)
+        .map(|i| {
+            let mut sum_squared_correlation = 0.0;
+            let mut sum_squared_partial = 0.0;
+            for j in (0..n_vars).filter(|&j| j != i) {
+                sum_squared_correlation += correlation_matrix[(i, j)].powi(2);
+                sum_squared_partial += partial[(i, j)].powi(2);
+            }
+
+            if sum_squared_correlation + sum_squared_partial > 0.0 {
+                sum_squared_correlation / (sum_squared_correlation + sum_squared_partial)
+            } else {
+                0.0
+            }
+        })
+        .collect();
+
+    Some(msa)
+}
+
+// Anti-image correlations (partial correlations with sign reversed) with 1 on the diagonal
+pub fn partial_correlations(inverse: &DMatrix<f64>) -> DMatrix<f64> {
+    DMatrix::from_fn(inverse.nrows(), inverse.ncols(), |i, j| {
+        if i == j { 1.0 } else { -inverse[(i, j)] / (inverse[(i, i)] * inverse[(j, j)]).sqrt() }
+    })
+}
+
+pub fn build_kmo_bartletts_test(
+    correlation_matrix: &DMatrix<f64>,
+    n_obs: usize
+) -> KMOBartlettsTest {
+    let n_vars = correlation_matrix.nrows();
+    let _span = crate::trace_span!("build_kmo_bartletts_test", size = n_vars);
 
     // Calculate inverse of correlation matrix
     let inverse = match correlation_matrix.clone().try_inverse() {
         Some(inv) => inv,
         None => {
             // If matrix is singular, return default values
-            return Ok(KMOBartlettsTest {
+            return KMOBartlettsTest {
                 kaiser_meyer_olkin: 0.0,
                 bartletts_test_chi_square: 0.0,
                 df: (n_vars * (n_vars - 1)) / 2,
                 significance: 1.0,
-            });
+                n: Some(n_obs),
+                n_convention: None,
+            };
         }
     };
 
-    // Calculate anti-image correlation matrix
-    let mut anti_image_corr = DMatrix::zeros(n_vars, n_vars);
-    for i in 0..n_vars {
-        for j in 0..n_vars {
-            if i == j {
-                anti_image_corr[(i, j)] = 1.0;
-            } else {
-                anti_image_corr[(i, j)] =
-                    -inverse[(i, j)] / (inverse[(i, i)] * inverse[(j, j)]).sqrt();
-            }
-        }
-    }
-
-    // Calculate individual KMO measures
-    let mut kmo_measures = vec![0.0; n_vars];
-    for i in 0..n_vars {
-        let mut sum_squared_correlation = 0.0;
-        let mut sum_squared_partial = 0.0;
-
-        for j in 0..n_vars {
-            if i != j {
-                sum_squared_correlation += correlation_matrix[(i, j)].powi(2);
-                sum_squared_partial += anti_image_corr[(i, j)].powi(2);
-            }
-        }
-
-        if sum_squared_correlation + sum_squared_partial > 0.0 {
-            kmo_measures[i] =
-                sum_squared_correlation / (sum_squared_correlation + sum_squared_partial);
-        }
-    }
+    let anti_image_corr = partial_correlations(&inverse);
 
     // Calculate overall KMO
     let mut sum_squared_correlation = 0.0;
@@ -92,10 +116,12 @@ pub fn calculate_kmo_bartletts_test(
     // Calculate significance (p-value) using chi-square distribution
     let significance = chi_square_cdf(chi_square, df as f64);
 
-    Ok(KMOBartlettsTest {
+    KMOBartlettsTest {
         kaiser_meyer_olkin: kmo,
         bartletts_test_chi_square: chi_square,
         df,
         significance: 1.0 - significance,
-    })
+        n: Some(n_obs),
+        n_convention: None,
+    }
 }
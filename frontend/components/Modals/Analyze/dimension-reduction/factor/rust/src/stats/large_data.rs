@@ -0,0 +1,294 @@
+use nalgebra::DMatrix;
+use rand::Rng;
+
+use crate::models::{
+    config::{ DataMode, FactorAnalysisConfig },
+    data::{ AnalysisData, DataValue },
+    result::{ AnalysisSession, DataModeReport },
+};
+use crate::utils::rng::{ create_rng, resolve_seed };
+
+use super::core::{
+    build_analysis_session,
+    build_session_from_statistics,
+    case_is_selected,
+    create_analysis_session,
+    handle_missing_values,
+    require_matrix_cases,
+    require_pearson_session,
+    resolve_variable_names,
+    uses_pairwise_deletion,
+};
+
+// The in-memory path copies every value of a case into a hash map before building the matrix,
+// so each stored value costs roughly a map entry on top of its matrix cell
+const BYTES_PER_RECORD_VALUE: usize = 64;
+const BYTES_PER_MATRIX_CELL: usize = 8;
+
+// A subsampled value is held by the reservoir, the raw matrix and the cleaned copies
+const BYTES_PER_SUBSAMPLE_VALUE: usize = 32;
+const MIN_SUBSAMPLE_CASES: usize = 100;
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+// Decide whether the raw data fits the configured memory limit
+pub fn assess_data_mode(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<DataModeReport, String> {
+    let var_names = resolve_variable_names(data, config)?;
+    let estimated_bytes = estimate_in_memory_bytes(data, var_names.len());
+    let memory_limit_bytes = memory_limit_bytes(config);
+
+    // Encoding a categorical variable needs its levels over all cases, pooling within groups
+    // needs the group of every case and pairwise deletion the pairs observed on each cell, so
+    // those analyses stay in memory
+    let mode = match memory_limit_bytes {
+        _ if !config.main.categorical_vars.is_empty() => DataMode::InMemory,
+        _ if config.main.within_groups_var.is_some() => DataMode::InMemory,
+        _ if uses_pairwise_deletion(config) => DataMode::InMemory,
+        Some(limit) if estimated_bytes > limit => config.options.large_data_mode.clone(),
+        _ => DataMode::InMemory,
+    };
+
+    Ok(DataModeReport {
+        mode,
+        estimated_bytes,
+        memory_limit_bytes,
+        cases_available: count_cases(data),
+        cases_used: None,
+    })
+}
+
+// Rough peak memory of extracting the data matrix for the correlation stage
+pub fn estimate_in_memory_bytes(data: &AnalysisData, n_vars: usize) -> usize {
+    let values_per_case: usize = data.target_data
+        .iter()
+        .filter_map(|dataset| dataset.first())
+        .map(|record| record.values.len())
+        .sum();
+
+    count_cases(data) * (values_per_case * BYTES_PER_RECORD_VALUE + n_vars * BYTES_PER_MATRIX_CELL)
+}
+
+pub fn memory_limit_bytes(config: &FactorAnalysisConfig) -> Option<usize> {
+    config.options.memory_limit_mb
+        .filter(|mb| *mb > 0.0)
+        .map(|mb| (mb * BYTES_PER_MB) as usize)
+}
+
+// Build a session in the reported mode and record how many cases it used
+pub fn create_large_data_session(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    report: &mut DataModeReport
+) -> Result<AnalysisSession, String> {
+    let session = match report.mode {
+        DataMode::InMemory => create_analysis_session(data, config)?,
+        DataMode::Streaming => create_streaming_session(data, config)?,
+        DataMode::Subsample => {
+            let n_vars = resolve_variable_names(data, config)?.len().max(1);
+            let max_cases = report.memory_limit_bytes
+                .map(|limit| limit / (n_vars * BYTES_PER_SUBSAMPLE_VALUE))
+                .unwrap_or(report.cases_available)
+                .max(MIN_SUBSAMPLE_CASES);
+            create_subsample_session(data, config, max_cases)?
+        }
+    };
+
+    report.cases_used = Some(session.n_cases);
+    Ok(session)
+}
+
+// Correlations from running sums over the cases, without materializing the data matrix
+pub fn create_streaming_session(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<AnalysisSession, String> {
+    require_pearson_session(config)?;
+    let var_names = resolve_variable_names(data, config)?;
+    if var_names.is_empty() {
+        return Err("No valid variables found".to_string());
+    }
+    let n_vars = var_names.len();
+
+    // Incomplete cases are only kept when they will be mean-imputed, as in handle_missing_values
+    let keep_incomplete = config.options.replace_mean && !config.options.exclude_list_wise;
+
+    // First pass: means of the observed values for mean substitution
+    let mut imputation_means = vec![0.0; n_vars];
+    if config.options.replace_mean {
+        let mut counts = vec![0usize; n_vars];
+        for_each_selected_case(data, config, &var_names, |row| {
+            for (j, value) in row.iter().enumerate() {
+                if !value.is_nan() {
+                    imputation_means[j] += value;
+                    counts[j] += 1;
+                }
+            }
+        });
+        for j in 0..n_vars {
+            if counts[j] > 0 {
+                imputation_means[j] /= counts[j] as f64;
+            }
+        }
+    }
+
+    // Second pass: running means and co-moments
+    let mut moments = StreamingMoments::new(n_vars);
+    for_each_selected_case(data, config, &var_names, |row| {
+        if row.iter().any(|value| value.is_nan()) {
+            if !keep_incomplete {
+                return;
+            }
+            for (j, value) in row.iter_mut().enumerate() {
+                if value.is_nan() {
+                    *value = imputation_means[j];
+                }
+            }
+        }
+        moments.add(row);
+    });
+
+    require_matrix_cases(moments.n)?;
+
+    let covariance = moments.covariance();
+    let std_deviations: Vec<f64> = (0..n_vars).map(|j| covariance[(j, j)].sqrt()).collect();
+    let corr_matrix = DMatrix::from_fn(n_vars, n_vars, |i, j| {
+        covariance[(i, j)] / (std_deviations[i] * std_deviations[j])
+    });
+
+    build_session_from_statistics(
+        corr_matrix,
+        var_names,
+        moments.n,
+        moments.means,
+        std_deviations,
+        config
+    )
+}
+
+// Seeded reservoir sample of at most max_cases selected cases
+pub fn create_subsample_session(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    max_cases: usize
+) -> Result<AnalysisSession, String> {
+    let var_names = resolve_variable_names(data, config)?;
+    if var_names.is_empty() {
+        return Err("No valid variables found".to_string());
+    }
+
+    let mut rng = create_rng(resolve_seed(config));
+    let mut reservoir: Vec<Vec<f64>> = Vec::with_capacity(max_cases);
+    let mut seen = 0usize;
+
+    for_each_selected_case(data, config, &var_names, |row| {
+        seen += 1;
+        if reservoir.len() < max_cases {
+            reservoir.push(row.to_vec());
+        } else {
+            let slot = rng.gen_range(0..seen);
+            if slot < max_cases {
+                reservoir[slot] = row.to_vec();
+            }
+        }
+    });
+
+    if reservoir.is_empty() {
+        return Err("No valid records after filtering".to_string());
+    }
+
+    let raw_matrix = DMatrix::from_fn(reservoir.len(), var_names.len(), |i, j| reservoir[i][j]);
+    drop(reservoir);
+    let data_matrix = handle_missing_values(&raw_matrix, config)?;
+
+    build_analysis_session(&data_matrix, var_names, config)
+}
+
+// Visit the selected cases one at a time, with NaN wherever a value is missing
+fn for_each_selected_case<F>(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    var_names: &[String],
+    mut visit: F
+)
+    where F: FnMut(&mut [f64])
+{
+    let mut row = vec![0.0; var_names.len()];
+
+    for case_idx in 0..count_cases(data) {
+        if !case_is_selected(data, config, case_idx) {
+            continue;
+        }
+
+        // Later datasets take precedence, as when the records are merged
+        for (value, var_name) in row.iter_mut().zip(var_names) {
+            *value = data.target_data
+                .iter()
+                .rev()
+                .filter_map(|dataset| dataset.get(case_idx))
+                .find_map(|record| record.values.get(var_name))
+                .map(|value| {
+                    match value {
+                        DataValue::Number(number) => *number,
+                        _ => f64::NAN,
+                    }
+                })
+                .unwrap_or(f64::NAN);
+        }
+
+        visit(&mut row);
+    }
+}
+
+fn count_cases(data: &AnalysisData) -> usize {
+    data.target_data
+        .iter()
+        .map(|dataset| dataset.len())
+        .max()
+        .unwrap_or(0)
+}
+
+// Welford's online update of means and the centered cross-product matrix
+struct StreamingMoments {
+    n: usize,
+    means: Vec<f64>,
+    co_moments: DMatrix<f64>,
+    deltas: Vec<f64>,
+}
+
+impl StreamingMoments {
+    fn new(n_vars: usize) -> Self {
+        StreamingMoments {
+            n: 0,
+            means: vec![0.0; n_vars],
+            co_moments: DMatrix::zeros(n_vars, n_vars),
+            deltas: vec![0.0; n_vars],
+        }
+    }
+
+    fn add(&mut self, row: &[f64]) {
+        self.n += 1;
+        let n = self.n as f64;
+
+        for (j, value) in row.iter().enumerate() {
+            self.deltas[j] = value - self.means[j];
+            self.means[j] += self.deltas[j] / n;
+        }
+
+        for (i, delta) in self.deltas.iter().enumerate() {
+            for (j, (value, mean)) in row.iter().zip(&self.means).enumerate().skip(i) {
+                self.co_moments[(i, j)] += delta * (value - mean);
+            }
+        }
+    }
+
+    fn covariance(&self) -> DMatrix<f64> {
+        let denominator = (self.n - 1) as f64;
+        DMatrix::from_fn(self.co_moments.nrows(), self.co_moments.ncols(), |i, j| {
+            let (row, col) = if i <= j { (i, j) } else { (j, i) };
+            self.co_moments[(row, col)] / denominator
+        })
+    }
+}
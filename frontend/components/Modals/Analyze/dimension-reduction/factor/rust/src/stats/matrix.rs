@@ -1,30 +1,52 @@
 use std::collections::HashMap;
 
 use nalgebra::{ DMatrix, DVector };
+use statrs::distribution::{ ContinuousCDF, StudentsT };
 
 use crate::models::{
-    config::FactorAnalysisConfig,
+    config::{ EffectSizeThresholds, FactorAnalysisConfig },
     data::AnalysisData,
     result::{
         AntiImageMatrices,
         CorrelationMatrix,
         DescriptiveStatistic,
+        EffectSize,
+        EffectSizeSummary,
+        GroupDescriptiveStatistics,
+        GroupedDescriptiveStatistics,
         InverseCorrelationMatrix,
+        SscpMatrix,
     },
 };
 
-use super::core::{ extract_data_matrix, incomplete_beta };
+use super::core::{
+    build_pairwise_correlation_matrix,
+    calculate_msa,
+    calculate_pairwise_matrix,
+    distinct_levels,
+    extract_case_levels,
+    extract_correlation_matrix,
+    extract_data_matrix,
+    extract_raw_data_matrix,
+    handle_missing_values,
+    min_pairwise_n,
+    require_factorability_variables,
+    require_matrix_cases,
+    require_more_cases_than_variables,
+    uses_pairwise_deletion,
+};
 
+// Correlation, covariance, or sums of squares and cross-products about the means
+// ("adjusted_sscp") or about zero ("sscp")
 pub fn calculate_matrix(
     data_matrix: &DMatrix<f64>,
     matrix_type: &str
 ) -> Result<DMatrix<f64>, String> {
     let n_rows = data_matrix.nrows();
     let n_cols = data_matrix.ncols();
+    let _span = crate::trace_span!("calculate_matrix", rows = n_rows, cols = n_cols);
 
-    if n_rows < 2 {
-        return Err("Not enough data to calculate matrix".to_string());
-    }
+    require_matrix_cases(n_rows)?;
 
     // Calculate column means
     let mut means = DVector::zeros(n_cols);
@@ -35,6 +57,9 @@ pub fn calculate_matrix(
         }
         means[j] = sum / (n_rows as f64);
     }
+    if matrix_type == "sscp" {
+        means.fill(0.0);
+    }
 
     // Calculate standard deviations for correlation matrix
     let mut std_devs = DVector::zeros(n_cols);
@@ -57,11 +82,13 @@ pub fn calculate_matrix(
                 sum_product += (data_matrix[(k, i)] - means[i]) * (data_matrix[(k, j)] - means[j]);
             }
 
-            if matrix_type == "correlation" {
-                result[(i, j)] = sum_product / (((n_rows - 1) as f64) * std_devs[i] * std_devs[j]);
-            } else {
-                result[(i, j)] = sum_product / ((n_rows - 1) as f64);
-            }
+            result[(i, j)] = match matrix_type {
+                "correlation" => {
+                    sum_product / (((n_rows - 1) as f64) * std_devs[i] * std_devs[j])
+                }
+                "sscp" | "adjusted_sscp" => sum_product,
+                _ => sum_product / ((n_rows - 1) as f64),
+            };
         }
     }
 
@@ -74,10 +101,61 @@ pub fn calculate_descriptive_statistics(
     config: &FactorAnalysisConfig
 ) -> Result<Vec<DescriptiveStatistic>, String> {
     let (data_matrix, var_names) = extract_data_matrix(data, config)?;
+    let (means, std_devs) = calculate_column_statistics(&data_matrix);
+
+    Ok(build_descriptive_statistics(&var_names, &means, &std_devs, data_matrix.nrows()))
+}
+
+// Descriptive statistics of every group of the grouping variable. Missing values are handled
+// within each group; cases without a group are left out.
+pub fn calculate_grouped_descriptive_statistics(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<GroupedDescriptiveStatistics, String> {
+    let group_var = config.descriptives.group_var
+        .as_ref()
+        .ok_or_else(|| "No grouping variable selected".to_string())?;
+    let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+    let case_groups = extract_case_levels(data, config, group_var);
+
+    let group_levels = distinct_levels(&case_groups);
+    if group_levels.is_empty() {
+        return Err(format!("Grouping variable {} has no valid values", group_var));
+    }
+
+    let mut groups = Vec::with_capacity(group_levels.len());
+    for group in group_levels {
+        let rows: Vec<usize> = case_groups
+            .iter()
+            .enumerate()
+            .filter(|(_, level)| level.as_deref() == Some(group.as_str()))
+            .map(|(i, _)| i)
+            .collect();
+
+        // A group left without complete cases is reported with no statistics
+        let statistics = match handle_missing_values(&raw_matrix.select_rows(&rows), config) {
+            Ok(group_matrix) => {
+                let (means, std_devs) = calculate_column_statistics(&group_matrix);
+                build_descriptive_statistics(&var_names, &means, &std_devs, group_matrix.nrows())
+            }
+            Err(_) => Vec::new(),
+        };
+
+        groups.push(GroupDescriptiveStatistics { group, statistics });
+    }
+
+    Ok(GroupedDescriptiveStatistics {
+        group_variable: group_var.clone(),
+        groups,
+    })
+}
 
+// Column means and sample standard deviations of a data matrix
+pub fn calculate_column_statistics(data_matrix: &DMatrix<f64>) -> (Vec<f64>, Vec<f64>) {
     let n_rows = data_matrix.nrows();
     let n_cols = data_matrix.ncols();
-    let mut stats = Vec::with_capacity(n_cols);
+    let mut means = Vec::with_capacity(n_cols);
+    let mut std_devs = Vec::with_capacity(n_cols);
 
     for j in 0..n_cols {
         let mut sum = 0.0;
@@ -91,17 +169,30 @@ pub fn calculate_descriptive_statistics(
 
         let mean = sum / (n_rows as f64);
         let variance = (sum_sq - sum.powi(2) / (n_rows as f64)) / ((n_rows - 1) as f64);
-        let std_dev = variance.sqrt();
 
-        stats.push(DescriptiveStatistic {
-            variable: var_names[j].clone(),
-            mean,
-            std_deviation: std_dev,
-            analysis_n: n_rows,
-        });
+        means.push(mean);
+        std_devs.push(variance.sqrt());
     }
 
-    Ok(stats)
+    (means, std_devs)
+}
+
+pub fn build_descriptive_statistics(
+    var_names: &[String],
+    means: &[f64],
+    std_devs: &[f64],
+    n_rows: usize
+) -> Vec<DescriptiveStatistic> {
+    var_names
+        .iter()
+        .enumerate()
+        .map(|(j, var_name)| DescriptiveStatistic {
+            variable: var_name.clone(),
+            mean: means[j],
+            std_deviation: std_devs[j],
+            analysis_n: n_rows,
+        })
+        .collect()
 }
 
 // Independent correlation matrix functions
@@ -109,9 +200,69 @@ pub fn calculate_correlation_matrix(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<CorrelationMatrix, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let matrix = calculate_matrix(&data_matrix, "correlation")?;
+    calculate_correlation_table(data, config, "correlation")
+}
 
+pub fn calculate_covariance_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<CorrelationMatrix, String> {
+    calculate_correlation_table(data, config, "covariance")
+}
+
+fn calculate_correlation_table(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    matrix_type: &str
+) -> Result<CorrelationMatrix, String> {
+    let polychoric = config.extraction.polychoric && matrix_type == "correlation";
+    let (mut table, matrix, var_names) = if config.main.within_groups_var.is_some() || polychoric {
+        let (matrix, var_names, n_obs) = extract_correlation_matrix(data, config, matrix_type)?;
+        let table = build_correlation_matrix(&matrix, &var_names, n_obs)?;
+
+        (table, matrix, var_names)
+    } else if uses_pairwise_deletion(config) {
+        let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+        let mut pairwise = calculate_pairwise_matrix(&raw_matrix, matrix_type, min_pairwise_n(config))?;
+        let table = build_pairwise_correlation_matrix(&pairwise, &var_names)?;
+        for &(i, j) in &pairwise.flagged {
+            pairwise.matrix[(i, j)] = f64::NAN;
+            pairwise.matrix[(j, i)] = f64::NAN;
+        }
+
+        (table, pairwise.matrix, var_names)
+    } else {
+        let (data_matrix, var_names) = extract_data_matrix(data, config)?;
+        let matrix = calculate_matrix(&data_matrix, matrix_type)?;
+        let table = build_correlation_matrix(&matrix, &var_names, data_matrix.nrows())?;
+
+        (table, matrix, var_names)
+    };
+
+    // Magnitude bands only make sense for correlations
+    if config.descriptives.effect_sizes && matrix_type == "correlation" {
+        add_effect_sizes(&mut table, &matrix, &var_names, &effect_size_thresholds(config))?;
+    }
+    Ok(table)
+}
+
+// Build the correlation/covariance table with significance values for n_obs cases
+pub fn build_correlation_matrix(
+    matrix: &DMatrix<f64>,
+    var_names: &[String],
+    n_obs: usize
+) -> Result<CorrelationMatrix, String> {
+    build_correlation_table(matrix, var_names, |_, _| n_obs)
+}
+
+// Correlation table where the significance of cell (i, j) is based on n_for(i, j) cases
+pub fn build_correlation_table<F>(
+    matrix: &DMatrix<f64>,
+    var_names: &[String],
+    n_for: F
+) -> Result<CorrelationMatrix, String>
+    where F: Fn(usize, usize) -> usize
+{
     let n_vars = var_names.len();
     if matrix.nrows() != n_vars || matrix.ncols() != n_vars {
         return Err(
@@ -140,18 +291,7 @@ pub fn calculate_correlation_matrix(
             let p_value = if i == j {
                 0.0
             } else {
-                // Fisher's z-transformation for correlation significance
-                let n = matrix.nrows();
-                let r = matrix[(i, j)];
-                let z = 0.5 * ((1.0 + r) / (1.0 - r)).ln();
-                let se = 1.0 / ((n - 3) as f64).sqrt();
-                let t = z / se;
-
-                // Two-tailed p-value approximation using t distribution with n-2 degrees of freedom
-                let df = n - 2;
-                let x = (df as f64) / ((df as f64) + t * t);
-                let beta = 0.5 * incomplete_beta(0.5 * (df as f64), 0.5, x);
-                2.0 * beta
+                correlation_p_value(matrix[(i, j)], n_for(i, j))
             };
 
             var_sig_values.insert(other_var.clone(), p_value);
@@ -164,82 +304,187 @@ pub fn calculate_correlation_matrix(
     Ok(CorrelationMatrix {
         correlations,
         sig_values,
+        effect_sizes: None,
+        effect_size_summary: None,
     })
 }
 
-pub fn calculate_covariance_matrix(
-    data: &AnalysisData,
-    config: &FactorAnalysisConfig
-) -> Result<CorrelationMatrix, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let matrix = calculate_matrix(&data_matrix, "covariance")?;
+// Label every off-diagonal correlation of the table by its magnitude band
+pub fn add_effect_sizes(
+    table: &mut CorrelationMatrix,
+    matrix: &DMatrix<f64>,
+    var_names: &[String],
+    thresholds: &EffectSizeThresholds
+) -> Result<(), String> {
+    if !(0.0 < thresholds.small && thresholds.small < thresholds.medium) ||
+        thresholds.medium >= thresholds.large
+    {
+        return Err("Effect size thresholds must satisfy 0 < small < medium < large".to_string());
+    }
 
-    let n_vars = var_names.len();
-    if matrix.nrows() != n_vars || matrix.ncols() != n_vars {
-        return Err(
-            format!(
-                "Matrix dimensions {}x{} don't match variable count {}",
-                matrix.nrows(),
-                matrix.ncols(),
-                n_vars
-            )
-        );
+    let mut labels = HashMap::new();
+    let mut summary = EffectSizeSummary {
+        thresholds: thresholds.clone(),
+        negligible: 0,
+        small: 0,
+        medium: 0,
+        large: 0,
+    };
+    for (i, var_name) in var_names.iter().enumerate() {
+        let mut row = HashMap::new();
+        for (j, other_var) in var_names.iter().enumerate() {
+            let value = matrix[(i, j)];
+            if i == j || value.is_nan() {
+                continue;
+            }
+            let size = classify_effect_size(value, thresholds);
+            row.insert(other_var.clone(), size);
+
+            if i < j {
+                match size {
+                    EffectSize::Negligible => {
+                        summary.negligible += 1;
+                    }
+                    EffectSize::Small => {
+                        summary.small += 1;
+                    }
+                    EffectSize::Medium => {
+                        summary.medium += 1;
+                    }
+                    EffectSize::Large => {
+                        summary.large += 1;
+                    }
+                }
+            }
+        }
+        labels.insert(var_name.clone(), row);
     }
 
-    let mut correlations = HashMap::new();
-    let mut sig_values = HashMap::new();
+    table.effect_sizes = Some(labels);
+    table.effect_size_summary = Some(summary);
+    Ok(())
+}
 
-    for i in 0..n_vars {
-        let var_name = &var_names[i];
-        let mut var_correlations = HashMap::new();
-        let mut var_sig_values = HashMap::new();
+pub fn classify_effect_size(correlation: f64, thresholds: &EffectSizeThresholds) -> EffectSize {
+    let magnitude = correlation.abs();
+    if magnitude >= thresholds.large {
+        EffectSize::Large
+    } else if magnitude >= thresholds.medium {
+        EffectSize::Medium
+    } else if magnitude >= thresholds.small {
+        EffectSize::Small
+    } else {
+        EffectSize::Negligible
+    }
+}
 
-        for j in 0..n_vars {
-            let other_var = &var_names[j];
-            var_correlations.insert(other_var.clone(), matrix[(i, j)]);
+// Thresholds of the configuration, or Cohen's conventions
+pub fn effect_size_thresholds(config: &FactorAnalysisConfig) -> EffectSizeThresholds {
+    config.descriptives.effect_size_thresholds.clone().unwrap_or(EffectSizeThresholds {
+        small: 0.1,
+        medium: 0.3,
+        large: 0.5,
+    })
+}
 
-            // Calculate significance (p-value)
-            let p_value = if i == j {
-                0.0
-            } else {
-                // Fisher's z-transformation for correlation significance
-                let n = matrix.nrows();
-                let r = matrix[(i, j)];
-                let z = 0.5 * ((1.0 + r) / (1.0 - r)).ln();
-                let se = 1.0 / ((n - 3) as f64).sqrt();
-                let t = z / se;
-
-                // Two-tailed p-value approximation using t distribution with n-2 degrees of freedom
-                let df = n - 2;
-                let x = (df as f64) / ((df as f64) + t * t);
-                let beta = 0.5 * incomplete_beta(0.5 * (df as f64), 0.5, x);
-                2.0 * beta
-            };
+// Two-tailed p-value of a correlation coefficient based on n cases
+pub fn correlation_p_value(r: f64, n: usize) -> f64 {
+    if r.is_nan() || n < 3 {
+        return f64::NAN;
+    }
+    if r.abs() >= 1.0 {
+        return 0.0;
+    }
 
-            var_sig_values.insert(other_var.clone(), p_value);
-        }
+    // t = r * sqrt((n - 2) / (1 - r^2)) follows a t distribution with n - 2 degrees of freedom
+    let df = (n as f64) - 2.0;
+    let t = r * (df / (1.0 - r * r)).sqrt();
+    match StudentsT::new(0.0, 1.0, df) {
+        Ok(distribution) => 2.0 * distribution.sf(t.abs()),
+        Err(_) => f64::NAN,
+    }
+}
 
-        correlations.insert(var_name.clone(), var_correlations);
-        sig_values.insert(var_name.clone(), var_sig_values);
+// Raw and mean-adjusted sums of squares and cross-products of the analysis cases
+pub fn calculate_sscp_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<SscpMatrix, String> {
+    let (raw, var_names, n_obs) = extract_correlation_matrix(data, config, "sscp")?;
+    let (adjusted, _, _) = extract_correlation_matrix(data, config, "adjusted_sscp")?;
+
+    build_sscp_matrix(&raw, &adjusted, &var_names, n_obs)
+}
+
+pub fn build_sscp_matrix(
+    raw: &DMatrix<f64>,
+    adjusted: &DMatrix<f64>,
+    var_names: &[String],
+    n_obs: usize
+) -> Result<SscpMatrix, String> {
+    let n_vars = var_names.len();
+    if [raw, adjusted].iter().any(|matrix| matrix.nrows() != n_vars || matrix.ncols() != n_vars) {
+        return Err(format!("SSCP matrices must be {0} x {0} to match the variables", n_vars));
     }
 
-    Ok(CorrelationMatrix {
-        correlations,
-        sig_values,
+    let to_map = |matrix: &DMatrix<f64>| {
+        var_names
+            .iter()
+            .enumerate()
+            .map(|(i, var_name)| {
+                let row = var_names
+                    .iter()
+                    .enumerate()
+                    .map(|(j, other_var)| (other_var.clone(), matrix[(i, j)]))
+                    .collect();
+                (var_name.clone(), row)
+            })
+            .collect()
+    };
+
+    Ok(SscpMatrix {
+        raw: to_map(raw),
+        adjusted: to_map(adjusted),
+        n: n_obs,
     })
 }
 
+// Sums of squares and cross-products implied by a covariance matrix, the means and n cases
+pub fn sscp_from_covariance(
+    cov_matrix: &DMatrix<f64>,
+    means: &[f64],
+    n_obs: usize
+) -> (DMatrix<f64>, DMatrix<f64>) {
+    let n = n_obs as f64;
+    let adjusted = cov_matrix * (n - 1.0);
+    let raw = DMatrix::from_fn(adjusted.nrows(), adjusted.ncols(), |i, j| {
+        adjusted[(i, j)] + n * means[i] * means[j]
+    });
+
+    (raw, adjusted)
+}
+
 pub fn calculate_inverse_correlation_matrix(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<InverseCorrelationMatrix, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, n_obs) = extract_correlation_matrix(data, config, "correlation")?;
+    require_more_cases_than_variables("The inverse correlation matrix", n_obs, var_names.len())?;
 
-    let inverse = match corr_matrix.try_inverse() {
-        Some(inv) => inv,
-        None => {
-            return Err("Could not invert correlation matrix".to_string());
+    build_inverse_correlation_matrix(&corr_matrix, &var_names)
+}
+
+pub fn build_inverse_correlation_matrix(
+    corr_matrix: &DMatrix<f64>,
+    var_names: &[String]
+) -> Result<InverseCorrelationMatrix, String> {
+    let inverse = {
+        let _span = crate::trace_span!("invert_matrix", size = corr_matrix.nrows());
+        match corr_matrix.clone().try_inverse() {
+            Some(inv) => inv,
+            None => {
+                return Err("Could not invert correlation matrix".to_string());
+            }
         }
     };
 
@@ -267,16 +512,29 @@ pub fn calculate_anti_image_matrices(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<AntiImageMatrices, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, n_obs) = extract_correlation_matrix(data, config, "correlation")?;
+    require_factorability_variables("Anti-image matrices", var_names.len())?;
+    require_more_cases_than_variables("Anti-image matrices", n_obs, var_names.len())?;
 
-    let inverse = match corr_matrix.try_inverse() {
+    build_anti_image_matrices(&corr_matrix, &var_names, config.descriptives.msa)
+}
+
+// The anti-image correlation diagonal holds each variable's measure of sampling adequacy, as
+// SPSS prints it; include_msa also returns those values on their own
+pub fn build_anti_image_matrices(
+    corr_matrix: &DMatrix<f64>,
+    var_names: &[String],
+    include_msa: bool
+) -> Result<AntiImageMatrices, String> {
+    let inverse = match corr_matrix.clone().try_inverse() {
         Some(inv) => inv,
         None => {
             return Err("Could not invert correlation matrix".to_string());
         }
     };
 
+    let msa = calculate_msa(corr_matrix).ok_or("Could not invert correlation matrix")?;
+
     let n_vars = var_names.len();
     let mut anti_image_covariance = HashMap::new();
     let mut anti_image_correlation = HashMap::new();
@@ -300,7 +558,7 @@ pub fn calculate_anti_image_matrices(
 
             // Anti-image correlation: partial correlations with sign reversed
             let corr_value = if i == j {
-                1.0
+                msa[i]
             } else {
                 -inverse[(i, j)] / (inverse[(i, i)] * inverse[(j, j)]).sqrt()
             };
@@ -315,5 +573,6 @@ pub fn calculate_anti_image_matrices(
     Ok(AntiImageMatrices {
         anti_image_covariance,
         anti_image_correlation,
+        msa: include_msa.then(|| var_names.iter().cloned().zip(msa).collect()),
     })
 }
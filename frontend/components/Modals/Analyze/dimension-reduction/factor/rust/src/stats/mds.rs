@@ -0,0 +1,116 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ MdsDistance, MultidimensionalScaling, VariableCoordinates },
+};
+
+use super::core::{ build_principal_coordinates, extract_correlation_matrix };
+
+// Dimensions kept when MdsDimensions is not set
+pub const DEFAULT_MDS_DIMENSIONS: usize = 2;
+
+// Classical (Torgerson) scaling of the variables on 1 - |r|, so that variables loading on the
+// same factor, whatever the sign, end up close together
+pub fn calculate_multidimensional_scaling(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<MultidimensionalScaling, String> {
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
+
+    build_correlation_scaling(&corr_matrix, &var_names, config)
+}
+
+// Scaling of an already computed correlation matrix, as kept by a session
+pub fn build_correlation_scaling(
+    corr_matrix: &DMatrix<f64>,
+    var_names: &[String],
+    config: &FactorAnalysisConfig
+) -> Result<MultidimensionalScaling, String> {
+    let distances = corr_matrix.map(|r| 1.0 - r.abs().min(1.0));
+
+    build_multidimensional_scaling(
+        &distances,
+        var_names,
+        mds_dimensions(config),
+        MdsDistance::OneMinusAbsoluteCorrelation
+    )
+}
+
+pub fn mds_dimensions(config: &FactorAnalysisConfig) -> usize {
+    match config.extraction.mds_dimensions {
+        Some(3) => 3,
+        _ => DEFAULT_MDS_DIMENSIONS,
+    }
+}
+
+// Coordinates of the objects in a distance matrix and Kruskal's stress-1 of the fitted distances
+pub fn build_multidimensional_scaling(
+    distances: &DMatrix<f64>,
+    names: &[String],
+    dimensions: usize,
+    distance: MdsDistance
+) -> Result<MultidimensionalScaling, String> {
+    let n = distances.nrows();
+    if distances.ncols() != n || names.len() != n {
+        return Err("Distance matrix must be square with one name per row".to_string());
+    }
+    if dimensions == 0 {
+        return Err("Scaling needs at least one dimension".to_string());
+    }
+    for i in 0..n {
+        if distances[(i, i)].abs() > 1e-12 {
+            return Err(format!("Distance of '{}' to itself is not zero", names[i]));
+        }
+        for j in (i + 1)..n {
+            let (a, b) = (distances[(i, j)], distances[(j, i)]);
+            if !a.is_finite() || a < 0.0 {
+                return Err(
+                    format!(
+                        "Distance between '{}' and '{}' is not a non-negative number",
+                        names[i],
+                        names[j]
+                    )
+                );
+            }
+            if (a - b).abs() > 1e-9 * a.abs().max(1.0) {
+                return Err(
+                    format!("Distances between '{}' and '{}' are not symmetric", names[i], names[j])
+                );
+            }
+        }
+    }
+
+    let squared = distances.map(|d| d * d);
+    let (axes, coordinates, _) = build_principal_coordinates(&squared, dimensions)?;
+
+    // Stress-1 compares the given distances with those between the fitted points
+    let mut residual = 0.0;
+    let mut total = 0.0;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let fitted = (coordinates.row(i) - coordinates.row(j)).norm();
+            residual += (distances[(i, j)] - fitted).powi(2);
+            total += distances[(i, j)].powi(2);
+        }
+    }
+
+    Ok(MultidimensionalScaling {
+        distance,
+        dimensions: coordinates.ncols(),
+        eigenvalues: axes
+            .iter()
+            .map(|axis| axis.total)
+            .collect(),
+        coordinates: names
+            .iter()
+            .zip(coordinates.row_iter())
+            .map(|(name, row)| VariableCoordinates {
+                variable: name.clone(),
+                coordinates: row.iter().copied().collect(),
+            })
+            .collect(),
+        stress: if total > 0.0 { (residual / total).sqrt() } else { 0.0 },
+    })
+}
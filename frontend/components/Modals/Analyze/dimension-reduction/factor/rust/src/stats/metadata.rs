@@ -0,0 +1,114 @@
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    result::{
+        AnalysisMetadata,
+        DataModeReport,
+        ExtractionResult,
+        IterationSummary,
+        RotationResult,
+        StageTiming,
+    },
+};
+
+use super::core::{
+    extraction_convergence,
+    polychoric_search,
+    polychoric_search_outcome,
+    rotation_convergence,
+};
+
+// Name of the extraction routine as reported in the metadata
+pub fn extraction_routine_name(method: &ExtractionMethod) -> &'static str {
+    match method {
+        ExtractionMethod::PrincipalComponents => "extraction.principal_components",
+        ExtractionMethod::UnweightedLeastSquares => "extraction.unweighted_least_squares",
+        ExtractionMethod::GeneralizedLeastSquares => "extraction.generalized_least_squares",
+        ExtractionMethod::MaximumLikelihood => "extraction.maximum_likelihood",
+        ExtractionMethod::PrincipalAxisFactoring => "extraction.principal_axis_factoring",
+        ExtractionMethod::AlphaFactoring => "extraction.alpha_factoring",
+        ExtractionMethod::ImageFactoring => "extraction.image_factoring",
+    }
+}
+
+// Name of the rotation routine, following the same precedence as rotate_factors
+pub fn rotation_routine_name(config: &FactorAnalysisConfig) -> &'static str {
+    if config.rotation.none {
+        "rotation.none"
+    } else if config.rotation.varimax {
+        "rotation.varimax"
+    } else if config.rotation.quartimax {
+        "rotation.quartimax"
+    } else if config.rotation.equimax {
+        "rotation.equimax"
+    } else if config.rotation.oblimin {
+        "rotation.oblimin"
+    } else if config.rotation.promax {
+        "rotation.promax"
+    } else {
+        "rotation.varimax"
+    }
+}
+
+// Iteration counts and convergence status of the iterative routines
+pub fn build_iteration_summaries(
+    extraction_result: &ExtractionResult,
+    rotation_result: Option<&RotationResult>,
+    config: &FactorAnalysisConfig
+) -> Vec<IterationSummary> {
+    let mut summaries = vec![IterationSummary {
+        routine: extraction_routine_name(&config.extraction.method).to_string(),
+        iterations: extraction_result.iterations,
+        max_iterations: config.extraction.max_iter.max(0) as usize,
+        converged: extraction_result.converged,
+        tolerance: (extraction_result.iterations > 0).then(|| extraction_convergence(config)),
+        achieved_tolerance: finite(extraction_result.achieved_tolerance),
+    }];
+
+    // Every pair's correlation search stops at the same iteration, so one entry covers them all
+    if config.extraction.polychoric {
+        let (tolerance, max_iterations) = polychoric_search(config);
+        let (iterations, width) = polychoric_search_outcome(config);
+        summaries.push(IterationSummary {
+            routine: "correlation.polychoric".to_string(),
+            iterations,
+            max_iterations,
+            converged: width <= tolerance,
+            tolerance: Some(tolerance),
+            achieved_tolerance: Some(width),
+        });
+    }
+
+    if let Some(rotation_result) = rotation_result {
+        summaries.push(IterationSummary {
+            routine: rotation_routine_name(config).to_string(),
+            iterations: rotation_result.iterations,
+            max_iterations: config.rotation.max_iter.max(0) as usize,
+            converged: rotation_result.converged,
+            tolerance: Some(rotation_convergence(config)),
+            achieved_tolerance: finite(rotation_result.achieved_tolerance),
+        });
+    }
+
+    summaries
+}
+
+fn finite(value: f64) -> Option<f64> {
+    value.is_finite().then_some(value)
+}
+
+pub fn build_analysis_metadata(
+    stage_timings: Vec<StageTiming>,
+    total_time_ms: f64,
+    iterations: Vec<IterationSummary>,
+    seed: Option<u64>,
+    data_mode: Option<DataModeReport>
+) -> AnalysisMetadata {
+    AnalysisMetadata {
+        library_version: env!("CARGO_PKG_VERSION").to_string(),
+        seed,
+        stage_timings,
+        total_time_ms,
+        iterations,
+        data_mode,
+    }
+}
@@ -1,9 +1,49 @@
 pub mod core;
+pub mod batch;
+pub mod cache;
+pub mod canonical;
 pub mod common;
+pub mod comparison;
+pub mod conversion;
+pub mod covariance_extraction;
+pub mod distance_correlation;
+pub mod eap_scores;
+pub mod eigen;
+pub mod encoding;
+pub mod factor_count;
 pub mod factor_extraction;
 pub mod generate_plots;
+pub mod gower;
+pub mod influence;
+pub mod intermediate;
+pub mod intraclass;
 pub mod kmo_test;
+pub mod large_data;
 pub mod matrix;
+pub mod mds;
+pub mod metadata;
+pub mod model_comparison;
+pub mod pair_detail;
+pub mod pairwise;
+pub mod polychoric;
+pub mod parallel_analysis;
+pub mod perfect_correlation;
+pub mod pipeline;
+pub mod power;
 pub mod prepare_data;
+pub mod principal_coordinates;
+pub mod procrustes;
+pub mod psych;
+pub mod reliability;
 pub mod report;
+pub mod reproducibility;
+pub mod requirements;
+pub mod reshape;
 pub mod rotation;
+pub mod selection;
+pub mod session;
+pub mod simulate;
+pub mod validation;
+pub mod validity;
+pub mod weighted_least_squares;
+pub mod within_groups;
@@ -0,0 +1,111 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    data::AnalysisData,
+    result::{ ComponentFactorComparison, ModelFactorComparison, ModelVariableComparison },
+};
+
+use super::core::{
+    extract_correlation_matrix,
+    extract_factors,
+    optimal_factor_matching,
+    tucker_congruence,
+};
+
+// Tucker congruence from which two factors are considered equal (Lorenzo-Seva & ten Berge, 2006)
+pub const EQUAL_CONGRUENCE: f64 = 0.95;
+
+// Principal components against a common-factor extraction with the same number of factors
+pub fn calculate_component_factor_comparison(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<ComponentFactorComparison, String> {
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
+
+    build_component_factor_comparison(&corr_matrix, &var_names, config)
+}
+
+// The common-factor side uses the configured method, or principal axis factoring when the
+// configured method is principal components. Unrotated loadings are compared, since rotating
+// each solution on its own would hide how the extractions differ.
+pub fn build_component_factor_comparison(
+    corr_matrix: &DMatrix<f64>,
+    var_names: &[String],
+    config: &FactorAnalysisConfig
+) -> Result<ComponentFactorComparison, String> {
+    let mut component_config = config.clone();
+    component_config.extraction.method = ExtractionMethod::PrincipalComponents;
+    let components = extract_factors(corr_matrix, &component_config, var_names)?;
+
+    let mut factor_config = config.clone();
+    if factor_config.extraction.method == ExtractionMethod::PrincipalComponents {
+        factor_config.extraction.method = ExtractionMethod::PrincipalAxisFactoring;
+    }
+    factor_config.extraction.max_factors = Some(components.n_factors as i32);
+    let factors = extract_factors(corr_matrix, &factor_config, var_names)?;
+
+    if components.n_factors == 0 || factors.n_factors != components.n_factors {
+        return Err("Both extractions need the same, non-zero number of factors".to_string());
+    }
+
+    // Put the factors in component order with matching signs before comparing loadings
+    let congruence = tucker_congruence(&components.loadings, &factors.loadings);
+    let matching = optimal_factor_matching(&congruence);
+    let n_vars = var_names.len();
+    let mut aligned = DMatrix::zeros(n_vars, components.n_factors);
+    for &(component, factor) in &matching {
+        let sign = if congruence[(component, factor)] < 0.0 { -1.0 } else { 1.0 };
+        aligned.set_column(component, &(factors.loadings.column(factor) * sign));
+    }
+
+    let variance_percent = |loadings: &DMatrix<f64>, k: usize| {
+        (loadings.column(k).norm_squared() / (n_vars as f64)) * 100.0
+    };
+    let comparison_factors: Vec<ModelFactorComparison> = matching
+        .iter()
+        .map(|&(component, factor)| ModelFactorComparison {
+            component: component + 1,
+            factor: factor + 1,
+            congruence: congruence[(component, factor)].abs(),
+            component_percent: variance_percent(&components.loadings, component),
+            factor_percent: variance_percent(&factors.loadings, factor),
+            equal: congruence[(component, factor)].abs() >= EQUAL_CONGRUENCE,
+        })
+        .collect();
+
+    let difference = &components.loadings - &aligned;
+    let variables: Vec<ModelVariableComparison> = var_names
+        .iter()
+        .enumerate()
+        .map(|(i, var_name)| {
+            let component_communality = components.loadings.row(i).norm_squared();
+            let factor_communality = factors.loadings.row(i).norm_squared();
+            ModelVariableComparison {
+                variable: var_name.clone(),
+                component_communality,
+                factor_communality,
+                communality_difference: component_communality - factor_communality,
+                component_loadings: components.loadings.row(i).iter().copied().collect(),
+                factor_loadings: aligned.row(i).iter().copied().collect(),
+                max_loading_difference: difference.row(i).amax(),
+            }
+        })
+        .collect();
+
+    Ok(ComponentFactorComparison {
+        factor_method: factor_config.extraction.method,
+        n_factors: components.n_factors,
+        total_component_percent: comparison_factors
+            .iter()
+            .map(|factor| factor.component_percent)
+            .sum(),
+        total_factor_percent: comparison_factors
+            .iter()
+            .map(|factor| factor.factor_percent)
+            .sum(),
+        mean_absolute_loading_difference: difference.abs().mean(),
+        factors: comparison_factors,
+        variables,
+    })
+}
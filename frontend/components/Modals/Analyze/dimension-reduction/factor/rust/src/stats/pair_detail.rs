@@ -0,0 +1,132 @@
+use statrs::distribution::{ ContinuousCDF, Normal };
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ PairDetail, PairPoint },
+};
+
+use super::core::{
+    correlation_p_value,
+    extract_raw_data_matrix,
+    require_fisher_z_cases,
+    selected_case_numbers,
+    uses_pairwise_deletion,
+};
+
+pub const DEFAULT_PAIR_CONFIDENCE: f64 = 0.95;
+
+// Cases behind one cell of the correlation matrix under the configured case selection and
+// missing value handling, with the correlation, its confidence interval and the regression line
+// of the second variable on the first
+pub fn calculate_pair_detail(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    variable_x: &str,
+    variable_y: &str,
+    confidence: Option<f64>
+) -> Result<PairDetail, String> {
+    let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+    let column = |name: &str| {
+        var_names
+            .iter()
+            .position(|var_name| var_name == name)
+            .ok_or(format!("Variable '{}' is not among the analysis variables", name))
+    };
+    let (col_x, col_y) = (column(variable_x)?, column(variable_y)?);
+    let case_numbers = selected_case_numbers(data, config);
+
+    // Listwise deletion drops a case missing any analysis variable, not only the two shown;
+    // mean replacement keeps every case, as in handle_missing_values
+    let pairwise = uses_pairwise_deletion(config);
+    let mean_imputed = config.options.replace_mean && !config.options.exclude_list_wise;
+    let column_mean = |j: usize| {
+        let values: Vec<f64> = raw_matrix
+            .column(j)
+            .iter()
+            .copied()
+            .filter(|value| !value.is_nan())
+            .collect();
+        values.iter().sum::<f64>() / (values.len() as f64)
+    };
+    let (mean_x, mean_y) = (column_mean(col_x), column_mean(col_y));
+
+    let mut points = Vec::new();
+    for (i, row) in raw_matrix.row_iter().enumerate() {
+        let (x, y) = (row[col_x], row[col_y]);
+        let pair_missing = x.is_nan() || y.is_nan();
+        if mean_imputed {
+            points.push(PairPoint {
+                case_number: case_numbers[i],
+                x: if x.is_nan() { mean_x } else { x },
+                y: if y.is_nan() { mean_y } else { y },
+                imputed: pair_missing,
+            });
+        } else if !pair_missing && (pairwise || !row.iter().any(|value| value.is_nan())) {
+            points.push(PairPoint { case_number: case_numbers[i], x, y, imputed: false });
+        }
+    }
+
+    let confidence = confidence.unwrap_or(DEFAULT_PAIR_CONFIDENCE);
+    build_pair_detail(variable_x, variable_y, points, confidence)
+}
+
+// Pearson correlation with a Fisher z interval, and the least squares line y = a + b x
+pub fn build_pair_detail(
+    variable_x: &str,
+    variable_y: &str,
+    points: Vec<PairPoint>,
+    confidence: f64
+) -> Result<PairDetail, String> {
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(format!("Confidence level must lie between 0 and 1, got {}", confidence));
+    }
+    let n = points.len();
+    if n < 3 {
+        return Err(
+            format!("{} and {} share {} cases, at least 3 are needed", variable_x, variable_y, n)
+        );
+    }
+
+    let count = n as f64;
+    let mean_x = points.iter().map(|point| point.x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|point| point.y).sum::<f64>() / count;
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for point in &points {
+        let (dx, dy) = (point.x - mean_x, point.y - mean_y);
+        sxx += dx * dx;
+        syy += dy * dy;
+        sxy += dx * dy;
+    }
+    if sxx <= 0.0 || syy <= 0.0 {
+        return Err(format!("{} or {} is constant over these cases", variable_x, variable_y));
+    }
+
+    let correlation = (sxy / (sxx * syy).sqrt()).clamp(-1.0, 1.0);
+    let slope = sxy / sxx;
+
+    // No Fisher interval below four cases or at |r| = 1
+    let interval = require_fisher_z_cases(n).is_ok() && correlation.abs() < 1.0;
+    let (lower_bound, upper_bound) = if interval {
+        let normal = Normal::new(0.0, 1.0).map_err(|e| e.to_string())?;
+        let half_width = normal.inverse_cdf(0.5 + confidence / 2.0) / (count - 3.0).sqrt();
+        let z = correlation.atanh();
+        (Some((z - half_width).tanh()), Some((z + half_width).tanh()))
+    } else {
+        (None, None)
+    };
+
+    Ok(PairDetail {
+        variable_x: variable_x.to_string(),
+        variable_y: variable_y.to_string(),
+        n,
+        correlation,
+        significance: correlation_p_value(correlation, n),
+        confidence_level: confidence,
+        lower_bound,
+        upper_bound,
+        intercept: mean_y - slope * mean_x,
+        slope,
+        points,
+    })
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::{ FactorAnalysisConfig, PairwiseNConvention },
+    data::AnalysisData,
+    result::{ CorrelationMatrix, FlaggedPair, PairwiseDeletion, PairwiseMatrix },
+};
+
+use super::core::{
+    build_correlation_table,
+    cache_enabled,
+    cached_correlation_matrix,
+    calculate_matrix,
+    calculate_polychoric_matrix,
+    calculate_pooled_within_groups_matrix,
+    extract_data_matrix,
+    extract_raw_data_matrix,
+    require_matrix_cases,
+};
+
+// Fewer complete pairs than this give correlations of +-1 or close to it
+pub const DEFAULT_MIN_PAIRWISE_N: usize = 3;
+
+// Pairwise deletion applies unless missing values are replaced by the mean
+pub fn uses_pairwise_deletion(config: &FactorAnalysisConfig) -> bool {
+    config.options.exclude_pair_wise && !config.options.replace_mean
+}
+
+pub fn min_pairwise_n(config: &FactorAnalysisConfig) -> usize {
+    config.options.min_pairwise_n
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MIN_PAIRWISE_N)
+}
+
+// Correlation or covariance matrix under the configured missing value handling, along with
+// the variable names and the number of cases it is based on
+pub fn extract_correlation_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    matrix_type: &str
+) -> Result<(DMatrix<f64>, Vec<String>, usize), String> {
+    if cache_enabled(config) {
+        return cached_correlation_matrix(data, config, matrix_type, || {
+            compute_correlation_matrix(data, config, matrix_type)
+        });
+    }
+
+    compute_correlation_matrix(data, config, matrix_type)
+}
+
+fn compute_correlation_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    matrix_type: &str
+) -> Result<(DMatrix<f64>, Vec<String>, usize), String> {
+    if let Some(group_var) = &config.main.within_groups_var {
+        if config.extraction.polychoric {
+            return Err("Polychoric correlations can't be pooled within groups".to_string());
+        }
+        return calculate_pooled_within_groups_matrix(data, config, group_var, matrix_type);
+    }
+    if config.extraction.polychoric && matrix_type == "correlation" {
+        return calculate_polychoric_matrix(data, config);
+    }
+    if uses_pairwise_deletion(config) {
+        let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+        let pairwise = calculate_pairwise_matrix(&raw_matrix, matrix_type, min_pairwise_n(config))?;
+        let n_obs = pairwise_analysis_n(&pairwise, &config.options.pairwise_n);
+
+        Ok((pairwise.matrix, var_names, n_obs))
+    } else {
+        let (data_matrix, var_names) = extract_data_matrix(data, config)?;
+        let matrix = calculate_matrix(&data_matrix, matrix_type)?;
+
+        Ok((matrix, var_names, data_matrix.nrows()))
+    }
+}
+
+// Each cell uses the cases where both variables are present. Cells with fewer than min_pairs
+// pairs are treated as missing: they are flagged and set to zero so the matrix stays usable.
+pub fn calculate_pairwise_matrix(
+    raw_matrix: &DMatrix<f64>,
+    matrix_type: &str,
+    min_pairs: usize
+) -> Result<PairwiseMatrix, String> {
+    let n_cols = raw_matrix.ncols();
+    let _span = crate::trace_span!(
+        "calculate_pairwise_matrix",
+        rows = raw_matrix.nrows(),
+        cols = n_cols
+    );
+    let mut matrix = DMatrix::zeros(n_cols, n_cols);
+    let mut pair_counts = vec![vec![0usize; n_cols]; n_cols];
+    let mut flagged = Vec::new();
+
+    for i in 0..n_cols {
+        for j in i..n_cols {
+            let pairs: Vec<(f64, f64)> = (0..raw_matrix.nrows())
+                .map(|k| (raw_matrix[(k, i)], raw_matrix[(k, j)]))
+                .filter(|(x, y)| !x.is_nan() && !y.is_nan())
+                .collect();
+            let n = pairs.len();
+            pair_counts[i][j] = n;
+            pair_counts[j][i] = n;
+
+            if i == j && n < 2 {
+                continue;
+            }
+            if i != j && n < min_pairs.max(2) {
+                flagged.push((i, j));
+                continue;
+            }
+
+            let (mean_x, mean_y) = if matrix_type == "sscp" {
+                (0.0, 0.0)
+            } else {
+                (
+                    pairs.iter().map(|(x, _)| x).sum::<f64>() / (n as f64),
+                    pairs.iter().map(|(_, y)| y).sum::<f64>() / (n as f64),
+                )
+            };
+            let mut sum_xy = 0.0;
+            let mut sum_xx = 0.0;
+            let mut sum_yy = 0.0;
+            for (x, y) in &pairs {
+                sum_xy += (x - mean_x) * (y - mean_y);
+                sum_xx += (x - mean_x).powi(2);
+                sum_yy += (y - mean_y).powi(2);
+            }
+
+            let value = match matrix_type {
+                "correlation" if i == j => 1.0,
+                "correlation" => sum_xy / (sum_xx * sum_yy).sqrt(),
+                "sscp" | "adjusted_sscp" => sum_xy,
+                _ => sum_xy / ((n - 1) as f64),
+            };
+            matrix[(i, j)] = value;
+            matrix[(j, i)] = value;
+        }
+    }
+
+    // Every variable needs two valid cases of its own
+    require_matrix_cases((0..n_cols).map(|j| pair_counts[j][j]).min().unwrap_or(0))?;
+
+    let mut pairwise = PairwiseMatrix {
+        matrix,
+        pair_counts,
+        n_obs: 0,
+        flagged,
+    };
+    pairwise.n_obs = pairwise_analysis_n(&pairwise, &PairwiseNConvention::Minimum);
+
+    Ok(pairwise)
+}
+
+// Single N for statistics on a pairwise matrix, summarizing the pair counts of the retained
+// off-diagonal cells; the diagonal counts are used when every off-diagonal cell is flagged
+pub fn pairwise_analysis_n(pairwise: &PairwiseMatrix, convention: &PairwiseNConvention) -> usize {
+    let n_cols = pairwise.pair_counts.len();
+    let mut counts: Vec<f64> = (0..n_cols)
+        .flat_map(|i| ((i + 1)..n_cols).map(move |j| (i, j)))
+        .filter(|cell| !pairwise.flagged.contains(cell))
+        .map(|(i, j)| pairwise.pair_counts[i][j] as f64)
+        .collect();
+    if counts.is_empty() {
+        counts = (0..n_cols).map(|j| pairwise.pair_counts[j][j] as f64).collect();
+    }
+    if counts.is_empty() {
+        return 0;
+    }
+
+    let n = match convention {
+        PairwiseNConvention::Minimum => counts.iter().copied().fold(f64::INFINITY, f64::min),
+        PairwiseNConvention::Average => counts.iter().sum::<f64>() / (counts.len() as f64),
+        PairwiseNConvention::Harmonic => {
+            (counts.len() as f64) / counts.iter().map(|count| 1.0 / count).sum::<f64>()
+        }
+    };
+
+    // Rounded down so a test never claims more cases than the summary supports
+    n.floor() as usize
+}
+
+// Correlation table with each significance based on its own pair count; flagged cells are blank
+pub fn build_pairwise_correlation_matrix(
+    pairwise: &PairwiseMatrix,
+    var_names: &[String]
+) -> Result<CorrelationMatrix, String> {
+    let mut matrix = pairwise.matrix.clone();
+    for &(i, j) in &pairwise.flagged {
+        matrix[(i, j)] = f64::NAN;
+        matrix[(j, i)] = f64::NAN;
+    }
+
+    build_correlation_table(&matrix, var_names, |i, j| pairwise.pair_counts[i][j])
+}
+
+pub fn calculate_pairwise_deletion(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<PairwiseDeletion, String> {
+    let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+    let min_pairs = min_pairwise_n(config);
+    let pairwise = calculate_pairwise_matrix(&raw_matrix, "correlation", min_pairs)?;
+
+    Ok(build_pairwise_deletion(&pairwise, &var_names, min_pairs, &config.options.pairwise_n))
+}
+
+pub fn build_pairwise_deletion(
+    pairwise: &PairwiseMatrix,
+    var_names: &[String],
+    min_pairs: usize,
+    convention: &PairwiseNConvention
+) -> PairwiseDeletion {
+    let mut pair_counts = HashMap::new();
+    for (i, var_name) in var_names.iter().enumerate() {
+        let counts: HashMap<String, usize> = var_names
+            .iter()
+            .enumerate()
+            .map(|(j, other_var)| (other_var.clone(), pairwise.pair_counts[i][j]))
+            .collect();
+        pair_counts.insert(var_name.clone(), counts);
+    }
+
+    PairwiseDeletion {
+        min_pairwise_n: min_pairs,
+        analysis_n: pairwise_analysis_n(pairwise, convention),
+        n_convention: convention.clone(),
+        pair_counts,
+        flagged_pairs: pairwise.flagged
+            .iter()
+            .map(|&(i, j)| FlaggedPair {
+                variable_a: var_names[i].clone(),
+                variable_b: var_names[j].clone(),
+                n: pairwise.pair_counts[i][j],
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,125 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::ParallelAnalysis,
+};
+use crate::utils::rng::{ resolve_seed, run_replicates };
+
+use super::core::{
+    calculate_matrix,
+    extract_correlation_matrix,
+    independent_factor_model,
+    robust_symmetric_eigen,
+    simulate_matrix,
+};
+
+// Default settings for Horn's parallel analysis
+pub const DEFAULT_PARALLEL_ITERATIONS: usize = 100;
+pub const DEFAULT_PARALLEL_PERCENTILE: f64 = 95.0;
+
+// Horn's parallel analysis: compare observed eigenvalues with eigenvalues of random data
+pub fn calculate_parallel_analysis(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<ParallelAnalysis, String> {
+    let (corr_matrix, _, n_obs) = extract_correlation_matrix(data, config, "correlation")?;
+
+    build_parallel_analysis(&corr_matrix, n_obs, config)
+}
+
+pub fn build_parallel_analysis(
+    corr_matrix: &DMatrix<f64>,
+    n_obs: usize,
+    config: &FactorAnalysisConfig
+) -> Result<ParallelAnalysis, String> {
+    let n_vars = corr_matrix.nrows();
+
+    let iterations = match config.extraction.parallel_iter {
+        Some(iter) if iter > 0 => iter as usize,
+        _ => DEFAULT_PARALLEL_ITERATIONS,
+    };
+    let percentile = match config.extraction.parallel_percentile {
+        Some(p) if p > 0.0 && p < 100.0 => p,
+        _ => DEFAULT_PARALLEL_PERCENTILE,
+    };
+
+    let seed = resolve_seed(config);
+
+    let observed_eigenvalues = sorted_eigenvalues(corr_matrix);
+    let random_eigenvalues = simulate_random_eigenvalues(n_obs, n_vars, iterations, seed)?;
+
+    let mut random_mean_eigenvalues = vec![0.0; n_vars];
+    let mut random_percentile_eigenvalues = vec![0.0; n_vars];
+
+    for j in 0..n_vars {
+        let mut values: Vec<f64> = random_eigenvalues
+            .iter()
+            .map(|eigenvalues| eigenvalues[j])
+            .collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        random_mean_eigenvalues[j] = values.iter().sum::<f64>() / (values.len() as f64);
+        random_percentile_eigenvalues[j] = percentile_of_sorted(&values, percentile);
+    }
+
+    // Retain factors while the observed eigenvalue exceeds the random percentile
+    let suggested_factors = observed_eigenvalues
+        .iter()
+        .zip(random_percentile_eigenvalues.iter())
+        .take_while(|(observed, random)| observed > random)
+        .count();
+
+    Ok(ParallelAnalysis {
+        observed_eigenvalues,
+        random_mean_eigenvalues,
+        random_percentile_eigenvalues,
+        percentile,
+        iterations,
+        suggested_factors,
+        seed,
+    })
+}
+
+// Eigenvalues of correlation matrices of uncorrelated normal data, one vector per replicate.
+// Each replicate draws from its own seed stream, so the result doesn't depend on thread count.
+pub fn simulate_random_eigenvalues(
+    n_obs: usize,
+    n_vars: usize,
+    iterations: usize,
+    seed: u64
+) -> Result<Vec<Vec<f64>>, String> {
+    let model = independent_factor_model(n_vars);
+    run_replicates(seed, iterations, |_, rng| {
+        let random_data = simulate_matrix(&model, n_obs, rng)?;
+        let random_corr = calculate_matrix(&random_data, "correlation")?;
+        Ok(sorted_eigenvalues(&random_corr))
+    })
+        .into_iter()
+        .collect()
+}
+
+// Eigenvalues of a symmetric matrix in descending order
+pub fn sorted_eigenvalues(matrix: &DMatrix<f64>) -> Vec<f64> {
+    let mut eigenvalues: Vec<f64> = robust_symmetric_eigen(matrix)
+        .eigenvalues.iter()
+        .copied()
+        .collect();
+    eigenvalues.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    eigenvalues
+}
+
+// Percentile (0-100) of an ascending sorted slice using linear interpolation
+pub fn percentile_of_sorted(values: &[f64], percentile: f64) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+
+    let position = (percentile / 100.0) * ((values.len() - 1) as f64);
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    let fraction = position - (lower as f64);
+
+    values[lower] + (values[upper] - values[lower]) * fraction
+}
@@ -0,0 +1,165 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::{ FactorAnalysisConfig, PerfectCorrelationPolicy },
+    data::AnalysisData,
+};
+use crate::utils::error::ErrorCollector;
+
+use super::core::{ extract_correlation_matrix, resolve_variable_names };
+
+// Correlations this close to +1 or -1 count as perfect
+pub const PERFECT_CORRELATION_TOLERANCE: f64 = 1e-8;
+
+// First variable, second variable and their correlation
+pub type PerfectPair = (String, String, f64);
+
+// Applies the perfect correlation policy to the analysis variables, recording the pairs and
+// the action in the warnings. Returns the configuration for the rest of the analysis: the
+// same one, or under Drop one whose target variables leave out the dropped variables.
+pub fn apply_perfect_correlation_policy(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    error_collector: &mut ErrorCollector
+) -> Result<FactorAnalysisConfig, String> {
+    // Without a matrix the stages report their own errors
+    let Ok((corr_matrix, var_names, _)) = extract_correlation_matrix(data, config, "correlation")
+    else {
+        return Ok(config.clone());
+    };
+    let pairs = find_perfect_correlations(&corr_matrix, &var_names);
+    if pairs.is_empty() {
+        return Ok(config.clone());
+    }
+
+    let variables = resolve_variable_names(data, config)?;
+    let dropped = handle_perfect_correlations(&pairs, &variables, config, error_collector)?;
+    if dropped.is_empty() {
+        return Ok(config.clone());
+    }
+
+    let mut reduced_config = config.clone();
+    reduced_config.main.target_var = Some(
+        variables
+            .into_iter()
+            .filter(|var| !dropped.contains(var))
+            .collect()
+    );
+    Ok(reduced_config)
+}
+
+// The policy on a stored solution, whose variables are fixed: Drop keeps the pairs as Warn
+// does and says so
+pub fn check_stored_perfect_correlations(
+    corr_matrix: &DMatrix<f64>,
+    var_names: &[String],
+    config: &FactorAnalysisConfig,
+    error_collector: &mut ErrorCollector
+) -> Result<(), String> {
+    let pairs = find_perfect_correlations(corr_matrix, var_names);
+    if pairs.is_empty() {
+        return Ok(());
+    }
+    if config.options.perfect_correlation != PerfectCorrelationPolicy::Drop {
+        return handle_perfect_correlations(&pairs, var_names, config, error_collector).map(|_| ());
+    }
+
+    let listed = list_pairs(&pairs);
+    error_collector.add_warning(
+        "perfect_correlation",
+        &format!(
+            "Perfectly correlated variables kept, a stored solution can't drop them: {}",
+            listed
+        )
+    );
+    Ok(())
+}
+
+// Pairs of matrix columns with |r| within the tolerance of 1, in column order
+pub fn find_perfect_correlations(
+    corr_matrix: &DMatrix<f64>,
+    var_names: &[String]
+) -> Vec<PerfectPair> {
+    let n = corr_matrix.nrows();
+    let mut pairs = Vec::new();
+    for i in 0..n {
+        for j in i + 1..n {
+            let r = corr_matrix[(i, j)];
+            if (r.abs() - 1.0).abs() <= PERFECT_CORRELATION_TOLERANCE {
+                pairs.push((var_names[i].clone(), var_names[j].clone(), r));
+            }
+        }
+    }
+    pairs
+}
+
+// Reports every pair in the warnings, or fails under Error, and returns the variables to
+// drop. A column of an encoded categorical variable stands for the whole variable; two columns
+// of the same variable are kept, since dropping the variable would lose both.
+pub fn handle_perfect_correlations(
+    pairs: &[PerfectPair],
+    variables: &[String],
+    config: &FactorAnalysisConfig,
+    error_collector: &mut ErrorCollector
+) -> Result<Vec<String>, String> {
+    let listed = list_pairs(pairs);
+    let mut dropped: Vec<String> = Vec::new();
+    match config.options.perfect_correlation {
+        PerfectCorrelationPolicy::Error => {
+            return Err(format!("Perfectly correlated variables: {}", listed));
+        }
+        PerfectCorrelationPolicy::Warn => {
+            error_collector.add_warning(
+                "perfect_correlation",
+                &format!("Perfectly correlated variables kept: {}", listed)
+            );
+        }
+        PerfectCorrelationPolicy::Drop => {
+            let source = |column: &String| {
+                variables
+                    .iter()
+                    .find(|var| {
+                        *var == column || column.starts_with(&format!("{}=", var))
+                    })
+                    .cloned()
+            };
+            for (column_one, column_two, r) in pairs {
+                let (Some(var_one), Some(var_two)) = (source(column_one), source(column_two)) else {
+                    continue;
+                };
+                // A pair with a dropped variable no longer exists
+                if dropped.contains(&var_one) || dropped.contains(&var_two) {
+                    continue;
+                }
+                let msg = if var_one == var_two {
+                    format!(
+                        "{} and {} (r = {:.3}) both encode {}, which is kept",
+                        column_one,
+                        column_two,
+                        r,
+                        var_one
+                    )
+                } else {
+                    dropped.push(var_two.clone());
+                    format!(
+                        "{} dropped, correlating perfectly with {} (r = {:.3})",
+                        var_two,
+                        column_one,
+                        r
+                    )
+                };
+                error_collector.add_warning("perfect_correlation", &msg);
+            }
+        }
+    }
+
+    Ok(dropped)
+}
+
+fn list_pairs(pairs: &[PerfectPair]) -> String {
+    pairs
+        .iter()
+        .map(|(var_one, var_two, r)| format!("{} and {} (r = {:.3})", var_one, var_two, r))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,1308 @@
+use crate::models::{
+    config::{ DataMode, FactorAnalysisConfig },
+    data::AnalysisData,
+    result::{
+        AnalysisSession,
+        DataModeReport,
+        ExtractionResult,
+        FactorAnalysisResult,
+        StageResult,
+    },
+};
+use crate::utils::{ error::ErrorCollector, rng::resolve_seed, timing::{ now_ms, StageTimer } };
+
+use super::core;
+
+// Complete analysis of the data: validation, descriptives, matrices, adequacy tests, extraction,
+// rotation, scores and diagnostics, each stage switched by its configuration flag
+pub fn run_factor_analysis(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<FactorAnalysisResult, String> {
+    let mut error_collector = ErrorCollector::default();
+    run_factor_analysis_with_errors(data, config, &mut error_collector)
+}
+
+// As run_factor_analysis, keeping the errors of optional stages that were skipped
+pub fn run_factor_analysis_with_errors(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    error_collector: &mut ErrorCollector
+) -> Result<FactorAnalysisResult, String> {
+    if let Err(e) = validate_config(config) {
+        error_collector.add_error("config.validation", &e);
+        return Err(e);
+    }
+
+    // Data above the memory limit is never materialized as a matrix
+    let data_mode = match core::assess_data_mode(data, config) {
+        Ok(data_mode) => data_mode,
+        Err(e) => {
+            error_collector.add_error("assess_data_mode", &e);
+            return Err(e);
+        }
+    };
+
+    if data_mode.mode == DataMode::InMemory {
+        run_data_pipeline(data, config, error_collector)
+    } else {
+        run_large_data_pipeline(data, config, data_mode, error_collector).map(|(result, _)| result)
+    }
+}
+
+pub fn validate_config(config: &FactorAnalysisConfig) -> Result<(), String> {
+    if config.main.target_var.as_ref().is_none_or(|vars| vars.is_empty()) {
+        return Err("No target variables selected for factor analysis".to_string());
+    }
+    for (name, converge) in [
+        ("Extraction", config.extraction.converge),
+        ("Rotation", config.rotation.converge),
+        ("Polychoric", config.extraction.polychoric_converge),
+    ] {
+        if converge.is_some_and(|tolerance| !(tolerance > 0.0 && tolerance.is_finite())) {
+            return Err(format!("{} convergence tolerance must be a positive number", name));
+        }
+    }
+    if config.extraction.polychoric_max_iter.is_some_and(|max_iter| max_iter < 1) {
+        return Err("Polychoric MaxIter must be at least 1".to_string());
+    }
+
+    Ok(())
+}
+
+// Every stage computed from the data in memory
+pub fn run_data_pipeline(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    error_collector: &mut ErrorCollector
+) -> Result<FactorAnalysisResult, String> {
+    run_data_pipeline_with_stages(data, config, error_collector, &mut |_| {})
+}
+
+// As run_data_pipeline, handing the descriptives, the correlation matrix, the extraction and
+// the rotation to on_stage as each is ready, so they can be shown before the analysis ends
+pub fn run_data_pipeline_with_stages(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    error_collector: &mut ErrorCollector,
+    on_stage: &mut dyn FnMut(StageResult)
+) -> Result<FactorAnalysisResult, String> {
+    let _span = crate::trace_span!(
+        "run_data_pipeline",
+        datasets = data.target_data.len(),
+        cases = data.target_data.first().map_or(0, |dataset| dataset.len())
+    );
+
+    // Initialize result with executed functions tracking
+    let mut executed_functions = Vec::new();
+    let mut timer = StageTimer::start();
+
+    // Filter Data based on value target if present
+    let stage_started = now_ms();
+    let filtered_data = match core::filter_valid_cases(data, config) {
+        Ok(filtered) => filtered,
+        Err(e) => {
+            error_collector.add_error("filter_valid_cases", &e);
+            return Err(e);
+        }
+    };
+    timer.record("filter_valid_cases", stage_started);
+
+    // Perfectly correlated variables leave the matrix singular, so the policy runs before
+    // anything is computed on it
+    executed_functions.push("apply_perfect_correlation_policy".to_string());
+    let stage_started = now_ms();
+    let adjusted_config = match
+        core::apply_perfect_correlation_policy(&filtered_data, config, error_collector)
+    {
+        Ok(adjusted) => adjusted,
+        Err(e) => {
+            error_collector.add_error("apply_perfect_correlation_policy", &e);
+            return Err(e);
+        }
+    };
+    let config = &adjusted_config;
+    timer.record("apply_perfect_correlation_policy", stage_started);
+
+    // Every extraction stage would fail the same way, so unmet requirements end the analysis
+    if let Err(e) = core::check_analysis_requirements(&filtered_data, config) {
+        error_collector.add_error("check_analysis_requirements", &e);
+        return Err(e);
+    }
+
+    // Step 1: Calculate Descriptive Statistics if requested
+    let mut descriptive_statistics = None;
+    if config.descriptives.univar_desc {
+        executed_functions.push("calculate_descriptive_statistics".to_string());
+        let stage_started = now_ms();
+        match core::calculate_descriptive_statistics(&filtered_data, config) {
+            Ok(stats) => {
+                descriptive_statistics = Some(stats);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_descriptive_statistics", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_descriptive_statistics", stage_started);
+    }
+
+    // Descriptives broken down by the grouping variable
+    let mut grouped_descriptive_statistics = None;
+    if config.descriptives.univar_desc && config.descriptives.group_var.is_some() {
+        executed_functions.push("calculate_grouped_descriptive_statistics".to_string());
+        let stage_started = now_ms();
+        match core::calculate_grouped_descriptive_statistics(&filtered_data, config) {
+            Ok(stats) => {
+                grouped_descriptive_statistics = Some(stats);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_grouped_descriptive_statistics", &e);
+            }
+        }
+        timer.record("calculate_grouped_descriptive_statistics", stage_started);
+    }
+    if config.descriptives.univar_desc {
+        on_stage(StageResult::Descriptives {
+            descriptive_statistics: descriptive_statistics.clone(),
+            grouped_descriptive_statistics: grouped_descriptive_statistics.clone(),
+        });
+    }
+
+    // Step 2: Calculate Correlation/Covariance Matrix based on Analyze selection
+    let mut correlation_matrix = None;
+    if config.extraction.correlation {
+        executed_functions.push("calculate_correlation_matrix".to_string());
+        let stage_started = now_ms();
+        match core::calculate_correlation_matrix(&filtered_data, config) {
+            Ok(matrix) => {
+                correlation_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_correlation_matrix", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_correlation_matrix", stage_started);
+    } else if config.extraction.covariance {
+        executed_functions.push("calculate_covariance_matrix".to_string());
+        let stage_started = now_ms();
+        match core::calculate_covariance_matrix(&filtered_data, config) {
+            Ok(matrix) => {
+                correlation_matrix = Some(matrix); // Store in the same field
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_covariance_matrix", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_covariance_matrix", stage_started);
+    }
+    if let Some(matrix) = &correlation_matrix {
+        on_stage(StageResult::CorrelationMatrix { correlation_matrix: matrix.clone() });
+    }
+
+    // Solver used on the analyzed matrix, its asymmetry and any negative eigenvalues
+    executed_functions.push("calculate_eigen_diagnostics".to_string());
+    let stage_started = now_ms();
+    let eigen_diagnostics = match core::calculate_eigen_diagnostics(&filtered_data, config) {
+        Ok(diagnostics) => Some(diagnostics),
+        Err(e) => {
+            error_collector.add_error("calculate_eigen_diagnostics", &e);
+            None
+        }
+    };
+    timer.record("calculate_eigen_diagnostics", stage_started);
+
+    // Cases whose removal moves the correlation matrix the most
+    let mut case_influence = None;
+    if config.descriptives.case_influence {
+        executed_functions.push("calculate_case_influence".to_string());
+        let stage_started = now_ms();
+        match core::calculate_case_influence(&filtered_data, config) {
+            Ok(influence) => {
+                case_influence = Some(influence);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_case_influence", &e);
+            }
+        }
+        timer.record("calculate_case_influence", stage_started);
+    }
+
+    // Gower distances and principal coordinates of the cases, for mixed-type variables
+    let mut principal_coordinates = None;
+    if config.extraction.pcoa {
+        executed_functions.push("calculate_principal_coordinates".to_string());
+        let stage_started = now_ms();
+        match core::calculate_principal_coordinates(&filtered_data, config) {
+            Ok(coordinates) => {
+                principal_coordinates = Some(coordinates);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_principal_coordinates", &e);
+            }
+        }
+        timer.record("calculate_principal_coordinates", stage_started);
+    }
+
+    // Map of the variables from the distances 1 - |r|
+    let mut multidimensional_scaling = None;
+    if config.extraction.mds {
+        executed_functions.push("calculate_multidimensional_scaling".to_string());
+        let stage_started = now_ms();
+        match core::calculate_multidimensional_scaling(&filtered_data, config) {
+            Ok(scaling) => {
+                multidimensional_scaling = Some(scaling);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_multidimensional_scaling", &e);
+            }
+        }
+        timer.record("calculate_multidimensional_scaling", stage_started);
+    }
+
+    // Principal components against a common-factor extraction of the same matrix
+    let mut component_factor_comparison = None;
+    if config.extraction.compare_models {
+        executed_functions.push("calculate_component_factor_comparison".to_string());
+        let stage_started = now_ms();
+        match core::calculate_component_factor_comparison(&filtered_data, config) {
+            Ok(comparison) => {
+                component_factor_comparison = Some(comparison);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_component_factor_comparison", &e);
+            }
+        }
+        timer.record("calculate_component_factor_comparison", stage_started);
+    }
+
+    // Pair counts and pairs below the minimum when missing values are excluded pairwise
+    let mut pairwise_deletion = None;
+    if core::uses_pairwise_deletion(config) {
+        executed_functions.push("calculate_pairwise_deletion".to_string());
+        let stage_started = now_ms();
+        match core::calculate_pairwise_deletion(&filtered_data, config) {
+            Ok(report) => {
+                pairwise_deletion = Some(report);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_pairwise_deletion", &e);
+            }
+        }
+        timer.record("calculate_pairwise_deletion", stage_started);
+    }
+
+    // Distance correlations alongside the Pearson matrix, for non-linear dependence
+    let mut distance_correlation_matrix = None;
+    if config.descriptives.distance_corr {
+        executed_functions.push("calculate_distance_correlation_matrix".to_string());
+        let stage_started = now_ms();
+        match core::calculate_distance_correlation_matrix(&filtered_data, config) {
+            Ok(matrix) => {
+                distance_correlation_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_distance_correlation_matrix", &e);
+            }
+        }
+        timer.record("calculate_distance_correlation_matrix", stage_started);
+    }
+
+    // Sums of squares and cross-products, raw and about the means
+    let mut sscp_matrix = None;
+    if config.descriptives.sscp {
+        executed_functions.push("calculate_sscp_matrix".to_string());
+        let stage_started = now_ms();
+        match core::calculate_sscp_matrix(&filtered_data, config) {
+            Ok(matrix) => {
+                sscp_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_sscp_matrix", &e);
+            }
+        }
+        timer.record("calculate_sscp_matrix", stage_started);
+    }
+
+    // Step 3: Calculate Inverse Matrix if requested
+    let mut inverse_correlation_matrix = None;
+    if config.descriptives.inverse {
+        executed_functions.push("calculate_inverse_correlation_matrix".to_string());
+        let stage_started = now_ms();
+        match core::calculate_inverse_correlation_matrix(&filtered_data, config) {
+            Ok(matrix) => {
+                inverse_correlation_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_inverse_correlation_matrix", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_inverse_correlation_matrix", stage_started);
+    }
+
+    // Step 4: Calculate KMO and Bartlett's Test if requested
+    let mut kmo_bartletts_test = None;
+    if config.descriptives.kmo {
+        executed_functions.push("calculate_kmo_bartletts_test".to_string());
+        let stage_started = now_ms();
+        match core::calculate_kmo_bartletts_test(&filtered_data, config) {
+            Ok(test) => {
+                kmo_bartletts_test = Some(test);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_kmo_bartletts_test", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_kmo_bartletts_test", stage_started);
+    }
+
+    // Intraclass correlations as a reliability check of the variable set
+    let mut intraclass_correlation = None;
+    if config.descriptives.icc {
+        executed_functions.push("calculate_intraclass_correlation".to_string());
+        let stage_started = now_ms();
+        match core::calculate_intraclass_correlation(&filtered_data, config) {
+            Ok(icc) => {
+                intraclass_correlation = Some(icc);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_intraclass_correlation", &e);
+            }
+        }
+        timer.record("calculate_intraclass_correlation", stage_started);
+    }
+
+    // Step 5: Calculate Anti-Image Matrices if requested
+    let mut anti_image_matrices = None;
+    if config.descriptives.anti_image {
+        executed_functions.push("calculate_anti_image_matrices".to_string());
+        let stage_started = now_ms();
+        match core::calculate_anti_image_matrices(&filtered_data, config) {
+            Ok(matrices) => {
+                anti_image_matrices = Some(matrices);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_anti_image_matrices", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_anti_image_matrices", stage_started);
+    }
+
+    // The unrotated and rotated solutions behind the extraction and rotation tables and the
+    // iteration summaries, computed once
+    executed_functions.push("extract_factors".to_string());
+    let stage_started = now_ms();
+    let correlation = core::extract_correlation_matrix(&filtered_data, config, "correlation");
+    let extraction_result = correlation.and_then(|(corr_matrix, var_names, _)| {
+        core::extract_factors(&corr_matrix, config, &var_names)
+    });
+    let rotation_result = match &extraction_result {
+        Ok(extraction) if !config.rotation.none => core::rotate_factors(extraction, config),
+        Ok(_) => Err("No rotation requested".to_string()),
+        Err(e) => Err(e.clone()),
+    };
+    timer.record("extract_factors", stage_started);
+    if let Ok(extraction) = &extraction_result {
+        warn_not_converged(extraction, error_collector);
+    }
+
+    // Step 6: Calculate Communalities
+    executed_functions.push("calculate_communalities".to_string());
+    let stage_started = now_ms();
+    let communalities = match &extraction_result {
+        Ok(extraction) => Some(core::build_communalities(extraction, &extraction.var_names)),
+        Err(e) => {
+            error_collector.add_error("calculate_communalities", e);
+            None
+        }
+    };
+    timer.record("calculate_communalities", stage_started);
+
+    // Step 7: Calculate Total Variance Explained
+    executed_functions.push("calculate_total_variance_explained".to_string());
+    let stage_started = now_ms();
+    // If rotation fails, the table goes without rotation sums
+    let total_variance_explained = match &extraction_result {
+        Ok(extraction) => {
+            Some(
+                core::build_total_variance_explained(
+                    extraction,
+                    rotation_result.as_ref().ok(),
+                    extraction.var_names.len(),
+                    config
+                )
+            )
+        }
+        Err(e) => {
+            error_collector.add_error("calculate_total_variance_explained", e);
+            None
+        }
+    };
+    timer.record("calculate_total_variance_explained", stage_started);
+
+    // Step 8: Calculate Factor/Component Matrix
+    executed_functions.push("calculate_component_matrix".to_string());
+    let stage_started = now_ms();
+    let component_matrix = match &extraction_result {
+        Ok(extraction) => Some(core::build_component_matrix(extraction, &extraction.var_names)),
+        Err(e) => {
+            error_collector.add_error("calculate_component_matrix", e);
+            None
+        }
+    };
+    timer.record("calculate_component_matrix", stage_started);
+    on_stage(StageResult::Extraction {
+        communalities: communalities.clone(),
+        total_variance_explained: total_variance_explained.clone(),
+        component_matrix: component_matrix.clone(),
+    });
+
+    // Raw and rescaled solution when the covariance matrix is analyzed
+    let mut covariance_extraction = None;
+    if core::analyzes_covariance(config) {
+        executed_functions.push("calculate_covariance_extraction".to_string());
+        let stage_started = now_ms();
+        match core::calculate_covariance_extraction(&filtered_data, config) {
+            Ok(extraction) => {
+                covariance_extraction = Some(extraction);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_covariance_extraction", &e);
+            }
+        }
+        timer.record("calculate_covariance_extraction", stage_started);
+    }
+
+    // Step 9: Calculate Scree Plot if requested
+    let mut scree_plot = None;
+    if config.extraction.scree {
+        executed_functions.push("calculate_scree_plot".to_string());
+        let stage_started = now_ms();
+        match core::calculate_scree_plot(&filtered_data, config) {
+            Ok(plot) => {
+                scree_plot = Some(plot);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_scree_plot", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_scree_plot", stage_started);
+    }
+
+    // Step 10: Calculate Reproduced Correlations if requested
+    let mut reproduced_correlations = None;
+    if config.descriptives.reproduced {
+        executed_functions.push("calculate_reproduced_correlations".to_string());
+        let stage_started = now_ms();
+        match core::calculate_reproduced_correlations(&filtered_data, config) {
+            Ok(correlations) => {
+                reproduced_correlations = Some(correlations);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_reproduced_correlations", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_reproduced_correlations", stage_started);
+    }
+
+    // Step 11: Calculate Rotated Component Matrix if not using 'None' rotation method
+    let mut rotated_component_matrix = None;
+    if !config.rotation.none && config.rotation.rotated_sol {
+        executed_functions.push("calculate_rotated_component_matrix".to_string());
+        let stage_started = now_ms();
+        match (&extraction_result, &rotation_result) {
+            (Ok(extraction), Ok(rotation)) => {
+                rotated_component_matrix = Some(
+                    core::create_rotated_component_matrix(rotation, &extraction.var_names)
+                );
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                error_collector.add_error("calculate_rotated_component_matrix", e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_rotated_component_matrix", stage_started);
+    }
+
+    // Step 12: Calculate Component Transformation Matrix if rotation is performed
+    let mut component_transformation_matrix = None;
+    if !config.rotation.none && config.rotation.rotated_sol {
+        executed_functions.push("calculate_component_transformation_matrix".to_string());
+        let stage_started = now_ms();
+        match &rotation_result {
+            Ok(rotation) => {
+                component_transformation_matrix = Some(
+                    core::create_component_transformation_matrix(rotation)
+                );
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_component_transformation_matrix", e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_component_transformation_matrix", stage_started);
+    }
+    if !config.rotation.none && config.rotation.rotated_sol {
+        on_stage(StageResult::Rotation {
+            rotated_component_matrix: rotated_component_matrix.clone(),
+            component_transformation_matrix: component_transformation_matrix.clone(),
+        });
+    }
+
+    // Step 13: Calculate Component Score Coefficient Matrix if scores are saved
+    let mut component_score_coefficient_matrix = None;
+    if config.scores.save_var {
+        executed_functions.push("calculate_component_score_coefficient_matrix".to_string());
+        let stage_started = now_ms();
+        match core::calculate_component_score_coefficient_matrix(&filtered_data, config) {
+            Ok(matrix) => {
+                component_score_coefficient_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_component_score_coefficient_matrix", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_component_score_coefficient_matrix", stage_started);
+    }
+
+    // Step 14: Calculate Component Score Covariance Matrix if scores are saved
+    let mut component_score_covariance_matrix = None;
+    if config.scores.save_var {
+        executed_functions.push("calculate_component_score_covariance_matrix".to_string());
+        let stage_started = now_ms();
+        match core::calculate_component_score_covariance_matrix(&filtered_data, config) {
+            Ok(matrix) => {
+                component_score_covariance_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_component_score_covariance_matrix", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_component_score_covariance_matrix", stage_started);
+    }
+
+    // Determinacy and validity of the saved factor scores
+    let mut factor_score_determinacy = None;
+    if config.scores.save_var {
+        executed_functions.push("calculate_factor_score_determinacy".to_string());
+        let stage_started = now_ms();
+        match core::calculate_factor_score_determinacy(&filtered_data, config) {
+            Ok(determinacy) => {
+                factor_score_determinacy = Some(determinacy);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_factor_score_determinacy", &e);
+            }
+        }
+        timer.record("calculate_factor_score_determinacy", stage_started);
+    }
+
+    // Alpha, omega, AVE and composite reliability of the items of each factor
+    let mut factor_reliability = None;
+    if config.descriptives.reliability {
+        executed_functions.push("calculate_factor_reliability".to_string());
+        let stage_started = now_ms();
+        match core::calculate_factor_reliability(&filtered_data, config) {
+            Ok(reliability) => {
+                factor_reliability = Some(reliability);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_factor_reliability", &e);
+            }
+        }
+        timer.record("calculate_factor_reliability", stage_started);
+    }
+
+    // HTMT ratios and the Fornell-Larcker criterion between the factors
+    let mut discriminant_validity = None;
+    if config.descriptives.discriminant_validity {
+        executed_functions.push("calculate_discriminant_validity".to_string());
+        let stage_started = now_ms();
+        match core::calculate_discriminant_validity(&filtered_data, config) {
+            Ok(validity) => {
+                discriminant_validity = Some(validity);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_discriminant_validity", &e);
+            }
+        }
+        timer.record("calculate_discriminant_validity", stage_started);
+    }
+
+    // Scores from the ordinal response patterns rather than a linear combination
+    let mut eap_scores = None;
+    if config.scores.eap {
+        executed_functions.push("calculate_eap_scores".to_string());
+        let stage_started = now_ms();
+        match core::calculate_eap_scores(&filtered_data, config) {
+            Ok(scores) => {
+                eap_scores = Some(scores);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_eap_scores", &e);
+            }
+        }
+        timer.record("calculate_eap_scores", stage_started);
+    }
+
+    // Step 15: Generate Loading Plots if requested
+    if config.rotation.loading_plot {
+        executed_functions.push("generate_loading_plots".to_string());
+        let stage_started = now_ms();
+        match core::generate_loading_plots(&filtered_data, config) {
+            Ok(_) => {}
+            Err(e) => {
+                error_collector.add_error("generate_loading_plots", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("generate_loading_plots", stage_started);
+    }
+
+    // Step 16: Run parallel analysis if requested
+    let mut parallel_analysis = None;
+    if config.extraction.parallel_analysis {
+        executed_functions.push("calculate_parallel_analysis".to_string());
+        let stage_started = now_ms();
+        match core::calculate_parallel_analysis(&filtered_data, config) {
+            Ok(analysis) => {
+                parallel_analysis = Some(analysis);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_parallel_analysis", &e);
+                // Continue execution despite errors for non-critical functions
+            }
+        }
+        timer.record("calculate_parallel_analysis", stage_started);
+    }
+
+    // Maximum likelihood fit of each factor count, with chi-square difference tests
+    let mut factor_count_comparison = None;
+    if config.extraction.factor_count_comparison {
+        executed_functions.push("calculate_factor_count_comparison".to_string());
+        let stage_started = now_ms();
+        match core::calculate_factor_count_comparison(&filtered_data, config) {
+            Ok(comparison) => {
+                factor_count_comparison = Some(comparison);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_factor_count_comparison", &e);
+            }
+        }
+        timer.record("calculate_factor_count_comparison", stage_started);
+    }
+
+    // Diagonally weighted least squares factors of the polychoric matrix, with robust fit
+    let mut weighted_least_squares = None;
+    if config.extraction.wlsmv {
+        executed_functions.push("calculate_weighted_least_squares".to_string());
+        let stage_started = now_ms();
+        match core::calculate_weighted_least_squares(&filtered_data, config) {
+            Ok(solution) => {
+                weighted_least_squares = Some(solution);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_weighted_least_squares", &e);
+            }
+        }
+        timer.record("calculate_weighted_least_squares", stage_started);
+    }
+
+    // Steps of the computation selected for auditing
+    let mut intermediate_matrices = None;
+    if core::includes_intermediate_matrices(config) {
+        executed_functions.push("calculate_intermediate_matrices".to_string());
+        let stage_started = now_ms();
+        match core::calculate_intermediate_matrices(&filtered_data, config) {
+            Ok(intermediate) => {
+                intermediate_matrices = Some(intermediate);
+            }
+            Err(e) => {
+                error_collector.add_error("calculate_intermediate_matrices", &e);
+            }
+        }
+        timer.record("calculate_intermediate_matrices", stage_started);
+    }
+
+    // Step 17: Record the equivalent FACTOR syntax and the effective configuration
+    executed_functions.push("calculate_reproducibility_log".to_string());
+    let stage_started = now_ms();
+    let reproducibility = match core::calculate_reproducibility_log(&filtered_data, config) {
+        Ok(log) => Some(log),
+        Err(e) => {
+            error_collector.add_error("calculate_reproducibility_log", &e);
+            None
+        }
+    };
+    timer.record("calculate_reproducibility_log", stage_started);
+
+    // Step 18: Collect iteration counts and convergence status for the metadata
+    executed_functions.push("calculate_iteration_summaries".to_string());
+    let stage_started = now_ms();
+    let iterations = match &extraction_result {
+        Ok(extraction) => {
+            core::build_iteration_summaries(extraction, rotation_result.as_ref().ok(), config)
+        }
+        Err(e) => {
+            error_collector.add_error("calculate_iteration_summaries", e);
+            Vec::new()
+        }
+    };
+    timer.record("calculate_iteration_summaries", stage_started);
+
+    let metadata = Some(
+        core::build_analysis_metadata(
+            timer.timings(),
+            timer.elapsed_ms(),
+            iterations,
+            Some(resolve_seed(config)),
+            core::assess_data_mode(data, config).ok()
+        )
+    );
+
+    // Create the final result
+    let result = FactorAnalysisResult {
+        descriptive_statistics,
+        grouped_descriptive_statistics,
+        scree_plot,
+        correlation_matrix,
+        pairwise_deletion,
+        distance_correlation_matrix,
+        sscp_matrix,
+        inverse_correlation_matrix,
+        kmo_bartletts_test,
+        intraclass_correlation,
+        anti_image_matrices,
+        communalities,
+        total_variance_explained,
+        component_matrix,
+        covariance_extraction,
+        eigen_diagnostics,
+        case_influence,
+        principal_coordinates,
+        multidimensional_scaling,
+        component_factor_comparison,
+        eap_scores,
+        reproduced_correlations,
+        rotated_component_matrix,
+        component_transformation_matrix,
+        component_score_coefficient_matrix,
+        component_score_covariance_matrix,
+        factor_score_determinacy,
+        factor_reliability,
+        discriminant_validity,
+        parallel_analysis,
+        factor_count_comparison,
+        weighted_least_squares,
+        intermediate_matrices,
+        reproducibility,
+        metadata,
+    };
+
+    Ok(result)
+}
+
+// Reduce data above the memory limit to a session by streaming or subsampling, then analyze it
+pub fn run_large_data_pipeline(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    data_mode: DataModeReport,
+    error_collector: &mut ErrorCollector
+) -> Result<(FactorAnalysisResult, AnalysisSession), String> {
+    let mut data_mode = data_mode;
+    let session = match core::create_large_data_session(data, config, &mut data_mode) {
+        Ok(session) => session,
+        Err(e) => {
+            error_collector.add_error("create_large_data_session", &e);
+            return Err(e);
+        }
+    };
+
+    let mut result = run_session_pipeline(&session, config, error_collector)?;
+    if let Some(metadata) = result.metadata.as_mut() {
+        metadata.data_mode = Some(data_mode);
+    }
+
+    Ok((result, session))
+}
+
+// Everything downstream of extraction from a saved session
+pub fn run_session_pipeline(
+    session: &AnalysisSession,
+    config: &FactorAnalysisConfig,
+    error_collector: &mut ErrorCollector
+) -> Result<FactorAnalysisResult, String> {
+    if let Err(e) = validate_config(config) {
+        error_collector.add_error("config.validation", &e);
+        return Err(e);
+    }
+
+    let mut timer = StageTimer::start();
+
+    if let Err(e) = core::check_session_compatibility(session, config) {
+        error_collector.add_error("check_session_compatibility", &e);
+        return Err(e);
+    }
+    warn_raw_data_stages(config, error_collector);
+
+    let var_names = &session.var_names;
+    let n_cases = session.n_cases;
+    let corr_matrix = core::session_correlation_matrix(session);
+    if
+        let Err(e) = core::check_stored_perfect_correlations(
+            &corr_matrix,
+            var_names,
+            config,
+            error_collector
+        )
+    {
+        error_collector.add_error("check_stored_perfect_correlations", &e);
+        return Err(e);
+    }
+    let extraction_result = core::session_extraction_result(session);
+    warn_not_converged(&extraction_result, error_collector);
+
+    // Step 1: Rotate the stored unrotated solution
+    let mut rotation_result = None;
+    if !config.rotation.none {
+        let stage_started = now_ms();
+        match core::rotate_factors(&extraction_result, config) {
+            Ok(rotation) => {
+                rotation_result = Some(rotation);
+            }
+            Err(e) => {
+                error_collector.add_error("rotate_factors", &e);
+            }
+        }
+        timer.record("rotate_factors", stage_started);
+    }
+
+    // Step 2: Descriptive statistics from the stored sufficient statistics
+    let descriptive_statistics = if config.descriptives.univar_desc {
+        Some(
+            core::build_descriptive_statistics(
+                var_names,
+                &session.means,
+                &session.std_deviations,
+                n_cases
+            )
+        )
+    } else {
+        None
+    };
+
+    // Step 3: Correlation/Covariance Matrix
+    let mut correlation_matrix = None;
+    if config.extraction.correlation || config.extraction.covariance {
+        let matrix = if config.extraction.correlation {
+            corr_matrix.clone()
+        } else {
+            core::session_covariance_matrix(session)
+        };
+        match core::build_correlation_matrix(&matrix, var_names, n_cases) {
+            Ok(mut table) => {
+                if config.extraction.correlation && config.descriptives.effect_sizes {
+                    let thresholds = core::effect_size_thresholds(config);
+                    if
+                        let Err(e) = core::add_effect_sizes(
+                            &mut table,
+                            &matrix,
+                            var_names,
+                            &thresholds
+                        )
+                    {
+                        error_collector.add_error("add_effect_sizes", &e);
+                    }
+                }
+                correlation_matrix = Some(table);
+            }
+            Err(e) => {
+                error_collector.add_error("build_correlation_matrix", &e);
+            }
+        }
+    }
+
+    // Solver used on the analyzed matrix, its asymmetry and any negative eigenvalues
+    let eigen_diagnostics = if core::analyzes_covariance(config) {
+        core::build_eigen_diagnostics(&core::session_covariance_matrix(session))
+    } else {
+        core::build_eigen_diagnostics(&corr_matrix)
+    };
+
+    // Raw and rescaled solution from the stored covariances
+    let mut covariance_extraction = None;
+    if core::analyzes_covariance(config) {
+        let stage_started = now_ms();
+        match
+            core::build_covariance_extraction(
+                &core::session_covariance_matrix(session),
+                var_names,
+                config
+            )
+        {
+            Ok(extraction) => {
+                covariance_extraction = Some(extraction);
+            }
+            Err(e) => {
+                error_collector.add_error("build_covariance_extraction", &e);
+            }
+        }
+        timer.record("build_covariance_extraction", stage_started);
+    }
+
+    // Map of the variables from the stored correlations
+    let mut multidimensional_scaling = None;
+    if config.extraction.mds {
+        let stage_started = now_ms();
+        match core::build_correlation_scaling(&corr_matrix, var_names, config) {
+            Ok(scaling) => {
+                multidimensional_scaling = Some(scaling);
+            }
+            Err(e) => {
+                error_collector.add_error("build_correlation_scaling", &e);
+            }
+        }
+        timer.record("build_correlation_scaling", stage_started);
+    }
+
+    // SSCP recovered from the stored covariances and means
+    let mut sscp_matrix = None;
+    if config.descriptives.sscp {
+        let (raw, adjusted) = core::sscp_from_covariance(
+            &core::session_covariance_matrix(session),
+            &session.means,
+            n_cases
+        );
+        match core::build_sscp_matrix(&raw, &adjusted, var_names, n_cases) {
+            Ok(matrix) => {
+                sscp_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("build_sscp_matrix", &e);
+            }
+        }
+    }
+
+    // Step 4: Inverse Matrix
+    let mut inverse_correlation_matrix = None;
+    if config.descriptives.inverse {
+        match core::build_inverse_correlation_matrix(&corr_matrix, var_names) {
+            Ok(matrix) => {
+                inverse_correlation_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("build_inverse_correlation_matrix", &e);
+            }
+        }
+    }
+
+    // Step 5: KMO and Bartlett's Test
+    let kmo_bartletts_test = if config.descriptives.kmo {
+        Some(core::build_kmo_bartletts_test(&corr_matrix, n_cases))
+    } else {
+        None
+    };
+
+    // Step 6: Anti-Image Matrices
+    let mut anti_image_matrices = None;
+    if config.descriptives.anti_image {
+        match core::build_anti_image_matrices(
+            &corr_matrix,
+            var_names,
+            config.descriptives.msa
+        ) {
+            Ok(matrices) => {
+                anti_image_matrices = Some(matrices);
+            }
+            Err(e) => {
+                error_collector.add_error("build_anti_image_matrices", &e);
+            }
+        }
+    }
+
+    // Step 7: Extraction tables
+    let communalities = Some(core::build_communalities(&extraction_result, var_names));
+    let total_variance_explained = Some(
+        core::build_total_variance_explained(
+            &extraction_result,
+            rotation_result.as_ref(),
+            var_names.len(),
+            config
+        )
+    );
+    let component_matrix = Some(core::build_component_matrix(&extraction_result, var_names));
+    let scree_plot = if config.extraction.scree {
+        Some(core::build_scree_plot(&extraction_result, var_names.len()))
+    } else {
+        None
+    };
+    let reproduced_correlations = if config.descriptives.reproduced {
+        Some(core::build_reproduced_correlations(&corr_matrix, &extraction_result, var_names))
+    } else {
+        None
+    };
+
+    // Step 8: Rotation tables
+    let mut rotated_component_matrix = None;
+    let mut component_transformation_matrix = None;
+    if config.rotation.rotated_sol {
+        if let Some(rotation) = &rotation_result {
+            rotated_component_matrix = Some(
+                core::create_rotated_component_matrix(rotation, var_names)
+            );
+            component_transformation_matrix = Some(
+                core::create_component_transformation_matrix(rotation)
+            );
+        }
+    }
+
+    // Step 9: Factor score tables
+    let mut component_score_coefficient_matrix = None;
+    let mut component_score_covariance_matrix = None;
+    let mut factor_score_determinacy = None;
+    if config.scores.save_var {
+        let stage_started = now_ms();
+        match
+            core::build_component_score_coefficient_matrix(
+                &corr_matrix,
+                &extraction_result,
+                var_names,
+                config
+            )
+        {
+            Ok(matrix) => {
+                component_score_coefficient_matrix = Some(matrix);
+            }
+            Err(e) => {
+                error_collector.add_error("build_component_score_coefficient_matrix", &e);
+            }
+        }
+        component_score_covariance_matrix = Some(
+            core::build_component_score_covariance_matrix(&corr_matrix, &extraction_result, config)
+        );
+        match core::build_factor_score_determinacy(&corr_matrix, &extraction_result, config) {
+            Ok(determinacy) => {
+                factor_score_determinacy = Some(determinacy);
+            }
+            Err(e) => {
+                error_collector.add_error("build_factor_score_determinacy", &e);
+            }
+        }
+        timer.record("build_component_scores", stage_started);
+    }
+
+    let mut factor_reliability = None;
+    if config.descriptives.reliability {
+        let loadings = match &rotation_result {
+            Some(rotation) => &rotation.rotated_loadings,
+            None => &extraction_result.loadings,
+        };
+        match core::build_factor_reliability(&corr_matrix, loadings, var_names, config) {
+            Ok(reliability) => {
+                factor_reliability = Some(reliability);
+            }
+            Err(e) => {
+                error_collector.add_error("build_factor_reliability", &e);
+            }
+        }
+    }
+
+    let mut discriminant_validity = None;
+    if config.descriptives.discriminant_validity {
+        let (loadings, factor_correlations) = match &rotation_result {
+            Some(rotation) => (&rotation.rotated_loadings, rotation.factor_correlations.as_ref()),
+            None => (&extraction_result.loadings, None),
+        };
+        match
+            core::build_discriminant_validity(
+                &corr_matrix,
+                loadings,
+                factor_correlations,
+                var_names,
+                config
+            )
+        {
+            Ok(validity) => {
+                discriminant_validity = Some(validity);
+            }
+            Err(e) => {
+                error_collector.add_error("build_discriminant_validity", &e);
+            }
+        }
+    }
+
+    // Step 10: Parallel analysis only needs the correlation matrix and the case count
+    let mut parallel_analysis = None;
+    if config.extraction.parallel_analysis {
+        let stage_started = now_ms();
+        match core::build_parallel_analysis(&corr_matrix, n_cases, config) {
+            Ok(analysis) => {
+                parallel_analysis = Some(analysis);
+            }
+            Err(e) => {
+                error_collector.add_error("build_parallel_analysis", &e);
+            }
+        }
+        timer.record("build_parallel_analysis", stage_started);
+    }
+
+    let mut factor_count_comparison = None;
+    if config.extraction.factor_count_comparison {
+        let stage_started = now_ms();
+        match core::build_factor_count_comparison(&corr_matrix, var_names, n_cases, config) {
+            Ok(comparison) => {
+                factor_count_comparison = Some(comparison);
+            }
+            Err(e) => {
+                error_collector.add_error("build_factor_count_comparison", &e);
+            }
+        }
+        timer.record("build_factor_count_comparison", stage_started);
+    }
+
+    // The centered data needs the cases, so a session has the other steps only
+    let mut intermediate_matrices = None;
+    if core::includes_intermediate_matrices(config) {
+        let stage_started = now_ms();
+        match
+            core::build_intermediate_matrices(
+                &corr_matrix,
+                &extraction_result,
+                rotation_result.as_ref(),
+                var_names,
+                config
+            )
+        {
+            Ok(intermediate) => {
+                intermediate_matrices = Some(intermediate);
+            }
+            Err(e) => {
+                error_collector.add_error("build_intermediate_matrices", &e);
+            }
+        }
+        timer.record("build_intermediate_matrices", stage_started);
+    }
+
+    let mut component_factor_comparison = None;
+    if config.extraction.compare_models {
+        let stage_started = now_ms();
+        match core::build_component_factor_comparison(&corr_matrix, var_names, config) {
+            Ok(comparison) => {
+                component_factor_comparison = Some(comparison);
+            }
+            Err(e) => {
+                error_collector.add_error("build_component_factor_comparison", &e);
+            }
+        }
+        timer.record("build_component_factor_comparison", stage_started);
+    }
+
+    let reproducibility = match core::build_reproducibility_log(config, var_names) {
+        Ok(log) => Some(log),
+        Err(e) => {
+            error_collector.add_error("build_reproducibility_log", &e);
+            None
+        }
+    };
+
+    let iterations = core::build_iteration_summaries(
+        &extraction_result,
+        rotation_result.as_ref(),
+        config
+    );
+    let metadata = Some(
+        core::build_analysis_metadata(
+            timer.timings(),
+            timer.elapsed_ms(),
+            iterations,
+            Some(resolve_seed(config)),
+            None
+        )
+    );
+
+    Ok(FactorAnalysisResult {
+        descriptive_statistics,
+        grouped_descriptive_statistics: None,
+        scree_plot,
+        correlation_matrix,
+        pairwise_deletion: None,
+        distance_correlation_matrix: None,
+        sscp_matrix,
+        inverse_correlation_matrix,
+        kmo_bartletts_test,
+        intraclass_correlation: None,
+        anti_image_matrices,
+        communalities,
+        total_variance_explained,
+        component_matrix,
+        covariance_extraction,
+        eigen_diagnostics: Some(eigen_diagnostics),
+        case_influence: None,
+        principal_coordinates: None,
+        multidimensional_scaling,
+        component_factor_comparison,
+        eap_scores: None,
+        reproduced_correlations,
+        rotated_component_matrix,
+        component_transformation_matrix,
+        component_score_coefficient_matrix,
+        component_score_covariance_matrix,
+        factor_score_determinacy,
+        factor_reliability,
+        discriminant_validity,
+        parallel_analysis,
+        factor_count_comparison,
+        weighted_least_squares: None,
+        intermediate_matrices,
+        reproducibility,
+        metadata,
+    })
+}
+
+// An extraction stopped at MaxIter still gives its last iterate, reported as not converged
+fn warn_not_converged(extraction: &ExtractionResult, error_collector: &mut ErrorCollector) {
+    if !extraction.converged {
+        let msg = format!(
+            "Extraction did not converge in {} iterations; the last iterate is reported",
+            extraction.iterations
+        );
+        error_collector.add_warning("extract_factors", &msg);
+    }
+}
+
+// Stages computed from the cases themselves; a session keeps only the sufficient statistics, so
+// each requested one is reported as skipped rather than silently left out
+fn warn_raw_data_stages(config: &FactorAnalysisConfig, error_collector: &mut ErrorCollector) {
+    let stages = [
+        (
+            config.descriptives.univar_desc && config.descriptives.group_var.is_some(),
+            "calculate_grouped_descriptive_statistics",
+        ),
+        (core::uses_pairwise_deletion(config), "calculate_pairwise_deletion"),
+        (config.descriptives.distance_corr, "calculate_distance_correlation_matrix"),
+        (config.descriptives.icc, "calculate_intraclass_correlation"),
+        (config.descriptives.case_influence, "calculate_case_influence"),
+        (config.extraction.pcoa, "calculate_principal_coordinates"),
+        (config.scores.eap, "calculate_eap_scores"),
+        (config.extraction.wlsmv, "calculate_weighted_least_squares"),
+        (config.options.intermediate.centered_data, "calculate_centered_data_summary"),
+    ];
+
+    for (requested, stage) in stages {
+        if requested {
+            error_collector.add_warning(
+                stage,
+                "Skipped: this stage needs the raw data, which the session doesn't keep"
+            );
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use std::f64::consts::{ PI, SQRT_2 };
+
+use nalgebra::DMatrix;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use statrs::{ distribution::Normal, function::erf::erfc };
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::PairwiseMatrix,
+};
+
+use super::core::{
+    extract_case_levels,
+    level_positions,
+    level_thresholds,
+    min_pairwise_n,
+    pairwise_analysis_n,
+    require_matrix_cases,
+    resolve_variable_names,
+    uses_pairwise_deletion,
+    MAX_ORDINAL_LEVELS,
+};
+
+// Polychoric correlations are searched within +-RHO_BOUND; the likelihood is flat at +-1
+const RHO_BOUND: f64 = 0.9999;
+
+// Width of the final bracket of the correlation search
+pub const DEFAULT_POLYCHORIC_CONVERGENCE: f64 = 1e-8;
+
+// Bracket reductions allowed in the correlation search; 1e-8 takes 40
+pub const DEFAULT_POLYCHORIC_MAX_ITER: usize = 100;
+
+// Golden section shrinks the bracket by this factor at every iteration
+const GOLDEN_RATIO: f64 = 0.618_033_988_749_895;
+
+// Variable names, the level position of every case on each variable and the number of levels
+type OrdinalResponses = (Vec<String>, Vec<Vec<Option<usize>>>, Vec<usize>);
+
+// Polychoric correlation matrix of the analysis variables as ordered categories, with the
+// variable names and the number of cases it is based on. Mean replacement has no meaning for
+// categories, so cases with missing values are deleted listwise unless pairwise deletion is set.
+pub fn calculate_polychoric_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<(DMatrix<f64>, Vec<String>, usize), String> {
+    let (var_names, responses, n_levels) = extract_ordinal_responses(data, config)?;
+    let (matrix, n_obs) = build_polychoric_matrix(&responses, &n_levels, config)?;
+
+    Ok((matrix, var_names, n_obs))
+}
+
+// Bracket width and iteration limit of the correlation search
+pub fn polychoric_search(config: &FactorAnalysisConfig) -> (f64, usize) {
+    let tolerance = config.extraction.polychoric_converge.unwrap_or(DEFAULT_POLYCHORIC_CONVERGENCE);
+    let max_iter = config.extraction.polychoric_max_iter
+        .map_or(DEFAULT_POLYCHORIC_MAX_ITER, |max_iter| max_iter.max(1) as usize);
+    (tolerance, max_iter)
+}
+
+// Iterations taken by every correlation search and the final bracket width. The bracket
+// shrinks by the same factor whatever the data, so all pairs stop at the same iteration.
+pub fn polychoric_search_outcome(config: &FactorAnalysisConfig) -> (usize, f64) {
+    let (tolerance, max_iter) = polychoric_search(config);
+    let mut width = 2.0 * RHO_BOUND;
+    let mut iterations = 0;
+    while width > tolerance && iterations < max_iter {
+        width *= GOLDEN_RATIO;
+        iterations += 1;
+    }
+    (iterations, width)
+}
+
+// Level position of every selected case on every analysis variable
+pub fn extract_ordinal_responses(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<OrdinalResponses, String> {
+    let var_names = resolve_variable_names(data, config)?;
+    let mut responses = Vec::with_capacity(var_names.len());
+    let mut n_levels = Vec::with_capacity(var_names.len());
+    for var_name in &var_names {
+        let levels = extract_case_levels(data, config, var_name);
+        let (order, positions) = level_positions(config, var_name, &levels);
+        if order.len() < 2 {
+            return Err(
+                format!("'{}' needs at least 2 observed levels for a polychoric matrix", var_name)
+            );
+        }
+        if order.len() > MAX_ORDINAL_LEVELS {
+            return Err(
+                format!(
+                    "'{}' has {} levels, too many to correlate as an ordinal variable",
+                    var_name,
+                    order.len()
+                )
+            );
+        }
+        responses.push(positions);
+        n_levels.push(order.len());
+    }
+
+    Ok((var_names, responses, n_levels))
+}
+
+// Two-step estimate: the thresholds of each variable come from its marginal proportions, then
+// each correlation maximizes the bivariate normal likelihood of the pair's contingency table
+pub fn build_polychoric_matrix(
+    responses: &[Vec<Option<usize>>],
+    n_levels: &[usize],
+    config: &FactorAnalysisConfig
+) -> Result<(DMatrix<f64>, usize), String> {
+    let n_vars = responses.len();
+    let n_cases = responses
+        .first()
+        .map_or(0, |column| column.len());
+    let pairwise = uses_pairwise_deletion(config);
+    let complete: Vec<bool> = (0..n_cases)
+        .map(|i| pairwise || responses.iter().all(|column| column[i].is_some()))
+        .collect();
+
+    let pairs: Vec<(usize, usize)> = (0..n_vars)
+        .flat_map(|i| ((i + 1)..n_vars).map(move |j| (i, j)))
+        .collect();
+    let standard_normal = Normal::new(0.0, 1.0).map_err(|e| e.to_string())?;
+    let search = polychoric_search(config);
+    let estimate_pair = |&(i, j): &(usize, usize)| {
+        let (x, y): (Vec<Option<usize>>, Vec<Option<usize>>) = (0..n_cases)
+            .filter(|&case| complete[case])
+            .map(|case| (responses[i][case], responses[j][case]))
+            .unzip();
+        polychoric_correlation(&x, &y, (n_levels[i], n_levels[j]), &standard_normal, search)
+    };
+
+    // The pairs are estimated independently of each other
+    #[cfg(feature = "parallel")]
+    let estimates: Vec<(f64, usize)> = pairs.par_iter().map(estimate_pair).collect();
+    #[cfg(not(feature = "parallel"))]
+    let estimates: Vec<(f64, usize)> = pairs.iter().map(estimate_pair).collect();
+
+    let mut pairwise_matrix = PairwiseMatrix {
+        matrix: DMatrix::identity(n_vars, n_vars),
+        pair_counts: vec![vec![0; n_vars]; n_vars],
+        n_obs: 0,
+        flagged: Vec::new(),
+    };
+    for (j, column) in responses.iter().enumerate() {
+        pairwise_matrix.pair_counts[j][j] = column
+            .iter()
+            .zip(&complete)
+            .filter(|(level, is_complete)| **is_complete && level.is_some())
+            .count();
+    }
+    let min_pairs = min_pairwise_n(config).max(2);
+    for (&(i, j), &(correlation, n)) in pairs.iter().zip(&estimates) {
+        pairwise_matrix.pair_counts[i][j] = n;
+        pairwise_matrix.pair_counts[j][i] = n;
+        // As for the product-moment pairwise matrix, sparse cells are flagged and set to zero
+        let value = if n < min_pairs || correlation.is_nan() {
+            pairwise_matrix.flagged.push((i, j));
+            0.0
+        } else {
+            correlation
+        };
+        pairwise_matrix.matrix[(i, j)] = value;
+        pairwise_matrix.matrix[(j, i)] = value;
+    }
+
+    let n_obs = if pairwise {
+        let diagonal_min = (0..n_vars)
+            .map(|j| pairwise_matrix.pair_counts[j][j])
+            .min()
+            .unwrap_or(0);
+        require_matrix_cases(diagonal_min)?;
+        pairwise_analysis_n(&pairwise_matrix, &config.options.pairwise_n)
+    } else {
+        let n_complete = complete
+            .iter()
+            .filter(|&&is_complete| is_complete)
+            .count();
+        require_matrix_cases(n_complete)?;
+        n_complete
+    };
+
+    Ok((pairwise_matrix.matrix, n_obs))
+}
+
+// Maximum likelihood correlation of two ordinal variables given the thresholds from their
+// marginals, with the number of cases observed on both. NaN when either variable takes a
+// single level in these cases. The search stops at the bracket width or the iteration limit
+// of polychoric_search.
+pub fn polychoric_correlation(
+    x: &[Option<usize>],
+    y: &[Option<usize>],
+    (levels_x, levels_y): (usize, usize),
+    normal: &Normal,
+    search: (f64, usize)
+) -> (f64, usize) {
+    let mut counts = DMatrix::<f64>::zeros(levels_x, levels_y);
+    let (mut x_pairs, mut y_pairs) = (Vec::new(), Vec::new());
+    for (level_x, level_y) in x.iter().zip(y) {
+        if let (Some(level_x), Some(level_y)) = (level_x, level_y) {
+            counts[(*level_x, *level_y)] += 1.0;
+            x_pairs.push(Some(*level_x));
+            y_pairs.push(Some(*level_y));
+        }
+    }
+    let n = x_pairs.len();
+    let levels_observed_x = counts
+        .column_sum()
+        .iter()
+        .filter(|count| **count > 0.0)
+        .count();
+    let levels_observed_y = counts
+        .row_sum()
+        .iter()
+        .filter(|count| **count > 0.0)
+        .count();
+    if levels_observed_x < 2 || levels_observed_y < 2 {
+        return (f64::NAN, n);
+    }
+
+    let thresholds_x = bounded_thresholds(&x_pairs, levels_x, normal);
+    let thresholds_y = bounded_thresholds(&y_pairs, levels_y, normal);
+    let log_likelihood = |rho: f64| {
+        let mut total = 0.0;
+        for a in 0..levels_x {
+            for b in 0..levels_y {
+                let count = counts[(a, b)];
+                if count > 0.0 {
+                    let probability = bivariate_normal_rectangle(
+                        (thresholds_x[a], thresholds_x[a + 1]),
+                        (thresholds_y[b], thresholds_y[b + 1]),
+                        rho
+                    );
+                    total += count * probability.max(f64::MIN_POSITIVE).ln();
+                }
+            }
+        }
+        total
+    };
+
+    (golden_section_maximum(log_likelihood, (-RHO_BOUND, RHO_BOUND), search), n)
+}
+
+// Thresholds with -inf and +inf added at the ends, so level k lies between entries k and k + 1
+pub fn bounded_thresholds(
+    responses: &[Option<usize>],
+    n_levels: usize,
+    normal: &Normal
+) -> Vec<f64> {
+    let mut thresholds = vec![f64::NEG_INFINITY];
+    thresholds.extend(level_thresholds(responses, n_levels, normal));
+    thresholds.push(f64::INFINITY);
+    thresholds
+}
+
+// Probability of the rectangle (x_lower, x_upper] x (y_lower, y_upper] under the standard
+// bivariate normal with correlation rho
+pub fn bivariate_normal_rectangle(x_bounds: (f64, f64), y_bounds: (f64, f64), rho: f64) -> f64 {
+    let (x_lower, x_upper) = x_bounds;
+    let (y_lower, y_upper) = y_bounds;
+    let probability =
+        bivariate_normal_cdf(x_upper, y_upper, rho) -
+        bivariate_normal_cdf(x_lower, y_upper, rho) -
+        bivariate_normal_cdf(x_upper, y_lower, rho) +
+        bivariate_normal_cdf(x_lower, y_lower, rho);
+    probability.max(0.0)
+}
+
+// P(X < h, Y < k) for standard normal X and Y with correlation rho
+pub fn bivariate_normal_cdf(h: f64, k: f64, rho: f64) -> f64 {
+    if h == f64::NEG_INFINITY || k == f64::NEG_INFINITY {
+        return 0.0;
+    }
+    if h == f64::INFINITY {
+        return normal_cdf(k);
+    }
+    if k == f64::INFINITY {
+        return normal_cdf(h);
+    }
+    upper_orthant(-h, -k, rho).clamp(0.0, 1.0)
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+// P(X > h, Y > k) for finite h and k, after Genz (2004), "Numerical computation of
+// rectangular bivariate and trivariate normal and t probabilities": Gauss-Legendre quadrature
+// of Drezner and Wesolowsky's integral in rho, with a series expansion for |rho| near 1
+fn upper_orthant(h: f64, k: f64, rho: f64) -> f64 {
+    let (weights, nodes) = gauss_legendre_half(rho.abs());
+    let two_pi = 2.0 * PI;
+    let mut hk = h * k;
+    let mut bvn = 0.0;
+
+    if rho.abs() < 0.925 {
+        let hs = (h * h + k * k) / 2.0;
+        let asr = rho.asin();
+        for (w, x) in weights.iter().zip(nodes) {
+            for node in [x + 1.0, 1.0 - x] {
+                let sn = (asr * node / 2.0).sin();
+                bvn += w * ((sn * hk - hs) / (1.0 - sn * sn)).exp();
+            }
+        }
+        return (bvn * asr) / (2.0 * two_pi) + normal_cdf(-h) * normal_cdf(-k);
+    }
+
+    let k = if rho < 0.0 {
+        hk = -hk;
+        -k
+    } else {
+        k
+    };
+    if rho.abs() < 1.0 {
+        let a_sq = (1.0 - rho) * (1.0 + rho);
+        let mut a = a_sq.sqrt();
+        let b_sq = (h - k).powi(2);
+        let c = (4.0 - hk) / 8.0;
+        let d = (12.0 - hk) / 16.0;
+        let series =
+            1.0 - (c * (b_sq - a_sq) * (1.0 - (d * b_sq) / 5.0)) / 3.0 + (c * d * a_sq * a_sq) / 5.0;
+        bvn = a * (-(b_sq / a_sq + hk) / 2.0).exp() * series;
+        if hk > -160.0 {
+            let b = b_sq.sqrt();
+            bvn -=
+                (-hk / 2.0).exp() *
+                two_pi.sqrt() *
+                normal_cdf(-b / a) *
+                b *
+                (1.0 - (c * b_sq * (1.0 - (d * b_sq) / 5.0)) / 3.0);
+        }
+        a /= 2.0;
+        for (w, x) in weights.iter().zip(nodes) {
+            let xs = (a * (x + 1.0)).powi(2);
+            let rs = (1.0 - xs).sqrt();
+            bvn +=
+                a *
+                w *
+                ((-b_sq / (2.0 * xs) - hk / (1.0 + rs)).exp() / rs -
+                    (-(b_sq / xs + hk) / 2.0).exp() * (1.0 + c * xs * (1.0 + d * xs)));
+
+            let xs = (a_sq * (1.0 - x).powi(2)) / 4.0;
+            let rs = (1.0 - xs).sqrt();
+            bvn +=
+                a *
+                w *
+                (-(b_sq / xs + hk) / 2.0).exp() *
+                ((-(hk * (1.0 - rs)) / (2.0 * (1.0 + rs))).exp() / rs -
+                    (1.0 + c * xs * (1.0 + d * xs)));
+        }
+        bvn = -bvn / two_pi;
+    }
+
+    if rho > 0.0 {
+        bvn + normal_cdf(-h.max(k))
+    } else {
+        -bvn + (normal_cdf(-h) - normal_cdf(-k)).max(0.0)
+    }
+}
+
+// Weights and negative nodes of one half of the 6, 12 or 20 point Gauss-Legendre rule, more
+// points the larger |rho|
+fn gauss_legendre_half(abs_rho: f64) -> (&'static [f64], &'static [f64]) {
+    const WEIGHTS_6: [f64; 3] = [0.1713244923791705, 0.3607615730481384, 0.4679139345726904];
+    const NODES_6: [f64; 3] = [-0.9324695142031522, -0.6612093864662647, -0.238619186083197];
+    const WEIGHTS_12: [f64; 6] = [
+        0.04717533638651177, 0.1069393259953183, 0.1600783285433464, 0.2031674267230659,
+        0.2334925365383547, 0.2491470458134029,
+    ];
+    const NODES_12: [f64; 6] = [
+        -0.9815606342467191, -0.904117256370475, -0.769902674194305, -0.5873179542866171,
+        -0.3678314989981802, -0.1252334085114692,
+    ];
+    const WEIGHTS_20: [f64; 10] = [
+        0.01761400713915212, 0.04060142980038694, 0.06267204833410906, 0.08327674157670475,
+        0.1019301198172404, 0.1181945319615184, 0.1316886384491766, 0.1420961093183821,
+        0.1491729864726037, 0.1527533871307259,
+    ];
+    const NODES_20: [f64; 10] = [
+        -0.9931285991850949, -0.9639719272779138, -0.912234428251326, -0.8391169718222188,
+        -0.7463319064601508, -0.636053680726515, -0.5108670019508271, -0.3737060887154196,
+        -0.2277858511416451, -0.07652652113349733,
+    ];
+
+    if abs_rho < 0.3 {
+        (&WEIGHTS_6, &NODES_6)
+    } else if abs_rho < 0.75 {
+        (&WEIGHTS_12, &NODES_12)
+    } else {
+        (&WEIGHTS_20, &NODES_20)
+    }
+}
+
+// Maximum of a unimodal function on [lower, upper], to within the bracket width tolerance or
+// after max_iter bracket reductions, whichever comes first
+fn golden_section_maximum<F>(
+    f: F,
+    (lower, upper): (f64, f64),
+    (tolerance, max_iter): (f64, usize)
+) -> f64
+    where F: Fn(f64) -> f64
+{
+    let ratio = GOLDEN_RATIO;
+    let (mut a, mut b) = (lower, upper);
+    let mut c = b - ratio * (b - a);
+    let mut d = a + ratio * (b - a);
+    let (mut fc, mut fd) = (f(c), f(d));
+
+    let mut iterations = 0;
+    while b - a > tolerance && iterations < max_iter {
+        iterations += 1;
+        if fc > fd {
+            b = d;
+            d = c;
+            fd = fc;
+            c = b - ratio * (b - a);
+            fc = f(c);
+        } else {
+            a = c;
+            c = d;
+            fc = fd;
+            d = a + ratio * (b - a);
+            fd = f(d);
+        }
+    }
+
+    (a + b) / 2.0
+}
@@ -0,0 +1,211 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::{ AlignmentMethod, FactorAnalysisConfig, PowerConfig, SimulationConfig },
+    result::{ FactorModel, SampleSizePlan, SampleSizeRecovery },
+};
+use crate::utils::rng::{ run_replicates, DEFAULT_SEED };
+
+use super::core::{
+    alignment_transformation,
+    build_factor_model,
+    calculate_matrix,
+    extract_factors,
+    model_rmsea,
+    percentile_of_sorted,
+    simulate_matrix,
+    tucker_congruence,
+};
+
+// Defaults for sample size planning; the congruence follows Lorenzo-Seva & ten Berge (2006)
+pub const DEFAULT_POWER_SAMPLE_SIZES: [usize; 9] = [50, 100, 150, 200, 300, 400, 500, 750, 1000];
+pub const DEFAULT_POWER_REPLICATES: usize = 100;
+pub const DEFAULT_TARGET_CONGRUENCE: f64 = 0.95;
+pub const DEFAULT_TARGET_PROPORTION: f64 = 0.8;
+
+// Outcome of one simulated sample
+struct ReplicateRecovery {
+    congruence: Option<f64>,
+    rmsea: Option<f64>,
+}
+
+// Monte Carlo estimate of the cases needed to recover a hypothesized structure: for every
+// candidate sample size, data are drawn from the model, factored with the extraction settings
+// of the configuration and compared with the population loadings
+pub fn build_sample_size_plan(
+    spec: &PowerConfig,
+    config: &FactorAnalysisConfig
+) -> Result<SampleSizePlan, String> {
+    let model = build_factor_model(
+        &(SimulationConfig {
+            loadings: spec.loadings.clone(),
+            factor_correlations: spec.factor_correlations.clone(),
+            uniquenesses: spec.uniquenesses.clone(),
+            cases: 0,
+            seed: None,
+            categories: None,
+            thresholds: None,
+            variable_names: None,
+        })
+    )?;
+    let n_vars = model.loadings.nrows();
+    let n_factors = model.loadings.ncols();
+    if n_factors == 0 {
+        return Err("The hypothesized model needs at least one factor".to_string());
+    }
+
+    let mut sample_sizes = spec.sample_sizes
+        .clone()
+        .unwrap_or_else(|| DEFAULT_POWER_SAMPLE_SIZES.to_vec());
+    sample_sizes.sort_unstable();
+    sample_sizes.dedup();
+    match sample_sizes.first() {
+        None => {
+            return Err("No sample sizes to evaluate".to_string());
+        }
+        Some(&smallest) if smallest <= n_vars => {
+            return Err(
+                format!("Sample sizes must exceed the {} variables, got {}", n_vars, smallest)
+            );
+        }
+        _ => {}
+    }
+
+    let replicates = match spec.replicates {
+        Some(replicates) if replicates > 0 => replicates,
+        _ => DEFAULT_POWER_REPLICATES,
+    };
+    let target_congruence = match spec.target_congruence {
+        Some(value) if value > 0.0 && value <= 1.0 => value,
+        Some(value) => {
+            return Err(format!("Target congruence must lie in (0, 1], got {}", value));
+        }
+        None => DEFAULT_TARGET_CONGRUENCE,
+    };
+    let target_proportion = match spec.target_proportion {
+        Some(value) if value > 0.0 && value <= 1.0 => value,
+        Some(value) => {
+            return Err(format!("Target proportion must lie in (0, 1], got {}", value));
+        }
+        None => DEFAULT_TARGET_PROPORTION,
+    };
+    let seed = spec.seed.unwrap_or(DEFAULT_SEED);
+
+    // Every replicate asks for the hypothesized number of factors; the cache would only churn
+    let mut replicate_config = config.clone();
+    replicate_config.extraction.max_factors = Some(n_factors as i32);
+    replicate_config.options.cache = false;
+
+    let var_names: Vec<String> = (1..=n_vars).map(|j| format!("x{}", j)).collect();
+    let orthogonal = model.factor_correlations == DMatrix::identity(n_factors, n_factors);
+
+    let mut rows = Vec::with_capacity(sample_sizes.len());
+    for &cases in &sample_sizes {
+        // One seed per candidate, so adding a candidate leaves the others unchanged
+        let outcomes = run_replicates(seed.wrapping_add(cases as u64), replicates, |_, rng| {
+            simulate_matrix(&model, cases, rng)
+                .and_then(|data| calculate_matrix(&data, "correlation"))
+                .and_then(|corr_matrix| {
+                    let extraction = extract_factors(&corr_matrix, &replicate_config, &var_names)?;
+                    Ok(ReplicateRecovery {
+                        congruence: recovery_congruence(&extraction.loadings, &model, orthogonal),
+                        rmsea: model_rmsea(&corr_matrix, &extraction.loadings, cases),
+                    })
+                })
+                .unwrap_or(ReplicateRecovery { congruence: None, rmsea: None })
+        });
+
+        let row = summarize_recovery(cases, &outcomes, target_congruence, target_proportion);
+        let row = match spec.rmsea_width {
+            Some(width) => {
+                let (Some(lower), Some(upper)) = (row.rmsea_lower, row.rmsea_upper) else {
+                    return Err("The RMSEA target needs positive degrees of freedom".to_string());
+                };
+                let meets_target = row.meets_target && upper - lower <= width;
+                SampleSizeRecovery { meets_target, ..row }
+            }
+            None => row,
+        };
+        rows.push(row);
+    }
+
+    Ok(SampleSizePlan {
+        n_variables: n_vars,
+        n_factors,
+        method: config.extraction.method.clone(),
+        replicates,
+        target_congruence,
+        target_proportion,
+        rmsea_width: spec.rmsea_width,
+        recommended_cases: rows
+            .iter()
+            .find(|row| row.meets_target)
+            .map(|row| row.cases),
+        sample_sizes: rows,
+        seed,
+    })
+}
+
+// Lowest Tucker congruence between the aligned sample factors and the population factors.
+// Orthogonal models are aligned by orthogonal Procrustes; with correlated factors the sample
+// loadings are transformed by least squares, since no rotation reaches an oblique pattern.
+fn recovery_congruence(
+    loadings: &DMatrix<f64>,
+    model: &FactorModel,
+    orthogonal: bool
+) -> Option<f64> {
+    if loadings.ncols() != model.loadings.ncols() {
+        return None;
+    }
+
+    let transformation = if orthogonal {
+        alignment_transformation(loadings, &model.loadings, &AlignmentMethod::Orthogonal).ok()?
+    } else {
+        let cross_products = loadings.transpose() * loadings;
+        cross_products.try_inverse()? * loadings.transpose() * &model.loadings
+    };
+    let congruence = tucker_congruence(&(loadings * transformation), &model.loadings);
+
+    (0..congruence.ncols())
+        .map(|k| congruence[(k, k)])
+        .reduce(f64::min)
+}
+
+fn summarize_recovery(
+    cases: usize,
+    outcomes: &[ReplicateRecovery],
+    target_congruence: f64,
+    target_proportion: f64
+) -> SampleSizeRecovery {
+    let congruences: Vec<f64> = outcomes
+        .iter()
+        .filter_map(|outcome| outcome.congruence)
+        .collect();
+    let recovered = congruences
+        .iter()
+        .filter(|value| **value >= target_congruence)
+        .count();
+    let proportion_recovered = (recovered as f64) / (outcomes.len() as f64);
+
+    let mut rmsea: Vec<f64> = outcomes
+        .iter()
+        .filter_map(|outcome| outcome.rmsea)
+        .collect();
+    rmsea.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let has_rmsea = !rmsea.is_empty();
+
+    SampleSizeRecovery {
+        cases,
+        failed: outcomes.len() - congruences.len(),
+        mean_congruence: if congruences.is_empty() {
+            f64::NAN
+        } else {
+            congruences.iter().sum::<f64>() / (congruences.len() as f64)
+        },
+        proportion_recovered,
+        rmsea_mean: has_rmsea.then(|| rmsea.iter().sum::<f64>() / (rmsea.len() as f64)),
+        rmsea_lower: has_rmsea.then(|| percentile_of_sorted(&rmsea, 5.0)),
+        rmsea_upper: has_rmsea.then(|| percentile_of_sorted(&rmsea, 95.0)),
+        meets_target: proportion_recovered >= target_proportion,
+    }
+}
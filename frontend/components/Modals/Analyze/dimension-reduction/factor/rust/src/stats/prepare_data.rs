@@ -7,10 +7,30 @@ use crate::models::{
     data::{ AnalysisData, DataRecord, DataValue },
 };
 
+use super::core::{
+    available_variable_names,
+    categorical_variable,
+    encode_categorical,
+    expand_variable_entry,
+    level_of,
+};
+
 pub fn extract_data_matrix(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<(DMatrix<f64>, Vec<String>), String> {
+    let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+    let data_matrix = handle_missing_values(&raw_matrix, config)?;
+
+    Ok((data_matrix, var_names))
+}
+
+// Names of the analysis variables, resolving index-based entries, patterns and ranges in the
+// configuration
+pub fn resolve_variable_names(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<Vec<String>, String> {
     // Get the target variables
     let var_names = if let Some(vars) = &config.main.target_var {
         // If specific variables are provided, use them
@@ -20,16 +40,22 @@ pub fn extract_data_matrix(
             return Err("No variable definitions found".to_string());
         };
 
-        // Map variable names (might be index-based in configs)
-        vars.iter()
-            .map(|v| {
-                if let Ok(idx) = v.parse::<usize>() {
-                    if idx < var_defs.len() { var_defs[idx].name.clone() } else { v.clone() }
-                } else {
-                    v.clone()
+        // Map variable names (might be index-based in configs), expanding patterns and ranges
+        let available = available_variable_names(data);
+        let mut var_names: Vec<String> = Vec::new();
+        for v in vars {
+            let expanded = if let Ok(idx) = v.parse::<usize>() {
+                vec![if idx < var_defs.len() { var_defs[idx].name.clone() } else { v.clone() }]
+            } else {
+                expand_variable_entry(v, &available)?
+            };
+            for name in expanded {
+                if !var_names.contains(&name) {
+                    var_names.push(name);
                 }
-            })
-            .collect::<Vec<String>>()
+            }
+        }
+        var_names
     } else {
         // Collect all numeric variables from all datasets
         data.target_data
@@ -38,7 +64,10 @@ pub fn extract_data_matrix(
                 dataset.iter().flat_map(|record| {
                     record.values
                         .iter()
-                        .filter(|(_, value)| matches!(value, DataValue::Number(_)))
+                        .filter(|(key, value)| {
+                            matches!(value, DataValue::Number(_)) ||
+                                categorical_variable(config, key).is_some()
+                        })
                         .map(|(key, _)| key.clone())
                 })
             })
@@ -47,6 +76,16 @@ pub fn extract_data_matrix(
             .collect::<Vec<String>>()
     };
 
+    Ok(var_names)
+}
+
+// Selected cases for the target variables, with NaN wherever a value is missing
+pub fn extract_raw_data_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<(DMatrix<f64>, Vec<String>), String> {
+    let var_names = resolve_variable_names(data, config)?;
+
     if var_names.is_empty() {
         return Err("No valid variables found".to_string());
     }
@@ -85,88 +124,135 @@ pub fn extract_data_matrix(
         .collect();
 
     // Apply filtering based on value_target and selection if specified
-    let filtered_records = if let Some(value_target) = &config.main.value_target {
-        if let Some(selection) = &config.value.selection {
-            // Both value_target and selection are specified
-            if !data.value_target_data.is_empty() {
-                // Prepare to match each case with its value target
-                let mut filtered = Vec::new();
-
-                // For each case, check if the value target matches the selection
-                for (case_idx, record) in records.iter().enumerate() {
-                    let mut matches_selection = false;
-
-                    // Check across all value target datasets
-                    for value_dataset in &data.value_target_data {
-                        if case_idx < value_dataset.len() {
-                            let value_record = &value_dataset[case_idx];
-
-                            match value_record.values.get(value_target) {
-                                Some(DataValue::Text(text)) => {
-                                    if text.as_str() == selection.as_str() {
-                                        matches_selection = true;
-                                        break;
-                                    }
-                                }
-                                Some(DataValue::Number(num)) => {
-                                    if num.to_string() == *selection {
-                                        matches_selection = true;
-                                        break;
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
+    let filtered_records: Vec<DataRecord> = records
+        .into_iter()
+        .enumerate()
+        .filter(|(case_idx, _)| case_is_selected(data, config, *case_idx))
+        .map(|(_, record)| record)
+        .collect();
 
-                    if matches_selection {
-                        filtered.push(record.clone());
-                    }
-                }
+    if filtered_records.is_empty() {
+        return Err("No valid records after filtering".to_string());
+    }
 
-                filtered
-            } else {
-                // Value target data is not available, use all records
-                records.clone()
+    // Keep missing values as NaN so callers can decide how to handle them. Categorical
+    // variables are replaced by their encoded columns.
+    let mut column_names = Vec::with_capacity(var_names.len());
+    let mut columns: Vec<Vec<f64>> = Vec::with_capacity(var_names.len());
+    for var_name in &var_names {
+        if let Some(variable) = categorical_variable(config, var_name) {
+            let levels_by_case: Vec<Option<String>> = filtered_records
+                .iter()
+                .map(|record| record.values.get(var_name).and_then(level_of))
+                .collect();
+            for (name, column) in encode_categorical(variable, &levels_by_case)? {
+                column_names.push(name);
+                columns.push(column);
             }
         } else {
-            // No selection specified, use all records
-            records.clone()
+            column_names.push(var_name.clone());
+            columns.push(
+                filtered_records
+                    .iter()
+                    .map(|record| {
+                        match record.values.get(var_name) {
+                            Some(DataValue::Number(value)) => *value,
+                            _ => f64::NAN,
+                        }
+                    })
+                    .collect()
+            );
         }
-    } else {
-        // No value_target specified, use all records
-        records.clone()
+    }
+
+    let raw_matrix = DMatrix::from_fn(filtered_records.len(), columns.len(), |i, j| columns[j][i]);
+
+    Ok((raw_matrix, column_names))
+}
+
+// Level of a variable for each selected case, in the row order of extract_raw_data_matrix
+pub fn extract_case_levels(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    var_name: &str
+) -> Vec<Option<String>> {
+    let num_cases = data.target_data
+        .iter()
+        .map(|dataset| dataset.len())
+        .max()
+        .unwrap_or(0);
+
+    // Later datasets take precedence, as when the records are merged
+    (0..num_cases)
+        .filter(|case_idx| case_is_selected(data, config, *case_idx))
+        .map(|case_idx| {
+            data.target_data
+                .iter()
+                .rev()
+                .filter_map(|dataset| dataset.get(case_idx))
+                .find_map(|record| record.values.get(var_name))
+                .and_then(level_of)
+        })
+        .collect()
+}
+
+// 1-based data set positions of the selected cases, in the row order of extract_raw_data_matrix
+pub fn selected_case_numbers(data: &AnalysisData, config: &FactorAnalysisConfig) -> Vec<usize> {
+    let num_cases = data.target_data
+        .iter()
+        .map(|dataset| dataset.len())
+        .max()
+        .unwrap_or(0);
+
+    (0..num_cases)
+        .filter(|case_idx| case_is_selected(data, config, *case_idx))
+        .map(|case_idx| case_idx + 1)
+        .collect()
+}
+
+// Whether a case passes the value target selection; all cases pass when none is configured
+pub fn case_is_selected(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    case_idx: usize
+) -> bool {
+    let (Some(value_target), Some(selection)) = (
+        &config.main.value_target,
+        &config.value.selection,
+    ) else {
+        return true;
     };
 
-    if filtered_records.is_empty() {
-        return Err("No valid records after filtering".to_string());
+    // Value target data is not available, use all records
+    if data.value_target_data.is_empty() {
+        return true;
     }
 
-    // Count valid records based on options
+    // Check across all value target datasets
+    data.value_target_data.iter().any(|value_dataset| {
+        match value_dataset.get(case_idx).and_then(|record| record.values.get(value_target)) {
+            Some(DataValue::Text(text)) => text.as_str() == selection.as_str(),
+            Some(DataValue::Number(num)) => num.to_string() == *selection,
+            _ => false,
+        }
+    })
+}
+
+// Apply the configured missing value handling to a raw matrix with NaN for missing values
+pub fn handle_missing_values(
+    raw_matrix: &DMatrix<f64>,
+    config: &FactorAnalysisConfig
+) -> Result<DMatrix<f64>, String> {
+    let n_cols = raw_matrix.ncols();
     let mut valid_records: Vec<Vec<f64>> = Vec::new();
 
-    for record in &filtered_records {
-        let mut row = Vec::new();
-        let mut has_missing = false;
-
-        for var_name in &var_names {
-            match record.values.get(var_name) {
-                Some(DataValue::Number(value)) => row.push(*value),
-                _ => {
-                    has_missing = true;
-                    if config.options.replace_mean {
-                        row.push(f64::NAN); // Will replace with mean later
-                    } else {
-                        break; // Skip this record
-                    }
-                }
-            }
-        }
+    for i in 0..raw_matrix.nrows() {
+        let row: Vec<f64> = raw_matrix.row(i).iter().copied().collect();
+        let has_missing = row.iter().any(|value| value.is_nan());
 
-        if !has_missing || (has_missing && !config.options.exclude_list_wise) {
-            if row.len() == var_names.len() {
-                valid_records.push(row);
-            }
+        // Incomplete cases are only kept when they will be mean-imputed
+        if !has_missing || (config.options.replace_mean && !config.options.exclude_list_wise) {
+            valid_records.push(row);
         }
     }
 
@@ -181,7 +267,6 @@ pub fn extract_data_matrix(
 
     // Convert to DMatrix
     let n_rows = valid_records.len();
-    let n_cols = var_names.len();
     let mut data_matrix = DMatrix::zeros(n_rows, n_cols);
 
     for i in 0..n_rows {
@@ -190,7 +275,7 @@ pub fn extract_data_matrix(
         }
     }
 
-    Ok((data_matrix, var_names))
+    Ok(data_matrix)
 }
 
 // Replace missing values (NaN) with column means
@@ -0,0 +1,121 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ CaseCoordinates, PrincipalCoordinates, TotalVarianceComponent },
+};
+
+use super::core::{ calculate_gower_distances, robust_symmetric_eigen };
+
+// Axes kept when PCoAAxes is not set
+pub const DEFAULT_PCOA_AXES: usize = 2;
+
+// Eigenvalues within this fraction of the largest one are treated as zero
+const ZERO_EIGENVALUE: f64 = 1e-10;
+
+// Principal coordinates analysis (Gower, 1966) of the cases on Gower distances. The square root
+// of a Gower distance is Euclidean, so the Gower distance itself plays the squared distance.
+pub fn calculate_principal_coordinates(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<PrincipalCoordinates, String> {
+    let gower = calculate_gower_distances(data, config)?;
+    let (axes, coordinates, negative_eigenvalues) = build_principal_coordinates(
+        &gower.distances,
+        pcoa_axes(config)
+    )?;
+
+    Ok(PrincipalCoordinates {
+        n_cases: gower.case_numbers.len(),
+        variables: gower.variables,
+        axes,
+        coordinates: gower.case_numbers
+            .iter()
+            .zip(coordinates.row_iter())
+            .map(|(case_number, row)| CaseCoordinates {
+                case_number: *case_number,
+                coordinates: row.iter().copied().collect(),
+            })
+            .collect(),
+        negative_eigenvalues,
+    })
+}
+
+pub fn pcoa_axes(config: &FactorAnalysisConfig) -> usize {
+    match config.extraction.pcoa_axes {
+        Some(axes) if axes > 0 => axes as usize,
+        _ => DEFAULT_PCOA_AXES,
+    }
+}
+
+// Axes, case coordinates (cases x axes) and the number of negative eigenvalues for a matrix of
+// squared distances. Percentages are shares of the sum of the positive eigenvalues.
+pub fn build_principal_coordinates(
+    squared_distances: &DMatrix<f64>,
+    n_axes: usize
+) -> Result<(Vec<TotalVarianceComponent>, DMatrix<f64>, usize), String> {
+    if squared_distances.nrows() < 2 {
+        return Err("Principal coordinates need at least 2 points".to_string());
+    }
+
+    let (eigenvalues, eigenvectors) = classical_scaling(squared_distances);
+    let tolerance = eigenvalues.first().copied().unwrap_or(0.0).abs() * ZERO_EIGENVALUE;
+    let positive_total: f64 = eigenvalues
+        .iter()
+        .filter(|value| **value > tolerance)
+        .sum();
+    if positive_total <= 0.0 {
+        return Err("All points are at zero distance from each other".to_string());
+    }
+
+    let n_axes = n_axes.min(eigenvalues.iter().filter(|value| **value > tolerance).count());
+    let mut cumulative = 0.0;
+    let axes = eigenvalues
+        .iter()
+        .take(n_axes)
+        .map(|value| {
+            let percent = (value / positive_total) * 100.0;
+            cumulative += percent;
+            TotalVarianceComponent {
+                total: *value,
+                percent_of_variance: percent,
+                cumulative_percent: cumulative,
+            }
+        })
+        .collect();
+
+    let coordinates = DMatrix::from_fn(eigenvectors.nrows(), n_axes, |i, k| {
+        eigenvectors[(i, k)] * eigenvalues[k].sqrt()
+    });
+
+    Ok((axes, coordinates, eigenvalues.iter().filter(|value| **value < -tolerance).count()))
+}
+
+// Torgerson's double centering B = -1/2 J D2 J, returning its eigenvalues in descending order
+// with the eigenvectors as columns
+pub fn classical_scaling(squared_distances: &DMatrix<f64>) -> (Vec<f64>, DMatrix<f64>) {
+    let n = squared_distances.nrows();
+    let row_means: Vec<f64> = squared_distances
+        .row_iter()
+        .map(|row| row.sum() / (n as f64))
+        .collect();
+    let grand_mean = row_means.iter().sum::<f64>() / (n as f64);
+    let centered = DMatrix::from_fn(n, n, |i, j| {
+        -0.5 * (squared_distances[(i, j)] - row_means[i] - row_means[j] + grand_mean)
+    });
+
+    let eigen = robust_symmetric_eigen(&centered);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| {
+        eigen.eigenvalues[b].partial_cmp(&eigen.eigenvalues[a]).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    (
+        order
+            .iter()
+            .map(|&k| eigen.eigenvalues[k])
+            .collect(),
+        eigen.eigenvectors.select_columns(&order),
+    )
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+
+use crate::models::{ config::AlignmentMethod, result::FactorAlignment };
+
+use super::comparison::{ optimal_factor_matching, tucker_congruence };
+
+// Align loadings keyed by variable with a reference solution over the variables both share
+pub fn align_factor_loadings(
+    loadings: &HashMap<String, Vec<f64>>,
+    reference: &HashMap<String, Vec<f64>>,
+    method: &AlignmentMethod
+) -> Result<FactorAlignment, String> {
+    let mut var_names: Vec<String> = loadings
+        .keys()
+        .filter(|var| reference.contains_key(*var))
+        .cloned()
+        .collect();
+    var_names.sort();
+
+    if var_names.is_empty() {
+        return Err("The loadings and the reference have no variables in common".to_string());
+    }
+
+    let to_matrix = |values: &HashMap<String, Vec<f64>>| -> Result<DMatrix<f64>, String> {
+        let n_factors = values[&var_names[0]].len();
+        if var_names.iter().any(|var| values[var].len() != n_factors) {
+            return Err("Every variable needs the same number of loadings".to_string());
+        }
+        Ok(DMatrix::from_fn(var_names.len(), n_factors, |i, j| values[&var_names[i]][j]))
+    };
+
+    build_factor_alignment(&to_matrix(loadings)?, &to_matrix(reference)?, &var_names, method)
+}
+
+pub fn build_factor_alignment(
+    loadings: &DMatrix<f64>,
+    reference: &DMatrix<f64>,
+    var_names: &[String],
+    method: &AlignmentMethod
+) -> Result<FactorAlignment, String> {
+    let transformation = alignment_transformation(loadings, reference, method)?;
+    let aligned = loadings * &transformation;
+    let n_factors = transformation.ncols();
+
+    // The source of each reference factor is the factor weighted most in its column of T
+    let mut factor_order = Vec::with_capacity(n_factors);
+    let mut reflected = Vec::with_capacity(n_factors);
+    for column in transformation.column_iter() {
+        let source = column.iamax();
+        factor_order.push(source + 1);
+        reflected.push(column[source] < 0.0);
+    }
+
+    let congruence = tucker_congruence(&aligned, reference);
+    let residual_sum_of_squares = (&aligned - reference).norm_squared();
+
+    Ok(FactorAlignment {
+        method: method.clone(),
+        transformation: transformation
+            .row_iter()
+            .map(|row| row.iter().copied().collect())
+            .collect(),
+        aligned_loadings: var_names
+            .iter()
+            .enumerate()
+            .map(|(i, var_name)| (var_name.clone(), aligned.row(i).iter().copied().collect()))
+            .collect(),
+        factor_order,
+        reflected,
+        congruences: (0..n_factors).map(|j| congruence[(j, j)]).collect(),
+        residual_sum_of_squares,
+        rmsd: (residual_sum_of_squares / (aligned.len() as f64)).sqrt(),
+    })
+}
+
+// Transformation T minimizing ||loadings * T - reference|| within the chosen family
+pub fn alignment_transformation(
+    loadings: &DMatrix<f64>,
+    reference: &DMatrix<f64>,
+    method: &AlignmentMethod
+) -> Result<DMatrix<f64>, String> {
+    if loadings.nrows() != reference.nrows() {
+        return Err(
+            format!(
+                "Loadings have {} variables but the reference has {}",
+                loadings.nrows(),
+                reference.nrows()
+            )
+        );
+    }
+    if loadings.ncols() != reference.ncols() || loadings.ncols() == 0 {
+        return Err(
+            format!(
+                "Alignment needs the same number of factors, got {} and {}",
+                loadings.ncols(),
+                reference.ncols()
+            )
+        );
+    }
+
+    match method {
+        AlignmentMethod::Orthogonal => orthogonal_procrustes(loadings, reference),
+        AlignmentMethod::Permutation => Ok(permutation_reflection(loadings, reference)),
+    }
+}
+
+// Schönemann's solution: with A'B = U S V', T = U V'
+fn orthogonal_procrustes(
+    loadings: &DMatrix<f64>,
+    reference: &DMatrix<f64>
+) -> Result<DMatrix<f64>, String> {
+    let cross_products = loadings.transpose() * reference;
+    let svd = cross_products.svd(true, true);
+
+    match (svd.u, svd.v_t) {
+        (Some(u), Some(v_t)) => Ok(u * v_t),
+        _ => Err("Could not decompose the loadings for Procrustes alignment".to_string()),
+    }
+}
+
+// Signed permutation matrix from the one-to-one matching by absolute congruence
+fn permutation_reflection(loadings: &DMatrix<f64>, reference: &DMatrix<f64>) -> DMatrix<f64> {
+    let congruence = tucker_congruence(loadings, reference);
+    let mut transformation = DMatrix::zeros(loadings.ncols(), reference.ncols());
+
+    for (i, j) in optimal_factor_matching(&congruence) {
+        transformation[(i, j)] = if congruence[(i, j)] < 0.0 { -1.0 } else { 1.0 };
+    }
+
+    transformation
+}
@@ -0,0 +1,379 @@
+use nalgebra::DMatrix;
+use statrs::distribution::{ ChiSquared, ContinuousCDF };
+
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    data::AnalysisData,
+    result::{ ExtractionResult, PsychSolution, PsychVarianceAccounted, RotationResult },
+};
+
+use super::core::{
+    extract_correlation_matrix,
+    extract_factors,
+    matrix_to_rows,
+    robust_symmetric_eigen,
+    rotate_factors,
+};
+
+pub fn calculate_psych_solution(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<PsychSolution, String> {
+    let (corr_matrix, var_names, n_obs) = extract_correlation_matrix(data, config, "correlation")?;
+    let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
+    let rotation_result = if config.rotation.none {
+        None
+    } else {
+        Some(rotate_factors(&extraction_result, config)?)
+    };
+
+    Ok(
+        build_psych_solution(
+            &corr_matrix,
+            &extraction_result,
+            rotation_result.as_ref(),
+            n_obs,
+            config
+        )
+    )
+}
+
+// Solution in the layout of the object returned by psych::fa (or psych::principal for PCA)
+pub fn build_psych_solution(
+    corr_matrix: &DMatrix<f64>,
+    extraction_result: &ExtractionResult,
+    rotation_result: Option<&RotationResult>,
+    n_obs: usize,
+    config: &FactorAnalysisConfig
+) -> PsychSolution {
+    let n_vars = corr_matrix.nrows();
+    let n_factors = extraction_result.n_factors;
+
+    let loadings = rotation_result
+        .map(|rotation| rotation.rotated_loadings.clone())
+        .unwrap_or_else(|| extraction_result.loadings.clone());
+    let phi = rotation_result.and_then(|rotation| rotation.factor_correlations.clone());
+    let phi_or_identity = phi.clone().unwrap_or_else(|| DMatrix::identity(n_factors, n_factors));
+
+    let structure = &loadings * &phi_or_identity;
+    let model = &structure * loadings.transpose();
+    let residual = corr_matrix - &model;
+
+    let communality: Vec<f64> = (0..n_vars).map(|i| model[(i, i)]).collect();
+    let uniquenesses: Vec<f64> = communality
+        .iter()
+        .map(|h2| 1.0 - h2)
+        .collect();
+
+    // Hofmann's index of complexity
+    let complexity = (0..n_vars)
+        .map(|i| {
+            let row = loadings.row(i);
+            let squares: f64 = row.iter().map(|l| l.powi(2)).sum();
+            let fourths: f64 = row.iter().map(|l| l.powi(4)).sum();
+            if fourths > 0.0 { squares.powi(2) / fourths } else { 1.0 }
+        })
+        .collect();
+
+    let fit = calculate_fit_statistics(
+        corr_matrix,
+        &model,
+        &residual,
+        &uniquenesses,
+        n_obs,
+        n_factors
+    );
+
+    // Regression weights for factor scores and their squared multiple correlations
+    let (weights, r2) = match corr_matrix.clone().try_inverse() {
+        Some(inverse) => {
+            let weights = &inverse * &structure;
+            let r2 = (0..n_factors)
+                .map(|j| structure.column(j).dot(&weights.column(j)))
+                .collect();
+            (Some(matrix_to_rows(&weights)), Some(r2))
+        }
+        None => (None, None),
+    };
+
+    PsychSolution {
+        factors: n_factors,
+        n_obs,
+        fm: psych_method(&config.extraction.method).to_string(),
+        rotation: psych_rotation(config).to_string(),
+        rownames: extraction_result.var_names.clone(),
+        colnames: factor_names(config, phi.is_some(), rotation_result.is_some(), n_factors),
+        loadings: matrix_to_rows(&loadings),
+        phi: phi.as_ref().map(matrix_to_rows),
+        structure: matrix_to_rows(&structure),
+        communality,
+        uniquenesses,
+        complexity,
+        e_values: sorted_eigenvalues(corr_matrix),
+        values: sorted_eigenvalues(&model),
+        vaccounted: variance_accounted(&loadings, &phi_or_identity, n_vars),
+        residual: matrix_to_rows(&residual),
+        dof: fit.dof,
+        objective: fit.objective,
+        statistic: fit.statistic,
+        pval: fit.pval,
+        null_model: fit.null_model,
+        null_dof: fit.null_dof,
+        null_chisq: fit.null_chisq,
+        tli: fit.tli,
+        rmsea: fit.rmsea,
+        bic: fit.bic,
+        rms: fit.rms,
+        crms: fit.crms,
+        fit: fit.fit,
+        fit_off: fit.fit_off,
+        weights,
+        r2,
+    }
+}
+
+// RMSEA of unrotated loadings fitted to a correlation matrix; None without degrees of freedom
+pub fn model_rmsea(
+    corr_matrix: &DMatrix<f64>,
+    loadings: &DMatrix<f64>,
+    n_obs: usize
+) -> Option<f64> {
+    model_fit_statistics(corr_matrix, loadings, n_obs).rmsea
+}
+
+// Fit of unrotated loadings to a correlation matrix, the uniquenesses making up the diagonal
+pub fn model_fit_statistics(
+    corr_matrix: &DMatrix<f64>,
+    loadings: &DMatrix<f64>,
+    n_obs: usize
+) -> FitStatistics {
+    let model = loadings * loadings.transpose();
+    let residual = corr_matrix - &model;
+    let uniquenesses: Vec<f64> = (0..model.nrows()).map(|i| 1.0 - model[(i, i)]).collect();
+
+    calculate_fit_statistics(
+        corr_matrix,
+        &model,
+        &residual,
+        &uniquenesses,
+        n_obs,
+        loadings.ncols()
+    )
+}
+
+pub struct FitStatistics {
+    pub dof: i64,
+    pub objective: Option<f64>,
+    pub statistic: Option<f64>,
+    pub pval: Option<f64>,
+    pub null_model: Option<f64>,
+    pub null_dof: usize,
+    pub null_chisq: Option<f64>,
+    pub tli: Option<f64>,
+    pub rmsea: Option<f64>,
+    pub bic: Option<f64>,
+    pub rms: f64,
+    pub crms: Option<f64>,
+    pub fit: f64,
+    pub fit_off: f64,
+}
+
+// Same definitions as psych's fa.stats
+fn calculate_fit_statistics(
+    corr_matrix: &DMatrix<f64>,
+    model: &DMatrix<f64>,
+    residual: &DMatrix<f64>,
+    uniquenesses: &[f64],
+    n_obs: usize,
+    n_factors: usize
+) -> FitStatistics {
+    let p = corr_matrix.nrows() as f64;
+    let k = n_factors as f64;
+    let n = n_obs as f64;
+
+    let dof = (((p - k).powi(2) - (p + k)) / 2.0).round() as i64;
+    let null_dof = (corr_matrix.nrows() * corr_matrix.nrows().saturating_sub(1)) / 2;
+
+    let mut sum_r2 = 0.0;
+    let mut sum_r2_off = 0.0;
+    let mut sum_residual2 = 0.0;
+    let mut sum_residual2_off = 0.0;
+    for i in 0..corr_matrix.nrows() {
+        for j in 0..corr_matrix.ncols() {
+            sum_r2 += corr_matrix[(i, j)].powi(2);
+            sum_residual2 += residual[(i, j)].powi(2);
+            if i != j {
+                sum_r2_off += corr_matrix[(i, j)].powi(2);
+                sum_residual2_off += residual[(i, j)].powi(2);
+            }
+        }
+    }
+
+    // Maximum likelihood discrepancy between R and the implied matrix with unit diagonal
+    let mut implied = model.clone();
+    for (i, u2) in uniquenesses.iter().enumerate() {
+        implied[(i, i)] += u2;
+    }
+    let objective = implied.try_inverse().and_then(|inverse| {
+        let product = inverse * corr_matrix;
+        let determinant = product.determinant();
+        if determinant > 0.0 {
+            Some(product.trace() - determinant.ln() - p)
+        } else {
+            None
+        }
+    });
+
+    let correction = n - 1.0 - (2.0 * p + 5.0) / 6.0;
+    let statistic = objective
+        .filter(|_| n > 1.0)
+        .map(|f| (correction - (2.0 * k) / 3.0) * f);
+    let positive_dof = if dof > 0 { Some(dof as f64) } else { None };
+
+    let pval = match (statistic, positive_dof) {
+        (Some(chisq), Some(df)) => ChiSquared::new(df).ok().map(|dist| dist.sf(chisq.max(0.0))),
+        _ => None,
+    };
+
+    let determinant = corr_matrix.determinant();
+    let null_model = if determinant > 0.0 { Some(-determinant.ln()) } else { None };
+    let null_chisq = null_model.filter(|_| n > 1.0).map(|f| correction * f);
+
+    let tli = match (statistic, positive_dof, null_chisq) {
+        (Some(chisq), Some(df), Some(null_chisq)) if null_dof > 0 => {
+            let null_ratio = null_chisq / (null_dof as f64);
+            Some((null_ratio - chisq / df) / (null_ratio - 1.0))
+        }
+        _ => None,
+    };
+    let rmsea = match (statistic, positive_dof) {
+        (Some(chisq), Some(df)) if n > 1.0 => {
+            Some(((chisq - df) / (df * (n - 1.0))).max(0.0).sqrt())
+        }
+        _ => None,
+    };
+    let bic = match (statistic, positive_dof) {
+        (Some(chisq), Some(df)) if n > 0.0 => Some(chisq - df * n.ln()),
+        _ => None,
+    };
+
+    let off_diagonal = p * (p - 1.0);
+
+    FitStatistics {
+        dof,
+        objective,
+        statistic,
+        pval,
+        null_model,
+        null_dof,
+        null_chisq,
+        tli,
+        rmsea,
+        bic,
+        rms: if off_diagonal > 0.0 { (sum_residual2_off / off_diagonal).sqrt() } else { 0.0 },
+        crms: positive_dof.map(|df| (sum_residual2_off / (2.0 * df)).sqrt()),
+        fit: if sum_r2 > 0.0 { 1.0 - sum_residual2 / sum_r2 } else { 0.0 },
+        fit_off: if sum_r2_off > 0.0 { 1.0 - sum_residual2_off / sum_r2_off } else { 0.0 },
+    }
+}
+
+// Variance accounted for by each factor; for oblique solutions this is diag(Phi L'L)
+fn variance_accounted(
+    loadings: &DMatrix<f64>,
+    phi: &DMatrix<f64>,
+    n_vars: usize
+) -> PsychVarianceAccounted {
+    let sums = phi * loadings.transpose() * loadings;
+    let ss_loadings: Vec<f64> = (0..sums.nrows()).map(|j| sums[(j, j)]).collect();
+    let total: f64 = ss_loadings.iter().sum();
+
+    let proportion_var: Vec<f64> = ss_loadings
+        .iter()
+        .map(|ss| ss / (n_vars as f64))
+        .collect();
+    let proportion_explained: Vec<f64> = ss_loadings
+        .iter()
+        .map(|ss| if total > 0.0 { ss / total } else { 0.0 })
+        .collect();
+
+    PsychVarianceAccounted {
+        cumulative_var: cumulative_sum(&proportion_var),
+        cumulative_proportion: cumulative_sum(&proportion_explained),
+        ss_loadings,
+        proportion_var,
+        proportion_explained,
+    }
+}
+
+fn cumulative_sum(values: &[f64]) -> Vec<f64> {
+    values
+        .iter()
+        .scan(0.0, |sum, value| {
+            *sum += value;
+            Some(*sum)
+        })
+        .collect()
+}
+
+fn sorted_eigenvalues(matrix: &DMatrix<f64>) -> Vec<f64> {
+    let mut eigenvalues: Vec<f64> = robust_symmetric_eigen(matrix)
+        .eigenvalues.iter()
+        .copied()
+        .collect();
+    eigenvalues.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    eigenvalues
+}
+
+// psych's fm codes
+fn psych_method(method: &ExtractionMethod) -> &'static str {
+    match method {
+        ExtractionMethod::PrincipalComponents => "pc",
+        ExtractionMethod::UnweightedLeastSquares => "uls",
+        ExtractionMethod::GeneralizedLeastSquares => "gls",
+        ExtractionMethod::MaximumLikelihood => "ml",
+        ExtractionMethod::PrincipalAxisFactoring => "pa",
+        ExtractionMethod::AlphaFactoring => "alpha",
+        ExtractionMethod::ImageFactoring => "image",
+    }
+}
+
+// Same precedence as rotate_factors, spelled as psych's rotate argument
+fn psych_rotation(config: &FactorAnalysisConfig) -> &'static str {
+    if config.rotation.none {
+        "none"
+    } else if config.rotation.varimax {
+        "varimax"
+    } else if config.rotation.quartimax {
+        "quartimax"
+    } else if config.rotation.equimax {
+        "equamax"
+    } else if config.rotation.oblimin {
+        "oblimin"
+    } else if config.rotation.promax {
+        "promax"
+    } else {
+        "varimax"
+    }
+}
+
+// Column names as psych labels them: PC/RC/TC for principal, otherwise a method prefix
+fn factor_names(
+    config: &FactorAnalysisConfig,
+    oblique: bool,
+    rotated: bool,
+    n_factors: usize
+) -> Vec<String> {
+    let prefix = match config.extraction.method {
+        ExtractionMethod::PrincipalComponents if oblique => "TC",
+        ExtractionMethod::PrincipalComponents if rotated => "RC",
+        ExtractionMethod::PrincipalComponents => "PC",
+        ExtractionMethod::UnweightedLeastSquares => "ULS",
+        ExtractionMethod::GeneralizedLeastSquares => "GLS",
+        ExtractionMethod::MaximumLikelihood => "ML",
+        ExtractionMethod::PrincipalAxisFactoring => "PA",
+        ExtractionMethod::AlphaFactoring => "alpha",
+        ExtractionMethod::ImageFactoring => "IM",
+    };
+
+    (1..=n_factors).map(|j| format!("{}{}", prefix, j)).collect()
+}
@@ -0,0 +1,123 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    data::AnalysisData,
+    result::{ FactorReliability, ScaleReliability },
+};
+
+use super::core::{
+    average_variance_extracted,
+    extract_correlation_matrix,
+    extract_factors,
+    rotate_factors,
+};
+
+// Reliability of the item set of every factor, from the analyzed correlation matrix and the
+// rotated loadings, or the unrotated ones without rotation. On a polychoric matrix these are
+// ordinal alpha and ordinal omega (Zumbo, Gadermann and Zeisser, 2007).
+pub fn calculate_factor_reliability(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<FactorReliability, String> {
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
+    let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
+    let loadings = if config.rotation.none {
+        extraction_result.loadings
+    } else {
+        rotate_factors(&extraction_result, config)?.rotated_loadings
+    };
+
+    build_factor_reliability(&corr_matrix, &loadings, &var_names, config)
+}
+
+// Each item belongs to the factor of its largest absolute loading and is keyed by the sign of
+// that loading. Alpha is the standardized alpha of the keyed items; omega is the share of the
+// variance of their sum due to the factor, (sum of keyed loadings)^2 / 1'R1, leaving out
+// cross-loadings. Component loadings carry the unique variance too, so principal components
+// get no omega. Composite reliability is (sum of loadings)^2 over itself plus the summed
+// uniquenesses 1 - loading^2 (Fornell and Larcker, 1981).
+pub fn build_factor_reliability(
+    corr_matrix: &DMatrix<f64>,
+    loadings: &DMatrix<f64>,
+    var_names: &[String],
+    config: &FactorAnalysisConfig
+) -> Result<FactorReliability, String> {
+    if loadings.nrows() != corr_matrix.nrows() || var_names.len() != loadings.nrows() {
+        return Err("Loadings don't match the correlation matrix".to_string());
+    }
+
+    let factors = assign_items(loadings)
+        .into_iter()
+        .enumerate()
+        .map(|(factor, items)| {
+            let keys: Vec<f64> = items
+                .iter()
+                .map(|&i| loadings[(i, factor)].signum())
+                .collect();
+            let (alpha, omega) = if items.len() < 2 {
+                (None, None)
+            } else {
+                let k = items.len() as f64;
+                let mut total_variance = 0.0;
+                for (a, &i) in items.iter().enumerate() {
+                    for (b, &j) in items.iter().enumerate() {
+                        total_variance += keys[a] * keys[b] * corr_matrix[(i, j)];
+                    }
+                }
+                let keyed_loadings: f64 = items
+                    .iter()
+                    .map(|&i| loadings[(i, factor)].abs())
+                    .sum();
+
+                let omega = (config.extraction.method != ExtractionMethod::PrincipalComponents)
+                    .then(|| keyed_loadings.powi(2) / total_variance);
+
+                (Some((k / (k - 1.0)) * (1.0 - k / total_variance)), omega)
+            };
+
+            let composite_reliability = (!items.is_empty()).then(|| {
+                let (loading_sum, uniqueness_sum) = items
+                    .iter()
+                    .map(|&i| loadings[(i, factor)].abs())
+                    .fold((0.0, 0.0), |(loading_sum, uniqueness_sum), loading| {
+                        (loading_sum + loading, uniqueness_sum + 1.0 - loading.powi(2))
+                    });
+                loading_sum.powi(2) / (loading_sum.powi(2) + uniqueness_sum)
+            });
+
+            ScaleReliability {
+                factor: factor + 1,
+                items: items
+                    .iter()
+                    .map(|&i| var_names[i].clone())
+                    .collect(),
+                reversed_items: items
+                    .iter()
+                    .zip(&keys)
+                    .filter(|(_, key)| **key < 0.0)
+                    .map(|(&i, _)| var_names[i].clone())
+                    .collect(),
+                alpha,
+                omega,
+                ave: average_variance_extracted(loadings, &items, factor),
+                composite_reliability,
+            }
+        })
+        .collect();
+
+    Ok(FactorReliability {
+        ordinal: config.extraction.polychoric,
+        factors,
+    })
+}
+
+// Rows of the items of every factor, each item going to the factor of its largest absolute
+// loading
+pub fn assign_items(loadings: &DMatrix<f64>) -> Vec<Vec<usize>> {
+    let mut items = vec![Vec::new(); loadings.ncols()];
+    for (i, row) in loadings.row_iter().enumerate() {
+        items[row.iamax_full().1].push(i);
+    }
+    items
+}
@@ -5,30 +5,44 @@ use crate::models::{
     config::FactorAnalysisConfig,
     data::AnalysisData,
     result::{
+        ExtractionResult,
         Communalities,
         ComponentMatrix,
         ComponentScoreCoefficientMatrix,
         ComponentScoreCovarianceMatrix,
         ComponentTransformationMatrix,
+        FactorScoreDeterminacy,
         ReproducedCorrelations,
         RotatedComponentMatrix,
         RotationResult,
+        ScoreDeterminacy,
         ScreePlot,
         TotalVarianceComponent,
         TotalVarianceExplained,
     },
 };
 
-use super::core::{ calculate_matrix, extract_data_matrix, extract_factors, rotate_factors };
+use super::core::{
+    extract_correlation_matrix,
+    extract_factors,
+    robust_symmetric_eigen,
+    rotate_factors,
+};
 
 pub fn calculate_communalities(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<Communalities, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
 
+    Ok(build_communalities(&extraction_result, &var_names))
+}
+
+pub fn build_communalities(
+    extraction_result: &ExtractionResult,
+    var_names: &[String]
+) -> Communalities {
     let mut initial = HashMap::new();
     let mut extraction = HashMap::new();
 
@@ -39,22 +53,43 @@ pub fn calculate_communalities(
         }
     }
 
-    Ok(Communalities {
+    Communalities {
         initial,
         extraction,
-    })
+    }
 }
 
 pub fn calculate_total_variance_explained(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<TotalVarianceExplained, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
 
+    // If rotation fails, we continue without rotation sums
+    let rotation_result = if config.rotation.none {
+        None
+    } else {
+        rotate_factors(&extraction_result, config).ok()
+    };
+
+    Ok(
+        build_total_variance_explained(
+            &extraction_result,
+            rotation_result.as_ref(),
+            var_names.len(),
+            config
+        )
+    )
+}
+
+pub fn build_total_variance_explained(
+    extraction_result: &ExtractionResult,
+    rotation_result: Option<&RotationResult>,
+    n_variables: usize,
+    config: &FactorAnalysisConfig
+) -> TotalVarianceExplained {
     let n_factors = extraction_result.n_factors;
-    let n_variables = var_names.len();
 
     let mut initial_eigenvalues = Vec::with_capacity(n_variables);
     let mut extraction_sums = Vec::with_capacity(n_factors);
@@ -94,9 +129,8 @@ pub fn calculate_total_variance_explained(
 
     // Calculate rotation sums if rotation is applied (not NOROTATE)
     if !config.rotation.none {
-        // Perform rotation
-        match rotate_factors(&extraction_result, config) {
-            Ok(rotation_result) => {
+        match rotation_result {
+            Some(rotation_result) => {
                 let rotated_loadings = &rotation_result.rotated_loadings;
 
                 // Check if we have valid dimensions
@@ -163,27 +197,33 @@ pub fn calculate_total_variance_explained(
                     }
                 }
             }
-            Err(_) => {
+            None => {
                 // If rotation fails, we continue without rotation sums
             }
         }
     }
 
-    Ok(TotalVarianceExplained {
+    TotalVarianceExplained {
         initial_eigenvalues,
         extraction_sums,
         rotation_sums,
-    })
+    }
 }
 
 pub fn calculate_component_matrix(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<ComponentMatrix, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
 
+    Ok(build_component_matrix(&extraction_result, &var_names))
+}
+
+pub fn build_component_matrix(
+    extraction_result: &ExtractionResult,
+    var_names: &[String]
+) -> ComponentMatrix {
     let mut components = HashMap::new();
 
     for (i, var_name) in var_names.iter().enumerate() {
@@ -198,19 +238,26 @@ pub fn calculate_component_matrix(
         }
     }
 
-    Ok(ComponentMatrix {
+    ComponentMatrix {
         components,
-    })
+    }
 }
 
 pub fn calculate_reproduced_correlations(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<ReproducedCorrelations, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
 
+    Ok(build_reproduced_correlations(&corr_matrix, &extraction_result, &var_names))
+}
+
+pub fn build_reproduced_correlations(
+    corr_matrix: &DMatrix<f64>,
+    extraction_result: &ExtractionResult,
+    var_names: &[String]
+) -> ReproducedCorrelations {
     let n_vars = var_names.len();
     let mut reproduced_correlation = HashMap::new();
     let mut residual = HashMap::new();
@@ -247,22 +294,23 @@ pub fn calculate_reproduced_correlations(
         residual.insert(var_name.clone(), var_residual);
     }
 
-    Ok(ReproducedCorrelations {
+    ReproducedCorrelations {
         reproduced_correlation,
         residual,
-    })
+    }
 }
 
 pub fn calculate_scree_plot(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<ScreePlot, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
 
-    let n_variables = var_names.len();
+    Ok(build_scree_plot(&extraction_result, var_names.len()))
+}
 
+pub fn build_scree_plot(extraction_result: &ExtractionResult, n_variables: usize) -> ScreePlot {
     // Ensure we have eigenvalues for all variables
     let mut eigenvalues = extraction_result.eigenvalues.clone();
 
@@ -275,24 +323,63 @@ pub fn calculate_scree_plot(
         component_numbers.push(i + 1);
     }
 
-    Ok(ScreePlot {
+    ScreePlot {
         eigenvalues,
         component_numbers,
-    })
+    }
 }
 
 pub fn calculate_component_score_coefficient_matrix(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<ComponentScoreCoefficientMatrix, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
 
+    build_component_score_coefficient_matrix(&corr_matrix, &extraction_result, &var_names, config)
+}
+
+pub fn build_component_score_coefficient_matrix(
+    corr_matrix: &DMatrix<f64>,
+    extraction_result: &ExtractionResult,
+    var_names: &[String],
+    config: &FactorAnalysisConfig
+) -> Result<ComponentScoreCoefficientMatrix, String> {
+    let coefficients = score_coefficients(corr_matrix, extraction_result, config)?;
+    let n_rows = coefficients.nrows();
+    let n_cols = coefficients.ncols();
+
+    // Convert to result structure
+    let mut component_score_coefficient_matrix = ComponentScoreCoefficientMatrix {
+        components: HashMap::new(),
+    };
+
+    for (i, var_name) in var_names.iter().enumerate() {
+        if i < n_rows {
+            let mut factor_scores = Vec::with_capacity(n_cols);
+
+            for j in 0..n_cols {
+                factor_scores.push(coefficients[(i, j)]);
+            }
+
+            component_score_coefficient_matrix.components.insert(var_name.clone(), factor_scores);
+        }
+    }
+
+    Ok(component_score_coefficient_matrix)
+}
+
+// Score coefficient matrix W (variables x factors) for the selected score method
+fn score_coefficients(
+    corr_matrix: &DMatrix<f64>,
+    extraction_result: &ExtractionResult,
+    config: &FactorAnalysisConfig
+) -> Result<DMatrix<f64>, String> {
     // Calculate score coefficients directly
     let loadings = &extraction_result.loadings;
     let n_rows = loadings.nrows();
     let n_cols = loadings.ncols();
+    let _span = crate::trace_span!("score_coefficients", variables = n_rows, factors = n_cols);
 
     let mut coefficients = DMatrix::zeros(n_rows, n_cols);
 
@@ -327,7 +414,8 @@ pub fn calculate_component_score_coefficient_matrix(
 
         match a_transpose_u_inv_squared_a.try_inverse() {
             Some(ata_inv) => {
-                coefficients = ata_inv * loadings.transpose() * u_inv_squared;
+                // (A'U^-2 A)^-1 A'U^-2 has a row per factor; store it as variables x factors
+                coefficients = (ata_inv * loadings.transpose() * u_inv_squared).transpose();
             }
             None => {
                 return Err("Could not invert matrix for Bartlett method".to_string());
@@ -385,34 +473,92 @@ pub fn calculate_component_score_coefficient_matrix(
         }
     }
 
-    // Convert to result structure
-    let mut component_score_coefficient_matrix = ComponentScoreCoefficientMatrix {
-        components: HashMap::new(),
-    };
+    Ok(coefficients)
+}
 
-    for (i, var_name) in var_names.iter().enumerate() {
-        if i < n_rows {
-            let mut factor_scores = Vec::with_capacity(n_cols);
+// How well the factor scores stand in for the factors they estimate
+pub fn calculate_factor_score_determinacy(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<FactorScoreDeterminacy, String> {
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
+    let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
 
-            for j in 0..n_cols {
-                factor_scores.push(coefficients[(i, j)]);
-            }
+    build_factor_score_determinacy(&corr_matrix, &extraction_result, config)
+}
 
-            component_score_coefficient_matrix.components.insert(var_name.clone(), factor_scores);
-        }
+// The scores are built from the unrotated, uncorrelated factors, so the covariance of factor j
+// with score k is (A'W)_jk and the variance of score k is (W'RW)_kk. Determinacy is the
+// correlation of a factor with its own score; two equally valid score sets can correlate as
+// little as 2 * determinacy^2 - 1, which is negative once determinacy falls below 0.707.
+pub fn build_factor_score_determinacy(
+    corr_matrix: &DMatrix<f64>,
+    extraction_result: &ExtractionResult,
+    config: &FactorAnalysisConfig
+) -> Result<FactorScoreDeterminacy, String> {
+    let coefficients = score_coefficients(corr_matrix, extraction_result, config)?;
+    let loadings = &extraction_result.loadings;
+    let factor_score_covariances = loadings.transpose() * &coefficients;
+    let score_covariances = coefficients.transpose() * corr_matrix * &coefficients;
+    let n_factors = coefficients.ncols();
+
+    let mut factors = Vec::with_capacity(n_factors);
+    for j in 0..n_factors {
+        let validity_coefficients: Vec<f64> = (0..n_factors)
+            .map(|k| {
+                let score_variance = score_covariances[(k, k)];
+                if score_variance > 0.0 {
+                    factor_score_covariances[(j, k)] / score_variance.sqrt()
+                } else {
+                    f64::NAN
+                }
+            })
+            .collect();
+        let determinacy = validity_coefficients[j].min(1.0);
+        let reliability = determinacy.powi(2);
+
+        factors.push(ScoreDeterminacy {
+            factor: j + 1,
+            determinacy,
+            reliability,
+            minimum_correlation: 2.0 * reliability - 1.0,
+            validity_coefficients,
+        });
     }
 
-    Ok(component_score_coefficient_matrix)
+    Ok(FactorScoreDeterminacy {
+        method: score_method_name(config).to_string(),
+        factors,
+    })
+}
+
+fn score_method_name(config: &FactorAnalysisConfig) -> &'static str {
+    if config.scores.regression {
+        "Regression"
+    } else if config.scores.bartlett {
+        "Bartlett"
+    } else if config.scores.anderson {
+        "Anderson-Rubin"
+    } else {
+        "Regression"
+    }
 }
 
 pub fn calculate_component_score_covariance_matrix(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<ComponentScoreCovarianceMatrix, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
 
+    Ok(build_component_score_covariance_matrix(&corr_matrix, &extraction_result, config))
+}
+
+pub fn build_component_score_covariance_matrix(
+    corr_matrix: &DMatrix<f64>,
+    extraction_result: &ExtractionResult,
+    config: &FactorAnalysisConfig
+) -> ComponentScoreCovarianceMatrix {
     // Calculate score covariance matrix directly
     let loadings = &extraction_result.loadings;
     let n_rows = loadings.nrows();
@@ -496,7 +642,7 @@ pub fn calculate_component_score_covariance_matrix(
         }
     }
 
-    Ok(component_score_covariance_matrix)
+    component_score_covariance_matrix
 }
 
 // Helper function to calculate the symmetric square root of a matrix
@@ -507,7 +653,7 @@ pub fn symmetric_matrix_sqrt(matrix: &DMatrix<f64>) -> Option<DMatrix<f64>> {
     }
 
     // Perform eigenvalue decomposition
-    let eigen = matrix.clone().symmetric_eigen();
+    let eigen = robust_symmetric_eigen(matrix);
 
     // Create diagonal matrix of sqrt of eigenvalues
     let mut d_sqrt = DMatrix::zeros(n, n);
@@ -0,0 +1,273 @@
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    data::AnalysisData,
+    result::ReproducibilityLog,
+};
+use crate::utils::rng::resolve_seed;
+
+use super::core::{
+    analyzes_covariance,
+    distance_permutations,
+    extraction_convergence,
+    plausible_values,
+    resolve_variable_names,
+    rotation_convergence,
+    DEFAULT_PARALLEL_ITERATIONS,
+    DEFAULT_PARALLEL_PERCENTILE,
+};
+
+pub fn calculate_reproducibility_log(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<ReproducibilityLog, String> {
+    let var_names = resolve_variable_names(data, config)?;
+
+    build_reproducibility_log(config, &var_names)
+}
+
+pub fn build_reproducibility_log(
+    config: &FactorAnalysisConfig,
+    var_names: &[String]
+) -> Result<ReproducibilityLog, String> {
+    let effective = effective_config(config, var_names);
+
+    // serde_json::Value keeps object keys sorted, which makes the output canonical
+    let value = serde_json::to_value(&effective)
+        .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+    let effective_config = serde_json::to_string(&value)
+        .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+
+    Ok(ReproducibilityLog {
+        syntax: build_factor_syntax(&effective, var_names),
+        effective_config,
+    })
+}
+
+// Configuration with resolved variable names and every optional setting filled in
+pub fn effective_config(
+    config: &FactorAnalysisConfig,
+    var_names: &[String]
+) -> FactorAnalysisConfig {
+    let mut effective = config.clone();
+
+    effective.main.target_var = Some(var_names.to_vec());
+    effective.options.seed = Some(resolve_seed(config));
+    if effective.extraction.parallel_analysis {
+        effective.extraction.parallel_iter = Some(
+            config.extraction.parallel_iter
+                .filter(|iter| *iter > 0)
+                .unwrap_or(DEFAULT_PARALLEL_ITERATIONS as i32)
+        );
+        effective.extraction.parallel_percentile = Some(
+            config.extraction.parallel_percentile
+                .filter(|p| *p > 0.0 && *p < 100.0)
+                .unwrap_or(DEFAULT_PARALLEL_PERCENTILE)
+        );
+    }
+    effective.extraction.converge = Some(extraction_convergence(config));
+    effective.rotation.converge = Some(rotation_convergence(config));
+    if effective.descriptives.distance_corr {
+        effective.descriptives.distance_permutations = Some(distance_permutations(config) as i32);
+    }
+
+    effective
+}
+
+// SPSS FACTOR command equivalent to the configuration
+pub fn build_factor_syntax(config: &FactorAnalysisConfig, var_names: &[String]) -> String {
+    let variables = var_names.join(" ");
+    let mut lines = Vec::new();
+
+    lines.push(format!("SET SEED={}.", resolve_seed(config)));
+    if config.extraction.parallel_analysis {
+        lines.push(
+            format!(
+                "* Parallel analysis: {} iterations, percentile {}.",
+                config.extraction.parallel_iter.unwrap_or(DEFAULT_PARALLEL_ITERATIONS as i32),
+                format_number(
+                    config.extraction.parallel_percentile.unwrap_or(DEFAULT_PARALLEL_PERCENTILE)
+                )
+            )
+        );
+    }
+
+    if config.descriptives.distance_corr {
+        lines.push(
+            format!(
+                "* Distance correlations: {} permutations.",
+                distance_permutations(config)
+            )
+        );
+    }
+
+    if config.scores.eap && plausible_values(config) > 0 {
+        lines.push(
+            format!("* EAP scores: {} plausible values per case.", plausible_values(config))
+        );
+    }
+
+    lines.push("FACTOR".to_string());
+    lines.push(format!("  /VARIABLES {}", variables));
+
+    if let (Some(value_target), Some(selection)) = (
+        &config.main.value_target,
+        &config.value.selection,
+    ) {
+        lines.push(format!("  /SELECT={}({})", value_target, selection));
+    }
+
+    let missing = if config.options.replace_mean {
+        "MEANSUB"
+    } else if config.options.exclude_pair_wise {
+        "PAIRWISE"
+    } else {
+        "LISTWISE"
+    };
+    lines.push(format!("  /MISSING {}", missing));
+    lines.push(format!("  /ANALYSIS {}", variables));
+
+    let descriptives = &config.descriptives;
+    let mut print = Vec::new();
+    let print_flags = [
+        (descriptives.univar_desc, "UNIVARIATE"),
+        (descriptives.initial_sol, "INITIAL"),
+        (descriptives.coefficient, "CORRELATION"),
+        (descriptives.significance_lvl, "SIG"),
+        (descriptives.determinant, "DET"),
+        (descriptives.kmo, "KMO"),
+        (descriptives.inverse, "INV"),
+        (descriptives.reproduced, "REPR"),
+        (descriptives.anti_image, "AIC"),
+        (config.extraction.unrotated, "EXTRACTION"),
+        (!config.rotation.none && config.rotation.rotated_sol, "ROTATION"),
+        (config.scores.display_factor, "FSCORE"),
+    ];
+    for (enabled, keyword) in print_flags {
+        if enabled {
+            print.push(keyword);
+        }
+    }
+    if !print.is_empty() {
+        lines.push(format!("  /PRINT {}", print.join(" ")));
+    }
+
+    let mut format = Vec::new();
+    if config.options.sort_size {
+        format.push("SORT".to_string());
+    }
+    if config.options.suppress_values {
+        format.push(format!("BLANK({})", format_number(config.options.suppress_values_num)));
+    }
+    if !format.is_empty() {
+        lines.push(format!("  /FORMAT {}", format.join(" ")));
+    }
+
+    let mut plot = Vec::new();
+    if config.extraction.scree {
+        plot.push("EIGEN");
+    }
+    if config.rotation.loading_plot {
+        plot.push("ROTATION");
+    }
+    if !plot.is_empty() {
+        lines.push(format!("  /PLOT {}", plot.join(" ")));
+    }
+
+    let retention = match config.extraction.max_factors {
+        Some(n) if n > 0 => format!("FACTORS({})", n),
+        _ => format!("MINEIGEN({})", format_number(config.extraction.eigen_val)),
+    };
+    lines.push(
+        format!(
+            "  /CRITERIA {} ITERATE({}) ECONVERGE({})",
+            retention,
+            config.extraction.max_iter,
+            format_number(extraction_convergence(config))
+        )
+    );
+    lines.push(format!("  /EXTRACTION {}", extraction_keyword(&config.extraction.method)));
+
+    let rotation = rotation_keyword(config);
+    if rotation == "OBLIMIN" {
+        lines.push(
+            format!(
+                "  /CRITERIA ITERATE({}) RCONVERGE({}) DELTA({})",
+                config.rotation.max_iter,
+                format_number(rotation_convergence(config)),
+                format_number(config.rotation.delta)
+            )
+        );
+    } else if !config.rotation.none {
+        lines.push(
+            format!(
+                "  /CRITERIA ITERATE({}) RCONVERGE({})",
+                config.rotation.max_iter,
+                format_number(rotation_convergence(config))
+            )
+        );
+    }
+    lines.push(format!("  /ROTATION {}", rotation));
+
+    if config.scores.save_var {
+        let method = if config.scores.bartlett {
+            "BART"
+        } else if config.scores.anderson {
+            "AR"
+        } else {
+            "REG"
+        };
+        lines.push(format!("  /SAVE {}(ALL)", method));
+    }
+
+    let method = if analyzes_covariance(config) {
+        "COVARIANCE"
+    } else {
+        "CORRELATION"
+    };
+    lines.push(format!("  /METHOD={}.", method));
+
+    lines.join("\n")
+}
+
+fn extraction_keyword(method: &ExtractionMethod) -> &'static str {
+    match method {
+        ExtractionMethod::PrincipalComponents => "PC",
+        ExtractionMethod::UnweightedLeastSquares => "ULS",
+        ExtractionMethod::GeneralizedLeastSquares => "GLS",
+        ExtractionMethod::MaximumLikelihood => "ML",
+        ExtractionMethod::PrincipalAxisFactoring => "PAF",
+        ExtractionMethod::AlphaFactoring => "ALPHA",
+        ExtractionMethod::ImageFactoring => "IMAGE",
+    }
+}
+
+// Same precedence as rotate_factors
+fn rotation_keyword(config: &FactorAnalysisConfig) -> String {
+    if config.rotation.none {
+        "NOROTATE".to_string()
+    } else if config.rotation.varimax {
+        "VARIMAX".to_string()
+    } else if config.rotation.quartimax {
+        "QUARTIMAX".to_string()
+    } else if config.rotation.equimax {
+        "EQUIMAX".to_string()
+    } else if config.rotation.oblimin {
+        "OBLIMIN".to_string()
+    } else if config.rotation.promax {
+        format!("PROMAX({})", config.rotation.kappa)
+    } else {
+        "VARIMAX".to_string()
+    }
+}
+
+// SPSS-style number: no trailing zeros and no leading zero before the decimal point
+fn format_number(value: f64) -> String {
+    let text = format!("{}", value);
+    if let Some(stripped) = text.strip_prefix("0.") {
+        format!(".{}", stripped)
+    } else if let Some(stripped) = text.strip_prefix("-0.") {
+        format!("-.{}", stripped)
+    } else {
+        text
+    }
+}
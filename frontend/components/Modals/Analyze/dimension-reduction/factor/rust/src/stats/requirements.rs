@@ -0,0 +1,197 @@
+use std::fmt;
+
+use crate::models::{
+    config::{ ExtractionMethod, FactorAnalysisConfig },
+    data::AnalysisData,
+};
+
+use super::core::extract_correlation_matrix;
+
+// Minimum amount of data a statistic needs, naming the requirement and what the data has.
+// Converts into the String errors of the analysis functions, so `?` works on either.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataRequirement {
+    // A correlation or covariance needs a variance, so at least two cases
+    MatrixCases {
+        cases: usize,
+    },
+    // Statistics on the inverse correlation matrix need it to be non-singular, which takes more
+    // cases than variables
+    MoreCasesThanVariables {
+        statistic: &'static str,
+        cases: usize,
+        variables: usize,
+    },
+    // Partial correlations, and so the factorability measures, need at least three variables
+    FactorabilityVariables {
+        statistic: &'static str,
+        variables: usize,
+    },
+    // The standard error 1 / sqrt(n - 3) of Fisher's z needs more than three cases
+    FisherZCases {
+        cases: usize,
+    },
+    // A pooled within-groups matrix has N - G degrees of freedom
+    MoreCasesThanGroups {
+        cases: usize,
+        groups: usize,
+    },
+    // A fitted factor model has ((p - m)^2 - p - m) / 2 degrees of freedom, which may not be
+    // negative
+    ModelDegreesOfFreedom {
+        factors: usize,
+        variables: usize,
+    },
+}
+
+impl fmt::Display for DataRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataRequirement::MatrixCases { cases } => {
+                write!(
+                    f,
+                    "A correlation or covariance matrix requires at least 2 cases (you have {})",
+                    cases
+                )
+            }
+            DataRequirement::MoreCasesThanVariables { statistic, cases, variables } => {
+                write!(
+                    f,
+                    "{} requires more cases than variables (you have {} cases, {} variables)",
+                    statistic,
+                    cases,
+                    variables
+                )
+            }
+            DataRequirement::FactorabilityVariables { statistic, variables } => {
+                write!(
+                    f,
+                    "{} requires at least 3 variables (you have {})",
+                    statistic,
+                    variables
+                )
+            }
+            DataRequirement::FisherZCases { cases } => {
+                write!(
+                    f,
+                    "A Fisher z confidence interval requires at least 4 cases (you have {})",
+                    cases
+                )
+            }
+            DataRequirement::MoreCasesThanGroups { cases, groups } => {
+                write!(
+                    f,
+                    "Pooling within {} groups requires more than {} cases (you have {})",
+                    groups,
+                    groups,
+                    cases
+                )
+            }
+            DataRequirement::ModelDegreesOfFreedom { factors, variables } => {
+                write!(
+                    f,
+                    "{} factors leave negative degrees of freedom for a model of {} variables",
+                    factors,
+                    variables
+                )
+            }
+        }
+    }
+}
+
+impl From<DataRequirement> for String {
+    fn from(requirement: DataRequirement) -> String {
+        requirement.to_string()
+    }
+}
+
+pub fn require_matrix_cases(cases: usize) -> Result<(), DataRequirement> {
+    if cases < 2 {
+        return Err(DataRequirement::MatrixCases { cases });
+    }
+    Ok(())
+}
+
+pub fn require_more_cases_than_variables(
+    statistic: &'static str,
+    cases: usize,
+    variables: usize
+) -> Result<(), DataRequirement> {
+    if cases <= variables {
+        return Err(DataRequirement::MoreCasesThanVariables { statistic, cases, variables });
+    }
+    Ok(())
+}
+
+pub fn require_factorability_variables(
+    statistic: &'static str,
+    variables: usize
+) -> Result<(), DataRequirement> {
+    if variables < 3 {
+        return Err(DataRequirement::FactorabilityVariables { statistic, variables });
+    }
+    Ok(())
+}
+
+pub fn require_fisher_z_cases(cases: usize) -> Result<(), DataRequirement> {
+    if cases <= 3 {
+        return Err(DataRequirement::FisherZCases { cases });
+    }
+    Ok(())
+}
+
+pub fn require_more_cases_than_groups(cases: usize, groups: usize) -> Result<(), DataRequirement> {
+    if cases <= groups {
+        return Err(DataRequirement::MoreCasesThanGroups { cases, groups });
+    }
+    Ok(())
+}
+
+pub fn require_model_degrees_of_freedom(
+    factors: usize,
+    variables: usize
+) -> Result<(), DataRequirement> {
+    if factors >= variables || (variables - factors).pow(2) < variables + factors {
+        return Err(DataRequirement::ModelDegreesOfFreedom { factors, variables });
+    }
+    Ok(())
+}
+
+// Requirements of the configured extraction for a matrix of `variables` from `cases` cases
+pub fn check_extraction_requirements(
+    config: &FactorAnalysisConfig,
+    cases: usize,
+    variables: usize
+) -> Result<(), DataRequirement> {
+    match inverse_weighted_extraction(config) {
+        Some(statistic) => require_more_cases_than_variables(statistic, cases, variables),
+        None => Ok(()),
+    }
+}
+
+// Requirements checked once on the analysis cases, before any stage runs. A matrix that can't
+// be computed at all is left to the stages, which report it themselves.
+pub fn check_analysis_requirements(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<(), String> {
+    if inverse_weighted_extraction(config).is_none() {
+        return Ok(());
+    }
+    let Ok((corr_matrix, _, n_obs)) = extract_correlation_matrix(data, config, "correlation") else {
+        return Ok(());
+    };
+
+    Ok(check_extraction_requirements(config, n_obs, corr_matrix.nrows())?)
+}
+
+// Methods weighting by the inverse correlation matrix fail on a singular one; the others fall
+// back to other starting values
+fn inverse_weighted_extraction(config: &FactorAnalysisConfig) -> Option<&'static str> {
+    match config.extraction.method {
+        ExtractionMethod::MaximumLikelihood => Some("Maximum likelihood extraction"),
+        ExtractionMethod::GeneralizedLeastSquares => Some("Generalized least squares extraction"),
+        ExtractionMethod::ImageFactoring => Some("Image factoring"),
+        _ => None,
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use crate::models::{
+    config::{ DuplicateCellPolicy, FactorAnalysisConfig, MissingCellPolicy },
+    data::{
+        DataRecord,
+        DataValue,
+        LongRecord,
+        VariableAlign,
+        VariableDefinition,
+        VariableMeasure,
+        VariableRole,
+        VariableType,
+    },
+};
+
+use super::core::level_of;
+
+// Pivot long-format cells to one record per case. Cases and variables keep the order in which
+// they first appear, so value target data must list the cases in that order too.
+pub fn pivot_long_data(
+    long_data: &[LongRecord],
+    config: &FactorAnalysisConfig
+) -> Result<(Vec<DataRecord>, Vec<String>), String> {
+    let mut case_ids: Vec<String> = Vec::new();
+    let mut case_index: HashMap<String, usize> = HashMap::new();
+    let mut variables: Vec<String> = Vec::new();
+    let mut cells: Vec<HashMap<String, Vec<DataValue>>> = Vec::new();
+
+    for record in long_data {
+        let case_id = level_of(&record.case_id).ok_or_else(||
+            format!("Missing case id for a value of {}", record.variable)
+        )?;
+        let idx = *case_index.entry(case_id.clone()).or_insert_with(|| {
+            case_ids.push(case_id.clone());
+            cells.push(HashMap::new());
+            case_ids.len() - 1
+        });
+        if !variables.contains(&record.variable) {
+            variables.push(record.variable.clone());
+        }
+        cells[idx].entry(record.variable.clone()).or_default().push(record.value.clone());
+    }
+
+    let policy = &config.options.long_duplicates;
+    let mut records = Vec::with_capacity(cells.len());
+    for (case_id, case_cells) in case_ids.iter().zip(cells) {
+        if case_cells.len() < variables.len() {
+            match config.options.long_missing {
+                MissingCellPolicy::Missing => {}
+                MissingCellPolicy::DropCase => {
+                    continue;
+                }
+                MissingCellPolicy::Error => {
+                    let absent = variables
+                        .iter()
+                        .find(|variable| !case_cells.contains_key(*variable))
+                        .cloned()
+                        .unwrap_or_default();
+                    return Err(format!("Case {} has no value for {}", case_id, absent));
+                }
+            }
+        }
+
+        let mut values = HashMap::with_capacity(case_cells.len());
+        for (variable, cell_values) in case_cells {
+            let value = resolve_duplicates(case_id, &variable, cell_values, policy)?;
+            values.insert(variable, value);
+        }
+        records.push(DataRecord { values });
+    }
+
+    if records.is_empty() {
+        return Err("No cases left after pivoting long-format data".to_string());
+    }
+
+    Ok((records, variables))
+}
+
+// Definitions for pivoted variables when none are supplied: numeric unless a value is text
+pub fn infer_variable_definitions(
+    records: &[DataRecord],
+    variables: &[String]
+) -> Vec<VariableDefinition> {
+    variables
+        .iter()
+        .enumerate()
+        .map(|(column_index, name)| {
+            let numeric = records.iter().all(|record| {
+                !matches!(record.values.get(name), Some(DataValue::Text(_)))
+            });
+
+            VariableDefinition {
+                id: None,
+                column_index,
+                name: name.clone(),
+                r#type: if numeric { VariableType::Numeric } else { VariableType::String },
+                width: 8,
+                decimals: if numeric { 2 } else { 0 },
+                label: None,
+                values: Vec::new(),
+                missing: Vec::new(),
+                columns: 8,
+                align: if numeric { VariableAlign::Right } else { VariableAlign::Left },
+                measure: if numeric { VariableMeasure::Scale } else { VariableMeasure::Nominal },
+                role: VariableRole::Input,
+            }
+        })
+        .collect()
+}
+
+fn resolve_duplicates(
+    case_id: &str,
+    variable: &str,
+    mut values: Vec<DataValue>,
+    policy: &DuplicateCellPolicy
+) -> Result<DataValue, String> {
+    if values.len() == 1 {
+        return Ok(values.remove(0));
+    }
+
+    match policy {
+        DuplicateCellPolicy::Error => {
+            Err(format!("Case {} has {} values for {}", case_id, values.len(), variable))
+        }
+        DuplicateCellPolicy::First => Ok(values.remove(0)),
+        DuplicateCellPolicy::Last => Ok(values.pop().unwrap_or(DataValue::Null)),
+        DuplicateCellPolicy::Mean => {
+            let mut numbers = Vec::with_capacity(values.len());
+            for value in &values {
+                match value {
+                    DataValue::Number(number) => numbers.push(*number),
+                    DataValue::Null => {}
+                    _ => {
+                        return Err(
+                            format!(
+                                "Case {} has non-numeric duplicate values for {}",
+                                case_id,
+                                variable
+                            )
+                        );
+                    }
+                }
+            }
+
+            if numbers.is_empty() {
+                Ok(DataValue::Null)
+            } else {
+                Ok(DataValue::Number(numbers.iter().sum::<f64>() / (numbers.len() as f64)))
+            }
+        }
+    }
+}
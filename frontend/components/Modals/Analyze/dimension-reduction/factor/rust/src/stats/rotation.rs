@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use nalgebra::DMatrix;
 
 use crate::models::{
@@ -13,12 +11,47 @@ use crate::models::{
     },
 };
 
-use super::core::{ calculate_matrix, extract_data_matrix, extract_factors };
+use super::core::{
+    create_component_transformation_matrix,
+    create_rotated_component_matrix,
+    extract_correlation_matrix,
+    extract_factors,
+};
+
+// Largest change of the rotation criterion accepted as converged when Converge is not set
+pub const DEFAULT_ROTATION_CONVERGENCE: f64 = 1e-5;
+
+// Convergence tolerance of the iterative rotations
+pub fn rotation_convergence(config: &FactorAnalysisConfig) -> f64 {
+    config.rotation.converge.unwrap_or(DEFAULT_ROTATION_CONVERGENCE)
+}
 
 // Rotate factors using specified method
 pub fn rotate_factors(
     extraction_result: &ExtractionResult,
     config: &FactorAnalysisConfig
+) -> Result<RotationResult, String> {
+    let _span = crate::trace_span!(
+        "rotate_factors",
+        variables = extraction_result.loadings.nrows(),
+        factors = extraction_result.n_factors
+    );
+    let result = rotate_with_method(extraction_result, config);
+
+    if let Ok(rotation) = &result {
+        crate::trace_event!(
+            "rotation finished",
+            iterations = rotation.iterations,
+            converged = rotation.converged,
+            achieved_tolerance = rotation.achieved_tolerance
+        );
+    }
+    result
+}
+
+fn rotate_with_method(
+    extraction_result: &ExtractionResult,
+    config: &FactorAnalysisConfig
 ) -> Result<RotationResult, String> {
     if config.rotation.none {
         // No rotation, return original loadings
@@ -29,6 +62,9 @@ pub fn rotate_factors(
                 extraction_result.n_factors
             ),
             factor_correlations: None,
+            iterations: 0,
+            converged: true,
+            achieved_tolerance: f64::NAN,
         });
     }
 
@@ -93,8 +129,11 @@ pub fn rotate_varimax(
 
     // Iterative rotation
     let max_iterations = config.rotation.max_iter as usize;
-    let convergence_criterion = 1e-5;
+    let convergence_criterion = rotation_convergence(config);
     let mut prev_criterion = 0.0;
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut achieved_tolerance = f64::NAN;
 
     for iteration in 0..max_iterations {
         // Calculate varimax criterion
@@ -114,10 +153,15 @@ pub fn rotate_varimax(
         criterion /= n_rows as f64;
 
         // Check for convergence
-        if iteration > 0 && (criterion - prev_criterion).abs() < convergence_criterion {
+        if iteration > 0 {
+            achieved_tolerance = (criterion - prev_criterion).abs();
+        }
+        if iteration > 0 && achieved_tolerance < convergence_criterion {
+            converged = true;
             break;
         }
         prev_criterion = criterion;
+        iterations = iteration + 1;
 
         // Perform pair-wise rotations
         for j in 0..n_cols - 1 {
@@ -131,19 +175,22 @@ pub fn rotate_varimax(
                 for i in 0..n_rows {
                     let x = normalized_loadings[(i, j)];
                     let y = normalized_loadings[(i, k)];
+                    let u = x.powi(2) - y.powi(2);
+                    let v = 2.0 * x * y;
 
-                    a += x.powi(2) - y.powi(2);
-                    b += 2.0 * x * y;
-                    c += x.powi(2) - y.powi(2);
-                    d += 2.0 * x * y;
+                    a += u;
+                    b += v;
+                    c += u.powi(2) - v.powi(2);
+                    d += 2.0 * u * v;
                 }
 
                 // Varimax-specific formula
                 let x = d - (2.0 * a * b) / (n_rows as f64);
                 let y = c - (a.powi(2) - b.powi(2)) / (n_rows as f64);
 
-                // Calculate rotation angle
-                let phi = 0.25 * (x / y).atan();
+                // The columns turn by -phi below. atan2 keeps the angle in the right quadrant
+                // and is 0 for a pair already at the optimum, where x and y are both 0.
+                let phi = -0.25 * x.atan2(y);
 
                 if phi.sin().abs() <= 1e-15 {
                     continue; // Skip tiny rotations
@@ -228,6 +275,9 @@ pub fn rotate_varimax(
         rotated_loadings: sorted_loadings,
         transformation_matrix: sorted_transform,
         factor_correlations: None,
+        iterations,
+        converged,
+        achieved_tolerance,
     })
 }
 
@@ -276,8 +326,11 @@ pub fn rotate_quartimax(
 
     // Iterative rotation
     let max_iterations = config.rotation.max_iter as usize;
-    let convergence_criterion = 1e-5;
+    let convergence_criterion = rotation_convergence(config);
     let mut prev_criterion = 0.0;
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut achieved_tolerance = f64::NAN;
 
     for iteration in 0..max_iterations {
         // Calculate quartimax criterion (sum of 4th powers of loadings)
@@ -289,10 +342,15 @@ pub fn rotate_quartimax(
         }
 
         // Check for convergence
-        if iteration > 0 && (criterion - prev_criterion).abs() < convergence_criterion {
+        if iteration > 0 {
+            achieved_tolerance = (criterion - prev_criterion).abs();
+        }
+        if iteration > 0 && achieved_tolerance < convergence_criterion {
+            converged = true;
             break;
         }
         prev_criterion = criterion;
+        iterations = iteration + 1;
 
         // Perform pair-wise rotations
         for j in 0..n_cols - 1 {
@@ -394,6 +452,9 @@ pub fn rotate_quartimax(
         rotated_loadings: sorted_loadings,
         transformation_matrix: sorted_transform,
         factor_correlations: None,
+        iterations,
+        converged,
+        achieved_tolerance,
     })
 }
 
@@ -442,8 +503,11 @@ pub fn rotate_equimax(
 
     // Iterative rotation
     let max_iterations = config.rotation.max_iter as usize;
-    let convergence_criterion = 1e-5;
+    let convergence_criterion = rotation_convergence(config);
     let mut prev_criterion = 0.0;
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut achieved_tolerance = f64::NAN;
 
     for iteration in 0..max_iterations {
         // Calculate equamax criterion (weighted average of varimax and quartimax)
@@ -463,10 +527,15 @@ pub fn rotate_equimax(
         }
 
         // Check for convergence
-        if iteration > 0 && (criterion - prev_criterion).abs() < convergence_criterion {
+        if iteration > 0 {
+            achieved_tolerance = (criterion - prev_criterion).abs();
+        }
+        if iteration > 0 && achieved_tolerance < convergence_criterion {
+            converged = true;
             break;
         }
         prev_criterion = criterion;
+        iterations = iteration + 1;
 
         // Perform pair-wise rotations
         for j in 0..n_cols - 1 {
@@ -480,20 +549,23 @@ pub fn rotate_equimax(
                 for i in 0..n_rows {
                     let x = normalized_loadings[(i, j)];
                     let y = normalized_loadings[(i, k)];
+                    let u = x.powi(2) - y.powi(2);
+                    let v = 2.0 * x * y;
 
-                    a += x.powi(2) - y.powi(2);
-                    b += 2.0 * x * y;
-                    c += x.powi(2) - y.powi(2);
-                    d += 2.0 * x * y;
+                    a += u;
+                    b += v;
+                    c += u.powi(2) - v.powi(2);
+                    d += 2.0 * u * v;
                 }
 
-                // Equamax modification
+                // Orthomax with gamma = m / 2
                 let weight = (n_cols as f64) / 2.0;
-                let x = d - (weight * a * b) / (n_rows as f64);
-                let y = c - (weight * (a.powi(2) - b.powi(2))) / (2.0 * (n_rows as f64));
+                let x = d - (2.0 * weight * a * b) / (n_rows as f64);
+                let y = c - (weight * (a.powi(2) - b.powi(2))) / (n_rows as f64);
 
-                // Calculate rotation angle
-                let phi = 0.25 * (x / y).atan();
+                // The columns turn by -phi below. atan2 keeps the angle in the right quadrant
+                // and is 0 for a pair already at the optimum, where x and y are both 0.
+                let phi = -0.25 * x.atan2(y);
 
                 if phi.sin().abs() <= 1e-15 {
                     continue; // Skip tiny rotations
@@ -578,6 +650,9 @@ pub fn rotate_equimax(
         rotated_loadings: sorted_loadings,
         transformation_matrix: sorted_transform,
         factor_correlations: None,
+        iterations,
+        converged,
+        achieved_tolerance,
     })
 }
 
@@ -670,10 +745,15 @@ pub fn rotate_oblimin(
 
     // Iterative direct oblimin rotation
     let max_iterations = config.rotation.max_iter as usize;
-    let convergence_criterion = 1e-5;
+    let convergence_criterion = rotation_convergence(config);
     let mut prev_criterion = initial_criterion;
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut achieved_tolerance = f64::NAN;
 
     for iteration in 0..max_iterations {
+        iterations = iteration + 1;
+
         // For each pair of factors (p, q)
         for p in 0..n_cols {
             for q in 0..n_cols {
@@ -795,7 +875,11 @@ pub fn rotate_oblimin(
         let h_value = s_squared_sum - (delta / (n_rows as f64)) * d_sum.powi(2);
         let current_criterion = h_value - g_sum;
 
-        if (current_criterion - prev_criterion).abs() < initial_criterion * convergence_criterion {
+        // The tolerance is relative to the criterion of the unrotated solution
+        let change = (current_criterion - prev_criterion).abs();
+        achieved_tolerance = change / initial_criterion.abs();
+        if change < initial_criterion * convergence_criterion {
+            converged = true;
             break;
         }
 
@@ -813,6 +897,9 @@ pub fn rotate_oblimin(
         rotated_loadings,
         transformation_matrix,
         factor_correlations: Some(factor_correlations),
+        iterations,
+        converged,
+        achieved_tolerance,
     })
 }
 
@@ -964,6 +1051,9 @@ pub fn rotate_promax(
         rotated_loadings: sorted_loadings,
         transformation_matrix: sorted_transform,
         factor_correlations: Some(sorted_correlations),
+        iterations: varimax_result.iterations,
+        converged: varimax_result.converged,
+        achieved_tolerance: varimax_result.achieved_tolerance,
     })
 }
 
@@ -971,58 +1061,20 @@ pub fn calculate_rotated_component_matrix(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<RotatedComponentMatrix, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
     let rotation_result = rotate_factors(&extraction_result, config)?;
 
-    let mut components = HashMap::new();
-    let rotated_loadings = &rotation_result.rotated_loadings;
-    let n_rows = rotated_loadings.nrows();
-    let n_cols = rotated_loadings.ncols();
-
-    for (i, var_name) in var_names.iter().enumerate() {
-        if i < n_rows {
-            let mut loadings = Vec::with_capacity(n_cols);
-
-            for j in 0..n_cols {
-                loadings.push(rotated_loadings[(i, j)]);
-            }
-
-            components.insert(var_name.clone(), loadings);
-        }
-    }
-
-    Ok(RotatedComponentMatrix {
-        components,
-    })
+    Ok(create_rotated_component_matrix(&rotation_result, &var_names))
 }
 
 pub fn calculate_component_transformation_matrix(
     data: &AnalysisData,
     config: &FactorAnalysisConfig
 ) -> Result<ComponentTransformationMatrix, String> {
-    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
-    let corr_matrix = calculate_matrix(&data_matrix, "correlation")?;
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
     let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
     let rotation_result = rotate_factors(&extraction_result, config)?;
 
-    // Create component transformation matrix directly
-    let transformation_matrix = &rotation_result.transformation_matrix;
-    let n_rows = transformation_matrix.nrows();
-    let n_cols = transformation_matrix.ncols();
-
-    let mut components = Vec::with_capacity(n_rows);
-
-    for i in 0..n_rows {
-        let mut row = Vec::with_capacity(n_cols);
-
-        for j in 0..n_cols {
-            row.push(transformation_matrix[(i, j)]);
-        }
-
-        components.push(row);
-    }
-
-    Ok(ComponentTransformationMatrix { components })
+    Ok(create_component_transformation_matrix(&rotation_result))
 }
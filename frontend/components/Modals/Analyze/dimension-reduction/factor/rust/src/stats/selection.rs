@@ -0,0 +1,97 @@
+use regex::Regex;
+
+use crate::models::data::AnalysisData;
+
+// Entry forms of TargetVar besides plain names and column indices:
+//   item_*        glob, with * for any run of characters and ? for a single character
+//   /^q\d+$/      regular expression between slashes
+//   q1 to q20     range of consecutive variables in the data, as with TO in SPSS syntax
+pub fn expand_variable_entry(entry: &str, available: &[String]) -> Result<Vec<String>, String> {
+    let entry = entry.trim();
+
+    if let Some(pattern) = entry.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+        let regex = Regex::new(pattern).map_err(|e|
+            format!("Invalid variable pattern {}: {}", entry, e)
+        )?;
+        return matching_variables(entry, available, |name| regex.is_match(name));
+    }
+
+    if entry.contains('*') || entry.contains('?') {
+        let regex = Regex::new(&glob_to_regex(entry)).map_err(|e|
+            format!("Invalid variable pattern {}: {}", entry, e)
+        )?;
+        return matching_variables(entry, available, |name| regex.is_match(name));
+    }
+
+    if let Some((start, end)) = split_range(entry) {
+        let position = |name: &str| {
+            available
+                .iter()
+                .position(|available_name| available_name == name)
+                .ok_or_else(|| format!("Variable {} in range {} not found in the data", name, entry))
+        };
+        let (start_idx, end_idx) = (position(start)?, position(end)?);
+        if start_idx > end_idx {
+            return Err(
+                format!("Variable range {} is reversed: {} comes after {}", entry, start, end)
+            );
+        }
+        return Ok(available[start_idx..=end_idx].to_vec());
+    }
+
+    Ok(vec![entry.to_string()])
+}
+
+// Variable names in data order, for resolving patterns and ranges
+pub fn available_variable_names(data: &AnalysisData) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for var_def in data.target_data_defs.iter().flatten() {
+        if !names.contains(&var_def.name) {
+            names.push(var_def.name.clone());
+        }
+    }
+    names
+}
+
+fn matching_variables<F>(
+    entry: &str,
+    available: &[String],
+    is_match: F
+) -> Result<Vec<String>, String>
+    where F: Fn(&str) -> bool
+{
+    let matches: Vec<String> = available
+        .iter()
+        .filter(|name| is_match(name))
+        .cloned()
+        .collect();
+
+    if matches.is_empty() {
+        return Err(format!("Variable pattern {} matches no variables", entry));
+    }
+
+    Ok(matches)
+}
+
+// Anchored regular expression equivalent to a glob
+fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
+
+// "start to end", with the keyword in any case
+fn split_range(entry: &str) -> Option<(&str, &str)> {
+    let words: Vec<&str> = entry.split_whitespace().collect();
+    match words.as_slice() {
+        [start, keyword, end] if keyword.eq_ignore_ascii_case("to") => Some((start, end)),
+        _ => None,
+    }
+}
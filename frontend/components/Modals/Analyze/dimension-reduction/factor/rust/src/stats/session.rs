@@ -0,0 +1,344 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ AnalysisSession, ExtractionResult, SessionExtraction },
+};
+
+use super::core::{
+    analyzes_covariance,
+    calculate_column_statistics,
+    calculate_matrix,
+    calculate_pairwise_matrix,
+    check_extraction_requirements,
+    extract_correlation_matrix,
+    extract_data_matrix,
+    extract_factors,
+    extract_raw_data_matrix,
+    min_pairwise_n,
+    pairwise_analysis_n,
+    uses_pairwise_deletion,
+};
+
+// Bumped whenever the serialized session layout changes
+pub const SESSION_FORMAT_VERSION: u32 = 1;
+
+// Capture everything needed to redo rotation and scores without the raw data
+pub fn create_analysis_session(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<AnalysisSession, String> {
+    if config.main.within_groups_var.is_some() {
+        return build_within_groups_session(data, config);
+    }
+    if config.extraction.polychoric {
+        return build_polychoric_session(data, config);
+    }
+    if uses_pairwise_deletion(config) {
+        let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+        return build_pairwise_session(&raw_matrix, var_names, config);
+    }
+
+    let (data_matrix, var_names) = extract_data_matrix(data, config)?;
+
+    build_analysis_session(&data_matrix, var_names, config)
+}
+
+// Session from pairwise correlations; means and standard deviations use every available value
+pub fn build_pairwise_session(
+    raw_matrix: &DMatrix<f64>,
+    var_names: Vec<String>,
+    config: &FactorAnalysisConfig
+) -> Result<AnalysisSession, String> {
+    let pairwise = calculate_pairwise_matrix(raw_matrix, "correlation", min_pairwise_n(config))?;
+    let n_obs = pairwise_analysis_n(&pairwise, &config.options.pairwise_n);
+
+    let (means, std_deviations): (Vec<f64>, Vec<f64>) = raw_matrix
+        .column_iter()
+        .map(|column| {
+            let values: Vec<f64> = column
+                .iter()
+                .copied()
+                .filter(|value| !value.is_nan())
+                .collect();
+            let n = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let variance = values
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>() / (n - 1.0);
+            (mean, variance.sqrt())
+        })
+        .unzip();
+
+    build_session_from_statistics(
+        pairwise.matrix,
+        var_names,
+        n_obs,
+        means,
+        std_deviations,
+        config
+    )
+}
+
+// Session from the pooled within-groups matrix. Scores standardize on the grand means and the
+// pooled within-groups standard deviations.
+fn build_within_groups_session(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<AnalysisSession, String> {
+    let (corr_matrix, var_names, n_obs) = extract_correlation_matrix(data, config, "correlation")?;
+    let (cov_matrix, _, _) = extract_correlation_matrix(data, config, "covariance")?;
+    let std_deviations = cov_matrix
+        .diagonal()
+        .iter()
+        .map(|variance| variance.sqrt())
+        .collect();
+
+    let means = if uses_pairwise_deletion(config) {
+        let (raw_matrix, _) = extract_raw_data_matrix(data, config)?;
+        raw_matrix
+            .column_iter()
+            .map(|column| {
+                let values: Vec<f64> = column
+                    .iter()
+                    .copied()
+                    .filter(|value| !value.is_nan())
+                    .collect();
+                values.iter().sum::<f64>() / (values.len() as f64)
+            })
+            .collect()
+    } else {
+        let (data_matrix, _) = extract_data_matrix(data, config)?;
+        calculate_column_statistics(&data_matrix).0
+    };
+
+    build_session_from_statistics(corr_matrix, var_names, n_obs, means, std_deviations, config)
+}
+
+// Session from the polychoric matrix of the ordinal levels. Scores standardize on the means and
+// standard deviations of the level values.
+fn build_polychoric_session(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<AnalysisSession, String> {
+    let (corr_matrix, var_names, n_obs) = extract_correlation_matrix(data, config, "correlation")?;
+    let (data_matrix, _) = extract_data_matrix(data, config)?;
+    let (means, std_deviations) = calculate_column_statistics(&data_matrix);
+
+    build_session_from_statistics(corr_matrix, var_names, n_obs, means, std_deviations, config)
+}
+
+pub fn build_analysis_session(
+    data_matrix: &DMatrix<f64>,
+    var_names: Vec<String>,
+    config: &FactorAnalysisConfig
+) -> Result<AnalysisSession, String> {
+    require_pearson_session(config)?;
+    let corr_matrix = calculate_matrix(data_matrix, "correlation")?;
+    let (means, std_deviations) = calculate_column_statistics(data_matrix);
+
+    build_session_from_statistics(
+        corr_matrix,
+        var_names,
+        data_matrix.nrows(),
+        means,
+        std_deviations,
+        config
+    )
+}
+
+// Sessions built from a data matrix or running sums only have Pearson correlations; polychoric
+// ones need the ordinal levels of the cases
+pub fn require_pearson_session(config: &FactorAnalysisConfig) -> Result<(), String> {
+    if config.extraction.polychoric {
+        return Err(
+            "Polychoric correlations need the ordinal levels of every case, which this analysis \
+             path doesn't keep".to_string()
+        );
+    }
+    Ok(())
+}
+
+// Session from precomputed sufficient statistics, e.g. when the cases were streamed
+pub fn build_session_from_statistics(
+    corr_matrix: DMatrix<f64>,
+    var_names: Vec<String>,
+    n_cases: usize,
+    means: Vec<f64>,
+    std_deviations: Vec<f64>,
+    config: &FactorAnalysisConfig
+) -> Result<AnalysisSession, String> {
+    check_extraction_requirements(config, n_cases, var_names.len())?;
+    let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
+
+    Ok(AnalysisSession {
+        format_version: SESSION_FORMAT_VERSION,
+        library_version: env!("CARGO_PKG_VERSION").to_string(),
+        config: config.clone(),
+        var_names,
+        n_cases,
+        means,
+        std_deviations,
+        correlation_matrix: matrix_to_rows(&corr_matrix),
+        extraction: SessionExtraction {
+            loadings: matrix_to_rows(&extraction_result.loadings),
+            eigenvalues: extraction_result.eigenvalues,
+            communalities: extraction_result.communalities,
+            explained_variance: extraction_result.explained_variance,
+            cumulative_variance: extraction_result.cumulative_variance,
+            n_factors: extraction_result.n_factors,
+            iterations: extraction_result.iterations,
+            converged: extraction_result.converged,
+            achieved_tolerance: extraction_result.achieved_tolerance
+                .is_finite()
+                .then_some(extraction_result.achieved_tolerance),
+        },
+    })
+}
+
+pub fn session_to_json(session: &AnalysisSession) -> Result<String, String> {
+    serde_json::to_string(session).map_err(|e| format!("Failed to serialize session: {}", e))
+}
+
+pub fn session_from_json(json: &str) -> Result<AnalysisSession, String> {
+    let session: AnalysisSession = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse session: {}", e))?;
+
+    if session.format_version != SESSION_FORMAT_VERSION {
+        return Err(
+            format!(
+                "Unsupported session format version {} (expected {})",
+                session.format_version,
+                SESSION_FORMAT_VERSION
+            )
+        );
+    }
+
+    let n_vars = session.var_names.len();
+    if
+        session.correlation_matrix.len() != n_vars ||
+        session.correlation_matrix.iter().any(|row| row.len() != n_vars)
+    {
+        return Err("Session correlation matrix doesn't match its variable list".to_string());
+    }
+    if
+        session.extraction.loadings.len() != n_vars ||
+        session.extraction.loadings.iter().any(|row| row.len() != session.extraction.n_factors)
+    {
+        return Err("Session loadings don't match its variable list".to_string());
+    }
+    if session.means.len() != n_vars || session.std_deviations.len() != n_vars {
+        return Err("Session descriptive statistics don't match its variable list".to_string());
+    }
+
+    Ok(session)
+}
+
+// Reject configurations that would change the stored correlation matrix or extraction
+pub fn check_session_compatibility(
+    session: &AnalysisSession,
+    config: &FactorAnalysisConfig
+) -> Result<(), String> {
+    let stored = &session.config;
+    let mut changed = Vec::new();
+
+    if stored.main.target_var != config.main.target_var {
+        changed.push("target variables");
+    }
+    if stored.main.categorical_vars != config.main.categorical_vars {
+        changed.push("categorical variable coding");
+    }
+    if stored.main.within_groups_var != config.main.within_groups_var {
+        changed.push("within-groups variable");
+    }
+    if
+        stored.main.value_target != config.main.value_target ||
+        stored.value.selection != config.value.selection
+    {
+        changed.push("case selection");
+    }
+    if
+        stored.options.exclude_list_wise != config.options.exclude_list_wise ||
+        stored.options.exclude_pair_wise != config.options.exclude_pair_wise ||
+        stored.options.replace_mean != config.options.replace_mean ||
+        stored.options.min_pairwise_n != config.options.min_pairwise_n ||
+        stored.options.pairwise_n != config.options.pairwise_n
+    {
+        changed.push("missing value handling");
+    }
+    if stored.extraction.polychoric != config.extraction.polychoric {
+        changed.push("correlation type");
+    } else if
+        config.extraction.polychoric &&
+        (stored.extraction.polychoric_converge != config.extraction.polychoric_converge ||
+            stored.extraction.polychoric_max_iter != config.extraction.polychoric_max_iter)
+    {
+        changed.push("polychoric search settings");
+    }
+    if analyzes_covariance(stored) != analyzes_covariance(config) {
+        changed.push("analyzed matrix");
+    }
+    if
+        stored.extraction.method != config.extraction.method ||
+        stored.extraction.eigen_val != config.extraction.eigen_val ||
+        stored.extraction.max_factors != config.extraction.max_factors ||
+        stored.extraction.max_iter != config.extraction.max_iter ||
+        stored.extraction.converge != config.extraction.converge
+    {
+        changed.push("extraction settings");
+    }
+
+    if changed.is_empty() {
+        Ok(())
+    } else {
+        Err(
+            format!(
+                "Session can't be reused because the {} changed; rerun on the raw data",
+                changed.join(", ")
+            )
+        )
+    }
+}
+
+pub fn session_correlation_matrix(session: &AnalysisSession) -> DMatrix<f64> {
+    rows_to_matrix(&session.correlation_matrix, session.var_names.len())
+}
+
+// Covariance matrix recovered from the stored correlations and standard deviations
+pub fn session_covariance_matrix(session: &AnalysisSession) -> DMatrix<f64> {
+    let corr_matrix = session_correlation_matrix(session);
+    let sd = &session.std_deviations;
+
+    DMatrix::from_fn(corr_matrix.nrows(), corr_matrix.ncols(), |i, j| {
+        corr_matrix[(i, j)] * sd[i] * sd[j]
+    })
+}
+
+pub fn session_extraction_result(session: &AnalysisSession) -> ExtractionResult {
+    let extraction = &session.extraction;
+
+    ExtractionResult {
+        loadings: rows_to_matrix(&extraction.loadings, extraction.n_factors),
+        eigenvalues: extraction.eigenvalues.clone(),
+        communalities: extraction.communalities.clone(),
+        explained_variance: extraction.explained_variance.clone(),
+        cumulative_variance: extraction.cumulative_variance.clone(),
+        n_factors: extraction.n_factors,
+        var_names: session.var_names.clone(),
+        iterations: extraction.iterations,
+        converged: extraction.converged,
+        achieved_tolerance: extraction.achieved_tolerance.unwrap_or(f64::NAN),
+    }
+}
+
+pub fn matrix_to_rows(matrix: &DMatrix<f64>) -> Vec<Vec<f64>> {
+    (0..matrix.nrows())
+        .map(|i| (0..matrix.ncols()).map(|j| matrix[(i, j)]).collect())
+        .collect()
+}
+
+fn rows_to_matrix(rows: &[Vec<f64>], n_cols: usize) -> DMatrix<f64> {
+    DMatrix::from_fn(rows.len(), n_cols, |i, j| rows[i][j])
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use statrs::distribution::{ ContinuousCDF, Normal };
+
+use crate::models::{
+    config::SimulationConfig,
+    data::{ AnalysisData, DataRecord, DataValue, VariableMeasure },
+    result::FactorModel,
+};
+use crate::utils::rng::{ create_rng, AnalysisRng, DEFAULT_SEED };
+
+use super::core::infer_variable_definitions;
+
+// Data set drawn from the factor model of the specification, ready for the analysis functions
+pub fn simulate_factor_data(spec: &SimulationConfig) -> Result<AnalysisData, String> {
+    let model = build_factor_model(spec)?;
+    let n_vars = model.loadings.nrows();
+    let var_names = match &spec.variable_names {
+        Some(names) if names.len() == n_vars => names.clone(),
+        Some(names) => {
+            return Err(
+                format!("{} variable names given for {} variables", names.len(), n_vars)
+            );
+        }
+        None => (1..=n_vars).map(|j| format!("x{}", j)).collect(),
+    };
+
+    let mut rng = create_rng(spec.seed.unwrap_or(DEFAULT_SEED));
+    let mut matrix = simulate_matrix(&model, spec.cases, &mut rng)?;
+    let thresholds = simulation_thresholds(spec)?;
+    if let Some(thresholds) = &thresholds {
+        matrix = categorize(&matrix, &model, thresholds);
+    }
+
+    let records: Vec<DataRecord> = matrix
+        .row_iter()
+        .map(|row| DataRecord {
+            values: var_names
+                .iter()
+                .zip(row.iter())
+                .map(|(name, value)| (name.clone(), DataValue::Number(*value)))
+                .collect::<HashMap<_, _>>(),
+        })
+        .collect();
+    let mut defs = infer_variable_definitions(&records, &var_names);
+    if thresholds.is_some() {
+        for def in &mut defs {
+            def.measure = VariableMeasure::Ordinal;
+            def.decimals = 0;
+        }
+    }
+
+    Ok(AnalysisData {
+        target_data: vec![records],
+        value_target_data: Vec::new(),
+        target_data_defs: vec![defs],
+        value_target_data_defs: Vec::new(),
+    })
+}
+
+// Loadings, factor correlations and uniquenesses of a specification, checked for consistency.
+// Uniquenesses default to one minus the communalities, giving variables of unit variance.
+pub fn build_factor_model(spec: &SimulationConfig) -> Result<FactorModel, String> {
+    let n_vars = spec.loadings.len();
+    let n_factors = spec.loadings.first().map_or(0, |row| row.len());
+    if n_vars == 0 {
+        return Err("The loading matrix has no variables".to_string());
+    }
+    if spec.loadings.iter().any(|row| row.len() != n_factors) {
+        return Err("Every variable needs the same number of loadings".to_string());
+    }
+    let loadings = DMatrix::from_fn(n_vars, n_factors, |i, k| spec.loadings[i][k]);
+
+    let factor_correlations = match &spec.factor_correlations {
+        Some(rows) => {
+            if rows.len() != n_factors || rows.iter().any(|row| row.len() != n_factors) {
+                return Err(
+                    format!("Factor correlations must be a {0} x {0} matrix", n_factors)
+                );
+            }
+            let phi = DMatrix::from_fn(n_factors, n_factors, |i, j| rows[i][j]);
+            if (0..n_factors).any(|k| (phi[(k, k)] - 1.0).abs() > 1e-9) {
+                return Err("Factor correlations need ones on the diagonal".to_string());
+            }
+            if (&phi - phi.transpose()).amax() > 1e-9 {
+                return Err("Factor correlations must be symmetric".to_string());
+            }
+            phi
+        }
+        None => DMatrix::identity(n_factors, n_factors),
+    };
+
+    let communalities: Vec<f64> = (0..n_vars)
+        .map(|i| (loadings.row(i) * &factor_correlations * loadings.row(i).transpose())[(0, 0)])
+        .collect();
+    let uniquenesses = match &spec.uniquenesses {
+        Some(values) if values.len() != n_vars => {
+            return Err(format!("{} uniquenesses given for {} variables", values.len(), n_vars));
+        }
+        Some(values) => values.clone(),
+        None => communalities.iter().map(|h2| 1.0 - h2).collect(),
+    };
+    if let Some(i) = uniquenesses.iter().position(|value| !(value.is_finite() && *value >= 0.0)) {
+        return Err(
+            format!(
+                "Variable {} has a negative uniqueness; its communality is {:.3}",
+                i + 1,
+                communalities[i]
+            )
+        );
+    }
+
+    Ok(FactorModel { loadings, factor_correlations, uniquenesses })
+}
+
+// Model without common factors: uncorrelated standard normal variables
+pub fn independent_factor_model(n_vars: usize) -> FactorModel {
+    FactorModel {
+        loadings: DMatrix::zeros(n_vars, 0),
+        factor_correlations: DMatrix::zeros(0, 0),
+        uniquenesses: vec![1.0; n_vars],
+    }
+}
+
+// Cases x variables draw of F L' + E with F ~ N(0, Phi) and E ~ N(0, diag(uniquenesses)).
+// Factor scores are drawn before the unique parts, each column by column.
+pub fn simulate_matrix(
+    model: &FactorModel,
+    n_cases: usize,
+    rng: &mut AnalysisRng
+) -> Result<DMatrix<f64>, String> {
+    let n_vars = model.loadings.nrows();
+    let n_factors = model.loadings.ncols();
+
+    let common = if n_factors > 0 {
+        let cholesky = model.factor_correlations
+            .clone()
+            .cholesky()
+            .ok_or("Factor correlations are not positive definite".to_string())?;
+        let scores = DMatrix::from_fn(n_cases, n_factors, |_, _| {
+            rng.sample::<f64, _>(StandardNormal)
+        });
+        scores * cholesky.l().transpose() * model.loadings.transpose()
+    } else {
+        DMatrix::zeros(n_cases, n_vars)
+    };
+
+    let unique_sd: Vec<f64> = model.uniquenesses
+        .iter()
+        .map(|value| value.sqrt())
+        .collect();
+    let unique = DMatrix::from_fn(n_cases, n_vars, |_, j| {
+        rng.sample::<f64, _>(StandardNormal) * unique_sd[j]
+    });
+
+    Ok(common + unique)
+}
+
+// Ordered categories 1..K from cut points on the standardized scale of each variable
+pub fn categorize(matrix: &DMatrix<f64>, model: &FactorModel, thresholds: &[f64]) -> DMatrix<f64> {
+    let sd: Vec<f64> = (0..model.loadings.nrows())
+        .map(|j| {
+            let row = model.loadings.row(j);
+            let common = (row * &model.factor_correlations * row.transpose())
+                .get((0, 0))
+                .copied()
+                .unwrap_or(0.0);
+            (common + model.uniquenesses[j]).sqrt()
+        })
+        .collect();
+
+    DMatrix::from_fn(matrix.nrows(), matrix.ncols(), |i, j| {
+        let z = if sd[j] > 0.0 { matrix[(i, j)] / sd[j] } else { 0.0 };
+        (thresholds.iter().filter(|threshold| z > **threshold).count() + 1) as f64
+    })
+}
+
+// Cut points from Thresholds, or equal-probability cut points for Categories; None for
+// continuous data
+pub fn simulation_thresholds(spec: &SimulationConfig) -> Result<Option<Vec<f64>>, String> {
+    if let Some(thresholds) = &spec.thresholds {
+        if thresholds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err("Thresholds must be strictly increasing".to_string());
+        }
+        return Ok(Some(thresholds.clone()));
+    }
+
+    match spec.categories {
+        Some(categories) if categories >= 2 => {
+            let normal = Normal::new(0.0, 1.0).map_err(|e| e.to_string())?;
+            Ok(
+                Some(
+                    (1..categories)
+                        .map(|k| normal.inverse_cdf((k as f64) / (categories as f64)))
+                        .collect()
+                )
+            )
+        }
+        Some(_) => Err("Ordinal data needs at least 2 categories".to_string()),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,387 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::{ AlignmentMethod, ExtractionMethod, FactorAnalysisConfig },
+    result::{ ValidationCase, ValidationCheck, ValidationReport },
+};
+
+use super::core::{
+    alignment_transformation,
+    build_kmo_bartletts_test,
+    build_total_variance_explained,
+    extract_factors,
+    rotate_factors,
+};
+
+// Largest accepted deviations: the printed precision of SPSS and R for each statistic, the
+// three decimals of a loading, communality or eigenvalue and two of a chi-square
+const LOADING_TOLERANCE: f64 = 0.001;
+const EIGENVALUE_TOLERANCE: f64 = 0.001;
+const KMO_TOLERANCE: f64 = 0.001;
+const CHI_SQUARE_TOLERANCE: f64 = 0.01;
+
+// Published correlation matrix, or that of an exactly fitting factor model, with the values a
+// reference package gives for it
+struct ReferenceCase {
+    name: &'static str,
+    source: &'static str,
+    matrix: ReferenceMatrix,
+    n: usize,
+    method: ExtractionMethod,
+    varimax: bool,
+    expected: ReferenceValues,
+}
+
+enum ReferenceMatrix {
+    // Population loadings, one row per variable; the matrix is LL' with a unit diagonal
+    ExactFit(&'static [&'static [f64]]),
+    // Lower triangle with the diagonal, row by row
+    Correlations(&'static [&'static [f64]]),
+}
+
+struct ReferenceValues {
+    eigenvalues: &'static [f64],
+    kmo: f64,
+    bartlett_chi_square: f64,
+    bartlett_df: usize,
+    communalities: &'static [f64],
+    loadings: &'static [&'static [f64]],
+    rotated_loadings: Option<&'static [&'static [f64]]>,
+}
+
+// Harman (1976), Table 2.3: eight physical measurements of 305 girls, R's datasets::Harman23.cor
+const HARMAN_PHYSICAL: &[&[f64]] = &[
+    &[1.0],
+    &[0.846, 1.0],
+    &[0.805, 0.881, 1.0],
+    &[0.859, 0.826, 0.801, 1.0],
+    &[0.473, 0.376, 0.38, 0.436, 1.0],
+    &[0.398, 0.326, 0.319, 0.329, 0.762, 1.0],
+    &[0.301, 0.277, 0.237, 0.327, 0.73, 0.583, 1.0],
+    &[0.382, 0.415, 0.345, 0.365, 0.629, 0.577, 0.539, 1.0],
+];
+const HARMAN_EIGENVALUES: &[f64] = &[4.673, 1.771, 0.481, 0.421, 0.233, 0.187, 0.137, 0.096];
+const HARMAN_KMO: f64 = 0.845;
+const HARMAN_BARTLETT_CHI_SQUARE: f64 = 2085.74;
+
+const ONE_FACTOR: &[&[f64]] = &[&[0.7], &[0.7], &[0.7], &[0.7], &[0.7], &[0.7]];
+const TWO_CLUSTERS: &[&[f64]] = &[
+    &[0.8, 0.0],
+    &[0.8, 0.0],
+    &[0.8, 0.0],
+    &[0.0, 0.6],
+    &[0.0, 0.6],
+    &[0.0, 0.6],
+];
+// sqrt(3.45 / 6), the first eigenvector scaled by the root of its eigenvalue
+const ONE_COMPONENT: &[&[f64]] = &[
+    &[0.758288],
+    &[0.758288],
+    &[0.758288],
+    &[0.758288],
+    &[0.758288],
+    &[0.758288],
+];
+const ONE_FACTOR_EIGENVALUES: &[f64] = &[3.45, 0.51, 0.51, 0.51, 0.51, 0.51];
+
+// Expected values of the exact-fit cases are closed-form: eigenvalues 1 + (p - 1)r and 1 - r of
+// an equicorrelation block, partial correlations r / (1 + (p - 2)r) within it, and Bartlett's
+// -(n - 1 - (2p + 5) / 6) ln|R|
+const REFERENCE_CASES: &[ReferenceCase] = &[
+    ReferenceCase {
+        name: "One factor, principal components",
+        source: "Equicorrelation r = .49 (loadings .70), N = 200; closed form",
+        matrix: ReferenceMatrix::ExactFit(ONE_FACTOR),
+        n: 200,
+        method: ExtractionMethod::PrincipalComponents,
+        varimax: false,
+        expected: ReferenceValues {
+            eigenvalues: ONE_FACTOR_EIGENVALUES,
+            kmo: 0.897558,
+            bartlett_chi_square: 417.511038,
+            bartlett_df: 15,
+            communalities: &[0.575, 0.575, 0.575, 0.575, 0.575, 0.575],
+            loadings: ONE_COMPONENT,
+            rotated_loadings: None,
+        },
+    },
+    ReferenceCase {
+        name: "One factor, maximum likelihood",
+        source: "Equicorrelation r = .49 (loadings .70), N = 200; exact fit",
+        matrix: ReferenceMatrix::ExactFit(ONE_FACTOR),
+        n: 200,
+        method: ExtractionMethod::MaximumLikelihood,
+        varimax: false,
+        expected: ReferenceValues {
+            eigenvalues: ONE_FACTOR_EIGENVALUES,
+            kmo: 0.897558,
+            bartlett_chi_square: 417.511038,
+            bartlett_df: 15,
+            communalities: &[0.49, 0.49, 0.49, 0.49, 0.49, 0.49],
+            loadings: ONE_FACTOR,
+            rotated_loadings: None,
+        },
+    },
+    ReferenceCase {
+        name: "Two independent clusters, maximum likelihood with varimax",
+        source: "Blocks r = .64 and r = .36 (loadings .80 and .60), N = 300; exact fit",
+        matrix: ReferenceMatrix::ExactFit(TWO_CLUSTERS),
+        n: 300,
+        method: ExtractionMethod::MaximumLikelihood,
+        varimax: true,
+        expected: ReferenceValues {
+            eigenvalues: &[2.28, 1.72, 0.64, 0.64, 0.36, 0.36],
+            kmo: 0.708021,
+            bartlett_chi_square: 464.797145,
+            bartlett_df: 15,
+            communalities: &[0.64, 0.64, 0.64, 0.36, 0.36, 0.36],
+            loadings: TWO_CLUSTERS,
+            rotated_loadings: Some(TWO_CLUSTERS),
+        },
+    },
+    ReferenceCase {
+        name: "Harman's eight physical variables, principal components with varimax",
+        source: "Harman (1976) Table 2.3, N = 305; two components as in psych::principal",
+        matrix: ReferenceMatrix::Correlations(HARMAN_PHYSICAL),
+        n: 305,
+        method: ExtractionMethod::PrincipalComponents,
+        varimax: true,
+        expected: ReferenceValues {
+            eigenvalues: HARMAN_EIGENVALUES,
+            kmo: HARMAN_KMO,
+            bartlett_chi_square: HARMAN_BARTLETT_CHI_SQUARE,
+            bartlett_df: 28,
+            communalities: &[0.877, 0.903, 0.872, 0.861, 0.85, 0.739, 0.717, 0.625],
+            loadings: &[
+                &[0.859, -0.372],
+                &[0.842, -0.441],
+                &[0.813, -0.459],
+                &[0.84, -0.395],
+                &[0.758, 0.525],
+                &[0.674, 0.533],
+                &[0.617, 0.58],
+                &[0.671, 0.418],
+            ],
+            rotated_loadings: Some(
+                &[
+                    &[0.9, 0.26],
+                    &[0.93, 0.195],
+                    &[0.919, 0.164],
+                    &[0.899, 0.229],
+                    &[0.251, 0.887],
+                    &[0.181, 0.84],
+                    &[0.107, 0.84],
+                    &[0.251, 0.75],
+                ]
+            ),
+        },
+    },
+    ReferenceCase {
+        name: "Harman's eight physical variables, maximum likelihood with varimax",
+        source: "Harman (1976) Table 2.3, N = 305; uniquenesses as printed by factanal, 2 factors",
+        matrix: ReferenceMatrix::Correlations(HARMAN_PHYSICAL),
+        n: 305,
+        method: ExtractionMethod::MaximumLikelihood,
+        varimax: true,
+        expected: ReferenceValues {
+            eigenvalues: HARMAN_EIGENVALUES,
+            kmo: HARMAN_KMO,
+            bartlett_chi_square: HARMAN_BARTLETT_CHI_SQUARE,
+            bartlett_df: 28,
+            // 1 - uniquenesses .170 .107 .166 .199 .089 .364 .416 .537
+            communalities: &[0.83, 0.893, 0.834, 0.801, 0.911, 0.636, 0.584, 0.463],
+            loadings: &[
+                &[0.88, -0.237],
+                &[0.874, -0.36],
+                &[0.846, -0.344],
+                &[0.855, -0.263],
+                &[0.705, 0.644],
+                &[0.589, 0.538],
+                &[0.526, 0.554],
+                &[0.574, 0.365],
+            ],
+            rotated_loadings: Some(
+                &[
+                    &[0.863, 0.293],
+                    &[0.926, 0.187],
+                    &[0.894, 0.185],
+                    &[0.857, 0.258],
+                    &[0.227, 0.927],
+                    &[0.189, 0.775],
+                    &[0.129, 0.753],
+                    &[0.273, 0.623],
+                ]
+            ),
+        },
+    },
+];
+
+// Runs every embedded reference case with the iteration and convergence settings of the
+// configuration and reports the largest deviation from each expected statistic
+pub fn run_validation(config: &FactorAnalysisConfig) -> ValidationReport {
+    let cases: Vec<ValidationCase> = REFERENCE_CASES.iter()
+        .map(|case| validate_case(case, config))
+        .collect();
+
+    ValidationReport {
+        passed: cases.iter().all(|case| case.passed),
+        cases,
+    }
+}
+
+fn validate_case(case: &ReferenceCase, config: &FactorAnalysisConfig) -> ValidationCase {
+    let mut validation = ValidationCase {
+        name: case.name.to_string(),
+        source: case.source.to_string(),
+        passed: false,
+        checks: Vec::new(),
+        error: None,
+    };
+    match check_case(case, config) {
+        Ok(checks) => {
+            validation.passed = checks.iter().all(|check| check.passed);
+            validation.checks = checks;
+        }
+        Err(e) => {
+            validation.error = Some(e);
+        }
+    }
+    validation
+}
+
+fn check_case(
+    case: &ReferenceCase,
+    config: &FactorAnalysisConfig
+) -> Result<Vec<ValidationCheck>, String> {
+    let corr_matrix = reference_matrix(&case.matrix);
+    let n_factors = case.expected.loadings.first().map_or(0, |row| row.len());
+    let var_names: Vec<String> = (1..=corr_matrix.nrows()).map(|i| format!("V{}", i)).collect();
+
+    let mut case_config = config.clone();
+    case_config.extraction.method = case.method.clone();
+    case_config.extraction.eigen = false;
+    case_config.extraction.factor = true;
+    case_config.extraction.max_factors = Some(n_factors as i32);
+    case_config.options.cache = false;
+    let rotation = &mut case_config.rotation;
+    rotation.oblimin = false;
+    rotation.quartimax = false;
+    rotation.equimax = false;
+    rotation.promax = false;
+    rotation.varimax = case.varimax;
+    rotation.none = !case.varimax;
+
+    let expected = &case.expected;
+    let mut checks = Vec::new();
+
+    let kmo_bartlett = build_kmo_bartletts_test(&corr_matrix, case.n);
+    checks.push(check("KMO", &[kmo_bartlett.kaiser_meyer_olkin], &[expected.kmo], KMO_TOLERANCE));
+    checks.push(
+        check(
+            "Bartlett's chi-square",
+            &[kmo_bartlett.bartletts_test_chi_square],
+            &[expected.bartlett_chi_square],
+            CHI_SQUARE_TOLERANCE
+        )
+    );
+    checks.push(
+        check("Bartlett's df", &[kmo_bartlett.df as f64], &[expected.bartlett_df as f64], 0.0)
+    );
+
+    let extraction_result = extract_factors(&corr_matrix, &case_config, &var_names)?;
+    let variance = build_total_variance_explained(
+        &extraction_result,
+        None,
+        var_names.len(),
+        &case_config
+    );
+    let eigenvalues: Vec<f64> = variance.initial_eigenvalues
+        .iter()
+        .map(|component| component.total)
+        .collect();
+    checks.push(
+        check("Initial eigenvalues", &eigenvalues, expected.eigenvalues, EIGENVALUE_TOLERANCE)
+    );
+    checks.push(
+        check(
+            "Communalities",
+            &extraction_result.communalities,
+            expected.communalities,
+            LOADING_TOLERANCE
+        )
+    );
+    checks.push(check_loadings("Loadings", &extraction_result.loadings, expected.loadings)?);
+
+    if let Some(rotated_loadings) = expected.rotated_loadings {
+        let rotation_result = rotate_factors(&extraction_result, &case_config)?;
+        checks.push(
+            check_loadings("Rotated loadings", &rotation_result.rotated_loadings, rotated_loadings)?
+        );
+    }
+
+    Ok(checks)
+}
+
+fn check(statistic: &str, actual: &[f64], expected: &[f64], tolerance: f64) -> ValidationCheck {
+    // f64::max would skip a NaN, so any NaN output makes the whole deviation NaN
+    let max_deviation = if actual.len() == expected.len() {
+        actual
+            .iter()
+            .zip(expected)
+            .map(|(a, e)| (a - e).abs())
+            .fold(0.0, |max: f64, deviation| {
+                if max.is_nan() || deviation.is_nan() { f64::NAN } else { max.max(deviation) }
+            })
+    } else {
+        f64::INFINITY
+    };
+
+    ValidationCheck {
+        statistic: statistic.to_string(),
+        max_deviation,
+        tolerance,
+        passed: max_deviation <= tolerance,
+    }
+}
+
+// Factors come out in any order and sign, so the loadings are matched to the reference by
+// reordering and reflecting them before comparing
+fn check_loadings(
+    statistic: &str,
+    actual: &DMatrix<f64>,
+    expected: &[&[f64]]
+) -> Result<ValidationCheck, String> {
+    let reference = rows_to_matrix(expected);
+    if actual.shape() != reference.shape() {
+        return Ok(check(statistic, &[], &[0.0], LOADING_TOLERANCE));
+    }
+    let transformation = alignment_transformation(
+        actual,
+        &reference,
+        &AlignmentMethod::Permutation
+    )?;
+    let aligned = actual * transformation;
+
+    Ok(check(statistic, aligned.as_slice(), reference.as_slice(), LOADING_TOLERANCE))
+}
+
+fn reference_matrix(matrix: &ReferenceMatrix) -> DMatrix<f64> {
+    match matrix {
+        ReferenceMatrix::ExactFit(population_loadings) => {
+            let population = rows_to_matrix(population_loadings);
+            let mut corr_matrix = &population * population.transpose();
+            corr_matrix.fill_diagonal(1.0);
+            corr_matrix
+        }
+        ReferenceMatrix::Correlations(lower) => {
+            DMatrix::from_fn(lower.len(), lower.len(), |i, j| {
+                if j <= i { lower[i][j] } else { lower[j][i] }
+            })
+        }
+    }
+}
+
+fn rows_to_matrix(rows: &[&[f64]]) -> DMatrix<f64> {
+    let n_cols = rows.first().map_or(0, |row| row.len());
+    DMatrix::from_fn(rows.len(), n_cols, |i, j| rows[i][j])
+}
@@ -0,0 +1,153 @@
+use nalgebra::DMatrix;
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ DiscriminantValidity, FactorPairValidity, FactorValidity },
+};
+
+use super::core::{ assign_items, extract_correlation_matrix, extract_factors, rotate_factors };
+
+// Henseler, Ringle and Sarstedt's (2015) conservative cutoff
+const DEFAULT_HTMT_THRESHOLD: f64 = 0.85;
+
+// Discriminant validity of the factors of the configured solution. The factor correlations
+// come from an oblique rotation; otherwise the factors are uncorrelated.
+pub fn calculate_discriminant_validity(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<DiscriminantValidity, String> {
+    let (corr_matrix, var_names, _) = extract_correlation_matrix(data, config, "correlation")?;
+    let extraction_result = extract_factors(&corr_matrix, config, &var_names)?;
+    if config.rotation.none {
+        return build_discriminant_validity(
+            &corr_matrix,
+            &extraction_result.loadings,
+            None,
+            &var_names,
+            config
+        );
+    }
+
+    let rotation_result = rotate_factors(&extraction_result, config)?;
+    build_discriminant_validity(
+        &corr_matrix,
+        &rotation_result.rotated_loadings,
+        rotation_result.factor_correlations.as_ref(),
+        &var_names,
+        config
+    )
+}
+
+pub fn htmt_threshold(config: &FactorAnalysisConfig) -> f64 {
+    match config.descriptives.htmt_threshold {
+        Some(threshold) if threshold > 0.0 => threshold,
+        _ => DEFAULT_HTMT_THRESHOLD,
+    }
+}
+
+// Fornell-Larcker compares the AVE of each factor with its squared correlations with the
+// others. HTMT divides the mean absolute correlation between the items of two factors by the
+// geometric mean of the mean absolute correlations within each factor's items.
+pub fn build_discriminant_validity(
+    corr_matrix: &DMatrix<f64>,
+    loadings: &DMatrix<f64>,
+    factor_correlations: Option<&DMatrix<f64>>,
+    var_names: &[String],
+    config: &FactorAnalysisConfig
+) -> Result<DiscriminantValidity, String> {
+    let n_factors = loadings.ncols();
+    if n_factors < 2 {
+        return Err("Discriminant validity needs at least two factors".to_string());
+    }
+    if loadings.nrows() != corr_matrix.nrows() || var_names.len() != loadings.nrows() {
+        return Err("Loadings don't match the correlation matrix".to_string());
+    }
+
+    let phi = factor_correlations
+        .cloned()
+        .unwrap_or_else(|| DMatrix::identity(n_factors, n_factors));
+    let items = assign_items(loadings);
+    let threshold = htmt_threshold(config);
+
+    let mean_correlation = |rows: &[usize], columns: &[usize]| -> Option<f64> {
+        let values: Vec<f64> = rows
+            .iter()
+            .flat_map(|&i| columns.iter().map(move |&j| (i, j)))
+            .filter(|(i, j)| i != j)
+            .map(|(i, j)| corr_matrix[(i, j)].abs())
+            .collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / (values.len() as f64))
+    };
+    let monotrait: Vec<Option<f64>> = items
+        .iter()
+        .map(|factor_items| mean_correlation(factor_items, factor_items))
+        .collect();
+
+    let mut pairs = Vec::new();
+    for a in 0..n_factors {
+        for b in a + 1..n_factors {
+            let htmt = match (monotrait[a], monotrait[b]) {
+                (Some(within_a), Some(within_b)) if within_a > 0.0 && within_b > 0.0 => {
+                    mean_correlation(&items[a], &items[b]).map(|heterotrait| {
+                        heterotrait / (within_a * within_b).sqrt()
+                    })
+                }
+                _ => None,
+            };
+            pairs.push(FactorPairValidity {
+                factor_one: a + 1,
+                factor_two: b + 1,
+                correlation: phi[(a, b)],
+                squared_correlation: phi[(a, b)].powi(2),
+                htmt,
+                htmt_discriminant: htmt.map(|ratio| ratio < threshold),
+            });
+        }
+    }
+
+    let factors = items
+        .iter()
+        .enumerate()
+        .map(|(factor, factor_items)| {
+            let ave = average_variance_extracted(loadings, factor_items, factor);
+            let max_squared_correlation = (0..n_factors)
+                .filter(|&other| other != factor)
+                .map(|other| phi[(factor, other)].powi(2))
+                .fold(0.0, f64::max);
+
+            FactorValidity {
+                factor: factor + 1,
+                items: factor_items
+                    .iter()
+                    .map(|&i| var_names[i].clone())
+                    .collect(),
+                ave,
+                max_squared_correlation,
+                fornell_larcker: ave.map(|ave| ave > max_squared_correlation),
+            }
+        })
+        .collect();
+
+    Ok(DiscriminantValidity {
+        htmt_threshold: threshold,
+        factors,
+        pairs,
+    })
+}
+
+// Mean squared loading of a factor's items on that factor
+pub fn average_variance_extracted(
+    loadings: &DMatrix<f64>,
+    items: &[usize],
+    factor: usize
+) -> Option<f64> {
+    if items.is_empty() {
+        return None;
+    }
+    let sum_of_squares: f64 = items
+        .iter()
+        .map(|&i| loadings[(i, factor)].powi(2))
+        .sum();
+    Some(sum_of_squares / (items.len() as f64))
+}
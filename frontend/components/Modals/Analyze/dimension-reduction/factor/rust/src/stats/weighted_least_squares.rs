@@ -0,0 +1,488 @@
+use std::{ collections::HashMap, f64::consts::PI };
+
+use nalgebra::{ DMatrix, DVector };
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use statrs::distribution::{ ChiSquared, Continuous, ContinuousCDF, Normal };
+
+use crate::models::{
+    config::FactorAnalysisConfig,
+    data::AnalysisData,
+    result::{ RobustFit, WeightedLeastSquares },
+};
+
+use super::core::{
+    bivariate_normal_rectangle,
+    bounded_thresholds,
+    build_polychoric_matrix,
+    determine_factors_to_retain,
+    extract_ordinal_responses,
+    extraction_convergence,
+    require_more_cases_than_variables,
+    robust_symmetric_eigen,
+    sorted_symmetric_eigen,
+};
+
+// Eigenvalues of J'WJ below this share of the largest belong to the rotations the loadings
+// aren't identified under
+const RANK_TOLERANCE: f64 = 1e-10;
+
+// Halvings of a Gauss-Newton step tried before the step is given up
+const MAX_STEP_HALVINGS: usize = 20;
+
+// Diagonally weighted least squares factors of the polychoric matrix. The asymptotic
+// covariances need every correlation on the same cases, so cases with a missing value are
+// left out whatever the missing value setting.
+pub fn calculate_weighted_least_squares(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig
+) -> Result<WeightedLeastSquares, String> {
+    if !config.extraction.polychoric {
+        return Err("Weighted least squares estimation needs the polychoric matrix".to_string());
+    }
+    let (var_names, responses, n_levels) = extract_ordinal_responses(data, config)?;
+    let n_cases = responses
+        .first()
+        .map_or(0, |column| column.len());
+    let complete: Vec<usize> = (0..n_cases)
+        .filter(|&case| responses.iter().all(|column| column[case].is_some()))
+        .collect();
+    let complete_responses: Vec<Vec<Option<usize>>> = responses
+        .iter()
+        .map(|column| complete.iter().map(|&case| column[case]).collect())
+        .collect();
+    require_more_cases_than_variables("Weighted least squares", complete.len(), var_names.len())?;
+    for (var_name, column) in var_names.iter().zip(&complete_responses) {
+        if column.iter().all(|level| *level == column[0]) {
+            return Err(format!("'{}' takes a single level in the complete cases", var_name));
+        }
+    }
+
+    let normal = Normal::new(0.0, 1.0).map_err(|e| e.to_string())?;
+    let thresholds: Vec<Vec<f64>> = complete_responses
+        .iter()
+        .zip(&n_levels)
+        .map(|(column, &levels)| bounded_thresholds(column, levels, &normal))
+        .collect();
+    let (corr_matrix, n_obs) = build_polychoric_matrix(&complete_responses, &n_levels, config)?;
+    let gamma = polychoric_asymptotic_covariance(
+        &complete_responses,
+        &thresholds,
+        &corr_matrix,
+        &normal
+    );
+
+    let (eigenvalues, _, _) = sorted_symmetric_eigen(&corr_matrix);
+    let n_factors = determine_factors_to_retain(&eigenvalues, config);
+    let finite_thresholds = var_names
+        .iter()
+        .cloned()
+        .zip(thresholds.iter().map(|bounded| bounded[1..bounded.len() - 1].to_vec()))
+        .collect();
+
+    build_weighted_least_squares(
+        &corr_matrix,
+        &gamma,
+        finite_thresholds,
+        &var_names,
+        n_obs,
+        n_factors,
+        config
+    )
+}
+
+// N times the asymptotic covariance matrix of the polychoric correlations, over the pairs
+// (i, j), i < j, in row order. Each case's influence on a correlation is the score of rho less
+// the part owed to the estimated thresholds, over the information of rho (Muthén and Satorra,
+// 1995); the matrix is the covariance of the influences over the cases.
+pub fn polychoric_asymptotic_covariance(
+    responses: &[Vec<Option<usize>>],
+    thresholds: &[Vec<f64>],
+    corr_matrix: &DMatrix<f64>,
+    normal: &Normal
+) -> DMatrix<f64> {
+    let n_cases = responses
+        .first()
+        .map_or(0, |column| column.len());
+    let pairs = lower_pairs(responses.len());
+    let influence_on_thresholds: Vec<DMatrix<f64>> = responses
+        .iter()
+        .zip(thresholds)
+        .map(|(column, bounded)| threshold_influence(column, bounded, normal))
+        .collect();
+
+    let pair_influence = |&(i, j): &(usize, usize)| -> Vec<f64> {
+        let cells = cell_influence(
+            (&thresholds[i], &thresholds[j]),
+            corr_matrix[(i, j)],
+            (&influence_on_thresholds[i], &influence_on_thresholds[j]),
+            normal
+        );
+        (0..n_cases)
+            .map(|case| {
+                match (responses[i][case], responses[j][case]) {
+                    (Some(a), Some(b)) => cells[(a, b)],
+                    _ => 0.0,
+                }
+            })
+            .collect()
+    };
+
+    // The pairs are independent of each other
+    #[cfg(feature = "parallel")]
+    let influences: Vec<Vec<f64>> = pairs.par_iter().map(pair_influence).collect();
+    #[cfg(not(feature = "parallel"))]
+    let influences: Vec<Vec<f64>> = pairs.iter().map(pair_influence).collect();
+
+    let mut scores = DMatrix::from_fn(n_cases, pairs.len(), |case, k| influences[k][case]);
+    for mut column in scores.column_iter_mut() {
+        let mean = column.mean();
+        column.add_scalar_mut(-mean);
+    }
+    scores.transpose() * &scores / (n_cases.max(1) as f64)
+}
+
+// Influence of a case at each level on each finite threshold, (1{level < m} - F_m) / phi(t_m)
+// with F_m the proportion of cases below threshold m
+fn threshold_influence(
+    column: &[Option<usize>],
+    thresholds: &[f64],
+    normal: &Normal
+) -> DMatrix<f64> {
+    let n_levels = thresholds.len() - 1;
+    let mut counts = vec![0.0; n_levels];
+    for level in column.iter().flatten() {
+        counts[*level] += 1.0;
+    }
+    let n = counts.iter().sum::<f64>().max(1.0);
+
+    DMatrix::from_fn(n_levels, n_levels - 1, |level, t| {
+        let m = t + 1;
+        let below = counts[..m].iter().sum::<f64>() / n;
+        let indicator = if level < m { 1.0 } else { 0.0 };
+        (indicator - below) / normal.pdf(thresholds[m])
+    })
+}
+
+// Influence on rho of a case in each cell of the pair's table. Only cells with positive
+// probability enter the information; empty levels have none.
+fn cell_influence(
+    (x, y): (&[f64], &[f64]),
+    rho: f64,
+    (influence_x, influence_y): (&DMatrix<f64>, &DMatrix<f64>),
+    normal: &Normal
+) -> DMatrix<f64> {
+    let (levels_x, levels_y) = (x.len() - 1, y.len() - 1);
+    let root = (1.0 - rho * rho).sqrt();
+    let density = |h: f64, k: f64| {
+        if !h.is_finite() || !k.is_finite() {
+            return 0.0;
+        }
+        (-(h * h - 2.0 * rho * h * k + k * k) / (2.0 * root * root)).exp() / (2.0 * PI * root)
+    };
+    // Derivative of the cell probability in a threshold h bounding it, along the cell's other
+    // range
+    let edge = |h: f64, (lower, upper): (f64, f64)| {
+        if !h.is_finite() {
+            return 0.0;
+        }
+        let within = normal.cdf((upper - rho * h) / root) - normal.cdf((lower - rho * h) / root);
+        normal.pdf(h) * within
+    };
+
+    let mut score = DMatrix::zeros(levels_x, levels_y);
+    let mut information = 0.0;
+    let mut cross_x = vec![0.0; levels_x - 1];
+    let mut cross_y = vec![0.0; levels_y - 1];
+    for a in 0..levels_x {
+        for b in 0..levels_y {
+            let probability = bivariate_normal_rectangle((x[a], x[a + 1]), (y[b], y[b + 1]), rho);
+            if probability <= f64::MIN_POSITIVE {
+                continue;
+            }
+            let d_rho =
+                density(x[a + 1], y[b + 1]) -
+                density(x[a], y[b + 1]) -
+                density(x[a + 1], y[b]) +
+                density(x[a], y[b]);
+            score[(a, b)] = d_rho / probability;
+            information += (d_rho * d_rho) / probability;
+
+            let weight = d_rho / probability;
+            if a + 1 < levels_x {
+                cross_x[a] += weight * edge(x[a + 1], (y[b], y[b + 1]));
+            }
+            if a > 0 {
+                cross_x[a - 1] -= weight * edge(x[a], (y[b], y[b + 1]));
+            }
+            if b + 1 < levels_y {
+                cross_y[b] += weight * edge(y[b + 1], (x[a], x[a + 1]));
+            }
+            if b > 0 {
+                cross_y[b - 1] -= weight * edge(y[b], (x[a], x[a + 1]));
+            }
+        }
+    }
+
+    DMatrix::from_fn(levels_x, levels_y, |a, b| {
+        let from_x: f64 = cross_x
+            .iter()
+            .enumerate()
+            .map(|(t, cross)| cross * influence_x[(a, t)])
+            .sum();
+        let from_y: f64 = cross_y
+            .iter()
+            .enumerate()
+            .map(|(t, cross)| cross * influence_y[(b, t)])
+            .sum();
+        (score[(a, b)] - from_x - from_y) / information
+    })
+}
+
+// Loadings minimizing sum w_ij (r_ij - l_i'l_j)^2 over the pairs, with w_ij one over the
+// asymptotic variance of r_ij, by Gauss-Newton from the principal components. The test of fit
+// is N times that minimum, scaled and shifted to the mean and variance of a chi-square on the
+// model's degrees of freedom (Asparouhov and Muthén, 2010), as WLSMV in Mplus and lavaan.
+pub fn build_weighted_least_squares(
+    corr_matrix: &DMatrix<f64>,
+    gamma: &DMatrix<f64>,
+    thresholds: HashMap<String, Vec<f64>>,
+    var_names: &[String],
+    n_obs: usize,
+    n_factors: usize,
+    config: &FactorAnalysisConfig
+) -> Result<WeightedLeastSquares, String> {
+    let n_vars = corr_matrix.nrows();
+    let pairs = lower_pairs(n_vars);
+    if gamma.nrows() != pairs.len() || gamma.ncols() != pairs.len() {
+        return Err("The asymptotic covariances don't match the correlation matrix".to_string());
+    }
+    if n_factors == 0 || n_factors >= n_vars {
+        return Err(format!("Can't fit {} factors to {} variables", n_factors, n_vars));
+    }
+    if !gamma.diagonal().iter().all(|variance| *variance > 0.0 && variance.is_finite()) {
+        return Err("Every polychoric correlation needs a positive asymptotic variance".to_string());
+    }
+    let weights = gamma.diagonal().map(|variance| 1.0 / variance);
+    let sample = DVector::from_iterator(
+        pairs.len(),
+        pairs.iter().map(|&(i, j)| corr_matrix[(i, j)])
+    );
+
+    let (eigenvalues, eigenvectors, _) = sorted_symmetric_eigen(corr_matrix);
+    let initial = DMatrix::from_fn(n_vars, n_factors, |i, f| {
+        eigenvectors[(i, f)] * eigenvalues[f].max(0.0).sqrt()
+    });
+    let (loadings, iterations, converged) = fit_weighted_loadings(
+        &sample,
+        &weights,
+        &pairs,
+        initial,
+        config.extraction.max_iter.max(0) as usize,
+        extraction_convergence(config)
+    );
+    let loadings = principal_orientation(&loadings);
+
+    Ok(WeightedLeastSquares {
+        n: n_obs,
+        n_factors,
+        thresholds,
+        loadings: var_names
+            .iter()
+            .enumerate()
+            .map(|(i, var_name)| (var_name.clone(), loadings.row(i).iter().copied().collect()))
+            .collect(),
+        communalities: var_names
+            .iter()
+            .enumerate()
+            .map(|(i, var_name)| (var_name.clone(), loadings.row(i).norm_squared()))
+            .collect(),
+        iterations,
+        converged,
+        fit: robust_fit(&sample, &weights, gamma, &pairs, &loadings, n_obs),
+    })
+}
+
+fn fit_weighted_loadings(
+    sample: &DVector<f64>,
+    weights: &DVector<f64>,
+    pairs: &[(usize, usize)],
+    initial: DMatrix<f64>,
+    max_iter: usize,
+    tolerance: f64
+) -> (DMatrix<f64>, usize, bool) {
+    let discrepancy = |loadings: &DMatrix<f64>| {
+        let residual = sample - implied_correlations(loadings, pairs);
+        residual.component_mul(weights).dot(&residual)
+    };
+
+    let mut loadings = initial;
+    let mut current = discrepancy(&loadings);
+    for iteration in 1..=max_iter {
+        let residual = sample - implied_correlations(&loadings, pairs);
+        let jacobian = implied_jacobian(&loadings, pairs);
+        let weighted = weighted_rows(&jacobian, weights);
+        let gradient = weighted.transpose() * &residual;
+        let step = symmetric_pseudo_inverse(&(weighted.transpose() * &jacobian)) * gradient;
+        let step = DMatrix::from_row_slice(loadings.nrows(), loadings.ncols(), step.as_slice());
+
+        let mut length = 1.0;
+        let mut halvings = 0;
+        let mut candidate = &loadings + &step;
+        let mut next = discrepancy(&candidate);
+        while next > current && halvings < MAX_STEP_HALVINGS {
+            length /= 2.0;
+            halvings += 1;
+            candidate = &loadings + &step * length;
+            next = discrepancy(&candidate);
+        }
+        if next > current {
+            return (loadings, iteration, false);
+        }
+
+        loadings = candidate;
+        current = next;
+        if step.amax() * length < tolerance {
+            return (loadings, iteration, true);
+        }
+    }
+    (loadings, max_iter, false)
+}
+
+fn robust_fit(
+    sample: &DVector<f64>,
+    weights: &DVector<f64>,
+    gamma: &DMatrix<f64>,
+    pairs: &[(usize, usize)],
+    loadings: &DMatrix<f64>,
+    n_obs: usize
+) -> RobustFit {
+    let (p, m) = (loadings.nrows() as f64, loadings.ncols() as f64);
+    let n = n_obs as f64;
+    let df = (((p - m).powi(2) - (p + m)) / 2.0).round() as i64;
+    let baseline_df = pairs.len();
+
+    let residual = sample - implied_correlations(loadings, pairs);
+    let chi_square = n * residual.component_mul(weights).dot(&residual);
+    let baseline = n * sample.component_mul(weights).dot(sample);
+
+    // U = W - WJ (J'WJ)^+ J'W; the baseline model has no parameters, so its U is W
+    let jacobian = implied_jacobian(loadings, pairs);
+    let weighted = weighted_rows(&jacobian, weights);
+    let projection =
+        &weighted * symmetric_pseudo_inverse(&(weighted.transpose() * &jacobian)) *
+        weighted.transpose();
+    let u_gamma = (DMatrix::from_diagonal(weights) - projection) * gamma;
+    let baseline_gamma = weighted_rows(gamma, weights);
+
+    let positive_df = (df > 0).then_some(df as f64);
+    let model = positive_df.map(|df| scaled_shifted(chi_square, df, &u_gamma));
+    let baseline_scaled = (baseline_df > 0).then(|| {
+        scaled_shifted(baseline, baseline_df as f64, &baseline_gamma).0
+    });
+    let significance = |statistic: f64, df: f64| {
+        ChiSquared::new(df)
+            .map(|distribution| distribution.sf(statistic.max(0.0)))
+            .ok()
+    };
+
+    let scaled_chi_square = model.map(|(scaled, _, _)| scaled);
+    let (cfi, tli, rmsea) = match (scaled_chi_square, positive_df, baseline_scaled) {
+        (Some(scaled), Some(df_model), Some(baseline_scaled)) => {
+            let baseline_dfs = baseline_df as f64;
+            let excess = (scaled - df_model).max(0.0);
+            let denominator = (baseline_scaled - baseline_dfs).max(excess);
+            let baseline_ratio = baseline_scaled / baseline_dfs;
+            (
+                Some(if denominator > 0.0 { 1.0 - excess / denominator } else { 1.0 }),
+                Some((baseline_ratio - scaled / df_model) / (baseline_ratio - 1.0)),
+                (n > 0.0).then(|| (excess / (df_model * n)).sqrt()),
+            )
+        }
+        _ => (None, None, None),
+    };
+
+    RobustFit {
+        df,
+        chi_square: Some(chi_square),
+        significance: positive_df.and_then(|df| significance(chi_square, df)),
+        scaled_chi_square,
+        scaled_significance: scaled_chi_square.zip(positive_df).and_then(|(scaled, df)| {
+            significance(scaled, df)
+        }),
+        scaling_factor: model.map(|(_, scaling, _)| scaling),
+        shift_parameter: model.map(|(_, _, shift)| shift),
+        baseline_chi_square: baseline_scaled,
+        baseline_df,
+        cfi,
+        tli,
+        rmsea,
+        srmr: if pairs.is_empty() {
+            0.0
+        } else {
+            (residual.norm_squared() / (pairs.len() as f64)).sqrt()
+        },
+    }
+}
+
+// a + T / c, with c and a giving the statistic the mean and variance of a chi-square on df
+// degrees of freedom; returns the statistic, c and a
+fn scaled_shifted(statistic: f64, df: f64, u_gamma: &DMatrix<f64>) -> (f64, f64, f64) {
+    let trace = u_gamma.trace();
+    let trace_squared = u_gamma.component_mul(&u_gamma.transpose()).sum();
+    let factor = (df / trace_squared).sqrt();
+    let shift = df - factor * trace;
+    (shift + factor * statistic, 1.0 / factor, shift)
+}
+
+// Off-diagonal correlations of the loadings, over the pairs
+fn implied_correlations(loadings: &DMatrix<f64>, pairs: &[(usize, usize)]) -> DVector<f64> {
+    DVector::from_iterator(
+        pairs.len(),
+        pairs.iter().map(|&(i, j)| loadings.row(i).dot(&loadings.row(j)))
+    )
+}
+
+// Derivatives of the implied correlations in the loadings, taken row by row
+fn implied_jacobian(loadings: &DMatrix<f64>, pairs: &[(usize, usize)]) -> DMatrix<f64> {
+    let n_factors = loadings.ncols();
+    let mut jacobian = DMatrix::zeros(pairs.len(), loadings.len());
+    for (k, &(i, j)) in pairs.iter().enumerate() {
+        for f in 0..n_factors {
+            jacobian[(k, i * n_factors + f)] = loadings[(j, f)];
+            jacobian[(k, j * n_factors + f)] = loadings[(i, f)];
+        }
+    }
+    jacobian
+}
+
+fn weighted_rows(matrix: &DMatrix<f64>, weights: &DVector<f64>) -> DMatrix<f64> {
+    DMatrix::from_fn(matrix.nrows(), matrix.ncols(), |k, l| weights[k] * matrix[(k, l)])
+}
+
+// Inverse on the eigenvectors with non-negligible eigenvalues, zero on the rest
+fn symmetric_pseudo_inverse(matrix: &DMatrix<f64>) -> DMatrix<f64> {
+    let eigen = robust_symmetric_eigen(matrix);
+    let largest = eigen.eigenvalues.amax();
+    let inverted = eigen.eigenvalues.map(|value| {
+        if value > largest * RANK_TOLERANCE { 1.0 / value } else { 0.0 }
+    });
+    &eigen.eigenvectors * DMatrix::from_diagonal(&inverted) * eigen.eigenvectors.transpose()
+}
+
+// Loadings turned to their principal axes, the first factor taking the most variance, with
+// every column summing to a positive value
+fn principal_orientation(loadings: &DMatrix<f64>) -> DMatrix<f64> {
+    let (_, vectors, _) = sorted_symmetric_eigen(&(loadings.transpose() * loadings));
+    let mut oriented = loadings * vectors;
+    for mut column in oriented.column_iter_mut() {
+        if column.sum() < 0.0 {
+            column.neg_mut();
+        }
+    }
+    oriented
+}
+
+fn lower_pairs(n_vars: usize) -> Vec<(usize, usize)> {
+    (0..n_vars).flat_map(|i| (i + 1..n_vars).map(move |j| (i, j))).collect()
+}
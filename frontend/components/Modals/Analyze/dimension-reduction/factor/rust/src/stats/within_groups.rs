@@ -0,0 +1,156 @@
+use nalgebra::DMatrix;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::models::{ config::FactorAnalysisConfig, data::AnalysisData };
+
+use super::core::{
+    calculate_matrix,
+    calculate_pairwise_matrix,
+    distinct_levels,
+    extract_case_levels,
+    extract_raw_data_matrix,
+    handle_missing_values,
+    min_pairwise_n,
+    pairwise_analysis_n,
+    require_more_cases_than_groups,
+    uses_pairwise_deletion,
+};
+
+// Correlation or covariance matrix pooled over the groups of the grouping variable, so that
+// differences between the group means don't enter the correlations. The number of cases
+// returned is N - G + 1, which gives the tests on the matrix their N - G degrees of freedom.
+pub fn calculate_pooled_within_groups_matrix(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    group_var: &str,
+    matrix_type: &str
+) -> Result<(DMatrix<f64>, Vec<String>, usize), String> {
+    let (raw_matrix, var_names) = extract_raw_data_matrix(data, config)?;
+    let encoded_prefix = format!("{}=", group_var);
+    if var_names.iter().any(|name| name == group_var || name.starts_with(&encoded_prefix)) {
+        return Err(
+            format!("Grouping variable {} can't also be an analysis variable", group_var)
+        );
+    }
+
+    let case_groups = extract_case_levels(data, config, group_var);
+    let (centered, n_groups) = center_within_groups(&raw_matrix, &case_groups, config)?;
+    if n_groups == 0 {
+        return Err(format!("Grouping variable {} has no valid values", group_var));
+    }
+    let (matrix, n_obs) = build_pooled_within_groups_matrix(
+        &centered,
+        n_groups,
+        matrix_type,
+        config
+    )?;
+
+    Ok((matrix, var_names, n_obs))
+}
+
+// Cases of every group centered on the group means, stacked in group order, with the number
+// of groups left. Missing values are handled within each group, as for the grouped
+// descriptives; cases without a group are left out, as are groups without complete cases.
+// Under pairwise deletion the missing values stay and each group is centered on its
+// available values.
+pub fn center_within_groups(
+    raw_matrix: &DMatrix<f64>,
+    case_groups: &[Option<String>],
+    config: &FactorAnalysisConfig
+) -> Result<(DMatrix<f64>, usize), String> {
+    let pairwise = uses_pairwise_deletion(config);
+    let group_levels = distinct_levels(case_groups);
+
+    let center_group = |group: &String| {
+        let rows: Vec<usize> = case_groups
+            .iter()
+            .enumerate()
+            .filter(|(_, level)| level.as_deref() == Some(group.as_str()))
+            .map(|(i, _)| i)
+            .collect();
+        let group_raw = raw_matrix.select_rows(&rows);
+        let mut group_matrix = if pairwise {
+            group_raw
+        } else {
+            handle_missing_values(&group_raw, config).ok()?
+        };
+
+        for mut column in group_matrix.column_iter_mut() {
+            let (sum, count) = column
+                .iter()
+                .filter(|value| !value.is_nan())
+                .fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+            if count > 0 {
+                let mean = sum / (count as f64);
+                column.iter_mut().for_each(|value| {
+                    *value -= mean;
+                });
+            }
+        }
+        (group_matrix.nrows() > 0).then_some(group_matrix)
+    };
+
+    // Groups are independent of each other, so they are centered in parallel
+    #[cfg(feature = "parallel")]
+    let groups: Vec<DMatrix<f64>> = group_levels.par_iter().filter_map(center_group).collect();
+    #[cfg(not(feature = "parallel"))]
+    let groups: Vec<DMatrix<f64>> = group_levels.iter().filter_map(center_group).collect();
+
+    let n_rows = groups
+        .iter()
+        .map(|group| group.nrows())
+        .sum();
+    let mut centered = DMatrix::zeros(n_rows, raw_matrix.ncols());
+    let mut row = 0;
+    for group in &groups {
+        centered.rows_mut(row, group.nrows()).copy_from(group);
+        row += group.nrows();
+    }
+
+    Ok((centered, groups.len()))
+}
+
+// Matrix of group-centered data on N - G degrees of freedom. The centering leaves the
+// column means at zero, so the correlations and sums of squares need no correction; the
+// covariances are rescaled from N - 1 to N - G.
+pub fn build_pooled_within_groups_matrix(
+    centered: &DMatrix<f64>,
+    n_groups: usize,
+    matrix_type: &str,
+    config: &FactorAnalysisConfig
+) -> Result<(DMatrix<f64>, usize), String> {
+    let (matrix, n_obs) = if uses_pairwise_deletion(config) {
+        let mut pairwise = calculate_pairwise_matrix(centered, matrix_type, min_pairwise_n(config))?;
+        if matrix_type == "covariance" {
+            for i in 0..pairwise.matrix.nrows() {
+                for j in 0..pairwise.matrix.ncols() {
+                    let n = pairwise.pair_counts[i][j];
+                    pairwise.matrix[(i, j)] *= pooled_scale(n, n_groups);
+                }
+            }
+        }
+        let n_obs = pairwise_analysis_n(&pairwise, &config.options.pairwise_n);
+
+        (pairwise.matrix, n_obs)
+    } else {
+        let mut matrix = calculate_matrix(centered, matrix_type)?;
+        if matrix_type == "covariance" {
+            matrix *= pooled_scale(centered.nrows(), n_groups);
+        }
+
+        (matrix, centered.nrows())
+    };
+    require_more_cases_than_groups(n_obs, n_groups)?;
+
+    Ok((matrix, n_obs + 1 - n_groups))
+}
+
+// (n - 1) / (n - G), taking a covariance on n - 1 degrees of freedom to n - G. Pairwise cells
+// with no more pairs than groups keep their n - 1.
+fn pooled_scale(n: usize, n_groups: usize) -> f64 {
+    if n <= n_groups {
+        return 1.0;
+    }
+    ((n - 1) as f64) / ((n - n_groups) as f64)
+}
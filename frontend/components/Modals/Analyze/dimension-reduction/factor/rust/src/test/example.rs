@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
+
+use crate::models::config::{ DataMode, ExtractionMethod };
+use crate::stats::core::{
+    assess_data_mode,
+    build_factor_reliability,
+    build_intraclass_correlation,
+    build_weighted_least_squares,
+    jacobi_eigen,
+    run_validation,
+};
+
+use super::sample::{
+    one_factor_polychoric,
+    sample_config,
+    sample_data,
+    shrout_fleiss_ratings,
+    tridiagonal_matrix,
+    two_scale_polychoric,
+};
+
+#[test]
+fn reference_cases_match_published_output() {
+    let report = run_validation(&sample_config());
+
+    for case in &report.cases {
+        assert!(case.error.is_none(), "{}: {:?}", case.name, case.error);
+        for check in &case.checks {
+            assert!(
+                check.passed,
+                "{}: {} deviates by {} (tolerance {})",
+                case.name,
+                check.statistic,
+                check.max_deviation,
+                check.tolerance
+            );
+        }
+    }
+    assert!(report.passed);
+}
+
+#[test]
+fn within_groups_pooling_stays_in_memory() {
+    let mut config = sample_config();
+    config.options.memory_limit_mb = Some(0.001);
+    config.options.large_data_mode = DataMode::Streaming;
+    let report = assess_data_mode(&sample_data(), &config).unwrap();
+    assert_eq!(report.mode, DataMode::Streaming);
+
+    config.main.within_groups_var = Some("g".to_string());
+    let report = assess_data_mode(&sample_data(), &config).unwrap();
+    assert_eq!(report.mode, DataMode::InMemory);
+}
+
+#[test]
+fn pairwise_deletion_stays_in_memory() {
+    let mut config = sample_config();
+    config.options.memory_limit_mb = Some(0.001);
+    config.options.large_data_mode = DataMode::Subsample;
+    config.options.exclude_list_wise = false;
+    config.options.exclude_pair_wise = true;
+    let report = assess_data_mode(&sample_data(), &config).unwrap();
+    assert_eq!(report.mode, DataMode::InMemory);
+}
+
+#[test]
+fn jacobi_eigen_decomposes_a_symmetric_matrix() {
+    let matrix = tridiagonal_matrix();
+    let (eigen, converged) = jacobi_eigen(&matrix, 50);
+    assert!(converged);
+
+    let mut eigenvalues: Vec<f64> = eigen.eigenvalues.iter().copied().collect();
+    eigenvalues.sort_by(|a, b| a.total_cmp(b));
+    let expected = [2.0 - 2.0_f64.sqrt(), 2.0, 2.0 + 2.0_f64.sqrt()];
+    for (value, expected) in eigenvalues.iter().zip(expected) {
+        assert!((value - expected).abs() < 1e-12, "{} against {}", value, expected);
+    }
+
+    let vectors = &eigen.eigenvectors;
+    let identity = DMatrix::<f64>::identity(3, 3);
+    assert!((vectors.transpose() * vectors - identity).amax() < 1e-12);
+    let reconstructed =
+        vectors * DMatrix::from_diagonal(&eigen.eigenvalues) * vectors.transpose();
+    assert!((reconstructed - matrix).amax() < 1e-12);
+}
+
+// Estimates and 95% limits as printed to two decimals by Shrout and Fleiss and by psych::ICC
+#[test]
+fn intraclass_correlations_match_shrout_fleiss() {
+    let icc = build_intraclass_correlation(&shrout_fleiss_ratings(), 0.95).unwrap();
+    let expected = [
+        ("ICC1", 0.17, -0.13, 0.72),
+        ("ICC2", 0.29, 0.02, 0.76),
+        ("ICC3", 0.71, 0.34, 0.95),
+        ("ICC1k", 0.44, -0.88, 0.91),
+        ("ICC2k", 0.62, 0.07, 0.93),
+        ("ICC3k", 0.91, 0.68, 0.99),
+    ];
+
+    assert_eq!(icc.estimates.len(), expected.len());
+    for (estimate, (model, value, lower, upper)) in icc.estimates.iter().zip(expected) {
+        assert_eq!(estimate.model, model);
+        for (actual, published) in [
+            (estimate.value, value),
+            (estimate.lower_bound, lower),
+            (estimate.upper_bound, upper),
+        ] {
+            assert!(
+                (actual - published).abs() <= 0.005,
+                "{}: {} against {}",
+                model,
+                actual,
+                published
+            );
+        }
+    }
+}
+
+// With one factor per scale, ordinal alpha and omega both equal 3r / (1 + 2r) for the scale's
+// mean polychoric correlation r
+#[test]
+fn ordinal_reliability_of_polychoric_scales() {
+    let mut config = sample_config();
+    config.extraction.polychoric = true;
+    config.extraction.method = ExtractionMethod::PrincipalAxisFactoring;
+    let (corr_matrix, loadings) = two_scale_polychoric();
+    let var_names: Vec<String> = ["a", "b", "c", "d", "e", "f"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+
+    let reliability = build_factor_reliability(&corr_matrix, &loadings, &var_names, &config)
+        .unwrap();
+    assert!(reliability.ordinal);
+    for (scale, r) in reliability.factors.iter().zip([0.5, 0.36]) {
+        let expected = (3.0 * r) / (1.0 + 2.0 * r);
+        assert_eq!(scale.items.len(), 3);
+        assert!((scale.alpha.unwrap() - expected).abs() < 1e-12);
+        assert!((scale.omega.unwrap() - expected).abs() < 1e-12);
+    }
+}
+
+// A matrix the model reproduces exactly gives back its loadings with a zero test of fit, and
+// with the weights the inverse of the asymptotic covariances the scaling factor is one
+#[test]
+fn weighted_least_squares_recovers_an_exact_model() {
+    let (corr_matrix, gamma, expected) = one_factor_polychoric();
+    let var_names: Vec<String> = ["a", "b", "c", "d"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+
+    let wlsmv = build_weighted_least_squares(
+        &corr_matrix,
+        &gamma,
+        HashMap::new(),
+        &var_names,
+        300,
+        1,
+        &sample_config()
+    ).unwrap();
+    assert!(wlsmv.converged);
+    for (var_name, loading) in var_names.iter().zip(expected) {
+        assert!((wlsmv.loadings[var_name][0] - loading).abs() < 1e-6);
+    }
+
+    let fit = &wlsmv.fit;
+    assert_eq!(fit.df, 2);
+    assert_eq!(fit.baseline_df, 6);
+    assert!(fit.chi_square.unwrap() < 1e-6);
+    assert!((fit.scaling_factor.unwrap() - 1.0).abs() < 1e-9);
+    // N times the weighted sum of the squared correlations
+    assert!((fit.baseline_chi_square.unwrap() - 32787.0).abs() < 1e-6);
+    assert!((fit.cfi.unwrap() - 1.0).abs() < 1e-9);
+    assert!(fit.srmr < 1e-6);
+}
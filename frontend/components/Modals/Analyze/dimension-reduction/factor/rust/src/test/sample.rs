@@ -0,0 +1,126 @@
+use nalgebra::DMatrix;
+
+use crate::models::{ config::FactorAnalysisConfig, data::AnalysisData };
+
+// Defaults of the dialog: principal components, eigenvalues over 1, varimax, 25 iterations
+const SAMPLE_CONFIG: &str =
+    r#"{
+    "main": { "TargetVar": ["a", "b", "c", "d", "e", "f"], "ValueTarget": null },
+    "value": { "Selection": null },
+    "descriptives": {
+        "UnivarDesc": true, "InitialSol": true, "Coefficient": true, "Inverse": false,
+        "SignificanceLvl": false, "Reproduced": false, "Determinant": true, "AntiImage": false,
+        "KMO": true
+    },
+    "extraction": {
+        "Method": "PrincipalComp", "Correlation": true, "Covariance": false, "Unrotated": true,
+        "Scree": false, "Eigen": true, "Factor": false, "EigenVal": 1.0, "MaxFactors": null,
+        "MaxIter": 25
+    },
+    "rotation": {
+        "None": false, "Varimax": true, "Oblimin": false, "Delta": 0.0, "Quartimax": false,
+        "Equimax": false, "Promax": false, "Kappa": 4, "RotatedSol": true, "LoadingPlot": false,
+        "MaxIter": 25
+    },
+    "scores": {
+        "SaveVar": false, "Regression": true, "Bartlett": false, "Anderson": false,
+        "DisplayFactor": false
+    },
+    "options": {
+        "ExcludeListWise": true, "ExcludePairWise": false, "ReplaceMean": false,
+        "SortSize": false, "SuppressValues": false, "SuppressValuesNum": 0.1
+    }
+}"#;
+
+pub fn sample_config() -> FactorAnalysisConfig {
+    serde_json::from_str(SAMPLE_CONFIG).expect("sample configuration parses")
+}
+
+// Eight cases of the six target variables with a grouping variable g
+const SAMPLE_DATA: &str =
+    r#"{
+    "target_data": [
+        [
+            { "a": 3, "b": 4, "c": 3, "d": 2, "e": 1, "f": 2, "g": 1 },
+            { "a": 4, "b": 4, "c": 5, "d": 1, "e": 2, "f": 1, "g": 1 },
+            { "a": 2, "b": 3, "c": 2, "d": 4, "e": 4, "f": 5, "g": 1 },
+            { "a": 5, "b": 5, "c": 4, "d": 2, "e": 3, "f": 2, "g": 1 },
+            { "a": 1, "b": 2, "c": 1, "d": 5, "e": 4, "f": 4, "g": 2 },
+            { "a": 3, "b": 3, "c": 4, "d": 3, "e": 3, "f": 3, "g": 2 },
+            { "a": 4, "b": 5, "c": 4, "d": 1, "e": 1, "f": 2, "g": 2 },
+            { "a": 2, "b": 1, "c": 2, "d": 4, "e": 5, "f": 4, "g": 2 }
+        ]
+    ],
+    "value_target_data": [],
+    "target_data_defs": [
+        [
+            { "columnIndex": 0, "name": "a", "type": "NUMERIC", "width": 8,
+              "decimals": 0, "values": [], "missing": [], "columns": 8,
+              "align": "right", "measure": "scale", "role": "input" },
+            { "columnIndex": 1, "name": "b", "type": "NUMERIC", "width": 8,
+              "decimals": 0, "values": [], "missing": [], "columns": 8,
+              "align": "right", "measure": "scale", "role": "input" },
+            { "columnIndex": 2, "name": "c", "type": "NUMERIC", "width": 8,
+              "decimals": 0, "values": [], "missing": [], "columns": 8,
+              "align": "right", "measure": "scale", "role": "input" },
+            { "columnIndex": 3, "name": "d", "type": "NUMERIC", "width": 8,
+              "decimals": 0, "values": [], "missing": [], "columns": 8,
+              "align": "right", "measure": "scale", "role": "input" },
+            { "columnIndex": 4, "name": "e", "type": "NUMERIC", "width": 8,
+              "decimals": 0, "values": [], "missing": [], "columns": 8,
+              "align": "right", "measure": "scale", "role": "input" },
+            { "columnIndex": 5, "name": "f", "type": "NUMERIC", "width": 8,
+              "decimals": 0, "values": [], "missing": [], "columns": 8,
+              "align": "right", "measure": "scale", "role": "input" },
+            { "columnIndex": 6, "name": "g", "type": "NUMERIC", "width": 8,
+              "decimals": 0, "values": [], "missing": [], "columns": 8,
+              "align": "right", "measure": "scale", "role": "input" }
+        ]
+    ],
+    "value_target_data_defs": []
+}"#;
+
+pub fn sample_data() -> AnalysisData {
+    serde_json::from_str(SAMPLE_DATA).expect("sample data parses")
+}
+
+// Second-difference matrix with eigenvalues 2 - sqrt(2), 2 and 2 + sqrt(2)
+pub fn tridiagonal_matrix() -> DMatrix<f64> {
+    DMatrix::from_row_slice(3, 3, &[2.0, -1.0, 0.0, -1.0, 2.0, -1.0, 0.0, -1.0, 2.0])
+}
+
+// Six targets rated by four judges (Shrout and Fleiss, 1979, table 2)
+pub fn shrout_fleiss_ratings() -> DMatrix<f64> {
+    DMatrix::from_row_slice(6, 4, &[
+        9.0, 2.0, 5.0, 8.0,
+        6.0, 1.0, 3.0, 2.0,
+        8.0, 4.0, 6.0, 8.0,
+        7.0, 1.0, 2.0, 6.0,
+        10.0, 5.0, 6.0, 9.0,
+        6.0, 2.0, 4.0, 7.0,
+    ])
+}
+
+// Polychoric matrix of two uncorrelated three-item scales with loadings sqrt(0.5) and 0.6
+pub fn two_scale_polychoric() -> (DMatrix<f64>, DMatrix<f64>) {
+    let loadings = DMatrix::from_row_slice(6, 2, &[
+        0.5_f64.sqrt(), 0.0,
+        0.5_f64.sqrt(), 0.0,
+        0.5_f64.sqrt(), 0.0,
+        0.0, 0.6,
+        0.0, 0.6,
+        0.0, 0.6,
+    ]);
+    let mut corr_matrix = &loadings * loadings.transpose();
+    corr_matrix.fill_diagonal(1.0);
+    (corr_matrix, loadings)
+}
+
+// One-factor polychoric matrix with loadings 0.8, 0.7, 0.6 and 0.5, and asymptotic covariances
+// of 0.01 on the diagonal only
+pub fn one_factor_polychoric() -> (DMatrix<f64>, DMatrix<f64>, [f64; 4]) {
+    let loadings = [0.8, 0.7, 0.6, 0.5];
+    let mut corr_matrix = DMatrix::from_fn(4, 4, |i, j| loadings[i] * loadings[j]);
+    corr_matrix.fill_diagonal(1.0);
+    (corr_matrix, DMatrix::from_diagonal_element(6, 6, 0.01), loadings)
+}
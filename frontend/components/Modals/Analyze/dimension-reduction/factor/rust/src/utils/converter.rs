@@ -2,13 +2,34 @@ use wasm_bindgen::JsValue;
 use serde::Serialize;
 
 use crate::models::result::{
+    AnalysisMetadata,
+    CaseInfluence,
+    ComponentFactorComparison,
     ComponentScoreCovarianceMatrix,
     ComponentTransformationMatrix,
+    CovarianceExtraction,
     DescriptiveStatistic,
+    DiscriminantValidity,
+    DistanceCorrelationMatrix,
+    EapScores,
+    EigenDiagnostics,
     FactorAnalysisResult,
+    FactorCountComparison,
+    FactorReliability,
+    FactorScoreDeterminacy,
+    GroupedDescriptiveStatistics,
+    IntermediateMatrices,
+    IntraclassCorrelation,
     KMOBartlettsTest,
+    MultidimensionalScaling,
+    PairwiseDeletion,
+    ParallelAnalysis,
+    PrincipalCoordinates,
+    ReproducibilityLog,
     ScreePlot,
+    SscpMatrix,
     TotalVarianceExplained,
+    WeightedLeastSquares,
 };
 
 pub fn string_to_js_error(error: String) -> JsValue {
@@ -28,19 +49,40 @@ pub fn format_result(result: &Option<FactorAnalysisResult>) -> Result<JsValue, J
 #[derive(Serialize)]
 struct FormatResult {
     descriptive_statistics: Option<Vec<DescriptiveStatistic>>,
+    grouped_descriptive_statistics: Option<GroupedDescriptiveStatistics>,
     scree_plot: Option<ScreePlot>,
     correlation_matrix: Option<FormattedCorrelation>,
+    pairwise_deletion: Option<PairwiseDeletion>,
+    distance_correlation_matrix: Option<DistanceCorrelationMatrix>,
+    sscp_matrix: Option<SscpMatrix>,
     inverse_correlation_matrix: Option<FormattedInverseCorrelation>,
     kmo_bartletts_test: Option<KMOBartlettsTest>,
+    intraclass_correlation: Option<IntraclassCorrelation>,
     anti_image_matrices: Option<FormattedAntiImage>,
     communalities: Option<FormattedCommunalities>,
     total_variance_explained: Option<TotalVarianceExplained>,
     component_matrix: Option<FormattedComponentMatrix>,
+    covariance_extraction: Option<CovarianceExtraction>,
+    eigen_diagnostics: Option<EigenDiagnostics>,
+    case_influence: Option<CaseInfluence>,
+    principal_coordinates: Option<PrincipalCoordinates>,
+    multidimensional_scaling: Option<MultidimensionalScaling>,
+    component_factor_comparison: Option<ComponentFactorComparison>,
+    eap_scores: Option<EapScores>,
     reproduced_correlations: Option<FormattedReproducedCorrelations>,
     rotated_component_matrix: Option<FormattedRotatedComponentMatrix>,
     component_transformation_matrix: Option<ComponentTransformationMatrix>,
     component_score_coefficient_matrix: Option<FormattedComponentScoreCoefficient>,
     component_score_covariance_matrix: Option<ComponentScoreCovarianceMatrix>,
+    factor_score_determinacy: Option<FactorScoreDeterminacy>,
+    factor_reliability: Option<FactorReliability>,
+    discriminant_validity: Option<DiscriminantValidity>,
+    parallel_analysis: Option<ParallelAnalysis>,
+    factor_count_comparison: Option<FactorCountComparison>,
+    weighted_least_squares: Option<WeightedLeastSquares>,
+    intermediate_matrices: Option<IntermediateMatrices>,
+    reproducibility: Option<ReproducibilityLog>,
+    metadata: Option<AnalysisMetadata>,
 }
 
 #[derive(Serialize)]
@@ -70,6 +112,7 @@ struct FormattedInverseCorrelation {
 struct FormattedAntiImage {
     anti_image_covariance: Vec<CorrelationEntry>,
     anti_image_correlation: Vec<CorrelationEntry>,
+    msa: Option<Vec<VariableValue>>,
 }
 
 #[derive(Serialize)]
@@ -211,9 +254,19 @@ impl FormatResult {
                 })
                 .collect();
 
+            let msa = matrices.msa.as_ref().map(|msa| {
+                msa.iter()
+                    .map(|(var_name, value)| VariableValue {
+                        variable: var_name.clone(),
+                        value: *value,
+                    })
+                    .collect()
+            });
+
             FormattedAntiImage {
                 anti_image_covariance,
                 anti_image_correlation,
+                msa,
             }
         });
 
@@ -339,19 +392,40 @@ impl FormatResult {
 
         FormatResult {
             descriptive_statistics: result.descriptive_statistics.clone(),
+            grouped_descriptive_statistics: result.grouped_descriptive_statistics.clone(),
             scree_plot: result.scree_plot.clone(),
             correlation_matrix,
+            pairwise_deletion: result.pairwise_deletion.clone(),
+            distance_correlation_matrix: result.distance_correlation_matrix.clone(),
+            sscp_matrix: result.sscp_matrix.clone(),
             inverse_correlation_matrix,
             kmo_bartletts_test: result.kmo_bartletts_test.clone(),
+            intraclass_correlation: result.intraclass_correlation.clone(),
             anti_image_matrices,
             communalities,
             total_variance_explained: result.total_variance_explained.clone(),
             component_matrix,
+            covariance_extraction: result.covariance_extraction.clone(),
+            eigen_diagnostics: result.eigen_diagnostics.clone(),
+            case_influence: result.case_influence.clone(),
+            principal_coordinates: result.principal_coordinates.clone(),
+            multidimensional_scaling: result.multidimensional_scaling.clone(),
+            component_factor_comparison: result.component_factor_comparison.clone(),
+            eap_scores: result.eap_scores.clone(),
             reproduced_correlations,
             rotated_component_matrix,
             component_transformation_matrix: result.component_transformation_matrix.clone(),
             component_score_coefficient_matrix,
             component_score_covariance_matrix: result.component_score_covariance_matrix.clone(),
+            factor_score_determinacy: result.factor_score_determinacy.clone(),
+            factor_reliability: result.factor_reliability.clone(),
+            discriminant_validity: result.discriminant_validity.clone(),
+            parallel_analysis: result.parallel_analysis.clone(),
+            factor_count_comparison: result.factor_count_comparison.clone(),
+            weighted_least_squares: result.weighted_least_squares.clone(),
+            intermediate_matrices: result.intermediate_matrices.clone(),
+            reproducibility: result.reproducibility.clone(),
+            metadata: result.metadata.clone(),
         }
     }
 }
@@ -7,6 +7,8 @@ pub type AnalysisResult<T> = Result<T, String>;
 #[derive(Debug, Clone, Default)]
 pub struct ErrorCollector {
     errors: HashMap<String, Vec<String>>,
+    // Peringatan tidak menghentikan analisis, misalnya variabel yang dikeluarkan
+    warnings: HashMap<String, Vec<String>>,
 }
 
 impl ErrorCollector {
@@ -16,6 +18,34 @@ impl ErrorCollector {
         entry.push(message.to_string());
     }
 
+    // Menambahkan peringatan baru ke collector
+    pub fn add_warning(&mut self, context: &str, message: &str) {
+        let entry = self.warnings.entry(context.to_string()).or_default();
+        entry.push(message.to_string());
+    }
+
+    // Mengecek apakah ada peringatan
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    // Mendapatkan seluruh peringatan sebagai formatted string
+    pub fn get_warning_summary(&self) -> String {
+        if !self.has_warnings() {
+            return "No warnings occurred.".to_string();
+        }
+
+        let mut summary = String::from("Warning Summary:\n");
+        for (context, warnings) in &self.warnings {
+            summary.push_str(&format!("Context: {}\n", context));
+            for (i, warning) in warnings.iter().enumerate() {
+                summary.push_str(&format!("  {}. {}\n", i + 1, warning));
+            }
+        }
+
+        summary
+    }
+
     // Mengecek apakah ada error
     pub fn has_errors(&self) -> bool {
         !self.errors.is_empty()
@@ -41,5 +71,6 @@ impl ErrorCollector {
     // Reset error collector
     pub fn clear(&mut self) {
         self.errors.clear();
+        self.warnings.clear();
     }
 }
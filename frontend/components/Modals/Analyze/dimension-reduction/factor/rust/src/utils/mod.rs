@@ -1,3 +1,6 @@
 pub mod converter;
 pub mod error;
 pub mod log;
+pub mod rng;
+pub mod timing;
+pub mod trace;
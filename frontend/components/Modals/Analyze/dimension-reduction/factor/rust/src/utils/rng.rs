@@ -0,0 +1,48 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::models::config::FactorAnalysisConfig;
+
+// Seed bawaan ketika konfigurasi tidak menyediakan seed (sama dengan SPSS)
+pub const DEFAULT_SEED: u64 = 2_000_000;
+
+// Tipe PRNG deterministik yang dipakai seluruh prosedur stokastik
+pub type AnalysisRng = ChaCha8Rng;
+
+// Mendapatkan seed efektif dari konfigurasi
+pub fn resolve_seed(config: &FactorAnalysisConfig) -> u64 {
+    config.options.seed.unwrap_or(DEFAULT_SEED)
+}
+
+// Membuat PRNG ChaCha dari seed sehingga hasil identik di semua platform
+pub fn create_rng(seed: u64) -> AnalysisRng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+// PRNG untuk satu replikasi: seed utama yang sama dengan aliran (stream) ChaCha per replikasi,
+// sehingga setiap replikasi independen dan tidak bergantung pada urutan eksekusi
+pub fn create_replicate_rng(seed: u64, replicate: usize) -> AnalysisRng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(replicate as u64);
+    rng
+}
+
+// Menjalankan replikasi (bootstrap, analisis paralel) dengan PRNG per replikasi; dengan fitur
+// `parallel` replikasi dibagi ke beberapa thread dan hasilnya tetap identik berapa pun threadnya
+pub fn run_replicates<T, F>(seed: u64, replicates: usize, replicate: F) -> Vec<T>
+    where T: Send, F: Fn(usize, &mut AnalysisRng) -> T + Sync + Send
+{
+    let run = |index: usize| {
+        let mut rng = create_replicate_rng(seed, index);
+        replicate(index, &mut rng)
+    };
+
+    #[cfg(feature = "parallel")]
+    let results = (0..replicates).into_par_iter().map(run).collect();
+    #[cfg(not(feature = "parallel"))]
+    let results = (0..replicates).map(run).collect();
+
+    results
+}
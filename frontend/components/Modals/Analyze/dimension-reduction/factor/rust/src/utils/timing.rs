@@ -0,0 +1,53 @@
+use crate::models::result::StageTiming;
+
+// Waktu saat ini dalam milidetik (jam browser ketika berjalan di wasm)
+pub fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime
+            ::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs_f64() * 1000.0)
+            .unwrap_or(0.0)
+    }
+}
+
+// Structure untuk pencatatan durasi setiap tahap analisis
+#[derive(Debug, Clone)]
+pub struct StageTimer {
+    started_at: f64,
+    timings: Vec<StageTiming>,
+}
+
+impl StageTimer {
+    // Memulai pencatatan waktu analisis
+    pub fn start() -> Self {
+        StageTimer {
+            started_at: now_ms(),
+            timings: Vec::new(),
+        }
+    }
+
+    // Mencatat durasi sebuah tahap yang dimulai pada `stage_started_at`
+    pub fn record(&mut self, stage: &str, stage_started_at: f64) {
+        self.timings.push(StageTiming {
+            stage: stage.to_string(),
+            duration_ms: (now_ms() - stage_started_at).max(0.0),
+        });
+    }
+
+    // Total waktu sejak analisis dimulai
+    pub fn elapsed_ms(&self) -> f64 {
+        (now_ms() - self.started_at).max(0.0)
+    }
+
+    // Mendapatkan seluruh durasi tahap yang tercatat
+    pub fn timings(&self) -> Vec<StageTiming> {
+        self.timings.clone()
+    }
+}
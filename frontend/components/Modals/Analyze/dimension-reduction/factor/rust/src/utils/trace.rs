@@ -0,0 +1,39 @@
+// Instrumentasi profiling dengan crate `tracing`. Tanpa fitur "tracing" makro di bawah tidak
+// menghasilkan span maupun event, sehingga build biasa tidak terbebani.
+
+// Guard span yang aktif; tanpa fitur "tracing" hanya struktur kosong
+#[cfg(feature = "tracing")]
+pub type SpanGuard = tracing::span::EnteredSpan;
+
+#[cfg(not(feature = "tracing"))]
+pub struct SpanGuard;
+
+// Membuka span bernama dengan field numerik atau teks, aktif sampai guard di-drop
+#[macro_export]
+macro_rules! trace_span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        let guard: $crate::utils::trace::SpanGuard = tracing::info_span!(
+            $name $(, $field = $value)*
+        ).entered();
+        #[cfg(not(feature = "tracing"))]
+        let guard = {
+            $( let _ = &$value; )*
+            $crate::utils::trace::SpanGuard
+        };
+        guard
+    }};
+}
+
+// Mencatat event di dalam span yang sedang aktif
+#[macro_export]
+macro_rules! trace_event {
+    ($message:literal $(, $field:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        tracing::info!($( $field = $value, )* $message);
+        #[cfg(not(feature = "tracing"))]
+        {
+            $( let _ = &$value; )*
+        }
+    }};
+}
@@ -1,10 +1,22 @@
+use std::collections::HashMap;
+
+use nalgebra::DMatrix;
 use wasm_bindgen::prelude::*;
 
+use crate::format::table::FormatOptions;
 use crate::models::{
-    config::FactorAnalysisConfig,
-    data::{ AnalysisData, DataRecord, VariableDefinition },
-    result::FactorAnalysisResult,
+    config::{
+        AlignmentMethod,
+        DataMode,
+        FactorAnalysisConfig,
+        PowerConfig,
+        SimulationConfig,
+        VariableBlock,
+    },
+    data::{ AnalysisData, DataRecord, LongRecord, VariableDefinition },
+    result::{ AnalysisSession, FactorAnalysisResult, MdsDistance, StandardizedCovariance },
 };
+use crate::stats::core;
 use crate::utils::{ converter::string_to_js_error, error::ErrorCollector };
 use crate::wasm::function;
 
@@ -13,6 +25,7 @@ pub struct FactorAnalysis {
     config: FactorAnalysisConfig,
     data: AnalysisData,
     result: Option<FactorAnalysisResult>,
+    session: Option<AnalysisSession>,
     error_collector: ErrorCollector,
 }
 
@@ -29,108 +42,379 @@ impl FactorAnalysis {
         // Initialize error collector
         let mut error_collector = ErrorCollector::default();
 
-        // Parse configuration
-        let config: FactorAnalysisConfig = match
-            serde_wasm_bindgen::from_value(config_data.clone())
-        {
-            Ok(data) => data,
-            Err(e) => {
-                let msg =
-                    format!("Failed to parse configuration: {}. Ensure field names match the expected format.", e);
-                error_collector.add_error("constructor.config", &msg);
-
-                // Try to get more detailed error by inspecting the config data
-                if let Ok(config_json) = js_sys::JSON::stringify(&config_data) {
-                    let config_str = config_json.as_string().unwrap_or_default();
-                    error_collector.add_error(
-                        "constructor.config.raw",
-                        &format!("Raw config: {}", config_str)
-                    );
-                }
+        let (config, data) = parse_input(
+            target_data,
+            value_target_data,
+            target_data_defs,
+            value_target_data_defs,
+            config_data,
+            &mut error_collector
+        )?;
 
-                return Err(string_to_js_error(msg));
-            }
-        };
+        FactorAnalysis::analyze(config, data, error_collector, None)
+    }
+
+    // Same as the constructor, calling on_stage with each stage's part of the result as soon as
+    // it is ready. Data above the memory limit is analyzed from a session and only returns the
+    // final result.
+    pub fn with_stages(
+        target_data: JsValue,
+        value_target_data: JsValue,
+        target_data_defs: JsValue,
+        value_target_data_defs: JsValue,
+        config_data: JsValue,
+        on_stage: js_sys::Function
+    ) -> Result<FactorAnalysis, JsValue> {
+        let mut error_collector = ErrorCollector::default();
+
+        let (config, data) = parse_input(
+            target_data,
+            value_target_data,
+            target_data_defs,
+            value_target_data_defs,
+            config_data,
+            &mut error_collector
+        )?;
+
+        FactorAnalysis::analyze(config, data, error_collector, Some(&on_stage))
+    }
 
-        // Parse target data
-        let target_data: Vec<Vec<DataRecord>> = match serde_wasm_bindgen::from_value(target_data) {
+    // Same as the constructor, with the target data as long-format (caseId, variable, value)
+    // cells. Variable definitions are inferred when target_data_defs is empty.
+    pub fn from_long_data(
+        long_data: JsValue,
+        value_target_data: JsValue,
+        target_data_defs: JsValue,
+        value_target_data_defs: JsValue,
+        config_data: JsValue
+    ) -> Result<FactorAnalysis, JsValue> {
+        let mut error_collector = ErrorCollector::default();
+
+        let (config, mut data) = parse_input(
+            js_sys::Array::new().into(),
+            value_target_data,
+            target_data_defs,
+            value_target_data_defs,
+            config_data,
+            &mut error_collector
+        )?;
+
+        let long_data: Vec<LongRecord> = match serde_wasm_bindgen::from_value(long_data) {
             Ok(data) => data,
             Err(e) => {
-                let msg = format!("Failed to parse target data: {}", e);
-                error_collector.add_error("constructor.target_data", &msg);
+                let msg = format!("Failed to parse long-format data: {}", e);
+                error_collector.add_error("from_long_data.long_data", &msg);
                 return Err(string_to_js_error(msg));
             }
         };
 
-        // Parse value target data
-        let value_target_data: Vec<Vec<DataRecord>> = match
-            serde_wasm_bindgen::from_value(value_target_data)
-        {
-            Ok(data) => data,
+        let (records, variables) = match core::pivot_long_data(&long_data, &config) {
+            Ok(pivoted) => pivoted,
             Err(e) => {
-                let msg = format!("Failed to parse value target data: {}", e);
-                error_collector.add_error("constructor.value_target_data", &msg);
-                return Err(string_to_js_error(msg));
+                error_collector.add_error("pivot_long_data", &e);
+                return Err(string_to_js_error(e));
             }
         };
+        if data.target_data_defs.iter().all(|defs| defs.is_empty()) {
+            data.target_data_defs = vec![core::infer_variable_definitions(&records, &variables)];
+        }
+        data.target_data = vec![records];
+
+        FactorAnalysis::analyze(config, data, error_collector, None)
+    }
+
+    // Rebuild the analysis from a saved session, e.g. with a different rotation or score method
+    pub fn from_session(
+        session_data: &str,
+        config_data: JsValue
+    ) -> Result<FactorAnalysis, JsValue> {
+        let mut error_collector = ErrorCollector::default();
 
-        // Parse target data definitions
-        let target_data_defs: Vec<Vec<VariableDefinition>> = match
-            serde_wasm_bindgen::from_value(target_data_defs)
-        {
+        let config: FactorAnalysisConfig = match serde_wasm_bindgen::from_value(config_data) {
             Ok(data) => data,
             Err(e) => {
-                let msg = format!("Failed to parse target data definitions: {}", e);
-                error_collector.add_error("constructor.target_data_defs", &msg);
+                let msg = format!("Failed to parse configuration: {}", e);
+                error_collector.add_error("from_session.config", &msg);
                 return Err(string_to_js_error(msg));
             }
         };
 
-        // Parse value target data definitions
-        let value_target_data_defs: Vec<Vec<VariableDefinition>> = match
-            serde_wasm_bindgen::from_value(value_target_data_defs)
-        {
-            Ok(data) => data,
+        let mut session = match core::session_from_json(session_data) {
+            Ok(session) => session,
             Err(e) => {
-                let msg = format!("Failed to parse value target data definitions: {}", e);
-                error_collector.add_error("constructor.value_target_data_defs", &msg);
-                return Err(string_to_js_error(msg));
+                error_collector.add_error("from_session.session", &e);
+                return Err(string_to_js_error(e));
             }
         };
 
-        // Validate configuration
-        if config.main.target_var.as_ref().map_or(true, |vars| vars.is_empty()) {
-            let msg = "No target variables selected for factor analysis".to_string();
-            error_collector.add_error("config.validation.target_var", &msg);
-            return Err(string_to_js_error(msg));
-        }
+        let result = function::run_session_analysis(&session, &config, &mut error_collector)?;
+
+        // Keep the latest rotation and score settings in the session for the next export
+        session.config = config.clone();
 
-        // Create analysis data structure
-        let data = AnalysisData {
+        Ok(FactorAnalysis {
+            config,
+            data: AnalysisData {
+                target_data: Vec::new(),
+                value_target_data: Vec::new(),
+                target_data_defs: Vec::new(),
+                value_target_data_defs: Vec::new(),
+            },
+            result,
+            session: Some(session),
+            error_collector,
+        })
+    }
+
+    // Serialize the correlation matrix and unrotated solution so the analysis can be resumed
+    pub fn export_session(&self) -> Result<JsValue, JsValue> {
+        function::export_session(&self.data, &self.config, &self.session)
+    }
+
+    // Solution as JSON with the field names of R's psych::fa output
+    pub fn export_psych(&self) -> Result<JsValue, JsValue> {
+        function::export_psych(&self.data, &self.config, &self.session)
+    }
+
+    // Run the same analysis on several variable blocks, e.g. one per questionnaire scale
+    pub fn analyze_blocks(
+        target_data: JsValue,
+        value_target_data: JsValue,
+        target_data_defs: JsValue,
+        value_target_data_defs: JsValue,
+        config_data: JsValue,
+        blocks_data: JsValue
+    ) -> Result<JsValue, JsValue> {
+        let mut error_collector = ErrorCollector::default();
+
+        let (config, data) = parse_input(
             target_data,
             value_target_data,
             target_data_defs,
             value_target_data_defs,
-        };
+            config_data,
+            &mut error_collector
+        )?;
+        if let Err(msg) = core::validate_config(&config) {
+            error_collector.add_error("config.validation", &msg);
+            return Err(string_to_js_error(msg));
+        }
 
-        // Create the analysis instance
-        let mut analysis = FactorAnalysis {
-            config,
-            data,
-            result: None,
-            error_collector,
+        let blocks: Vec<VariableBlock> = match serde_wasm_bindgen::from_value(blocks_data) {
+            Ok(blocks) => blocks,
+            Err(e) => {
+                let msg = format!("Failed to parse variable blocks: {}", e);
+                error_collector.add_error("analyze_blocks.blocks", &msg);
+                return Err(string_to_js_error(msg));
+            }
         };
 
-        // Run the analysis
-        match
-            function::run_analysis(&analysis.data, &analysis.config, &mut analysis.error_collector)
-        {
-            Ok(result) => {
-                analysis.result = result;
-                Ok(analysis)
-            }
-            Err(e) => Err(e),
+        let results = function::run_block_analyses(&data, &config, &blocks, &mut error_collector)?;
+        serde_wasm_bindgen::to_value(&results).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // All eigenvalues and eigenvectors of the analyzed matrix, rows in variable order
+    pub fn eigen_decomposition(&self) -> Result<JsValue, JsValue> {
+        function::eigen_decomposition(&self.data, &self.config, &self.session)
+    }
+
+    // Canonical correlations between two lists of variables of the dataset
+    pub fn canonical_correlation(
+        &self,
+        set_one: Vec<String>,
+        set_two: Vec<String>
+    ) -> Result<JsValue, JsValue> {
+        if self.data.target_data.is_empty() {
+            let msg = "Canonical correlation needs the data; this analysis only has a session";
+            return Err(string_to_js_error(msg.to_string()));
+        }
+
+        let canonical = core::calculate_canonical_correlation(
+            &self.data,
+            &self.config,
+            &set_one,
+            &set_two
+        ).map_err(string_to_js_error)?;
+        serde_wasm_bindgen::to_value(&canonical).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Data points, correlation and regression line behind one cell of the correlation matrix
+    pub fn pair_detail(
+        &self,
+        variable_x: String,
+        variable_y: String,
+        confidence: Option<f64>
+    ) -> Result<JsValue, JsValue> {
+        if self.data.target_data.is_empty() {
+            let msg = "Pair detail needs the data; this analysis only has a session".to_string();
+            return Err(string_to_js_error(msg));
         }
+
+        let detail = core::calculate_pair_detail(
+            &self.data,
+            &self.config,
+            &variable_x,
+            &variable_y,
+            confidence
+        ).map_err(string_to_js_error)?;
+        serde_wasm_bindgen::to_value(&detail).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Structured diff against another completed analysis
+    pub fn compare_with(&self, other: &FactorAnalysis) -> Result<JsValue, JsValue> {
+        function::compare_results(&self.result, &other.result)
+    }
+
+    // Same comparison for two results previously returned by get_results
+    pub fn compare_result_values(result_a: JsValue, result_b: JsValue) -> Result<JsValue, JsValue> {
+        let result_a: FactorAnalysisResult = serde_wasm_bindgen::from_value(result_a)
+            .map_err(|e| string_to_js_error(format!("Failed to parse first result: {}", e)))?;
+        let result_b: FactorAnalysisResult = serde_wasm_bindgen::from_value(result_b)
+            .map_err(|e| string_to_js_error(format!("Failed to parse second result: {}", e)))?;
+
+        function::compare_results(&Some(result_a), &Some(result_b))
+    }
+
+    // Rotate, reflect or reorder this solution to best match a reference analysis
+    pub fn align_with(
+        &self,
+        reference: &FactorAnalysis,
+        method_data: JsValue
+    ) -> Result<JsValue, JsValue> {
+        let method = parse_alignment_method(method_data)?;
+        function::align_results(&self.result, &reference.result, &method)
+    }
+
+    // Alignment of a loading matrix keyed by variable name, independent of any analysis
+    pub fn align_loading_values(
+        loadings: JsValue,
+        reference: JsValue,
+        method_data: JsValue
+    ) -> Result<JsValue, JsValue> {
+        let loadings: HashMap<String, Vec<f64>> = serde_wasm_bindgen::from_value(loadings)
+            .map_err(|e| string_to_js_error(format!("Failed to parse loadings: {}", e)))?;
+        let reference: HashMap<String, Vec<f64>> = serde_wasm_bindgen::from_value(reference)
+            .map_err(|e| string_to_js_error(format!("Failed to parse reference: {}", e)))?;
+        let method = parse_alignment_method(method_data)?;
+
+        let alignment = core::align_factor_loadings(&loadings, &reference, &method)
+            .map_err(string_to_js_error)?;
+        serde_wasm_bindgen::to_value(&alignment).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Classical scaling of a distance matrix given as rows, with one name per row
+    pub fn scale_distance_values(
+        distances: JsValue,
+        names: JsValue,
+        dimensions: usize
+    ) -> Result<JsValue, JsValue> {
+        let distances: Vec<Vec<f64>> = serde_wasm_bindgen::from_value(distances)
+            .map_err(|e| string_to_js_error(format!("Failed to parse distances: {}", e)))?;
+        let names: Vec<String> = serde_wasm_bindgen::from_value(names)
+            .map_err(|e| string_to_js_error(format!("Failed to parse names: {}", e)))?;
+        if distances.iter().any(|row| row.len() != distances.len()) {
+            return Err(string_to_js_error("Distance matrix must be square".to_string()));
+        }
+
+        let matrix = DMatrix::from_fn(distances.len(), distances.len(), |i, j| distances[i][j]);
+        let scaling = core::build_multidimensional_scaling(
+            &matrix,
+            &names,
+            dimensions,
+            MdsDistance::Supplied
+        ).map_err(string_to_js_error)?;
+        serde_wasm_bindgen::to_value(&scaling).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Eigenvalues and eigenvectors of a symmetric matrix given as rows, for the named variables
+    pub fn eigen_decomposition_values(
+        matrix_data: JsValue,
+        var_names: Vec<String>
+    ) -> Result<JsValue, JsValue> {
+        let matrix = parse_matrix_rows(matrix_data, "matrix")?;
+        let decomposition = core::build_eigen_decomposition(&matrix, &var_names).map_err(
+            string_to_js_error
+        )?;
+        serde_wasm_bindgen::to_value(&decomposition).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Correlations and standard deviations of a covariance matrix given as rows
+    pub fn covariance_to_correlation_values(covariances: JsValue) -> Result<JsValue, JsValue> {
+        let matrix = parse_matrix_rows(covariances, "covariance matrix")?;
+        let (corr_matrix, std_deviations) = core::covariance_to_correlation(&matrix).map_err(
+            string_to_js_error
+        )?;
+
+        let converted = StandardizedCovariance {
+            correlation_matrix: core::matrix_to_rows(&corr_matrix),
+            std_deviations,
+        };
+        serde_wasm_bindgen::to_value(&converted).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Covariance matrix rows from correlation rows and the standard deviations
+    pub fn correlation_to_covariance_values(
+        correlations: JsValue,
+        std_deviations: Vec<f64>
+    ) -> Result<JsValue, JsValue> {
+        let matrix = parse_matrix_rows(correlations, "correlation matrix")?;
+        let cov_matrix = core::correlation_to_covariance(&matrix, &std_deviations).map_err(
+            string_to_js_error
+        )?;
+        let rows = core::matrix_to_rows(&cov_matrix);
+        serde_wasm_bindgen::to_value(&rows).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Loadings moved between the standardized scale and the scale of the variables
+    pub fn rescale_loading_values(
+        loadings: JsValue,
+        std_deviations: Vec<f64>,
+        to_standardized: bool
+    ) -> Result<JsValue, JsValue> {
+        let matrix = parse_matrix_rows(loadings, "loadings")?;
+        let rescaled = if to_standardized {
+            core::standardize_loadings(&matrix, &std_deviations)
+        } else {
+            core::unstandardize_loadings(&matrix, &std_deviations)
+        }.map_err(string_to_js_error)?;
+        let rows = core::matrix_to_rows(&rescaled);
+        serde_wasm_bindgen::to_value(&rows).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Data set drawn from a factor model, in the same layout as the constructor's target data
+    pub fn simulate_data(spec_data: JsValue) -> Result<JsValue, JsValue> {
+        let spec: SimulationConfig = serde_wasm_bindgen::from_value(spec_data)
+            .map_err(|e| string_to_js_error(format!("Failed to parse simulation: {}", e)))?;
+
+        let data = core::simulate_factor_data(&spec).map_err(string_to_js_error)?;
+        serde_wasm_bindgen::to_value(&data).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Cases needed to recover a hypothesized structure, using the extraction settings of the
+    // configuration; meant for the planning step before any data exist
+    pub fn plan_sample_size(config_data: JsValue, spec_data: JsValue) -> Result<JsValue, JsValue> {
+        let config: FactorAnalysisConfig = serde_wasm_bindgen::from_value(config_data)
+            .map_err(|e| string_to_js_error(format!("Failed to parse configuration: {}", e)))?;
+        let spec: PowerConfig = serde_wasm_bindgen::from_value(spec_data)
+            .map_err(|e| string_to_js_error(format!("Failed to parse sample size plan: {}", e)))?;
+
+        let plan = core::build_sample_size_plan(&spec, &config).map_err(string_to_js_error)?;
+        serde_wasm_bindgen::to_value(&plan).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Embedded reference cases run with the iteration settings of the configuration, with the
+    // largest deviation from each expected statistic
+    pub fn validate_reference(config_data: JsValue) -> Result<JsValue, JsValue> {
+        let config: FactorAnalysisConfig = serde_wasm_bindgen::from_value(config_data)
+            .map_err(|e| string_to_js_error(format!("Failed to parse configuration: {}", e)))?;
+
+        let report = core::run_validation(&config);
+        serde_wasm_bindgen::to_value(&report).map_err(|e| string_to_js_error(e.to_string()))
+    }
+
+    // Drop the matrices and extractions kept by the Cache option, e.g. after the data changed
+    pub fn clear_cache() {
+        core::clear_analysis_cache();
     }
 
     // Function to get results
@@ -142,13 +426,242 @@ impl FactorAnalysis {
         function::get_formatted_results(&self.result)
     }
 
+    // Display-ready table descriptions; options default to the dialog's display settings
+    pub fn get_tables(&self, options_data: JsValue) -> Result<JsValue, JsValue> {
+        let options = parse_format_options(options_data)?;
+
+        function::get_tables(&self.result, &self.config, &self.var_names(), options)
+    }
+
+    // Tables rendered as "csv", "markdown" or "html"; titles_data optionally selects tables
+    pub fn export_tables(
+        &self,
+        format: &str,
+        options_data: JsValue,
+        titles_data: JsValue
+    ) -> Result<JsValue, JsValue> {
+        let options = parse_format_options(options_data)?;
+        let titles: Option<Vec<String>> = if titles_data.is_undefined() || titles_data.is_null() {
+            None
+        } else {
+            match serde_wasm_bindgen::from_value(titles_data) {
+                Ok(titles) => Some(titles),
+                Err(e) => {
+                    return Err(string_to_js_error(format!("Failed to parse table titles: {}", e)));
+                }
+            }
+        };
+
+        function::export_tables(
+            &self.result,
+            &self.config,
+            &self.var_names(),
+            format,
+            options,
+            titles
+        )
+    }
+
     // Function to get all errors
     pub fn get_all_errors(&self) -> JsValue {
         function::get_all_errors(&self.error_collector)
     }
 
+    // Function to get all warnings
+    pub fn get_all_warnings(&self) -> JsValue {
+        function::get_all_warnings(&self.error_collector)
+    }
+
     // Function to clear errors
     pub fn clear_errors(&mut self) -> JsValue {
         function::clear_errors(&mut self.error_collector)
     }
 }
+
+impl FactorAnalysis {
+    // Validate the configuration and run the analysis on parsed input
+    fn analyze(
+        config: FactorAnalysisConfig,
+        data: AnalysisData,
+        mut error_collector: ErrorCollector,
+        on_stage: Option<&js_sys::Function>
+    ) -> Result<FactorAnalysis, JsValue> {
+        // Validate configuration
+        if let Err(msg) = core::validate_config(&config) {
+            error_collector.add_error("config.validation", &msg);
+            return Err(string_to_js_error(msg));
+        }
+
+        // Create the analysis instance
+        let mut analysis = FactorAnalysis {
+            config,
+            data,
+            result: None,
+            session: None,
+            error_collector,
+        };
+
+        // Data above the memory limit is never materialized as a matrix
+        let data_mode = match core::assess_data_mode(&analysis.data, &analysis.config) {
+            Ok(data_mode) => data_mode,
+            Err(e) => {
+                analysis.error_collector.add_error("assess_data_mode", &e);
+                return Err(string_to_js_error(e));
+            }
+        };
+
+        // Run the analysis
+        if data_mode.mode == DataMode::InMemory {
+            analysis.result = function::run_analysis(
+                &analysis.data,
+                &analysis.config,
+                &mut analysis.error_collector,
+                on_stage
+            )?;
+        } else {
+            let (result, session) = function::run_large_data_analysis(
+                &analysis.data,
+                &analysis.config,
+                data_mode,
+                &mut analysis.error_collector
+            )?;
+            analysis.result = result;
+            analysis.session = Some(session);
+        }
+
+        Ok(analysis)
+    }
+
+    // Variables in analysis order, from the session when the data isn't available
+    fn var_names(&self) -> Vec<String> {
+        match &self.session {
+            Some(session) => session.var_names.clone(),
+            None => core::resolve_variable_names(&self.data, &self.config).unwrap_or_default(),
+        }
+    }
+}
+
+// Optional display options; undefined or null means "use the configuration"
+fn parse_format_options(options_data: JsValue) -> Result<Option<FormatOptions>, JsValue> {
+    if options_data.is_undefined() || options_data.is_null() {
+        return Ok(None);
+    }
+
+    match serde_wasm_bindgen::from_value(options_data) {
+        Ok(options) => Ok(Some(options)),
+        Err(e) => Err(string_to_js_error(format!("Failed to parse format options: {}", e))),
+    }
+}
+
+// Matrix given as rows of equal length
+fn parse_matrix_rows(rows_data: JsValue, label: &str) -> Result<DMatrix<f64>, JsValue> {
+    let rows: Vec<Vec<f64>> = serde_wasm_bindgen::from_value(rows_data)
+        .map_err(|e| string_to_js_error(format!("Failed to parse {}: {}", label, e)))?;
+    let n_cols = rows.first().map_or(0, |row| row.len());
+    if rows.iter().any(|row| row.len() != n_cols) {
+        let msg = format!("Every row of the {} needs {} values", label, n_cols);
+        return Err(string_to_js_error(msg));
+    }
+
+    Ok(DMatrix::from_fn(rows.len(), n_cols, |i, j| rows[i][j]))
+}
+
+// Alignment method name; undefined or null means orthogonal Procrustes
+fn parse_alignment_method(method_data: JsValue) -> Result<AlignmentMethod, JsValue> {
+    if method_data.is_undefined() || method_data.is_null() {
+        return Ok(AlignmentMethod::default());
+    }
+
+    serde_wasm_bindgen::from_value(method_data).map_err(|e|
+        string_to_js_error(format!("Failed to parse alignment method: {}", e))
+    )
+}
+
+// Parse the JavaScript inputs shared by every entry point
+fn parse_input(
+    target_data: JsValue,
+    value_target_data: JsValue,
+    target_data_defs: JsValue,
+    value_target_data_defs: JsValue,
+    config_data: JsValue,
+    error_collector: &mut ErrorCollector
+) -> Result<(FactorAnalysisConfig, AnalysisData), JsValue> {
+    // Parse configuration
+    let config: FactorAnalysisConfig = match
+        serde_wasm_bindgen::from_value(config_data.clone())
+    {
+        Ok(data) => data,
+        Err(e) => {
+            let msg =
+                format!("Failed to parse configuration: {}. Ensure field names match the expected format.", e);
+            error_collector.add_error("constructor.config", &msg);
+
+            // Try to get more detailed error by inspecting the config data
+            if let Ok(config_json) = js_sys::JSON::stringify(&config_data) {
+                let config_str = config_json.as_string().unwrap_or_default();
+                error_collector.add_error(
+                    "constructor.config.raw",
+                    &format!("Raw config: {}", config_str)
+                );
+            }
+
+            return Err(string_to_js_error(msg));
+        }
+    };
+
+    // Parse target data
+    let target_data: Vec<Vec<DataRecord>> = match serde_wasm_bindgen::from_value(target_data) {
+        Ok(data) => data,
+        Err(e) => {
+            let msg = format!("Failed to parse target data: {}", e);
+            error_collector.add_error("constructor.target_data", &msg);
+            return Err(string_to_js_error(msg));
+        }
+    };
+
+    // Parse value target data
+    let value_target_data: Vec<Vec<DataRecord>> = match
+        serde_wasm_bindgen::from_value(value_target_data)
+    {
+        Ok(data) => data,
+        Err(e) => {
+            let msg = format!("Failed to parse value target data: {}", e);
+            error_collector.add_error("constructor.value_target_data", &msg);
+            return Err(string_to_js_error(msg));
+        }
+    };
+
+    // Parse target data definitions
+    let target_data_defs: Vec<Vec<VariableDefinition>> = match
+        serde_wasm_bindgen::from_value(target_data_defs)
+    {
+        Ok(data) => data,
+        Err(e) => {
+            let msg = format!("Failed to parse target data definitions: {}", e);
+            error_collector.add_error("constructor.target_data_defs", &msg);
+            return Err(string_to_js_error(msg));
+        }
+    };
+
+    // Parse value target data definitions
+    let value_target_data_defs: Vec<Vec<VariableDefinition>> = match
+        serde_wasm_bindgen::from_value(value_target_data_defs)
+    {
+        Ok(data) => data,
+        Err(e) => {
+            let msg = format!("Failed to parse value target data definitions: {}", e);
+            error_collector.add_error("constructor.value_target_data_defs", &msg);
+            return Err(string_to_js_error(msg));
+        }
+    };
+
+    // Create analysis data structure
+    let data = AnalysisData {
+        target_data,
+        value_target_data,
+        target_data_defs,
+        value_target_data_defs,
+    };
+
+    Ok((config, data))
+}
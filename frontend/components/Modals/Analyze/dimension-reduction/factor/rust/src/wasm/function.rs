@@ -1,275 +1,294 @@
 use wasm_bindgen::prelude::*;
 
 use crate::models::{
-    config::FactorAnalysisConfig,
+    config::{ AlignmentMethod, FactorAnalysisConfig, VariableBlock },
     data::AnalysisData,
-    result::FactorAnalysisResult,
+    result::{
+        AnalysisSession,
+        BlockAnalysisResult,
+        DataModeReport,
+        FactorAnalysisResult,
+        StageResult,
+    },
+};
+use crate::format::{
+    export::{ render_tables, ExportFormat },
+    table::FormatOptions,
+    tables::build_result_tables,
 };
 use crate::stats::core;
 use crate::utils::converter::format_result;
 use crate::utils::{ converter::string_to_js_error, error::ErrorCollector };
 
+// Every stage from the data; with on_stage, each finished stage is passed to it as it completes
 pub fn run_analysis(
     data: &AnalysisData,
     config: &FactorAnalysisConfig,
-    error_collector: &mut ErrorCollector
+    error_collector: &mut ErrorCollector,
+    on_stage: Option<&js_sys::Function>
 ) -> Result<Option<FactorAnalysisResult>, JsValue> {
     web_sys::console::log_1(&"Starting factor analysis".into());
 
-    // Initialize result with executed functions tracking
-    let mut executed_functions = Vec::new();
-
     // Log configuration to track which methods will be executed
     web_sys::console::log_1(&format!("Config: {:?}", config).into());
 
-    // Filter Data based on value target if present
-    let filtered_data = match core::filter_valid_cases(data, config) {
-        Ok(filtered) => filtered,
+    let result = match on_stage {
+        Some(on_stage) => {
+            // A failing callback only costs the early display; the final result still comes back
+            let mut emit = |stage: StageResult| {
+                let emitted = serde_wasm_bindgen::to_value(&stage)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))
+                    .and_then(|value| on_stage.call1(&JsValue::NULL, &value));
+                if let Err(e) = emitted {
+                    web_sys::console::error_2(&"Stage callback failed:".into(), &e);
+                }
+            };
+            core::run_data_pipeline_with_stages(data, config, error_collector, &mut emit)
+        }
+        None => core::run_data_pipeline(data, config, error_collector),
+    };
+
+    result.map(Some).map_err(string_to_js_error)
+}
+
+// Reduce data above the memory limit to a session by streaming or subsampling, then analyze it
+pub fn run_large_data_analysis(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    data_mode: DataModeReport,
+    error_collector: &mut ErrorCollector
+) -> Result<(Option<FactorAnalysisResult>, AnalysisSession), JsValue> {
+    web_sys::console::log_1(
+        &format!("Data exceeds the memory limit, using {:?} mode", data_mode.mode).into()
+    );
+
+    core::run_large_data_pipeline(data, config, data_mode, error_collector)
+        .map(|(result, session)| (Some(result), session))
+        .map_err(string_to_js_error)
+}
+
+// Re-run everything downstream of extraction from a saved session
+pub fn run_session_analysis(
+    session: &AnalysisSession,
+    config: &FactorAnalysisConfig,
+    error_collector: &mut ErrorCollector
+) -> Result<Option<FactorAnalysisResult>, JsValue> {
+    web_sys::console::log_1(&"Resuming factor analysis from session".into());
+
+    core::run_session_pipeline(session, config, error_collector)
+        .map(Some)
+        .map_err(string_to_js_error)
+}
+
+// Analyze several variable blocks from one parse of the data
+pub fn run_block_analyses(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    blocks: &[VariableBlock],
+    error_collector: &mut ErrorCollector
+) -> Result<Vec<BlockAnalysisResult>, JsValue> {
+    web_sys::console::log_1(&format!("Starting block analysis of {} blocks", blocks.len()).into());
+
+    let sessions = match core::create_block_sessions(data, config, blocks) {
+        Ok(sessions) => sessions,
         Err(e) => {
-            error_collector.add_error("filter_valid_cases", &e);
+            error_collector.add_error("create_block_sessions", &e);
             return Err(string_to_js_error(e));
         }
     };
 
-    // Step 1: Calculate Descriptive Statistics if requested
-    let mut descriptive_statistics = None;
-    if config.descriptives.univar_desc {
-        executed_functions.push("calculate_descriptive_statistics".to_string());
-        match core::calculate_descriptive_statistics(&filtered_data, config) {
-            Ok(stats) => {
-                descriptive_statistics = Some(stats);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_descriptive_statistics", &e);
-                // Continue execution despite errors for non-critical functions
-            }
-        }
-    }
+    let mut results = Vec::with_capacity(blocks.len());
+    for (block, session) in blocks.iter().zip(sessions) {
+        let context = format!("block.{}", block.name);
+        let mut block_errors = ErrorCollector::default();
 
-    // Step 2: Calculate Correlation/Covariance Matrix based on Analyze selection
-    let mut correlation_matrix = None;
-    if config.extraction.correlation {
-        executed_functions.push("calculate_correlation_matrix".to_string());
-        match core::calculate_correlation_matrix(&filtered_data, config) {
-            Ok(matrix) => {
-                correlation_matrix = Some(matrix);
+        let (result, error) = match session {
+            Ok(session) => {
+                match run_session_analysis(&session, &session.config, &mut block_errors) {
+                    Ok(result) => (result, None),
+                    Err(e) => (None, e.as_string()),
+                }
             }
             Err(e) => {
-                error_collector.add_error("calculate_correlation_matrix", &e);
-                // Continue execution despite errors for non-critical functions
+                block_errors.add_error("create_block_sessions", &e);
+                (None, Some(e))
             }
+        };
+
+        if let Some(e) = &error {
+            error_collector.add_error(&context, e);
         }
-    } else if config.extraction.covariance {
-        executed_functions.push("calculate_covariance_matrix".to_string());
-        match core::calculate_covariance_matrix(&filtered_data, config) {
-            Ok(matrix) => {
-                correlation_matrix = Some(matrix); // Store in the same field
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_covariance_matrix", &e);
-                // Continue execution despite errors for non-critical functions
-            }
+        if block_errors.has_errors() {
+            error_collector.add_error(&context, &block_errors.get_error_summary());
         }
-    }
-
-    // Step 3: Calculate Inverse Matrix if requested
-    let mut inverse_correlation_matrix = None;
-    if config.descriptives.inverse {
-        executed_functions.push("calculate_inverse_correlation_matrix".to_string());
-        match core::calculate_inverse_correlation_matrix(&filtered_data, config) {
-            Ok(matrix) => {
-                inverse_correlation_matrix = Some(matrix);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_inverse_correlation_matrix", &e);
-                // Continue execution despite errors for non-critical functions
-            }
+        if block_errors.has_warnings() {
+            error_collector.add_warning(&context, &block_errors.get_warning_summary());
         }
-    }
 
-    // Step 4: Calculate KMO and Bartlett's Test if requested
-    let mut kmo_bartletts_test = None;
-    if config.descriptives.kmo {
-        executed_functions.push("calculate_kmo_bartletts_test".to_string());
-        match core::calculate_kmo_bartletts_test(&filtered_data, config) {
-            Ok(test) => {
-                kmo_bartletts_test = Some(test);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_kmo_bartletts_test", &e);
-                // Continue execution despite errors for non-critical functions
-            }
-        }
+        // Each block keeps its own messages, since the caller only receives the results
+        results.push(BlockAnalysisResult {
+            block: block.name.clone(),
+            variables: block.variables.clone(),
+            result,
+            error,
+            errors: block_errors.has_errors().then(|| block_errors.get_error_summary()),
+            warnings: block_errors.has_warnings().then(|| block_errors.get_warning_summary()),
+        });
     }
 
-    // Step 5: Calculate Anti-Image Matrices if requested
-    let mut anti_image_matrices = None;
-    if config.descriptives.anti_image {
-        executed_functions.push("calculate_anti_image_matrices".to_string());
-        match core::calculate_anti_image_matrices(&filtered_data, config) {
-            Ok(matrices) => {
-                anti_image_matrices = Some(matrices);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_anti_image_matrices", &e);
-                // Continue execution despite errors for non-critical functions
-            }
-        }
-    }
+    Ok(results)
+}
 
-    // Step 6: Calculate Communalities
-    executed_functions.push("calculate_communalities".to_string());
-    let communalities = match core::calculate_communalities(&filtered_data, config) {
-        Ok(communalities) => Some(communalities),
-        Err(e) => {
-            error_collector.add_error("calculate_communalities", &e);
-            None
+pub fn export_session(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    session: &Option<AnalysisSession>
+) -> Result<JsValue, JsValue> {
+    let session = match session {
+        Some(session) => session.clone(),
+        None => {
+            let filtered_data = core::filter_valid_cases(data, config).map_err(string_to_js_error)?;
+            core::create_analysis_session(&filtered_data, config).map_err(string_to_js_error)?
         }
     };
 
-    // Step 7: Calculate Total Variance Explained
-    executed_functions.push("calculate_total_variance_explained".to_string());
-    let total_variance_explained = match
-        core::calculate_total_variance_explained(&filtered_data, config)
-    {
-        Ok(variance) => Some(variance),
-        Err(e) => {
-            error_collector.add_error("calculate_total_variance_explained", &e);
-            None
-        }
-    };
+    let json = core::session_to_json(&session).map_err(string_to_js_error)?;
+    Ok(JsValue::from_str(&json))
+}
 
-    // Step 8: Calculate Factor/Component Matrix
-    executed_functions.push("calculate_component_matrix".to_string());
-    let component_matrix = match core::calculate_component_matrix(&filtered_data, config) {
-        Ok(matrix) => Some(matrix),
-        Err(e) => {
-            error_collector.add_error("calculate_component_matrix", &e);
-            None
+pub fn export_psych(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    session: &Option<AnalysisSession>
+) -> Result<JsValue, JsValue> {
+    let solution = match session {
+        Some(session) => {
+            let corr_matrix = core::session_correlation_matrix(session);
+            let extraction_result = core::session_extraction_result(session);
+            let rotation_result = if config.rotation.none {
+                None
+            } else {
+                Some(
+                    core::rotate_factors(&extraction_result, config).map_err(string_to_js_error)?
+                )
+            };
+
+            core::build_psych_solution(
+                &corr_matrix,
+                &extraction_result,
+                rotation_result.as_ref(),
+                session.n_cases,
+                config
+            )
+        }
+        None => {
+            let filtered_data = core::filter_valid_cases(data, config).map_err(string_to_js_error)?;
+            core::calculate_psych_solution(&filtered_data, config).map_err(string_to_js_error)?
         }
     };
 
-    // Step 9: Calculate Scree Plot if requested
-    let mut scree_plot = None;
-    if config.extraction.scree {
-        executed_functions.push("calculate_scree_plot".to_string());
-        match core::calculate_scree_plot(&filtered_data, config) {
-            Ok(plot) => {
-                scree_plot = Some(plot);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_scree_plot", &e);
-                // Continue execution despite errors for non-critical functions
-            }
-        }
-    }
+    let json = serde_json::to_string(&solution)
+        .map_err(|e| string_to_js_error(format!("Failed to serialize solution: {}", e)))?;
+    Ok(JsValue::from_str(&json))
+}
 
-    // Step 10: Calculate Reproduced Correlations if requested
-    let mut reproduced_correlations = None;
-    if config.descriptives.reproduced {
-        executed_functions.push("calculate_reproduced_correlations".to_string());
-        match core::calculate_reproduced_correlations(&filtered_data, config) {
-            Ok(correlations) => {
-                reproduced_correlations = Some(correlations);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_reproduced_correlations", &e);
-                // Continue execution despite errors for non-critical functions
-            }
+pub fn eigen_decomposition(
+    data: &AnalysisData,
+    config: &FactorAnalysisConfig,
+    session: &Option<AnalysisSession>
+) -> Result<JsValue, JsValue> {
+    let decomposition = match session {
+        Some(session) => {
+            let matrix = if core::analyzes_covariance(config) {
+                core::session_covariance_matrix(session)
+            } else {
+                core::session_correlation_matrix(session)
+            };
+            core::build_eigen_decomposition(&matrix, &session.var_names)
         }
-    }
-
-    // Step 11: Calculate Rotated Component Matrix if not using 'None' rotation method
-    let mut rotated_component_matrix = None;
-    if !config.rotation.none && config.rotation.rotated_sol {
-        executed_functions.push("calculate_rotated_component_matrix".to_string());
-        match core::calculate_rotated_component_matrix(&filtered_data, config) {
-            Ok(matrix) => {
-                rotated_component_matrix = Some(matrix);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_rotated_component_matrix", &e);
-                // Continue execution despite errors for non-critical functions
-            }
+        None => {
+            let filtered_data = core::filter_valid_cases(data, config).map_err(string_to_js_error)?;
+            core::calculate_eigen_decomposition(&filtered_data, config)
         }
-    }
+    }.map_err(string_to_js_error)?;
 
-    // Step 12: Calculate Component Transformation Matrix if rotation is performed
-    let mut component_transformation_matrix = None;
-    if !config.rotation.none && config.rotation.rotated_sol {
-        executed_functions.push("calculate_component_transformation_matrix".to_string());
-        match core::calculate_component_transformation_matrix(&filtered_data, config) {
-            Ok(matrix) => {
-                component_transformation_matrix = Some(matrix);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_component_transformation_matrix", &e);
-                // Continue execution despite errors for non-critical functions
-            }
+    serde_wasm_bindgen::to_value(&decomposition).map_err(|e| string_to_js_error(e.to_string()))
+}
+
+pub fn compare_results(
+    result_a: &Option<FactorAnalysisResult>,
+    result_b: &Option<FactorAnalysisResult>
+) -> Result<JsValue, JsValue> {
+    match (result_a, result_b) {
+        (Some(result_a), Some(result_b)) => {
+            let comparison = core::compare_solutions(result_a, result_b)
+                .map_err(string_to_js_error)?;
+            serde_wasm_bindgen::to_value(&comparison).map_err(|e| string_to_js_error(e.to_string()))
         }
+        _ => Err(string_to_js_error("No analysis results available".to_string())),
     }
+}
 
-    // Step 13: Calculate Component Score Coefficient Matrix if scores are saved
-    let mut component_score_coefficient_matrix = None;
-    if config.scores.save_var {
-        executed_functions.push("calculate_component_score_coefficient_matrix".to_string());
-        match core::calculate_component_score_coefficient_matrix(&filtered_data, config) {
-            Ok(matrix) => {
-                component_score_coefficient_matrix = Some(matrix);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_component_score_coefficient_matrix", &e);
-                // Continue execution despite errors for non-critical functions
-            }
+// Align the solution of one result with the solution of a reference result
+pub fn align_results(
+    result: &Option<FactorAnalysisResult>,
+    reference: &Option<FactorAnalysisResult>,
+    method: &AlignmentMethod
+) -> Result<JsValue, JsValue> {
+    match (result, reference) {
+        (Some(result), Some(reference)) => {
+            let loadings = core::solution_loadings(result).ok_or_else(|| {
+                string_to_js_error("Result has no component or rotated matrix".to_string())
+            })?;
+            let reference_loadings = core::solution_loadings(reference).ok_or_else(|| {
+                string_to_js_error("Reference has no component or rotated matrix".to_string())
+            })?;
+            let alignment = core::align_factor_loadings(loadings, reference_loadings, method)
+                .map_err(string_to_js_error)?;
+            serde_wasm_bindgen::to_value(&alignment).map_err(|e| string_to_js_error(e.to_string()))
         }
+        _ => Err(string_to_js_error("No analysis results available".to_string())),
     }
+}
 
-    // Step 14: Calculate Component Score Covariance Matrix if scores are saved
-    let mut component_score_covariance_matrix = None;
-    if config.scores.save_var {
-        executed_functions.push("calculate_component_score_covariance_matrix".to_string());
-        match core::calculate_component_score_covariance_matrix(&filtered_data, config) {
-            Ok(matrix) => {
-                component_score_covariance_matrix = Some(matrix);
-            }
-            Err(e) => {
-                error_collector.add_error("calculate_component_score_covariance_matrix", &e);
-                // Continue execution despite errors for non-critical functions
-            }
+pub fn get_tables(
+    result: &Option<FactorAnalysisResult>,
+    config: &FactorAnalysisConfig,
+    var_names: &[String],
+    options: Option<FormatOptions>
+) -> Result<JsValue, JsValue> {
+    match result {
+        Some(result) => {
+            let options = options.unwrap_or_else(|| FormatOptions::from_config(config));
+            let tables = build_result_tables(result, config, var_names, &options);
+            serde_wasm_bindgen::to_value(&tables).map_err(|e| string_to_js_error(e.to_string()))
         }
+        None => Err(string_to_js_error("No analysis results available".to_string())),
     }
+}
 
-    // Step 15: Generate Loading Plots if requested
-    if config.rotation.loading_plot {
-        executed_functions.push("generate_loading_plots".to_string());
-        match core::generate_loading_plots(&filtered_data, config) {
-            Ok(_) => {}
-            Err(e) => {
-                error_collector.add_error("generate_loading_plots", &e);
-                // Continue execution despite errors for non-critical functions
+// Render the result tables, optionally only those with the given titles
+pub fn export_tables(
+    result: &Option<FactorAnalysisResult>,
+    config: &FactorAnalysisConfig,
+    var_names: &[String],
+    format: &str,
+    options: Option<FormatOptions>,
+    titles: Option<Vec<String>>
+) -> Result<JsValue, JsValue> {
+    let format = ExportFormat::parse(format).map_err(string_to_js_error)?;
+
+    match result {
+        Some(result) => {
+            let options = options.unwrap_or_else(|| FormatOptions::from_config(config));
+            let mut tables = build_result_tables(result, config, var_names, &options);
+            if let Some(titles) = titles {
+                tables.retain(|table| titles.contains(&table.title));
             }
+            Ok(JsValue::from_str(&render_tables(&tables, format)))
         }
+        None => Err(string_to_js_error("No analysis results available".to_string())),
     }
-
-    // Create the final result
-    let result = FactorAnalysisResult {
-        descriptive_statistics,
-        scree_plot,
-        correlation_matrix,
-        inverse_correlation_matrix,
-        kmo_bartletts_test,
-        anti_image_matrices,
-        communalities,
-        total_variance_explained,
-        component_matrix,
-        reproduced_correlations,
-        rotated_component_matrix,
-        component_transformation_matrix,
-        component_score_coefficient_matrix,
-        component_score_covariance_matrix,
-    };
-
-    Ok(Some(result))
 }
 
 pub fn get_results(result: &Option<FactorAnalysisResult>) -> Result<JsValue, JsValue> {
@@ -287,6 +306,10 @@ pub fn get_all_errors(error_collector: &ErrorCollector) -> JsValue {
     JsValue::from_str(&error_collector.get_error_summary())
 }
 
+pub fn get_all_warnings(error_collector: &ErrorCollector) -> JsValue {
+    JsValue::from_str(&error_collector.get_warning_summary())
+}
+
 pub fn clear_errors(error_collector: &mut ErrorCollector) -> JsValue {
     error_collector.clear();
     JsValue::from_str("Error collector cleared")